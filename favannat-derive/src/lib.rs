@@ -0,0 +1,352 @@
+//! Derive macros for the network traits of `favannat`, which re-exports them under its feature `derive`.
+//!
+//! Fields are assigned their role by an attribute named after the trait, e.g. `#[node(id)]` or `#[edge(weight)]`.
+//! Roles that are not assigned fall back to the default implementation of the trait.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, spanned::Spanned, Data, DeriveInput, Error, GenericArgument,
+    Member, PathArguments, Result, Type,
+};
+
+/// Fields of a struct keyed by their role.
+struct Roles {
+    fields: Vec<(String, Member, Type)>,
+    scalar: Option<Type>,
+}
+
+impl Roles {
+    /// Collects the roles assigned by `#[<attribute>(<role>)]` on fields and `#[<attribute>(scalar = <type>)]` on the struct.
+    fn parse(input: &DeriveInput, attribute: &str, known: &[&str]) -> Result<Self> {
+        let data = match &input.data {
+            Data::Struct(data) => data,
+            _ => return Err(Error::new(input.span(), "only structs can be derived")),
+        };
+
+        let mut scalar = None;
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident(attribute))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("scalar") {
+                    scalar = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `scalar = <type>`"))
+                }
+            })?;
+        }
+
+        let mut fields: Vec<(String, Member, Type)> = Vec::new();
+        for (index, field) in data.fields.iter().enumerate() {
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(index.into()),
+            };
+            for attr in field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident(attribute))
+            {
+                attr.parse_nested_meta(|meta| {
+                    let role = match meta.path.get_ident() {
+                        Some(role) if known.contains(&role.to_string().as_str()) => {
+                            role.to_string()
+                        }
+                        _ => {
+                            return Err(meta.error(format!(
+                                "unknown role, expected one of: {}",
+                                known.join(", ")
+                            )))
+                        }
+                    };
+                    if fields.iter().any(|(assigned, ..)| *assigned == role) {
+                        return Err(meta.error("role is assigned more than once"));
+                    }
+                    fields.push((role, member.clone(), field.ty.clone()));
+                    Ok(())
+                })?;
+            }
+        }
+
+        Ok(Self { fields, scalar })
+    }
+
+    fn get(&self, role: &str) -> Option<(&Member, &Type)> {
+        self.fields
+            .iter()
+            .find(|(assigned, ..)| assigned == role)
+            .map(|(_, member, ty)| (member, ty))
+    }
+
+    fn require(
+        &self,
+        input: &DeriveInput,
+        attribute: &str,
+        role: &str,
+    ) -> Result<(&Member, &Type)> {
+        self.get(role).ok_or_else(|| {
+            Error::new(
+                input.ident.span(),
+                format!("missing field marked `#[{}({})]`", attribute, role),
+            )
+        })
+    }
+
+    /// Returns a method returning the field of `role` if one is assigned, `body` receives the access to the field.
+    fn method(
+        &self,
+        role: &str,
+        signature: TokenStream2,
+        body: impl Fn(TokenStream2) -> TokenStream2,
+    ) -> TokenStream2 {
+        match self.get(role) {
+            Some((member, _)) => {
+                let body = body(quote!(self.#member));
+                quote!(#signature { #body })
+            }
+            None => TokenStream2::new(),
+        }
+    }
+}
+
+/// Returns the element type of a collection like `Vec<T>`.
+fn element(ty: &Type) -> Result<&Type> {
+    if let Type::Path(path) = ty {
+        if let Some(segment) = path.path.segments.last() {
+            if let PathArguments::AngleBracketed(arguments) = &segment.arguments {
+                if let Some(GenericArgument::Type(element)) = arguments.args.first() {
+                    return Ok(element);
+                }
+            }
+        }
+    }
+    Err(Error::new(ty.span(), "expected a collection like `Vec<T>`"))
+}
+
+/// Implements `NodeLike` for a struct, see the trait for the meaning of each role.
+///
+/// `#[node(id)]` and `#[node(activation)]` are required, `bias`, `aggregation`, `time_constant`, `response`, `kind` and `label` are optional.
+/// The id type is the type of the id field, the label field is expected to be an `Option<String>`.
+/// The scalar is taken from `#[node(scalar = <type>)]` on the struct, else from the type of the bias, time constant or response field and defaults to `f32`.
+/// `NodeLike` requires the struct to be `Ord`, which has to be implemented separately.
+#[proc_macro_derive(NodeLike, attributes(node))]
+pub fn derive_node_like(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    node_like(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn node_like(input: &DeriveInput) -> Result<TokenStream2> {
+    let roles = Roles::parse(
+        input,
+        "node",
+        &[
+            "id",
+            "activation",
+            "bias",
+            "aggregation",
+            "time_constant",
+            "response",
+            "kind",
+            "label",
+        ],
+    )?;
+    let (id, id_ty) = roles.require(input, "node", "id")?;
+    let (activation, _) = roles.require(input, "node", "activation")?;
+    let scalar = roles
+        .scalar
+        .clone()
+        .or_else(|| {
+            ["bias", "time_constant", "response"]
+                .iter()
+                .find_map(|role| roles.get(role).map(|(_, ty)| ty.clone()))
+        })
+        .unwrap_or_else(|| parse_quote!(f32));
+
+    let bias = roles.method("bias", quote!(fn bias(&self) -> #scalar), |field| field);
+    let aggregation = roles.method(
+        "aggregation",
+        quote!(fn aggregation(&self) -> ::favannat::network::Aggregation),
+        |field| field,
+    );
+    let time_constant = roles.method(
+        "time_constant",
+        quote!(fn time_constant(&self) -> #scalar),
+        |field| field,
+    );
+    let response = roles.method("response", quote!(fn response(&self) -> #scalar), |field| {
+        field
+    });
+    let kind = roles.method(
+        "kind",
+        quote!(fn kind(&self) -> ::favannat::network::NodeKind),
+        |field| field,
+    );
+    let label = roles.method(
+        "label",
+        quote!(fn label(&self) -> ::core::option::Option<&str>),
+        |field| quote!(::core::option::Option::as_deref(&#field)),
+    );
+
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    let predicates = &mut generics.make_where_clause().predicates;
+    predicates.push(parse_quote!(#scalar: ::favannat::network::Scalar));
+    predicates.push(parse_quote!(#id_ty: ::favannat::network::NodeId));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::favannat::network::NodeLike<#scalar, #id_ty> for #name #ty_generics #where_clause {
+            fn id(&self) -> #id_ty {
+                self.#id
+            }
+            fn activation(&self) -> fn(#scalar) -> #scalar {
+                self.#activation
+            }
+            #bias
+            #aggregation
+            #time_constant
+            #response
+            #kind
+            #label
+        }
+    })
+}
+
+/// Implements `EdgeLike` for a struct, see the trait for the meaning of each role.
+///
+/// `#[edge(start)]`, `#[edge(end)]` and `#[edge(weight)]` are required, `gate`, `delay`, `enabled` and `id` are optional.
+/// The node id type is the type of the start field, the scalar the type of the weight field.
+#[proc_macro_derive(EdgeLike, attributes(edge))]
+pub fn derive_edge_like(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    edge_like(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn edge_like(input: &DeriveInput) -> Result<TokenStream2> {
+    let roles = Roles::parse(
+        input,
+        "edge",
+        &["start", "end", "weight", "gate", "delay", "enabled", "id"],
+    )?;
+    let (start, id_ty) = roles.require(input, "edge", "start")?;
+    let (end, _) = roles.require(input, "edge", "end")?;
+    let (weight, scalar) = roles.require(input, "edge", "weight")?;
+
+    let gate = roles.method(
+        "gate",
+        quote!(fn gate(&self) -> ::favannat::network::Gate),
+        |field| field,
+    );
+    let delay = roles.method("delay", quote!(fn delay(&self) -> usize), |field| field);
+    let enabled = roles.method("enabled", quote!(fn enabled(&self) -> bool), |field| field);
+    let id = roles.method(
+        "id",
+        quote!(fn id(&self) -> ::core::option::Option<usize>),
+        |field| field,
+    );
+
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    let predicates = &mut generics.make_where_clause().predicates;
+    predicates.push(parse_quote!(#scalar: ::favannat::network::Scalar));
+    predicates.push(parse_quote!(#id_ty: ::favannat::network::NodeId));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::favannat::network::EdgeLike<#scalar, #id_ty> for #name #ty_generics #where_clause {
+            fn start(&self) -> #id_ty {
+                self.#start
+            }
+            fn end(&self) -> #id_ty {
+                self.#end
+            }
+            fn weight(&self) -> #scalar {
+                self.#weight
+            }
+            #gate
+            #delay
+            #enabled
+            #id
+        }
+    })
+}
+
+/// Implements `NetworkLike` for a struct holding its nodes and edges in collections like `Vec`.
+///
+/// `#[network(inputs)]`, `#[network(hidden)]`, `#[network(outputs)]` and `#[network(edges)]` are required.
+/// If `#[network(recurrent_edges)]` is assigned, `Recurrent` is implemented as well.
+/// The scalar and id type are those of the nodes and edges.
+#[proc_macro_derive(NetworkLike, attributes(network))]
+pub fn derive_network_like(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    network_like(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn network_like(input: &DeriveInput) -> Result<TokenStream2> {
+    let roles = Roles::parse(
+        input,
+        "network",
+        &["inputs", "hidden", "outputs", "edges", "recurrent_edges"],
+    )?;
+    let (inputs, node_ty) = roles.require(input, "network", "inputs")?;
+    let (hidden, _) = roles.require(input, "network", "hidden")?;
+    let (outputs, _) = roles.require(input, "network", "outputs")?;
+    let (edges, edge_ty) = roles.require(input, "network", "edges")?;
+    let node_ty = element(node_ty)?;
+    let edge_ty = element(edge_ty)?;
+
+    let name = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let mut generics = input.generics.clone();
+    generics
+        .params
+        .push(parse_quote!(__S: ::favannat::network::Scalar));
+    generics
+        .params
+        .push(parse_quote!(__I: ::favannat::network::NodeId));
+    let predicates = &mut generics.make_where_clause().predicates;
+    predicates.push(parse_quote!(#node_ty: ::favannat::network::NodeLike<__S, __I>));
+    predicates.push(parse_quote!(#edge_ty: ::favannat::network::EdgeLike<__S, __I>));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let recurrent = match roles.get("recurrent_edges") {
+        Some((recurrent_edges, _)) => quote! {
+            impl #impl_generics ::favannat::network::Recurrent<#node_ty, #edge_ty, __S, __I> for #name #ty_generics #where_clause {
+                fn recurrent_edges(&self) -> ::std::vec::Vec<&#edge_ty> {
+                    self.#recurrent_edges.iter().collect()
+                }
+            }
+        },
+        None => TokenStream2::new(),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::favannat::network::NetworkLike<#node_ty, #edge_ty, __S, __I> for #name #ty_generics #where_clause {
+            fn edges(&self) -> ::std::vec::Vec<&#edge_ty> {
+                self.#edges.iter().collect()
+            }
+            fn inputs(&self) -> ::std::vec::Vec<&#node_ty> {
+                self.#inputs.iter().collect()
+            }
+            fn hidden(&self) -> ::std::vec::Vec<&#node_ty> {
+                self.#hidden.iter().collect()
+            }
+            fn outputs(&self) -> ::std::vec::Vec<&#node_ty> {
+                self.#outputs.iter().collect()
+            }
+        }
+
+        #recurrent
+    })
+}
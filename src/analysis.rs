@@ -0,0 +1,306 @@
+//! Measures the topology of [`NetworkLike`] structures, e.g. to penalize complexity.
+//!
+//! Only [`NetworkLike::edges`] are considered and like fabricators disabled edges are skipped.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec,
+    vec::Vec,
+};
+
+use crate::network::{prune::active_edges, EdgeLike, NetworkLike, NodeLike, Scalar};
+
+/// Assigns every computable node the number of edges on the longest path from any input to it.
+///
+/// Nodes depending on any node that is not computable are left out, just like fabricators never resolve them.
+pub(crate) fn levels<N, E, S>(net: &impl NetworkLike<N, E, S>) -> BTreeMap<usize, usize>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut levels: BTreeMap<usize, usize> = net.inputs_iter().map(|node| (node.id(), 0)).collect();
+
+    let mut pending: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for edge in active_edges(&net.nodes(), net.edges()) {
+        if !levels.contains_key(&edge.end()) {
+            pending.entry(edge.end()).or_default().push(edge.start());
+        }
+    }
+
+    loop {
+        let resolved: Vec<(usize, usize)> = pending
+            .iter()
+            .filter_map(|(&node, dependencies)| {
+                dependencies
+                    .iter()
+                    .map(|dependency| levels.get(dependency))
+                    .try_fold(0, |deepest, level| level.map(|&level| deepest.max(level)))
+                    .map(|deepest| (node, deepest + 1))
+            })
+            .collect();
+        if resolved.is_empty() {
+            return levels;
+        }
+        for (node, level) in resolved {
+            pending.remove(&node);
+            levels.insert(node, level);
+        }
+    }
+}
+
+/// Fails the way fabricators do if some node in `levels` is missing.
+fn resolved<N, E, S>(
+    net: &impl NetworkLike<N, E, S>,
+    levels: &BTreeMap<usize, usize>,
+    ids: impl IntoIterator<Item = usize>,
+) -> Result<(), &'static str>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    if ids.into_iter().all(|id| levels.contains_key(&id)) {
+        Ok(())
+    } else if !crate::validation::cycles(net).is_empty() {
+        Err("can't resolve dependencies, net contains a cycle")
+    } else {
+        Err("can't resolve dependencies, net invalid")
+    }
+}
+
+/// Returns the number of edges on the longest path from any input to any output.
+///
+/// Fails if some output can not be computed.
+pub fn depth<N, E, S>(net: &impl NetworkLike<N, E, S>) -> Result<usize, &'static str>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let levels = levels(net);
+    resolved(net, &levels, net.outputs_iter().map(|node| node.id()))?;
+    Ok(net
+        .outputs_iter()
+        .map(|node| levels[&node.id()])
+        .max()
+        .unwrap_or(0))
+}
+
+/// Returns the node ids along a longest path from an input to an output, its length in edges is the [`depth`].
+///
+/// Of equally long paths the one ending at the output with the smallest id and preferring small ids along the way is chosen.
+pub fn longest_path<N, E, S>(net: &impl NetworkLike<N, E, S>) -> Result<Vec<usize>, &'static str>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let levels = levels(net);
+    let mut outputs: Vec<usize> = net.outputs_iter().map(|node| node.id()).collect();
+    outputs.sort_unstable();
+    resolved(net, &levels, outputs.iter().copied())?;
+
+    let Some(mut current) = outputs.into_iter().rev().max_by_key(|id| levels[id]) else {
+        return Ok(Vec::new());
+    };
+
+    let edges = active_edges(&net.nodes(), net.edges());
+    let mut path = vec![current];
+    while levels[&current] > 0 {
+        // some dependency always lies exactly one level below
+        current = edges
+            .iter()
+            .filter(|edge| edge.end() == current)
+            .map(|edge| edge.start())
+            .filter(|start| levels[start] + 1 == levels[&current])
+            .min()
+            .unwrap();
+        path.push(current);
+    }
+    path.reverse();
+
+    Ok(path)
+}
+
+/// Returns the ratio of enabled edges to the `n * (n - 1) / 2` edges an acyclic net of `n` nodes can have at most.
+///
+/// Nets with fewer than two nodes have a density of zero.
+pub fn density<N, E, S>(net: &impl NetworkLike<N, E, S>) -> f64
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let nodes = net.nodes_iter().count();
+    if nodes < 2 {
+        return 0.0;
+    }
+    let edges = net.edges_iter().filter(|edge| edge.enabled()).count();
+    edges as f64 / (nodes * (nodes - 1) / 2) as f64
+}
+
+/// Groups node ids into layers the same way fabricators schedule their stages, each layer sorted by id.
+///
+/// The first layer holds the inputs, every other node is placed one layer after its deepest dependency.
+/// Nodes without incoming edges that are not inputs are not placed at all.
+/// Fails like fabricators if some node can not be computed.
+pub fn layers<N, E, S>(net: &impl NetworkLike<N, E, S>) -> Result<Vec<Vec<usize>>, &'static str>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let levels = levels(net);
+    resolved(
+        net,
+        &levels,
+        active_edges(&net.nodes(), net.edges())
+            .into_iter()
+            .map(|edge| edge.end()),
+    )?;
+
+    let mut layers = vec![Vec::new(); levels.values().copied().max().unwrap_or(0) + 1];
+    // levels are ordered by id, so every layer ends up sorted
+    for (id, level) in levels {
+        layers[level].push(id);
+    }
+    Ok(layers)
+}
+
+/// Returns the number of stages [`crate::matrix::feedforward::fabricator::MatrixFeedforwardFabricator::plan`] resolves, i.e. the [`layers`] after the inputs.
+///
+/// Fabricated evaluators may hold fewer stages, as consecutive linear stages are fused.
+pub fn stage_estimate<N, E, S>(net: &impl NetworkLike<N, E, S>) -> Result<usize, &'static str>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    layers(net).map(|layers| layers.len() - 1)
+}
+
+/// Returns the ids of all nodes reachable from `start` along enabled edges, including `start` itself.
+pub fn reachable<N, E, S>(
+    net: &impl NetworkLike<N, E, S>,
+    start: impl IntoIterator<Item = usize>,
+) -> BTreeSet<usize>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut successors: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for edge in net.edges_iter().filter(|edge| edge.enabled()) {
+        successors.entry(edge.start()).or_default().push(edge.end());
+    }
+
+    let mut reached: BTreeSet<usize> = BTreeSet::new();
+    let mut pending: Vec<usize> = start.into_iter().collect();
+    while let Some(id) = pending.pop() {
+        if reached.insert(id) {
+            pending.extend(successors.get(&id).into_iter().flatten().copied());
+        }
+    }
+    reached
+}
+
+/// Returns the ids of all nodes reachable from any input, including the inputs.
+pub fn reachable_from_inputs<N, E, S>(net: &impl NetworkLike<N, E, S>) -> BTreeSet<usize>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    reachable(net, net.inputs_iter().map(|node| node.id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        density, depth, layers, longest_path, reachable, reachable_from_inputs, stage_estimate,
+    };
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{net::Net, Fabricator},
+        nodes,
+    };
+
+    #[test]
+    fn measure_topology() {
+        let net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'r', 'r', 'r', 'l'),
+            edges!(
+                0--1.0->2,
+                2--1.0->3,
+                3--1.0->5,
+                1--1.0->5,
+                1--1.0->4
+            ),
+        );
+
+        assert_eq!(depth(&net), Ok(3));
+        assert_eq!(longest_path(&net), Ok(vec![0, 2, 3, 5]));
+        assert_eq!(density(&net), 5.0 / 15.0);
+        // the dead end at node 4 is resolved in the first stage as well
+        assert_eq!(stage_estimate(&net), Ok(3));
+        assert_eq!(
+            MatrixFeedforwardFabricator::fabricate_indexed(&net)
+                .unwrap()
+                .0
+                .stage_dimensions()
+                .len(),
+            3
+        );
+
+        assert_eq!(
+            reachable(&net, [2]).into_iter().collect::<Vec<_>>(),
+            vec![2, 3, 5]
+        );
+        assert_eq!(reachable_from_inputs(&net).len(), 6);
+    }
+
+    #[test]
+    fn layer_nodes() {
+        let net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 'r', 'r', 'l', 'l'),
+            edges!(
+                1--1.0->3,
+                0--1.0->2,
+                2--1.0->4,
+                3--1.0->4,
+                0--1.0->5
+            ),
+        );
+        assert_eq!(layers(&net), Ok(vec![vec![0, 1], vec![2, 3, 5], vec![4]]));
+
+        let net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--1.0->2));
+        assert_eq!(layers(&net), Err("can't resolve dependencies, net invalid"));
+    }
+
+    #[test]
+    fn reject_cycles() {
+        let net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--1.0->1, 1--1.0->2, 2--1.0->1, 2--1.0->3),
+        );
+        assert_eq!(
+            depth(&net),
+            Err("can't resolve dependencies, net contains a cycle")
+        );
+        assert!(stage_estimate(&net).is_err());
+        assert_eq!(
+            MatrixFeedforwardFabricator::fabricate(&net)
+                .err()
+                .map(<&str>::from),
+            depth(&net).err()
+        );
+    }
+}
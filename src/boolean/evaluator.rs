@@ -0,0 +1,99 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::DMatrix;
+
+use crate::network::{Evaluator, NetworkIO, Scalar};
+
+/// Non-input node in evaluation order.
+///
+/// `table` holds the output for every combination of the values at `inputs`, the value at `inputs[i]` selecting bit `i` of the index.
+#[derive(Debug, Clone)]
+pub struct BooleanNode {
+    pub inputs: Vec<usize>,
+    pub table: Vec<bool>,
+}
+
+/// Evaluates nodes one after another in topological order on words holding one bit per sample.
+///
+/// Words are laid out like the values of [`crate::interpreter::evaluator::LoopEvaluator`], the inputs followed by one word per entry of `nodes`.
+#[derive(Debug, Clone)]
+pub struct BooleanEvaluator {
+    pub input_count: usize,
+    pub nodes: Vec<BooleanNode>,
+    /// Word positions of the outputs.
+    pub output_ids: Vec<usize>,
+}
+
+impl BooleanEvaluator {
+    pub fn input_size(&self) -> usize {
+        self.input_count
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.output_ids.len()
+    }
+
+    /// Number of words [`Self::evaluate_words`] expects.
+    pub fn word_count(&self) -> usize {
+        self.input_count + self.nodes.len()
+    }
+
+    /// Evaluates 64 samples in place, bit `k` of every word belonging to sample `k`.
+    ///
+    /// `words` needs [`Self::word_count`] entries, the leading ones holding the inputs, outputs are found at [`Self::output_ids`] afterwards.
+    pub fn evaluate_words(&self, words: &mut [u64]) {
+        let largest = self.nodes.iter().map(|node| node.table.len()).max();
+        let mut leaves = vec![0; largest.unwrap_or(0)];
+        for (position, node) in (self.input_count..).zip(&self.nodes) {
+            for (leaf, &on) in leaves.iter_mut().zip(&node.table) {
+                *leaf = if on { u64::MAX } else { 0 };
+            }
+            // every input selects between the halves of the remaining table
+            let mut remaining = node.table.len();
+            for &input in &node.inputs {
+                let selector = words[input];
+                remaining /= 2;
+                for index in 0..remaining {
+                    leaves[index] =
+                        (selector & leaves[2 * index + 1]) | (!selector & leaves[2 * index]);
+                }
+            }
+            words[position] = leaves[0];
+        }
+    }
+}
+
+impl<S: Scalar> Evaluator<S> for BooleanEvaluator {
+    fn evaluate<T: NetworkIO<S>>(&self, input: T) -> T {
+        let input = NetworkIO::input(input);
+        let mut output = DMatrix::zeros(input.nrows(), self.output_ids.len());
+        let half = nalgebra::convert::<f64, S>(0.5);
+        let mut words = vec![0; self.word_count()];
+
+        for first in (0..input.nrows()).step_by(64) {
+            let samples = (input.nrows() - first).min(64);
+            for (column, word) in words.iter_mut().take(self.input_count).enumerate() {
+                *word = (0..samples)
+                    .filter(|&lane| input[(first + lane, column)] > half)
+                    .fold(0, |word, lane| word | 1 << lane);
+            }
+            self.evaluate_words(&mut words);
+            for (column, &id) in self.output_ids.iter().enumerate() {
+                for lane in 0..samples {
+                    if words[id] >> lane & 1 == 1 {
+                        output[(first + lane, column)] = S::one();
+                    }
+                }
+            }
+        }
+        NetworkIO::output(output)
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
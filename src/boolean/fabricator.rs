@@ -0,0 +1,131 @@
+use crate::{
+    interpreter::fabricator::LoopFabricator,
+    network::{
+        Aggregation, EdgeLike, FabricationError, Fabricator, NetworkLike, NodeId, NodeLike, Scalar,
+    },
+};
+
+use super::evaluator::{BooleanEvaluator, BooleanNode};
+
+/// Nodes with more incoming edges would need truth tables of more than 4096 entries.
+pub const MAX_FAN_IN: usize = 12;
+
+/// Fabricates a [`BooleanEvaluator`], nodes need to sum at most [`MAX_FAN_IN`] incoming edges.
+#[derive(Debug)]
+pub struct BooleanFabricator;
+
+impl<N, E, S, I> Fabricator<N, E, S, I> for BooleanFabricator
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    type Output = BooleanEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E, S, I>) -> Result<Self::Output, FabricationError> {
+        // the loop evaluator already orders the nodes and locates their inputs
+        let evaluator = LoopFabricator::fabricate(net)?;
+
+        let mut nodes = Vec::with_capacity(evaluator.nodes.len());
+        for node in evaluator.nodes {
+            if node.aggregation != Aggregation::Sum {
+                return Err("boolean evaluation needs nodes summing their inputs".into());
+            }
+            if node.inputs.len() > MAX_FAN_IN {
+                return Err(
+                    "boolean evaluation supports at most 12 incoming edges per node".into(),
+                );
+            }
+            let table = (0..1usize << node.inputs.len())
+                .map(|combination| {
+                    let sum = node
+                        .inputs
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, _)| combination >> index & 1 == 1)
+                        .fold(S::zero(), |sum, (_, &(_, weight))| sum + weight);
+                    node.bias + node.response * sum > S::zero()
+                })
+                .collect();
+            nodes.push(BooleanNode {
+                inputs: node.inputs.into_iter().map(|(input, _)| input).collect(),
+                table,
+            });
+        }
+
+        Ok(BooleanEvaluator {
+            input_count: evaluator.input_count,
+            nodes,
+            output_ids: evaluator.output_ids,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{dmatrix, DMatrix};
+
+    use super::BooleanFabricator;
+    use crate::{
+        edges,
+        interpreter::fabricator::LoopFabricator,
+        network::{
+            net::{activations, Net, Node},
+            Aggregation, Evaluator, Fabricator,
+        },
+        nodes,
+        rng::SplitMix,
+    };
+
+    fn xor() -> Net {
+        // or minus and
+        Net::new(
+            2,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::STEP).with_bias(-0.5),
+                Node::new(3, activations::STEP).with_bias(-1.5),
+                Node::new(4, activations::STEP).with_bias(-0.5),
+            ],
+            edges!(0--1.0->2, 1--1.0->2, 0--1.0->3, 1--1.0->3, 2--1.0->4, 3---1.0->4),
+        )
+    }
+
+    #[test]
+    fn evaluate_threshold_logic() {
+        let evaluator = BooleanFabricator::fabricate(&xor()).unwrap();
+        assert_eq!(
+            evaluator.evaluate(dmatrix![0.0, 0.0; 0.0, 1.0; 1.0, 0.0; 1.0, 1.0]),
+            dmatrix![0.0; 1.0; 1.0; 0.0]
+        );
+
+        // more samples than fit into a single word
+        let mut rng = SplitMix(3);
+        let input = DMatrix::from_fn(150, 2, |_, _| if rng.unit() < 0.5 { 0.0 } else { 1.0 });
+        let expected = LoopFabricator::fabricate(&xor())
+            .unwrap()
+            .evaluate(input.clone());
+        assert_eq!(evaluator.evaluate(input), expected);
+    }
+
+    #[test]
+    fn reject_other_aggregations() {
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::STEP).with_aggregation(Aggregation::Max),
+            ],
+            edges!(0--1.0->1),
+        );
+        assert!(BooleanFabricator::fabricate(&some_net).is_err());
+        assert!(
+            BooleanFabricator::fabricate(&Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1)))
+                .is_ok()
+        );
+    }
+}
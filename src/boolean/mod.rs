@@ -0,0 +1,9 @@
+//! Evaluates [`crate::network::NetworkLike`] structures as threshold logic on packed bits.
+//!
+//! Every non-input node acts like [`crate::network::net::activations::step`], it is on if its biased and responded weighted sum is positive, whatever its activation.
+//! Each node is turned into a truth table over its incoming edges on fabrication, evaluation then handles 64 samples at once with bitwise operations.
+//! Values are zero or one, inputs count as on if they are greater than one half.
+
+pub mod evaluator;
+#[cfg(feature = "std")]
+pub mod fabricator;
@@ -0,0 +1,195 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::DMatrix;
+
+use crate::{
+    matrix::feedforward::evaluator::MatrixFeedforwardEvaluator,
+    network::{Aggregation, EvaluateInto, Evaluator, NetworkIO, Scalar, Scratch},
+};
+
+/// Single operation of the virtual machine, which works on one accumulator and a file of registers.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Instruction<S: Scalar = f32> {
+    /// Sets the accumulator to the value.
+    Load(S),
+    /// Adds the register times the weight to the accumulator.
+    MultiplyAdd(usize, S),
+    /// Adds the aggregate of the register times weight pairs of the following `MultiplyAdd` instructions to the accumulator, they are skipped afterwards.
+    Aggregate(Aggregation, usize),
+    /// Applies the activation to the accumulator.
+    Activate(
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::network::serialization::activation")
+        )]
+        fn(S) -> S,
+    ),
+    /// Writes the accumulator to the register.
+    Store(usize),
+}
+
+/// Executes a program compiled from a [`MatrixFeedforwardEvaluator`].
+///
+/// Registers start with the input, every node stores its value in a register of its own.
+/// The input is interpreted row-wise like in [`MatrixFeedforwardEvaluator`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BytecodeEvaluator<S: Scalar = f32> {
+    pub program: Vec<Instruction<S>>,
+    pub input_count: usize,
+    pub register_count: usize,
+    /// Registers holding the outputs after execution.
+    pub output_ids: Vec<usize>,
+}
+
+impl<S: Scalar> BytecodeEvaluator<S> {
+    /// Lowers every stage to instructions, closure activations are not supported.
+    pub fn compile(evaluator: &MatrixFeedforwardEvaluator<S>) -> Result<Self, &'static str> {
+        let input_count = evaluator.input_size();
+        let mut program = Vec::new();
+        // registers holding the rows of the current stage
+        let mut registers: Vec<usize> = (0..input_count).collect();
+        let mut register_count = input_count;
+
+        for (index, stage) in evaluator.stages.iter().enumerate() {
+            let mut next_registers = Vec::with_capacity(stage.ncols());
+            for column in 0..stage.ncols() {
+                // carried values stay in the register they are already in
+                if evaluator.is_carry(index, column) {
+                    next_registers.push(registers[stage.column(column).iamax()]);
+                    continue;
+                }
+
+                program.push(Instruction::Load(evaluator.biases[index][column]));
+                match evaluator.aggregations[index]
+                    .iter()
+                    .find(|(aggregated, _, _)| *aggregated == column)
+                {
+                    Some((_, aggregation, weighted_inputs)) => {
+                        program.push(Instruction::Aggregate(*aggregation, weighted_inputs.len()));
+                        program.extend(weighted_inputs.iter().map(|&(row, weight)| {
+                            Instruction::MultiplyAdd(registers[row], weight)
+                        }));
+                    }
+                    None => program.extend(
+                        stage
+                            .column(column)
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, weight)| !weight.is_zero())
+                            .map(|(row, &weight)| Instruction::MultiplyAdd(registers[row], weight)),
+                    ),
+                }
+
+                let activation = &evaluator.transformations[index][column];
+                if activation.name() != Some("linear") {
+                    program.push(Instruction::Activate(
+                        activation
+                            .function()
+                            .ok_or("closure activations can not be compiled")?,
+                    ));
+                }
+                program.push(Instruction::Store(register_count));
+                next_registers.push(register_count);
+                register_count += 1;
+            }
+            registers = next_registers;
+        }
+
+        Ok(Self {
+            program,
+            input_count,
+            register_count,
+            output_ids: registers,
+        })
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.input_count
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.output_ids.len()
+    }
+
+    /// Executes the program for a single sample without allocating.
+    ///
+    /// `registers` needs [`Self::register_count`] entries, the leading ones holding the input, outputs are found at [`Self::output_ids`] afterwards.
+    pub fn execute(&self, registers: &mut [S]) {
+        let mut accumulator = S::zero();
+        let mut counter = 0;
+        while let Some(instruction) = self.program.get(counter) {
+            match *instruction {
+                Instruction::Load(value) => accumulator = value,
+                Instruction::MultiplyAdd(register, weight) => {
+                    accumulator += registers[register] * weight
+                }
+                Instruction::Aggregate(aggregation, count) => {
+                    let weighted_inputs = &self.program[counter + 1..counter + 1 + count];
+                    accumulator +=
+                        aggregation.aggregate(weighted_inputs.iter().map(|instruction| {
+                            match *instruction {
+                                Instruction::MultiplyAdd(register, weight) => {
+                                    registers[register] * weight
+                                }
+                                _ => unreachable!(
+                                    "aggregations are followed by their weighted inputs"
+                                ),
+                            }
+                        }));
+                    counter += count;
+                }
+                Instruction::Activate(activation) => accumulator = activation(accumulator),
+                Instruction::Store(register) => registers[register] = accumulator,
+            }
+            counter += 1;
+        }
+    }
+}
+
+impl<S: Scalar> Evaluator<S> for BytecodeEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&self, input: T) -> T {
+        let input = NetworkIO::input(input);
+        let mut output = DMatrix::zeros(input.nrows(), self.output_ids.len());
+        // the registers are reused for every sample
+        let mut registers = vec![S::zero(); self.register_count];
+        for (sample, row) in input.row_iter().enumerate() {
+            for (register, &value) in registers.iter_mut().zip(row.iter()) {
+                *register = value;
+            }
+            self.execute(&mut registers);
+            for (column, &id) in self.output_ids.iter().enumerate() {
+                output[(sample, column)] = registers[id];
+            }
+        }
+        NetworkIO::output(output)
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
+
+impl<S: Scalar> EvaluateInto<S> for BytecodeEvaluator<S> {
+    fn scratch(&self) -> Scratch<S> {
+        Scratch::new(self.register_count)
+    }
+
+    fn evaluate_into(&self, input: &[S], output: &mut [S], scratch: &mut Scratch<S>) {
+        scratch.current[..self.input_count].copy_from_slice(input);
+        self.execute(&mut scratch.current);
+        assert_eq!(
+            output.len(),
+            self.output_size(),
+            "output dimension does not match"
+        );
+        for (value, &id) in output.iter_mut().zip(&self.output_ids) {
+            *value = scratch.current[id];
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{
+    matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+    network::{EdgeLike, FabricationError, Fabricator, NetworkLike, NodeId, NodeLike, Scalar},
+};
+
+use super::evaluator::BytecodeEvaluator;
+
+/// Fabricates a [`BytecodeEvaluator`] by compiling the result of the [`MatrixFeedforwardFabricator`].
+#[derive(Debug)]
+pub struct BytecodeFabricator;
+
+impl<N, E, S, I> Fabricator<N, E, S, I> for BytecodeFabricator
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    type Output = BytecodeEvaluator<S>;
+
+    fn fabricate(net: &impl NetworkLike<N, E, S, I>) -> Result<Self::Output, FabricationError> {
+        Ok(BytecodeEvaluator::compile(
+            &MatrixFeedforwardFabricator::fabricate(net)?,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::BytecodeFabricator;
+    use crate::{
+        bytecode::evaluator::Instruction,
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Net, Node},
+            Aggregation, EvaluateInto, Evaluator, Fabricator,
+        },
+    };
+
+    fn some_net() -> Net {
+        Net::new(
+            2,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::SIGMOID).with_bias(0.5),
+                Node::new(3, activations::TANH).with_aggregation(Aggregation::Max),
+                Node::new(4, activations::LINEAR),
+            ],
+            edges!(
+                0--0.5->2,
+                1--0.5->2,
+                2--1.0->3,
+                0--0.25->3,
+                0--2.0->4,
+                2--1.0->4
+            ),
+        )
+    }
+
+    #[test]
+    fn bytecode_matches_matrix() {
+        let evaluator = BytecodeFabricator::fabricate(&some_net()).unwrap();
+        let matrix = MatrixFeedforwardFabricator::fabricate(&some_net()).unwrap();
+
+        // the carry of node 0 into the second stage is not compiled
+        assert_eq!(
+            evaluator
+                .program
+                .iter()
+                .filter(|instruction| matches!(instruction, Instruction::Store(_)))
+                .count(),
+            3
+        );
+
+        let input = dmatrix![1.0, 2.0; -1.0, 0.5; 0.0, -3.0];
+        let difference = evaluator.evaluate(input.clone()) - matrix.evaluate(input);
+        assert!(difference.amax() < 1e-6);
+
+        let mut scratch = evaluator.scratch();
+        let mut output = [0.0; 2];
+        evaluator.evaluate_into(&[0.0, -3.0], &mut output, &mut scratch);
+        assert_eq!(
+            dmatrix![output[0], output[1]],
+            evaluator.evaluate(dmatrix![0.0, -3.0])
+        );
+    }
+
+    #[test]
+    fn reject_closures() {
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_custom_activation(|val: f32| val * 2.0),
+            ],
+            edges!(0--1.0->1),
+        );
+        assert!(BytecodeFabricator::fabricate(&some_net).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn bytecode_serde() {
+        let evaluator = BytecodeFabricator::fabricate(&some_net()).unwrap();
+
+        let serialized = serde_json::to_string(&evaluator).unwrap();
+        let deserialized: crate::bytecode::evaluator::BytecodeEvaluator =
+            serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(
+            deserialized.evaluate(dmatrix![1.0, 2.0]),
+            evaluator.evaluate(dmatrix![1.0, 2.0])
+        );
+    }
+}
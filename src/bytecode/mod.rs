@@ -0,0 +1,10 @@
+//! Evaluates [`crate::network::NetworkLike`] structures by executing a compact bytecode on a small virtual machine.
+//!
+//! The stages of a [`crate::matrix::feedforward::evaluator::MatrixFeedforwardEvaluator`] are lowered to one short program per node,
+//! which loads the bias, multiplies and adds the weighted inputs, activates and stores the result in a register.
+//! Carries do not produce any instructions and zero weights are skipped, so programs are a middle ground between the interpreter and the `jit` backend.
+//! Programs can be (de)serialized with the feature `serde` to deploy them without fabricating the net again.
+
+pub mod evaluator;
+#[cfg(feature = "std")]
+pub mod fabricator;
@@ -0,0 +1,235 @@
+//! Helpers to use fabricated networks as CPPNs (compositional pattern producing networks), e.g. for HyperNEAT.
+
+use alloc::vec::Vec;
+
+use nalgebra::DMatrix;
+
+use crate::network::{
+    net::{activations, Edge, Net, Node},
+    Evaluator, Scalar,
+};
+
+/// Queries a CPPN for the weights between every pair of source and target coordinates of a substrate.
+///
+/// Every pair is presented to the CPPN as the source coordinates followed by the target coordinates.
+/// All pairs are evaluated in one batch, so the CPPN needs to evaluate every input row as separate sample,
+/// like [`crate::matrix::feedforward::evaluator::MatrixFeedforwardEvaluator`] does.
+#[derive(Debug, Clone)]
+pub struct SubstrateQuery<S: Scalar = f32> {
+    sources: Vec<Vec<S>>,
+    targets: Vec<Vec<S>>,
+}
+
+impl<S: Scalar> SubstrateQuery<S> {
+    /// Fails if the coordinates of the sources or of the targets differ in dimension.
+    pub fn new(sources: Vec<Vec<S>>, targets: Vec<Vec<S>>) -> Result<Self, &'static str> {
+        for coordinates in [&sources, &targets] {
+            if let Some(first) = coordinates.first() {
+                if coordinates.iter().any(|point| point.len() != first.len()) {
+                    return Err("coordinates differ in dimension");
+                }
+            }
+        }
+        Ok(Self { sources, targets })
+    }
+
+    pub fn sources(&self) -> &[Vec<S>] {
+        &self.sources
+    }
+
+    pub fn targets(&self) -> &[Vec<S>] {
+        &self.targets
+    }
+
+    /// Returns the batch of CPPN inputs, one row per pair with the targets varying fastest.
+    pub fn inputs(&self) -> DMatrix<S> {
+        let width =
+            self.sources.first().map_or(0, Vec::len) + self.targets.first().map_or(0, Vec::len);
+
+        DMatrix::from_row_iterator(
+            self.sources.len() * self.targets.len(),
+            width,
+            self.sources.iter().flat_map(|source| {
+                self.targets
+                    .iter()
+                    .flat_map(move |target| source.iter().chain(target.iter()).copied())
+            }),
+        )
+    }
+
+    /// Returns the weight matrix of the first CPPN output, rows are sources and columns are targets.
+    pub fn query(&self, cppn: &impl Evaluator<S>) -> DMatrix<S> {
+        self.query_output(cppn, 0)
+    }
+
+    /// Like [`SubstrateQuery::query`] for any CPPN output, e.g. when a second output encodes a bias or expression.
+    pub fn query_output(&self, cppn: &impl Evaluator<S>, output: usize) -> DMatrix<S> {
+        let outputs: DMatrix<S> = cppn.evaluate(self.inputs());
+
+        DMatrix::from_fn(self.sources.len(), self.targets.len(), |source, target| {
+            outputs[(source * self.targets.len() + target, output)]
+        })
+    }
+}
+
+/// Builds a [`Net`] on a layered substrate whose weights are queried from a CPPN, completing the HyperNEAT workflow.
+///
+/// Every layer is fully connected to the next one, the first layer holds the inputs and the last one the outputs.
+/// Edges whose queried weight does not exceed the threshold in magnitude are pruned.
+/// The resulting [`Net`] can be fabricated by any [`crate::network::Fabricator`].
+#[derive(Debug, Clone)]
+pub struct SubstrateFabricator<S: Scalar = f32> {
+    layers: Vec<Vec<Vec<S>>>,
+    threshold: S,
+    hidden_activation: fn(S) -> S,
+    output_activation: fn(S) -> S,
+}
+
+impl<S: Scalar> SubstrateFabricator<S> {
+    /// Takes the coordinates of every node per layer, defaults to a threshold of `0.2` and sigmoid activations.
+    pub fn new(layers: Vec<Vec<Vec<S>>>) -> Result<Self, &'static str> {
+        if layers.len() < 2 {
+            return Err("substrate needs at least an input and an output layer");
+        }
+        Ok(Self {
+            layers,
+            threshold: nalgebra::convert(0.2),
+            hidden_activation: activations::sigmoid,
+            output_activation: activations::sigmoid,
+        })
+    }
+
+    pub fn with_threshold(mut self, threshold: S) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_activations(mut self, hidden: fn(S) -> S, output: fn(S) -> S) -> Self {
+        self.hidden_activation = hidden;
+        self.output_activation = output;
+        self
+    }
+
+    /// Queries the CPPN for every pair of consecutive layers, see [`SubstrateQuery`].
+    pub fn fabricate(&self, cppn: &impl Evaluator<S>) -> Result<Net<S>, &'static str> {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut first_ids = Vec::with_capacity(self.layers.len());
+
+        for (index, layer) in self.layers.iter().enumerate() {
+            let activation = if index == 0 {
+                activations::linear
+            } else if index == self.layers.len() - 1 {
+                self.output_activation
+            } else {
+                self.hidden_activation
+            };
+            first_ids.push(nodes.len());
+            for _ in layer {
+                nodes.push(Node::new(nodes.len(), activation));
+            }
+        }
+
+        for (index, pair) in self.layers.windows(2).enumerate() {
+            let weights = SubstrateQuery::new(pair[0].clone(), pair[1].clone())?.query(cppn);
+
+            for ((source, target), &weight) in (0..weights.ncols())
+                .flat_map(|target| (0..weights.nrows()).map(move |source| (source, target)))
+                .zip(weights.iter())
+            {
+                if weight.abs() > self.threshold {
+                    edges.push(Edge::new(
+                        first_ids[index] + source,
+                        first_ids[index + 1] + target,
+                        weight,
+                    ));
+                }
+            }
+        }
+
+        Ok(Net::new(
+            self.layers[0].len(),
+            self.layers[self.layers.len() - 1].len(),
+            nodes,
+            edges,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{SubstrateFabricator, SubstrateQuery};
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Net},
+            Evaluator, Fabricator, NetworkLike,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn query_all_pairs() {
+        // weight = x1 + 2 * x2, second output = x2
+        let cppn = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--1.0->2, 1--2.0->2, 1--1.0->3),
+        );
+        let cppn = MatrixFeedforwardFabricator::fabricate(&cppn).unwrap();
+
+        let query = SubstrateQuery::new(
+            vec![vec![-1.0], vec![0.0], vec![1.0]],
+            vec![vec![0.5], vec![1.0]],
+        )
+        .unwrap();
+
+        assert_eq!(
+            query.query(&cppn),
+            dmatrix![
+                0.0, 1.0;
+                1.0, 2.0;
+                2.0, 3.0
+            ]
+        );
+        assert_eq!(
+            query.query_output(&cppn, 1),
+            dmatrix![
+                0.5, 1.0;
+                0.5, 1.0;
+                0.5, 1.0
+            ]
+        );
+        assert!(SubstrateQuery::new(vec![vec![0.0], vec![0.0, 1.0]], vec![]).is_err());
+    }
+
+    #[test]
+    fn substrate_with_pruning() {
+        // weight = x1 + 2 * x2
+        let cppn = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--2.0->2));
+        let cppn = MatrixFeedforwardFabricator::fabricate(&cppn).unwrap();
+
+        let substrate = SubstrateFabricator::new(vec![
+            vec![vec![-1.0], vec![1.0]],
+            vec![vec![0.5]],
+            vec![vec![0.0], vec![0.25]],
+        ])
+        .unwrap()
+        .with_threshold(0.25)
+        .with_activations(activations::LINEAR, activations::LINEAR)
+        .fabricate(&cppn)
+        .unwrap();
+
+        // only -1 -> 0.5 queries a weight of 0.0 and is pruned
+        assert_eq!(substrate.nodes().len(), 5);
+        assert_eq!(substrate.edges().len(), 3);
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&substrate).unwrap();
+        // hidden = 2.0 * 2.0, outputs = 4.0 * 0.5 and 4.0 * 1.0
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 2.0]), dmatrix![2.0, 4.0]);
+    }
+}
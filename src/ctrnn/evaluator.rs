@@ -0,0 +1,157 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::DMatrix;
+
+use crate::network::{
+    Aggregation, NetworkIO, Scalar, Scratch, StatefulEvaluateInto, StatefulEvaluator,
+};
+
+#[derive(Debug)]
+pub struct IntegratorNode<S: Scalar = f32> {
+    pub activation: fn(S) -> S,
+    pub bias: S,
+    pub response: S,
+    pub aggregation: Aggregation,
+    pub time_constant: S,
+    pub inputs: Vec<(usize, S)>,
+}
+
+#[derive(Debug)]
+pub struct CtrnnEvaluator<S: Scalar = f32> {
+    pub input_ids: Vec<usize>,
+    pub output_ids: Vec<usize>,
+    pub nodes: Vec<IntegratorNode<S>>,
+    pub states: Vec<S>,
+    /// Ids of the nodes as given to the fabricator, default to their indices.
+    pub node_ids: Vec<usize>,
+    /// Integration step, defaults to `0.1`.
+    pub dt: S,
+    // activated states, reused between steps
+    outputs: Vec<S>,
+}
+
+impl<S: Scalar> CtrnnEvaluator<S> {
+    /// Starts from all states at zero, `inputs` of every node are pairs of source index and weight.
+    pub fn new(
+        input_ids: Vec<usize>,
+        output_ids: Vec<usize>,
+        nodes: Vec<IntegratorNode<S>>,
+    ) -> Self {
+        Self {
+            input_ids,
+            output_ids,
+            states: vec![S::zero(); nodes.len()],
+            outputs: vec![S::zero(); nodes.len()],
+            node_ids: (0..nodes.len()).collect(),
+            nodes,
+            dt: nalgebra::convert(0.1),
+        }
+    }
+
+    pub fn with_dt(mut self, dt: S) -> Self {
+        self.dt = dt;
+        self
+    }
+
+    pub fn with_node_ids(mut self, node_ids: Vec<usize>) -> Self {
+        self.node_ids = node_ids;
+        self
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.input_ids.len()
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.output_ids.len()
+    }
+
+    /// Advances the dynamics by one Euler step.
+    fn step(&mut self, input: &[S]) {
+        for (id, node) in self.nodes.iter().enumerate() {
+            self.outputs[id] = (node.activation)(self.states[id]);
+        }
+        for (&id, &value) in self.input_ids.iter().zip(input.iter()) {
+            self.states[id] = value;
+            self.outputs[id] = value;
+        }
+
+        // all nodes integrate the outputs of the previous step simultaneously
+        for (id, node) in self.nodes.iter().enumerate() {
+            if self.input_ids.contains(&id) {
+                continue;
+            }
+            let total = node.bias
+                + node.response
+                    * node.aggregation.aggregate(
+                        node.inputs
+                            .iter()
+                            .map(|&(source, weight)| self.outputs[source] * weight),
+                    );
+            let state = self.states[id];
+            self.states[id] = state + self.dt / node.time_constant * (total - state);
+        }
+    }
+}
+
+impl<S: Scalar> StatefulEvaluator<S> for CtrnnEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T {
+        let input = NetworkIO::input(input);
+        self.step(input.as_slice());
+
+        NetworkIO::output(DMatrix::from_iterator(
+            1,
+            self.output_ids.len(),
+            self.output_ids
+                .iter()
+                .map(|&id| (self.nodes[id].activation)(self.states[id])),
+        ))
+    }
+
+    fn reset_internal_state(&mut self) {
+        for value in self.states.iter_mut().chain(self.outputs.iter_mut()) {
+            *value = S::zero();
+        }
+    }
+
+    fn reset_state_for(&mut self, node_ids: &[usize]) {
+        for (index, id) in self.node_ids.iter().enumerate() {
+            if node_ids.contains(id) {
+                self.states[index] = S::zero();
+                self.outputs[index] = S::zero();
+            }
+        }
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
+
+impl<S: Scalar> StatefulEvaluateInto<S> for CtrnnEvaluator<S> {
+    /// The states are kept by the evaluator itself, so no buffers are needed.
+    fn scratch(&self) -> Scratch<S> {
+        Scratch::new(0)
+    }
+
+    fn evaluate_into(&mut self, input: &[S], output: &mut [S], _scratch: &mut Scratch<S>) {
+        assert_eq!(
+            input.len(),
+            self.input_size(),
+            "input dimension does not match"
+        );
+        self.step(input);
+        assert_eq!(
+            output.len(),
+            self.output_size(),
+            "output dimension does not match"
+        );
+        for (value, &id) in output.iter_mut().zip(&self.output_ids) {
+            *value = (self.nodes[id].activation)(self.states[id]);
+        }
+    }
+}
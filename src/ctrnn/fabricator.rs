@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use crate::network::{
+    prune::active_edges, remap::fabricable_recurrent, EdgeLike, FabricationError, NetworkLike,
+    NodeId, NodeLike, Recurrent, Scalar, StatefulFabricator,
+};
+
+use super::evaluator::{CtrnnEvaluator, IntegratorNode};
+
+/// Fabricates a [`CtrnnEvaluator`], its integration step can be changed with [`CtrnnEvaluator::with_dt`].
+#[derive(Debug)]
+pub struct CtrnnFabricator;
+
+impl<N, E, S, I> StatefulFabricator<N, E, S, I> for CtrnnFabricator
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    type Output = CtrnnEvaluator<S>;
+
+    fn fabricate(net: &impl Recurrent<N, E, S, I>) -> Result<Self::Output, FabricationError> {
+        let net = &fabricable_recurrent(net);
+        let mut id_map: HashMap<usize, usize> = HashMap::new();
+        let mut nodes = Vec::new();
+
+        for node in net.nodes_iter() {
+            if node.time_constant() <= S::zero() {
+                return Err("time constants need to be positive".into());
+            }
+            id_map.insert(node.id(), nodes.len());
+            nodes.push(IntegratorNode {
+                activation: node.activation(),
+                bias: node.bias(),
+                response: node.response(),
+                aggregation: node.aggregation(),
+                time_constant: node.time_constant(),
+                inputs: Vec::new(),
+            });
+        }
+
+        let net_nodes = net.nodes();
+        for edge in active_edges(&net_nodes, net.edges())
+            .into_iter()
+            .chain(active_edges(&net_nodes, net.recurrent_edges()))
+        {
+            let start = *id_map.get(&edge.start()).ok_or("edge from unknown node")?;
+            let end = *id_map.get(&edge.end()).ok_or("edge to unknown node")?;
+            nodes[end].inputs.push((start, edge.weight()));
+        }
+
+        let input_ids = net.inputs_iter().map(|node| id_map[&node.id()]).collect();
+        let output_ids = net
+            .outputs()
+            .iter()
+            .map(|node| id_map[&node.id()])
+            .collect();
+
+        let node_ids = net.nodes_iter().map(|node| node.id()).collect();
+
+        Ok(CtrnnEvaluator::new(input_ids, output_ids, nodes).with_node_ids(node_ids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::CtrnnFabricator;
+    use crate::{
+        edges,
+        network::{
+            net::{activations, Net, Node},
+            StatefulEvaluator, StatefulFabricator,
+        },
+    };
+
+    #[test]
+    fn leaky_integration() {
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_time_constant(2.0),
+            ],
+            edges!(0--1.0->1),
+        );
+
+        let mut evaluator = CtrnnFabricator::fabricate(&some_net).unwrap().with_dt(1.0);
+
+        // the state moves half way towards the input with every step
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![0.5]);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![0.75]);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![0.875]);
+
+        evaluator.reset_internal_state();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![0.5]);
+    }
+
+    #[test]
+    fn self_recurrent_decay() {
+        let mut some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_bias(1.0),
+            ],
+            edges!(0--1.0->1),
+        );
+        some_net.set_recurrent_edges(edges!(1--0.5->1));
+
+        let mut evaluator = CtrnnFabricator::fabricate(&some_net).unwrap().with_dt(0.5);
+
+        // y += 0.5 * (-y + 1 + 0.5 * y)
+        assert_eq!(evaluator.evaluate(dmatrix![0.0]), dmatrix![0.5]);
+        assert_eq!(evaluator.evaluate(dmatrix![0.0]), dmatrix![0.875]);
+
+        // converges to the fixed point y = 1 + 0.5 * y
+        let mut output = dmatrix![0.0];
+        for _ in 0..100 {
+            output = evaluator.evaluate(dmatrix![0.0]);
+        }
+        assert!((output[0] - 2.0).abs() < 1e-4);
+
+        assert!(CtrnnFabricator::fabricate(&Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_time_constant(0.0),
+            ],
+            edges!(0--1.0->1),
+        ))
+        .is_err());
+    }
+}
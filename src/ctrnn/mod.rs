@@ -0,0 +1,10 @@
+//! Evaluates [`crate::network::Recurrent`] [`crate::network::NetworkLike`] structures as continuous-time recurrent neural networks (CTRNN).
+//!
+//! Every non-input node is a leaky integrator with state `y` following `tau * dy/dt = -y + bias + aggregated weighted inputs`,
+//! where `tau` is [`crate::network::NodeLike::time_constant`] and inputs are the activated states of their source nodes.
+//! Input nodes take the input values directly, recurrent and regular edges are treated alike.
+//! Every call to [`crate::network::StatefulEvaluator::evaluate`] advances the dynamics by one Euler step of size `dt`.
+
+pub mod evaluator;
+#[cfg(feature = "std")]
+pub mod fabricator;
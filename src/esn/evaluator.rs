@@ -0,0 +1,127 @@
+use nalgebra::DMatrix;
+
+use crate::{
+    matrix::recurrent::evaluator::MatrixRecurrentEvaluator,
+    network::{NetworkIO, Scalar, StatefulEvaluator},
+};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EsnEvaluator<S: Scalar = f32> {
+    /// The fixed reservoir, its outputs are the states of all reservoir nodes.
+    pub reservoir: MatrixRecurrentEvaluator<S>,
+    /// Readout weights with one row per reservoir node and a last row of biases, one column per output.
+    pub readout: DMatrix<S>,
+}
+
+impl<S: Scalar> EsnEvaluator<S> {
+    /// Starts with a readout of all zeros.
+    pub fn new(reservoir: MatrixRecurrentEvaluator<S>, outputs: usize) -> Self {
+        let readout = DMatrix::zeros(reservoir.output_size() + 1, outputs);
+        Self { reservoir, readout }
+    }
+
+    /// Replaces the readout, e.g. by evolved weights, its shape needs to match [`Self::readout`].
+    pub fn with_readout(mut self, readout: DMatrix<S>) -> Result<Self, &'static str> {
+        if readout.shape() != self.readout.shape() {
+            return Err("readout shape does not match reservoir and outputs");
+        }
+        self.readout = readout;
+        Ok(self)
+    }
+
+    pub fn reservoir_size(&self) -> usize {
+        self.reservoir.output_size()
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.reservoir.input_size()
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.readout.ncols()
+    }
+
+    /// Drives the reservoir by every row of `inputs` and returns its states, one row per input row, continuing from the current state.
+    pub fn states(&mut self, inputs: &DMatrix<S>) -> DMatrix<S> {
+        let mut states = DMatrix::zeros(inputs.nrows(), self.reservoir_size());
+        for (row, input) in inputs.row_iter().enumerate() {
+            let state: DMatrix<S> = self.reservoir.evaluate(DMatrix::from_iterator(
+                1,
+                input.len(),
+                input.iter().copied(),
+            ));
+            states.set_row(row, &state.row(0));
+        }
+        states
+    }
+
+    /// Fits the readout to `targets` by ridge regression over the states reached from a reset reservoir driven by `inputs`.
+    ///
+    /// The first `washout` states are skipped, as they still depend on the initial state, `ridge` penalizes large readout weights.
+    /// The reservoir is reset again afterwards.
+    pub fn fit_readout(
+        &mut self,
+        inputs: &DMatrix<S>,
+        targets: &DMatrix<S>,
+        washout: usize,
+        ridge: S,
+    ) -> Result<(), &'static str> {
+        if inputs.ncols() != self.input_size() || targets.ncols() != self.output_size() {
+            return Err("inputs or targets do not match the evaluator");
+        }
+        if inputs.nrows() != targets.nrows() {
+            return Err("inputs and targets need the same number of rows");
+        }
+        if washout >= inputs.nrows() {
+            return Err("washout leaves no states to fit");
+        }
+
+        self.reservoir.reset_internal_state();
+        let states = self.states(inputs);
+        self.reservoir.reset_internal_state();
+
+        let samples = inputs.nrows() - washout;
+        let size = self.reservoir_size();
+        // the column of ones fits the biases
+        let design = DMatrix::from_fn(samples, size + 1, |row, column| {
+            if column < size {
+                states[(washout + row, column)]
+            } else {
+                S::one()
+            }
+        });
+        let targets = targets.rows(washout, samples);
+
+        let gram = design.transpose() * &design + DMatrix::identity(size + 1, size + 1) * ridge;
+        self.readout = gram
+            .cholesky()
+            .ok_or("readout regression is singular, increase the ridge")?
+            .solve(&(design.transpose() * targets));
+        Ok(())
+    }
+}
+
+impl<S: Scalar> StatefulEvaluator<S> for EsnEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T {
+        let state: DMatrix<S> = self.reservoir.evaluate(NetworkIO::input(input));
+        let size = self.reservoir_size();
+        NetworkIO::output(state * self.readout.rows(0, size) + self.readout.rows(size, 1))
+    }
+
+    fn reset_internal_state(&mut self) {
+        self.reservoir.reset_internal_state();
+    }
+
+    fn reset_state_for(&mut self, node_ids: &[usize]) {
+        self.reservoir.reset_state_for(node_ids);
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
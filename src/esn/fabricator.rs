@@ -0,0 +1,226 @@
+use nalgebra::{linalg::Schur, DMatrix};
+
+use crate::{
+    matrix::recurrent::fabricator::MatrixRecurrentFabricator,
+    network::{
+        net::{activations, Edge, Net, Node},
+        Scalar, StatefulFabricator,
+    },
+    rng::SplitMix,
+};
+
+use super::evaluator::EsnEvaluator;
+
+/// Fabricates an [`EsnEvaluator`] with a random reservoir, equal settings and seeds give equal reservoirs.
+///
+/// Inputs have the ids `0..inputs` and are connected to every reservoir node with weights uniform in `[-input_scaling, input_scaling)`.
+/// Reservoir nodes have the ids `inputs..inputs + reservoir` and are connected by recurrent edges with probability `density`.
+/// The recurrent weights are scaled such that their largest absolute eigenvalue is the spectral radius, below one the reservoir forgets its initial state.
+#[derive(Debug, Clone)]
+pub struct EsnFabricator {
+    pub inputs: usize,
+    pub reservoir: usize,
+    pub outputs: usize,
+    /// Defaults to `0.1`.
+    pub density: f64,
+    /// Defaults to `0.9`.
+    pub spectral_radius: f64,
+    /// Defaults to `1.0`.
+    pub input_scaling: f64,
+    /// Defaults to `0`.
+    pub seed: u64,
+}
+
+impl EsnFabricator {
+    pub fn new(inputs: usize, reservoir: usize, outputs: usize) -> Self {
+        Self {
+            inputs,
+            reservoir,
+            outputs,
+            density: 0.1,
+            spectral_radius: 0.9,
+            input_scaling: 1.0,
+            seed: 0,
+        }
+    }
+
+    pub fn with_density(mut self, density: f64) -> Self {
+        self.density = density;
+        self
+    }
+
+    pub fn with_spectral_radius(mut self, spectral_radius: f64) -> Self {
+        self.spectral_radius = spectral_radius;
+        self
+    }
+
+    pub fn with_input_scaling(mut self, input_scaling: f64) -> Self {
+        self.input_scaling = input_scaling;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Builds the reservoir as [`Net`] whose outputs are the reservoir nodes, e.g. to inspect or export it.
+    pub fn reservoir_net<S: Scalar>(&self) -> Result<Net<S>, &'static str> {
+        if self.inputs == 0 || self.reservoir == 0 {
+            return Err("reservoir needs at least one input and one node");
+        }
+
+        let mut rng = SplitMix(self.seed);
+        let scale = |value: f32, factor: f64| nalgebra::convert::<f64, S>(value as f64 * factor);
+
+        let nodes = (0..self.inputs)
+            .map(|id| Node::new(id, activations::linear))
+            .chain((0..self.reservoir).map(|index| {
+                // the response undoes the steepness of the NEAT tanh
+                Node::new(self.inputs + index, activations::tanh)
+                    .with_response(nalgebra::convert(1.0 / 4.9))
+            }))
+            .collect();
+        let mut edges = Vec::new();
+        for start in 0..self.inputs {
+            for end in 0..self.reservoir {
+                edges.push(Edge::new(
+                    start,
+                    self.inputs + end,
+                    scale(rng.weight(), self.input_scaling),
+                ));
+            }
+        }
+
+        let mut weights = DMatrix::zeros(self.reservoir, self.reservoir);
+        for start in 0..self.reservoir {
+            for end in 0..self.reservoir {
+                if (rng.unit() as f64) < self.density {
+                    weights[(end, start)] = scale(rng.weight(), 1.0);
+                }
+            }
+        }
+        let radius = spectral_radius(&weights)?;
+        if !radius.is_zero() {
+            weights *= nalgebra::convert::<f64, S>(self.spectral_radius) / radius;
+        }
+
+        let mut net = Net::new(self.inputs, self.reservoir, nodes, edges);
+        net.set_recurrent_edges(
+            weights
+                .column_iter()
+                .enumerate()
+                .flat_map(|(start, column)| {
+                    column
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, weight)| !weight.is_zero())
+                        .map(|(end, &weight)| {
+                            Edge::new(self.inputs + start, self.inputs + end, weight)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+        );
+        Ok(net)
+    }
+
+    /// Fabricates the reservoir of [`Self::reservoir_net`] with a readout of all zeros.
+    pub fn fabricate<S: Scalar>(&self) -> Result<EsnEvaluator<S>, &'static str> {
+        let reservoir = MatrixRecurrentFabricator::fabricate(&self.reservoir_net::<S>()?)?;
+        Ok(EsnEvaluator::new(reservoir, self.outputs))
+    }
+}
+
+/// Largest absolute eigenvalue of a square matrix.
+fn spectral_radius<S: Scalar>(matrix: &DMatrix<S>) -> Result<S, &'static str> {
+    let schur = Schur::try_new(matrix.clone(), S::default_epsilon(), 10_000)
+        .ok_or("spectral radius of the reservoir did not converge")?;
+    Ok(schur
+        .complex_eigenvalues()
+        .iter()
+        .map(|eigenvalue| (eigenvalue.re * eigenvalue.re + eigenvalue.im * eigenvalue.im).sqrt())
+        .fold(S::zero(), |largest, modulus| largest.max(modulus)))
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{dmatrix, DMatrix};
+
+    use super::{spectral_radius, EsnFabricator};
+    use crate::{
+        network::{EdgeLike, NetworkLike, Recurrent, StatefulEvaluator},
+        rng::SplitMix,
+    };
+
+    #[test]
+    fn scale_reservoir() {
+        // eigenvalues 2 and -3
+        assert!((spectral_radius(&dmatrix![0.0, 2.0; 3.0, -1.0]).unwrap() - 3.0f64).abs() < 1e-9);
+        // rotation by 90 degrees, eigenvalues ±2i
+        assert!((spectral_radius(&dmatrix![0.0, -2.0; 2.0, 0.0]).unwrap() - 2.0f64).abs() < 1e-9);
+
+        let fabricator = EsnFabricator::new(2, 30, 1).with_density(0.2).with_seed(7);
+        let net = fabricator.reservoir_net::<f64>().unwrap();
+        assert_eq!(net.edges().len(), 60);
+        assert_eq!(net.outputs().len(), 30);
+
+        let mut weights = DMatrix::zeros(30, 30);
+        for edge in net.recurrent_edges() {
+            weights[(edge.end() - 2, edge.start() - 2)] = edge.weight();
+        }
+        assert!((spectral_radius(&weights).unwrap() - 0.9).abs() < 1e-6);
+
+        assert_eq!(
+            fabricator
+                .reservoir_net::<f64>()
+                .unwrap()
+                .recurrent_edges()
+                .len(),
+            net.recurrent_edges().len()
+        );
+    }
+
+    #[test]
+    fn learn_delay_line() {
+        let mut rng = SplitMix(1);
+        let inputs = DMatrix::from_fn(400, 1, |_, _| rng.weight() as f64);
+        // the target is the input of the previous step
+        let targets = DMatrix::from_fn(
+            400,
+            1,
+            |row, _| {
+                if row == 0 {
+                    0.0
+                } else {
+                    inputs[(row - 1, 0)]
+                }
+            },
+        );
+
+        let mut evaluator = EsnFabricator::new(1, 40, 1)
+            .with_density(0.2)
+            .with_input_scaling(0.5)
+            .fabricate::<f64>()
+            .unwrap();
+        evaluator
+            .fit_readout(
+                &inputs.rows(0, 300).into_owned(),
+                &targets.rows(0, 300).into_owned(),
+                20,
+                1e-8,
+            )
+            .unwrap();
+
+        let mut error = 0.0;
+        for row in 0..400 {
+            let output: DMatrix<f64> = evaluator.evaluate(dmatrix![inputs[(row, 0)]]);
+            if row >= 300 {
+                error += (output[0] - targets[(row, 0)]).powi(2);
+            }
+        }
+        assert!(error / 100.0 < 1e-3);
+
+        assert!(evaluator.fit_readout(&inputs, &targets, 400, 1e-8).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+//! Echo state networks, i.e. reservoir computing on top of [`crate::matrix::recurrent::evaluator::MatrixRecurrentEvaluator`].
+//!
+//! A fixed random reservoir of `tanh` nodes is driven by the inputs, its state `x_t = tanh(W_in u_t + W x_{t-1})` is mapped to the outputs by a linear readout.
+//! Only the readout is adapted, either by evolving [`evaluator::EsnEvaluator::readout`] or by ridge regression with [`evaluator::EsnEvaluator::fit_readout`].
+
+pub mod evaluator;
+#[cfg(feature = "std")]
+pub mod fabricator;
@@ -0,0 +1,148 @@
+use std::fmt::Write;
+
+use crate::network::{
+    net::activations, Aggregation, EdgeLike, NetworkLike, NodeLike, Recurrent, Scalar,
+};
+
+/// Renders a [`NetworkLike`] structure as a Graphviz DOT digraph.
+///
+/// Nodes are labeled with their id and activation, inputs are drawn as boxes and outputs as double circles.
+/// Edges are labeled with their weight.
+pub fn to_dot<N, E, S>(net: &impl NetworkLike<N, E, S>) -> String
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut dot = String::new();
+    write_graph(&mut dot, net, &[]);
+    dot
+}
+
+/// Renders a [`Recurrent`] [`NetworkLike`] structure like [`to_dot`] and adds its recurrent edges as dashed edges.
+pub fn recurrent_to_dot<N, E, S>(net: &impl Recurrent<N, E, S>) -> String
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut dot = String::new();
+    write_graph(&mut dot, net, &net.recurrent_edges());
+    dot
+}
+
+fn write_graph<N, E, S>(dot: &mut String, net: &impl NetworkLike<N, E, S>, recurrent_edges: &[&E])
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    // writing to a String can not fail
+    let _ = writeln!(dot, "digraph {{");
+    let _ = writeln!(dot, "    rankdir=LR;");
+
+    for (nodes, shape, rank) in [
+        (net.inputs(), "box", Some("source")),
+        (net.hidden(), "circle", None),
+        (net.outputs(), "doublecircle", Some("sink")),
+    ] {
+        if let Some(rank) = rank {
+            let _ = writeln!(dot, "    subgraph {{");
+            let _ = writeln!(dot, "        rank={};", rank);
+            for node in &nodes {
+                let _ = writeln!(dot, "        {};", node.id());
+            }
+            let _ = writeln!(dot, "    }}");
+        }
+        for node in nodes {
+            let _ = writeln!(
+                dot,
+                "    {} [label=\"{}\", shape={}];",
+                node.id(),
+                node_label(node),
+                shape
+            );
+        }
+    }
+
+    for edge in net.edges() {
+        let _ = writeln!(
+            dot,
+            "    {} -> {} [label=\"{}\"];",
+            edge.start(),
+            edge.end(),
+            edge.weight()
+        );
+    }
+
+    for edge in recurrent_edges {
+        let _ = writeln!(
+            dot,
+            "    {} -> {} [label=\"{}\", style=dashed, constraint=false];",
+            edge.start(),
+            edge.end(),
+            edge.weight()
+        );
+    }
+
+    let _ = writeln!(dot, "}}");
+}
+
+fn node_label<N: NodeLike<S>, S: Scalar>(node: &N) -> String {
+    let mut label = format!(
+        "{}\\n{}",
+        node.id(),
+        activations::name(node.activation()).unwrap_or("custom")
+    );
+    if node.bias() != S::zero() {
+        let _ = write!(label, "\\nbias {}", node.bias());
+    }
+    if node.aggregation() != Aggregation::Sum {
+        let _ = write!(label, "\\n{:?}", node.aggregation());
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{recurrent_to_dot, to_dot};
+    use crate::{
+        edges,
+        network::net::{activations, Net, Node},
+        nodes,
+    };
+
+    #[test]
+    fn dot_contains_nodes_and_edges() {
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::SIGMOID).with_bias(0.5),
+                Node::new(2, activations::TANH),
+            ],
+            edges!(0--0.5->1, 1--2.0->2),
+        );
+
+        let dot = to_dot(&some_net);
+
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains("0 [label=\"0\\nlinear\", shape=box];"));
+        assert!(dot.contains("1 [label=\"1\\nsigmoid\\nbias 0.5\", shape=circle];"));
+        assert!(dot.contains("2 [label=\"2\\ntanh\", shape=doublecircle];"));
+        assert!(dot.contains("0 -> 1 [label=\"0.5\"];"));
+        assert!(dot.contains("1 -> 2 [label=\"2\"];"));
+        assert!(!dot.contains("dashed"));
+    }
+
+    #[test]
+    fn dot_draws_recurrent_edges_dashed() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--0.5->0));
+
+        let dot = recurrent_to_dot(&some_net);
+
+        assert!(dot.contains("1 -> 0 [label=\"0.5\", style=dashed, constraint=false];"));
+    }
+}
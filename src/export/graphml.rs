@@ -0,0 +1,308 @@
+//! Writes [`NetworkLike`] structures to GraphML and reads GraphML back into [`Net`], available with the feature `graphml`.
+//!
+//! Nodes carry their `role` (input, hidden or output), `activation` by name, `bias` and `aggregation`.
+//! Edges carry their `weight`, whether they are `recurrent` and their numeric `id` if they have one.
+//! Data is matched by the `attr.name` of its key, so files re-saved by tools like Gephi or yEd can be read back.
+
+use std::{collections::HashMap, fmt::Write};
+
+use crate::network::{
+    net::{activations, Edge, Net, Node},
+    Aggregation, EdgeLike, NetworkLike, NodeLike, Recurrent, Scalar,
+};
+
+const KEYS: &str = r#"  <key id="role" for="node" attr.name="role" attr.type="string"/>
+  <key id="activation" for="node" attr.name="activation" attr.type="string"/>
+  <key id="bias" for="node" attr.name="bias" attr.type="double"/>
+  <key id="aggregation" for="node" attr.name="aggregation" attr.type="string"/>
+  <key id="weight" for="edge" attr.name="weight" attr.type="double"/>
+  <key id="recurrent" for="edge" attr.name="recurrent" attr.type="boolean"/>
+"#;
+
+/// Writes a [`NetworkLike`] structure as GraphML document.
+///
+/// Activations not found in [`activations`] are written as `custom` and can not be read back.
+pub fn to_graphml<N, E, S>(net: &impl NetworkLike<N, E, S>) -> String
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    write_document(net, &[])
+}
+
+/// Writes a [`Recurrent`] [`NetworkLike`] structure like [`to_graphml`] including its recurrent edges.
+pub fn recurrent_to_graphml<N, E, S>(net: &impl Recurrent<N, E, S>) -> String
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    write_document(net, &net.recurrent_edges())
+}
+
+/// Reads a GraphML document, e.g. written by [`to_graphml`], into a [`Net`].
+///
+/// Node ids need to be numeric and every node needs a `role` and a known `activation`.
+pub fn from_graphml<S: Scalar>(graphml: &str) -> Result<Net<S>, &'static str> {
+    let document = roxmltree::Document::parse(graphml).map_err(|_| "invalid xml")?;
+    let root = document.root_element();
+
+    // maps key ids to the attribute names they stand for
+    let keys = root
+        .children()
+        .filter(|element| element.has_tag_name("key"))
+        .filter_map(|key| Some((key.attribute("id")?, key.attribute("attr.name")?)))
+        .collect::<HashMap<_, _>>();
+
+    let graph = root
+        .children()
+        .find(|element| element.has_tag_name("graph"))
+        .ok_or("no graph element")?;
+
+    let parse_scalar = |text: &str| {
+        text.parse::<f64>()
+            .map(nalgebra::convert::<f64, S>)
+            .map_err(|_| "invalid number")
+    };
+
+    let mut inputs = Vec::new();
+    let mut hidden = Vec::new();
+    let mut outputs = Vec::new();
+
+    for element in graph.children().filter(|child| child.has_tag_name("node")) {
+        let id = element
+            .attribute("id")
+            .ok_or("node without id")?
+            .parse::<usize>()
+            .map_err(|_| "node ids must be numeric")?;
+        let activation = data(element, &keys, "activation")
+            .and_then(activations::from_name)
+            .ok_or("node without known activation")?;
+
+        let mut node = Node::new(id, activation);
+        if let Some(bias) = data(element, &keys, "bias") {
+            node = node.with_bias(parse_scalar(bias)?);
+        }
+        if let Some(aggregation) = data(element, &keys, "aggregation") {
+            node = node.with_aggregation(parse_aggregation(aggregation)?);
+        }
+
+        match data(element, &keys, "role") {
+            Some("input") => inputs.push(node),
+            Some("hidden") => hidden.push(node),
+            Some("output") => outputs.push(node),
+            _ => return Err("node without valid role"),
+        }
+    }
+
+    let mut edges = Vec::new();
+    let mut recurrent_edges = Vec::new();
+
+    for element in graph.children().filter(|child| child.has_tag_name("edge")) {
+        let endpoint = |attribute: &str| {
+            element
+                .attribute(attribute)
+                .ok_or("edge without endpoint")?
+                .parse::<usize>()
+                .map_err(|_| "node ids must be numeric")
+        };
+        let weight = parse_scalar(data(element, &keys, "weight").ok_or("edge without weight")?)?;
+        // ids of other tools need not be numeric and are dropped then
+        let id = element.attribute("id").and_then(|id| id.parse().ok());
+        let edge = Edge::new(endpoint("source")?, endpoint("target")?, weight).with_id(id);
+
+        if data(element, &keys, "recurrent") == Some("true") {
+            recurrent_edges.push(edge);
+        } else {
+            edges.push(edge);
+        }
+    }
+
+    let inputs_count = inputs.len();
+    let outputs_count = outputs.len();
+    let nodes = inputs.into_iter().chain(hidden).chain(outputs).collect();
+
+    let mut net = Net::new(inputs_count, outputs_count, nodes, edges);
+    net.set_recurrent_edges(recurrent_edges);
+    Ok(net)
+}
+
+fn write_document<N, E, S>(net: &impl NetworkLike<N, E, S>, recurrent_edges: &[&E]) -> String
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut graphml = String::new();
+
+    // writing to a String can not fail
+    let _ = writeln!(graphml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        graphml,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    );
+    graphml.push_str(KEYS);
+    let _ = writeln!(graphml, r#"  <graph id="net" edgedefault="directed">"#);
+
+    for (nodes, role) in [
+        (net.inputs(), "input"),
+        (net.hidden(), "hidden"),
+        (net.outputs(), "output"),
+    ] {
+        for node in nodes {
+            let _ = writeln!(
+                graphml,
+                r#"    <node id="{}"><data key="role">{}</data><data key="activation">{}</data><data key="bias">{}</data><data key="aggregation">{}</data></node>"#,
+                node.id(),
+                role,
+                activations::name(node.activation()).unwrap_or("custom"),
+                node.bias(),
+                aggregation_name(node.aggregation())
+            );
+        }
+    }
+
+    for (edge, recurrent) in net
+        .edges()
+        .into_iter()
+        .map(|edge| (edge, false))
+        .chain(recurrent_edges.iter().map(|&edge| (edge, true)))
+    {
+        let id = edge
+            .id()
+            .map(|id| format!(r#" id="{}""#, id))
+            .unwrap_or_default();
+        let _ = writeln!(
+            graphml,
+            r#"    <edge{} source="{}" target="{}"><data key="weight">{}</data><data key="recurrent">{}</data></edge>"#,
+            id,
+            edge.start(),
+            edge.end(),
+            edge.weight(),
+            recurrent
+        );
+    }
+
+    let _ = writeln!(graphml, "  </graph>");
+    let _ = writeln!(graphml, "</graphml>");
+
+    graphml
+}
+
+/// Finds the text of the data element whose key stands for the attribute `name`.
+fn data<'a>(
+    element: roxmltree::Node<'a, '_>,
+    keys: &HashMap<&str, &str>,
+    name: &str,
+) -> Option<&'a str> {
+    element
+        .children()
+        .filter(|child| child.has_tag_name("data"))
+        .find(|child| {
+            child
+                .attribute("key")
+                .and_then(|key| keys.get(key))
+                .is_some_and(|attribute| *attribute == name)
+        })
+        .and_then(|child| child.text())
+        .map(str::trim)
+}
+
+fn aggregation_name(aggregation: Aggregation) -> &'static str {
+    match aggregation {
+        Aggregation::Sum => "sum",
+        Aggregation::Product => "product",
+        Aggregation::Max => "max",
+        Aggregation::Min => "min",
+        Aggregation::Mean => "mean",
+    }
+}
+
+fn parse_aggregation(name: &str) -> Result<Aggregation, &'static str> {
+    match name {
+        "sum" => Ok(Aggregation::Sum),
+        "product" => Ok(Aggregation::Product),
+        "max" => Ok(Aggregation::Max),
+        "min" => Ok(Aggregation::Min),
+        "mean" => Ok(Aggregation::Mean),
+        _ => Err("unknown aggregation"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{from_graphml, recurrent_to_graphml};
+    use crate::{
+        edges,
+        matrix::recurrent::fabricator::MatrixRecurrentFabricator,
+        network::{
+            net::{activations, Edge, Net, Node},
+            Aggregation, EdgeLike, NetworkLike, Recurrent, StatefulEvaluator, StatefulFabricator,
+        },
+    };
+
+    #[test]
+    fn graphml_round_trip() {
+        let mut some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::RELU).with_bias(0.5),
+                Node::new(2, activations::TANH).with_aggregation(Aggregation::Max),
+            ],
+            vec![
+                Edge::new(0, 1, 0.5).with_id(Some(4)),
+                Edge::new(1, 2, 2.0),
+                Edge::new(0, 2, 0.25),
+            ],
+        );
+        some_net.set_recurrent_edges(edges!(2--0.5->1));
+
+        let graphml = recurrent_to_graphml(&some_net);
+        assert!(graphml.contains(r#"<edge id="4" source="0" target="1">"#));
+        assert!(graphml.contains(r#"<data key="activation">relu</data>"#));
+        assert!(graphml.contains(r#"<data key="recurrent">true</data>"#));
+
+        let restored: Net = from_graphml(&graphml).unwrap();
+        assert_eq!(restored.inputs().len(), 1);
+        assert_eq!(restored.outputs().len(), 1);
+        assert_eq!(restored.edges().len(), 3);
+        assert_eq!(restored.edges()[0].id(), Some(4));
+        assert_eq!(restored.recurrent_edges().len(), 1);
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        let mut restored_evaluator = MatrixRecurrentFabricator::fabricate(&restored).unwrap();
+        for _ in 0..3 {
+            assert_eq!(
+                restored_evaluator.evaluate(dmatrix![1.0]),
+                evaluator.evaluate(dmatrix![1.0])
+            );
+        }
+    }
+
+    // keys renamed by other tools are resolved through their attribute names
+    #[test]
+    fn graphml_foreign_keys() {
+        let graphml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="d0" for="node" attr.name="role" attr.type="string"/>
+  <key id="d1" for="node" attr.name="activation" attr.type="string"/>
+  <key id="d2" for="edge" attr.name="weight" attr.type="double"/>
+  <graph edgedefault="directed">
+    <node id="1"><data key="d0">output</data><data key="d1">linear</data></node>
+    <node id="0"><data key="d0">input</data><data key="d1">linear</data></node>
+    <edge source="0" target="1"><data key="d2">1.5</data></edge>
+  </graph>
+</graphml>"#;
+
+        let net: Net = from_graphml(graphml).unwrap();
+
+        assert_eq!(net.inputs()[0], &Node::new(0, activations::LINEAR));
+        assert_eq!(net.edges().len(), 1);
+        assert!(from_graphml::<f32>("<graphml/>").is_err());
+    }
+}
@@ -0,0 +1,11 @@
+//! Renders [`crate::network::NetworkLike`] structures into formats understood by other tools.
+
+mod dot;
+#[cfg(feature = "graphml")]
+pub mod graphml;
+#[cfg(feature = "petgraph")]
+pub mod petgraph;
+#[cfg(feature = "svg")]
+pub mod svg;
+
+pub use self::dot::{recurrent_to_dot, to_dot};
@@ -0,0 +1,224 @@
+//! Converts between [`NetworkLike`] structures and `petgraph` graphs, available with the feature `petgraph`.
+//!
+//! Graph nodes are [`GraphNode`]s marking their [`Role`], graph edges carry only their weight.
+//! Gates, delays and the enabled flag of edges are not represented.
+
+use petgraph::graph::{Graph, NodeIndex};
+
+use crate::network::{
+    net::{Edge, Node},
+    prune::copy_node,
+    Aggregation, EdgeLike, NetworkLike, NodeKind, NodeLike, Scalar,
+};
+
+/// Marks the position of a [`GraphNode`] in the net.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Input,
+    Hidden,
+    Output,
+}
+
+/// Node weight of graphs used as [`NetworkLike`] via [`PetgraphNet`].
+///
+/// Edges refer to the id of [`Self::node`], not to the index in the graph.
+#[derive(Debug)]
+pub struct GraphNode<S: Scalar = f32> {
+    pub role: Role,
+    pub node: Node<S>,
+}
+
+impl<S: Scalar> GraphNode<S> {
+    pub fn new(role: Role, node: Node<S>) -> Self {
+        Self { role, node }
+    }
+}
+
+impl<S: Scalar> NodeLike<S> for GraphNode<S> {
+    fn id(&self) -> usize {
+        self.node.id()
+    }
+    fn activation(&self) -> fn(S) -> S {
+        self.node.activation()
+    }
+    fn bias(&self) -> S {
+        self.node.bias()
+    }
+    fn aggregation(&self) -> Aggregation {
+        self.node.aggregation()
+    }
+    fn time_constant(&self) -> S {
+        self.node.time_constant()
+    }
+    fn response(&self) -> S {
+        self.node.response()
+    }
+    fn kind(&self) -> NodeKind {
+        self.node.kind()
+    }
+    fn label(&self) -> Option<&str> {
+        self.node.label()
+    }
+    fn custom_activation(&self) -> Option<crate::network::net::activations::Closure<S>> {
+        self.node.custom_activation()
+    }
+}
+
+impl<S: Scalar> PartialEq for GraphNode<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl<S: Scalar> Eq for GraphNode<S> {}
+
+impl<S: Scalar> PartialOrd for GraphNode<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S: Scalar> Ord for GraphNode<S> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.id().cmp(&other.id())
+    }
+}
+
+/// Presents a `petgraph` [`Graph`] as [`NetworkLike`] structure, so it can be fabricated directly.
+///
+/// The edges are collected once on construction, nodes are borrowed from the graph.
+#[derive(Debug)]
+pub struct PetgraphNet<'a, S: Scalar = f32> {
+    graph: &'a Graph<GraphNode<S>, S>,
+    edges: Vec<Edge<S>>,
+}
+
+impl<'a, S: Scalar> PetgraphNet<'a, S> {
+    pub fn new(graph: &'a Graph<GraphNode<S>, S>) -> Self {
+        let edges = graph
+            .raw_edges()
+            .iter()
+            .map(|edge| {
+                Edge::new(
+                    graph[edge.source()].id(),
+                    graph[edge.target()].id(),
+                    edge.weight,
+                )
+            })
+            .collect();
+        Self { graph, edges }
+    }
+
+    fn with_role(&self, role: Role) -> impl Iterator<Item = &'a GraphNode<S>> {
+        self.graph
+            .node_weights()
+            .filter(move |node| node.role == role)
+    }
+}
+
+impl<S: Scalar> NetworkLike<GraphNode<S>, Edge<S>, S> for PetgraphNet<'_, S> {
+    fn edges(&self) -> Vec<&Edge<S>> {
+        self.edges.iter().collect()
+    }
+    fn inputs(&self) -> Vec<&GraphNode<S>> {
+        self.with_role(Role::Input).collect()
+    }
+    fn hidden(&self) -> Vec<&GraphNode<S>> {
+        self.with_role(Role::Hidden).collect()
+    }
+    fn outputs(&self) -> Vec<&GraphNode<S>> {
+        self.with_role(Role::Output).collect()
+    }
+}
+
+/// Copies a [`NetworkLike`] structure into a `petgraph` [`Graph`], e.g. to use its algorithms.
+///
+/// Edges to unknown node ids are skipped.
+pub fn to_petgraph<N, E, S>(net: &impl NetworkLike<N, E, S>) -> Graph<GraphNode<S>, S>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut graph = Graph::new();
+    let mut indices: std::collections::BTreeMap<usize, NodeIndex> = Default::default();
+
+    for (nodes, role) in [
+        (net.inputs(), Role::Input),
+        (net.hidden(), Role::Hidden),
+        (net.outputs(), Role::Output),
+    ] {
+        for node in nodes {
+            let index = graph.add_node(GraphNode::new(role, copy_node(node)));
+            indices.insert(node.id(), index);
+        }
+    }
+    for edge in net.edges_iter() {
+        if let (Some(&start), Some(&end)) = (indices.get(&edge.start()), indices.get(&edge.end())) {
+            graph.add_edge(start, end, edge.weight());
+        }
+    }
+
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+    use petgraph::{algo::toposort, graph::Graph};
+
+    use super::{to_petgraph, GraphNode, PetgraphNet, Role};
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Net, Node},
+            Evaluator, Fabricator,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn fabricate_graph() {
+        let mut graph = Graph::new();
+        let input = graph.add_node(GraphNode::new(
+            Role::Input,
+            Node::new(0, activations::LINEAR),
+        ));
+        let hidden = graph.add_node(GraphNode::new(
+            Role::Hidden,
+            Node::new(1, activations::LINEAR).with_bias(1.0),
+        ));
+        let output = graph.add_node(GraphNode::new(
+            Role::Output,
+            Node::new(2, activations::LINEAR),
+        ));
+        graph.add_edge(input, hidden, 2.0);
+        graph.add_edge(hidden, output, 0.5);
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&PetgraphNet::new(&graph)).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![3.0]), dmatrix![3.5]);
+    }
+
+    #[test]
+    fn net_to_graph() {
+        let net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 's', 'l'),
+            edges!(0--0.5->2, 1--0.5->2, 2--1.0->3),
+        );
+
+        let graph = to_petgraph(&net);
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 3);
+        assert!(toposort(&graph, None).is_ok());
+
+        let round_trip = MatrixFeedforwardFabricator::fabricate(&PetgraphNet::new(&graph)).unwrap();
+        let original = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        assert_eq!(
+            round_trip.evaluate(dmatrix![1.0, 2.0]),
+            original.evaluate(dmatrix![1.0, 2.0])
+        );
+    }
+}
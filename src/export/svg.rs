@@ -0,0 +1,267 @@
+//! Draws [`NetworkLike`] structures as SVG images, available with the feature `svg`.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::network::{net::activations, EdgeLike, NetworkLike, NodeLike, Recurrent, Scalar};
+
+const MARGIN: f64 = 40.0;
+const COLUMN_SPACING: f64 = 120.0;
+const ROW_SPACING: f64 = 60.0;
+const RADIUS: f64 = 15.0;
+
+/// Draws a [`NetworkLike`] structure as SVG, with one column per layer as returned by [`crate::layers`].
+///
+/// Inputs are drawn as squares on the left, outputs as thick circles on the right and hidden nodes without incoming edges next to the inputs.
+/// Edges are scaled in thickness by their absolute weight, negative weights are drawn red.
+/// Fails like fabricators if the net can not be layered.
+pub fn to_svg<N, E, S>(net: &impl NetworkLike<N, E, S>) -> Result<String, &'static str>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    draw(net, &[])
+}
+
+/// Draws a [`Recurrent`] [`NetworkLike`] structure like [`to_svg`] and adds its recurrent edges as dashed curves.
+pub fn recurrent_to_svg<N, E, S>(net: &impl Recurrent<N, E, S>) -> Result<String, &'static str>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    draw(net, &net.recurrent_edges())
+}
+
+fn draw<N, E, S>(
+    net: &impl NetworkLike<N, E, S>,
+    recurrent_edges: &[&E],
+) -> Result<String, &'static str>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut outputs: Vec<usize> = net.outputs_iter().map(|node| node.id()).collect();
+    outputs.sort_unstable();
+
+    // outputs are moved into a column of their own, unplaced hidden nodes next to the inputs
+    let mut columns: Vec<Vec<usize>> = crate::layers(net)?
+        .into_iter()
+        .map(|layer| {
+            layer
+                .into_iter()
+                .filter(|id| !outputs.contains(id))
+                .collect::<Vec<_>>()
+        })
+        .filter(|layer| !layer.is_empty())
+        .collect();
+    let placed: Vec<usize> = columns.iter().flatten().copied().collect();
+    let mut unplaced: Vec<usize> = net
+        .hidden_iter()
+        .map(|node| node.id())
+        .filter(|id| !placed.contains(id))
+        .collect();
+    if !unplaced.is_empty() {
+        unplaced.sort_unstable();
+        columns.insert(1.min(columns.len()), unplaced);
+    }
+    columns.push(outputs.clone());
+
+    let tallest = columns.iter().map(Vec::len).max().unwrap_or(0);
+    let width = 2.0 * MARGIN + (columns.len().saturating_sub(1)) as f64 * COLUMN_SPACING;
+    let height = 2.0 * MARGIN + (tallest.saturating_sub(1)) as f64 * ROW_SPACING;
+
+    let mut positions: BTreeMap<usize, (f64, f64)> = BTreeMap::new();
+    for (column, ids) in columns.iter().enumerate() {
+        // center every column vertically
+        let offset = (tallest - ids.len()) as f64 * ROW_SPACING / 2.0;
+        for (row, &id) in ids.iter().enumerate() {
+            positions.insert(
+                id,
+                (
+                    MARGIN + column as f64 * COLUMN_SPACING,
+                    MARGIN + offset + row as f64 * ROW_SPACING,
+                ),
+            );
+        }
+    }
+
+    let edges = net.edges();
+    let heaviest = edges
+        .iter()
+        .chain(recurrent_edges.iter())
+        .map(|edge| edge.weight().abs())
+        .fold(S::zero(), |heaviest, weight| heaviest.max(weight));
+    let thickness = |weight: S| {
+        let scale = if heaviest.is_zero() {
+            S::zero()
+        } else {
+            weight.abs() / heaviest
+        };
+        nalgebra::convert::<f64, S>(0.5) + nalgebra::convert::<f64, S>(3.0) * scale
+    };
+    let color = |weight: S| {
+        if weight < S::zero() {
+            "#c0392b"
+        } else {
+            "#333"
+        }
+    };
+
+    let mut svg = String::new();
+    // writing to a String can not fail
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        width, height, width, height
+    );
+
+    for edge in edges.iter().filter(|edge| edge.enabled()) {
+        if let (Some(&(x1, y1)), Some(&(x2, y2))) =
+            (positions.get(&edge.start()), positions.get(&edge.end()))
+        {
+            let _ = writeln!(
+                svg,
+                "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{:.2}\"><title>{} -> {}: {}</title></line>",
+                x1,
+                y1,
+                x2,
+                y2,
+                color(edge.weight()),
+                thickness(edge.weight()),
+                edge.start(),
+                edge.end(),
+                edge.weight()
+            );
+        }
+    }
+
+    for edge in recurrent_edges.iter().filter(|edge| edge.enabled()) {
+        if let (Some(&(x1, y1)), Some(&(x2, y2))) =
+            (positions.get(&edge.start()), positions.get(&edge.end()))
+        {
+            // bend recurrent edges above the nodes, loops on a single node become a small arc
+            let path = if edge.start() == edge.end() {
+                format!(
+                    "M {} {} C {} {} {} {} {} {}",
+                    x1 - RADIUS / 2.0,
+                    y1 - RADIUS,
+                    x1 - RADIUS * 2.0,
+                    y1 - RADIUS * 3.0,
+                    x1 + RADIUS * 2.0,
+                    y1 - RADIUS * 3.0,
+                    x1 + RADIUS / 2.0,
+                    y1 - RADIUS
+                )
+            } else {
+                format!(
+                    "M {} {} Q {} {} {} {}",
+                    x1,
+                    y1,
+                    (x1 + x2) / 2.0,
+                    y1.min(y2) - ROW_SPACING,
+                    x2,
+                    y2
+                )
+            };
+            let _ = writeln!(
+                svg,
+                "  <path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{:.2}\" stroke-dasharray=\"4 3\"><title>{} -> {}: {}</title></path>",
+                path,
+                color(edge.weight()),
+                thickness(edge.weight()),
+                edge.start(),
+                edge.end(),
+                edge.weight()
+            );
+        }
+    }
+
+    let inputs: Vec<usize> = net.inputs_iter().map(|node| node.id()).collect();
+    for node in net.nodes_iter() {
+        let Some(&(x, y)) = positions.get(&node.id()) else {
+            continue;
+        };
+        let title = format!(
+            "<title>{} {}</title>",
+            node.id(),
+            activations::name(node.activation()).unwrap_or("custom")
+        );
+        if inputs.contains(&node.id()) {
+            let _ = writeln!(
+                svg,
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\" stroke=\"#333\">{}</rect>",
+                x - RADIUS,
+                y - RADIUS,
+                2.0 * RADIUS,
+                2.0 * RADIUS,
+                title
+            );
+        } else {
+            let stroke = if outputs.contains(&node.id()) { 3 } else { 1 };
+            let _ = writeln!(
+                svg,
+                "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"white\" stroke=\"#333\" stroke-width=\"{}\">{}</circle>",
+                x, y, RADIUS, stroke, title
+            );
+        }
+        let _ = writeln!(
+            svg,
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" font-family=\"sans-serif\" font-size=\"12\">{}</text>",
+            x,
+            y,
+            node.id()
+        );
+    }
+
+    let _ = writeln!(svg, "</svg>");
+    Ok(svg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{recurrent_to_svg, to_svg};
+    use crate::{
+        edges,
+        network::net::{Edge, Net},
+        nodes,
+    };
+
+    #[test]
+    fn svg_layers_nodes_and_scales_edges() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 's', 'l'),
+            vec![
+                Edge::new(0, 1, 2.0),
+                Edge::new(1, 2, -1.0),
+                Edge::new(0, 2, 0.5),
+            ],
+        );
+
+        let svg = to_svg(&some_net).unwrap();
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"320\""));
+        assert!(svg.contains("<rect x=\"25\" y=\"25\""));
+        assert!(svg.contains("<circle cx=\"160\" cy=\"40\" r=\"15\""));
+        assert!(svg.contains("<circle cx=\"280\" cy=\"40\" r=\"15\" fill=\"white\" stroke=\"#333\" stroke-width=\"3\">"));
+        assert!(svg.contains("stroke=\"#333\" stroke-width=\"3.50\"><title>0 -> 1: 2</title>"));
+        assert!(svg.contains("stroke=\"#c0392b\" stroke-width=\"2.00\"><title>1 -> 2: -1</title>"));
+        assert!(!svg.contains("dasharray"));
+    }
+
+    #[test]
+    fn svg_draws_recurrent_edges_dashed() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--0.5->1));
+
+        let svg = recurrent_to_svg(&some_net).unwrap();
+
+        assert!(svg.contains("stroke-dasharray=\"4 3\"><title>1 -> 1: 0.5</title>"));
+
+        let cyclic = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1, 1--1.0->1));
+        assert!(to_svg(&cyclic).is_err());
+    }
+}
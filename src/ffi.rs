@@ -0,0 +1,216 @@
+//! C bindings to load serialized evaluators and evaluate raw buffers, available with the feature `ffi`.
+//!
+//! Evaluators are serialized as JSON with the feature `serde`, e.g. via `serde_json::to_vec`.
+//! Build a shared library with `cargo rustc --lib --release --features ffi --crate-type cdylib` and declare:
+//!
+//! ```c
+//! typedef struct FavannatEvaluator FavannatEvaluator;
+//!
+//! FavannatEvaluator *favannat_evaluator_load(const uint8_t *json, size_t length);
+//! size_t favannat_evaluator_inputs(const FavannatEvaluator *evaluator);
+//! size_t favannat_evaluator_outputs(const FavannatEvaluator *evaluator);
+//! int32_t favannat_evaluator_evaluate(const FavannatEvaluator *evaluator, const float *input, size_t samples, float *output);
+//! void favannat_evaluator_free(FavannatEvaluator *evaluator);
+//! ```
+
+use std::{panic, slice};
+
+use nalgebra::DMatrix;
+
+use crate::{matrix::feedforward::evaluator::MatrixFeedforwardEvaluator, network::Evaluator};
+
+/// Opaque handle to a [`MatrixFeedforwardEvaluator`] owned by C code.
+pub struct FavannatEvaluator {
+    evaluator: MatrixFeedforwardEvaluator,
+    inputs: usize,
+    outputs: usize,
+}
+
+/// Loads a JSON serialized [`MatrixFeedforwardEvaluator`], returns null if it is invalid.
+///
+/// # Safety
+///
+/// `json` needs to point to `length` readable bytes.
+/// The returned handle needs to be released with [`favannat_evaluator_free`].
+#[no_mangle]
+pub unsafe extern "C" fn favannat_evaluator_load(
+    json: *const u8,
+    length: usize,
+) -> *mut FavannatEvaluator {
+    if json.is_null() {
+        return std::ptr::null_mut();
+    }
+    let json = slice::from_raw_parts(json, length);
+
+    match serde_json::from_slice::<MatrixFeedforwardEvaluator>(json) {
+        Ok(evaluator) => {
+            let inputs = evaluator.input_size();
+            let outputs = evaluator.output_size();
+            Box::into_raw(Box::new(FavannatEvaluator {
+                evaluator,
+                inputs,
+                outputs,
+            }))
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Returns the number of inputs per sample.
+///
+/// # Safety
+///
+/// `evaluator` needs to be a handle returned by [`favannat_evaluator_load`].
+#[no_mangle]
+pub unsafe extern "C" fn favannat_evaluator_inputs(evaluator: *const FavannatEvaluator) -> usize {
+    (*evaluator).inputs
+}
+
+/// Returns the number of outputs per sample.
+///
+/// # Safety
+///
+/// `evaluator` needs to be a handle returned by [`favannat_evaluator_load`].
+#[no_mangle]
+pub unsafe extern "C" fn favannat_evaluator_outputs(evaluator: *const FavannatEvaluator) -> usize {
+    (*evaluator).outputs
+}
+
+/// Evaluates `samples` consecutive samples from `input` and writes their outputs consecutively to `output`.
+///
+/// Returns `0` on success and `-1` if any pointer is null or evaluation panicked.
+///
+/// # Safety
+///
+/// `evaluator` needs to be a handle returned by [`favannat_evaluator_load`],
+/// `input` needs to hold `samples * inputs` and `output` `samples * outputs` floats.
+#[no_mangle]
+pub unsafe extern "C" fn favannat_evaluator_evaluate(
+    evaluator: *const FavannatEvaluator,
+    input: *const f32,
+    samples: usize,
+    output: *mut f32,
+) -> i32 {
+    if evaluator.is_null() || input.is_null() || output.is_null() {
+        return -1;
+    }
+    let evaluator = &*evaluator;
+    let input = slice::from_raw_parts(input, samples * evaluator.inputs);
+    let output = slice::from_raw_parts_mut(output, samples * evaluator.outputs);
+
+    // unwinding into C is undefined behavior, the evaluator is only read so it stays consistent
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        evaluator
+            .evaluator
+            .evaluate(DMatrix::from_row_slice(samples, evaluator.inputs, input))
+    }));
+
+    match result {
+        Ok(result) => {
+            // back to row-major order, one sample after the other
+            for (target, value) in output.iter_mut().zip(result.transpose().iter()) {
+                *target = *value;
+            }
+            0
+        }
+        Err(_) => -1,
+    }
+}
+
+/// Releases an evaluator, null is ignored.
+///
+/// # Safety
+///
+/// `evaluator` needs to be a handle returned by [`favannat_evaluator_load`] that was not released before.
+#[no_mangle]
+pub unsafe extern "C" fn favannat_evaluator_free(evaluator: *mut FavannatEvaluator) {
+    if !evaluator.is_null() {
+        drop(Box::from_raw(evaluator));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        favannat_evaluator_evaluate, favannat_evaluator_free, favannat_evaluator_inputs,
+        favannat_evaluator_load, favannat_evaluator_outputs,
+    };
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Net, Node},
+            Fabricator,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn ffi_load_and_evaluate() {
+        let some_net = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::RELU).with_bias(0.5),
+            ],
+            edges!(0--0.5->2, 1--1.0->2),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let json = serde_json::to_vec(&evaluator).unwrap();
+
+        unsafe {
+            let handle = favannat_evaluator_load(json.as_ptr(), json.len());
+            assert!(!handle.is_null());
+            assert_eq!(favannat_evaluator_inputs(handle), 2);
+            assert_eq!(favannat_evaluator_outputs(handle), 1);
+
+            let input = [1.0, 2.0, -4.0, 0.0];
+            let mut output = [0.0; 2];
+            assert_eq!(
+                favannat_evaluator_evaluate(handle, input.as_ptr(), 2, output.as_mut_ptr()),
+                0
+            );
+            assert_eq!(output, [3.0, 0.0]);
+
+            favannat_evaluator_free(handle);
+
+            let invalid = b"{}";
+            assert!(favannat_evaluator_load(invalid.as_ptr(), invalid.len()).is_null());
+        }
+    }
+
+    #[test]
+    fn ffi_load_gathered_carries() {
+        // node 4 is computed in the first stage and carried through the output stage
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 'r', 'l', 'l'),
+            edges!(0--1.0->2, 2--2.0->3, 1--3.0->4),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_gathered_carries();
+        assert_eq!(evaluator.stages.last().unwrap().ncols(), 1);
+        let json = serde_json::to_vec(&evaluator).unwrap();
+
+        unsafe {
+            let handle = favannat_evaluator_load(json.as_ptr(), json.len());
+            assert!(!handle.is_null());
+            assert_eq!(favannat_evaluator_inputs(handle), 2);
+            assert_eq!(favannat_evaluator_outputs(handle), 2);
+
+            let input = [1.0, 2.0, -4.0, 0.5];
+            let mut output = [0.0; 4];
+            assert_eq!(
+                favannat_evaluator_evaluate(handle, input.as_ptr(), 2, output.as_mut_ptr()),
+                0
+            );
+            assert_eq!(output, [2.0, 6.0, 0.0, 1.5]);
+
+            favannat_evaluator_free(handle);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::sync::{Arc, OnceLock};
+
+use wgpu::util::DeviceExt;
+
+const SHADER: &str = r#"
+struct Dimensions {
+    samples: u32,
+    rows: u32,
+    columns: u32,
+    stride: u32,
+}
+
+@group(0) @binding(0) var<uniform> dimensions: Dimensions;
+// samples x rows, row major
+@group(0) @binding(1) var<storage, read> state: array<f32>;
+// rows x columns, column major
+@group(0) @binding(2) var<storage, read> stage: array<f32>;
+@group(0) @binding(3) var<storage, read> bias: array<f32>;
+// samples x columns, row major
+@group(0) @binding(4) var<storage, read_write> result: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x + id.y * dimensions.stride;
+    if (index >= dimensions.samples * dimensions.columns) {
+        return;
+    }
+    let sample = index / dimensions.columns;
+    let column = index % dimensions.columns;
+
+    var sum = bias[column];
+    for (var row = 0u; row < dimensions.rows; row++) {
+        sum += state[sample * dimensions.rows + row] * stage[column * dimensions.rows + row];
+    }
+    result[index] = sum;
+}
+"#;
+
+const WORKGROUP_SIZE: u32 = 64;
+const MAX_WORKGROUPS: u32 = 65535;
+
+/// Device, queue and pipeline shared by all evaluators of the process.
+#[derive(Debug)]
+pub(crate) struct GpuContext {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+static CONTEXT: OnceLock<Option<Arc<GpuContext>>> = OnceLock::new();
+
+impl GpuContext {
+    /// Returns the shared context, initializing it on first use, or `None` if no adapter is available.
+    pub(crate) fn get() -> Option<Arc<GpuContext>> {
+        CONTEXT
+            .get_or_init(|| pollster::block_on(Self::init()).map(Arc::new))
+            .clone()
+    }
+
+    async fn init() -> Option<GpuContext> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .ok()?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default())
+            .await
+            .ok()?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("favannat stage"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("favannat stage"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Some(GpuContext {
+            device,
+            queue,
+            pipeline,
+        })
+    }
+
+    /// Uploads data into a read-only storage buffer.
+    pub(crate) fn storage(&self, data: &[f32]) -> wgpu::Buffer {
+        // bindings must not be empty
+        let padding = [0.0];
+        let data = if data.is_empty() { &padding[..] } else { data };
+
+        self.device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: &data
+                    .iter()
+                    .flat_map(|value| value.to_le_bytes())
+                    .collect::<Vec<u8>>(),
+                usage: wgpu::BufferUsages::STORAGE,
+            })
+    }
+
+    /// Computes `state * stage + bias` on the GPU and returns the row major result.
+    ///
+    /// `state` is row major with `samples` rows, `stage` is column major with `rows` rows and `columns` columns.
+    pub(crate) fn multiply(
+        &self,
+        state: &[f32],
+        samples: usize,
+        stage: &wgpu::Buffer,
+        bias: &wgpu::Buffer,
+        (rows, columns): (usize, usize),
+    ) -> Vec<f32> {
+        let count = (samples * columns) as u32;
+        let workgroups = count.div_ceil(WORKGROUP_SIZE).max(1);
+        let (x, y) = (
+            workgroups.min(MAX_WORKGROUPS),
+            workgroups.div_ceil(MAX_WORKGROUPS),
+        );
+
+        let dimensions = [
+            samples as u32,
+            rows as u32,
+            columns as u32,
+            x * WORKGROUP_SIZE,
+        ];
+        let dimensions = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: &dimensions
+                    .iter()
+                    .flat_map(|value| value.to_le_bytes())
+                    .collect::<Vec<u8>>(),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let state = self.storage(state);
+        let size = (count.max(1) as u64) * 4;
+        let result = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: dimensions.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: state.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: stage.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: bias.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: result.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(x, y, 1);
+        }
+        encoder.copy_buffer_to_buffer(&result, 0, &readback, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .expect("gpu device lost during evaluation");
+
+        let values = slice
+            .get_mapped_range()
+            .chunks_exact(4)
+            .take(samples * columns)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        readback.unmap();
+
+        values
+    }
+}
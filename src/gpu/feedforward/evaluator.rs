@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use nalgebra::DMatrix;
+
+use crate::{
+    gpu::context::GpuContext,
+    network::{Evaluator, NetworkIO},
+};
+
+/// A stage whose matrix and biases live in GPU memory.
+#[derive(Debug)]
+pub struct WgpuStage {
+    pub(crate) matrix: wgpu::Buffer,
+    pub(crate) biases: wgpu::Buffer,
+    pub rows: usize,
+    pub columns: usize,
+}
+
+/// Evaluates stages on the GPU, activations and non-sum aggregations are applied on the CPU between stages.
+///
+/// Like [`crate::matrix::feedforward::evaluator::MatrixFeedforwardEvaluator`] every row of the input is a separate sample,
+/// which is where the GPU pays off.
+#[derive(Debug)]
+pub struct WgpuFeedforwardEvaluator {
+    pub(crate) context: Arc<GpuContext>,
+    pub stages: Vec<WgpuStage>,
+    pub transformations: Vec<crate::Transformations>,
+    pub biases: Vec<Vec<f32>>,
+    pub aggregations: Vec<crate::Aggregations>,
+}
+
+impl WgpuFeedforwardEvaluator {
+    /// Number of values every sample provides as input.
+    pub fn input_size(&self) -> usize {
+        self.stages.first().map_or(0, |stage| stage.rows)
+    }
+
+    /// Number of values evaluated for every sample.
+    pub fn output_size(&self) -> usize {
+        self.stages.last().map_or(0, |stage| stage.columns)
+    }
+
+    pub fn stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Dimensions of every stage as `(rows, columns)`, i.e. the number of values entering and leaving it.
+    pub fn stage_dimensions(&self) -> Vec<(usize, usize)> {
+        self.stages
+            .iter()
+            .map(|stage| (stage.rows, stage.columns))
+            .collect()
+    }
+}
+
+impl Evaluator for WgpuFeedforwardEvaluator {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        let input = NetworkIO::input(input);
+        let samples = input.nrows();
+        // keep state row major as expected by the shader
+        let mut state: Vec<f32> = input.transpose().iter().cloned().collect();
+
+        for (((stage, transformations), biases), aggregations) in self
+            .stages
+            .iter()
+            .zip(&self.transformations)
+            .zip(&self.biases)
+            .zip(&self.aggregations)
+        {
+            let mut next = self.context.multiply(
+                &state,
+                samples,
+                &stage.matrix,
+                &stage.biases,
+                (stage.rows, stage.columns),
+            );
+
+            // compute values of nodes that do not sum their inputs
+            for (column, aggregation, weighted_inputs) in aggregations {
+                for sample in 0..samples {
+                    next[sample * stage.columns + column] = biases[*column]
+                        + aggregation.aggregate(
+                            weighted_inputs
+                                .iter()
+                                .map(|&(row, weight)| state[sample * stage.rows + row] * weight),
+                        );
+                }
+            }
+
+            for (index, value) in next.iter_mut().enumerate() {
+                *value = transformations[index % stage.columns].apply(*value);
+            }
+
+            state = next;
+        }
+
+        let columns = self
+            .stages
+            .last()
+            .map_or(input.ncols(), |stage| stage.columns);
+        NetworkIO::output(DMatrix::from_row_slice(samples, columns, &state))
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
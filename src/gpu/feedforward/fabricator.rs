@@ -0,0 +1,163 @@
+use crate::{
+    gpu::context::GpuContext,
+    matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+    network::{EdgeLike, FabricationError, Fabricator, NetworkLike, NodeId, NodeLike},
+};
+
+use super::evaluator::{WgpuFeedforwardEvaluator, WgpuStage};
+
+/// Fabricates like [`MatrixFeedforwardFabricator`] and uploads the resulting stages to the GPU.
+pub struct WgpuFeedforwardFabricator;
+
+impl<N, E, I> Fabricator<N, E, f32, I> for WgpuFeedforwardFabricator
+where
+    N: NodeLike<f32, I>,
+    E: EdgeLike<f32, I>,
+    I: NodeId,
+{
+    type Output = WgpuFeedforwardEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E, f32, I>) -> Result<Self::Output, FabricationError> {
+        let context = GpuContext::get().ok_or("no gpu adapter available")?;
+        let evaluator = MatrixFeedforwardFabricator::fabricate(net)?;
+
+        let stages = evaluator
+            .stages
+            .iter()
+            .zip(&evaluator.biases)
+            .map(|(stage_matrix, biases)| WgpuStage {
+                matrix: context.storage(stage_matrix.as_slice()),
+                biases: context.storage(biases.as_slice()),
+                rows: stage_matrix.nrows(),
+                columns: stage_matrix.ncols(),
+            })
+            .collect();
+
+        Ok(WgpuFeedforwardEvaluator {
+            context,
+            stages,
+            transformations: evaluator.transformations,
+            biases: evaluator
+                .biases
+                .into_iter()
+                .map(|biases| biases.iter().cloned().collect())
+                .collect(),
+            aggregations: evaluator.aggregations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::WgpuFeedforwardFabricator;
+    use crate::{
+        edges,
+        gpu::feedforward::evaluator::WgpuFeedforwardEvaluator,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Net, Node},
+            Aggregation, Evaluator, FabricationError, Fabricator,
+        },
+        test_util::random_net,
+        verify::equivalent,
+    };
+
+    // none if the machine has no gpu adapter, the tests are skipped then
+    fn fabricate(net: &Net) -> Option<WgpuFeedforwardEvaluator> {
+        match WgpuFeedforwardFabricator::fabricate(net) {
+            Ok(evaluator) => Some(evaluator),
+            Err(FabricationError::Invalid("no gpu adapter available")) => None,
+            Err(message) => panic!("{}", message),
+        }
+    }
+
+    // compares against the cpu backend
+    #[test]
+    fn gpu_net_evaluator_0() {
+        let some_net = Net::new(
+            2,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::RELU).with_bias(-1.0),
+                Node::new(3, activations::LINEAR).with_aggregation(Aggregation::Product),
+                Node::new(4, activations::LINEAR),
+            ],
+            edges!(
+                0--1.0->2,
+                1--1.0->2,
+                0--1.0->3,
+                2--1.0->3,
+                2--0.5->4
+            ),
+        );
+
+        let Some(evaluator) = fabricate(&some_net) else {
+            return;
+        };
+        let reference = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let input = dmatrix![
+            1.0, 2.0;
+            0.0, 0.0;
+            2.0, 3.0
+        ];
+
+        assert_eq!(evaluator.evaluate(input.clone()), reference.evaluate(input));
+    }
+
+    #[test]
+    fn gpu_net_evaluator_stages() {
+        for seed in 0..4 {
+            let some_net = random_net(3, 2, 8, 0.4, 0.0, seed);
+            let Some(evaluator) = fabricate(&some_net) else {
+                return;
+            };
+            let reference = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+            assert!(evaluator.stage_count() > 1);
+            assert_eq!(equivalent(&evaluator, &reference, 64, 1e-5), Ok(None));
+        }
+    }
+
+    #[test]
+    fn gpu_net_evaluator_aggregations() {
+        // every non-sum aggregation, computed in consecutive stages
+        let some_net = Net::new(
+            2,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::LINEAR).with_aggregation(Aggregation::Max),
+                Node::new(3, activations::TANH).with_aggregation(Aggregation::Min),
+                Node::new(4, activations::LINEAR)
+                    .with_aggregation(Aggregation::Mean)
+                    .with_bias(0.5),
+                Node::new(5, activations::LINEAR).with_aggregation(Aggregation::Product),
+                Node::new(6, activations::SIGMOID),
+            ],
+            edges!(
+                0--1.0->2,
+                1---2.0->2,
+                0--0.5->3,
+                1--1.0->3,
+                2--1.0->4,
+                3--2.0->4,
+                1--1.0->4,
+                4--1.0->5,
+                2--0.5->5,
+                4--1.0->6,
+                3---1.0->6
+            ),
+        );
+
+        let Some(evaluator) = fabricate(&some_net) else {
+            return;
+        };
+        let reference = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(equivalent(&evaluator, &reference, 64, 1e-5), Ok(None));
+    }
+}
@@ -0,0 +1,4 @@
+//! GPU backend evaluating large batches with `wgpu` compute shaders, available with the feature `gpu`.
+
+mod context;
+pub mod feedforward;
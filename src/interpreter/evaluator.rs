@@ -0,0 +1,103 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::DMatrix;
+
+use crate::network::{
+    net::activations::Activation, Aggregation, EvaluateInto, Evaluator, NetworkIO, Scalar, Scratch,
+};
+
+/// Non-input node in evaluation order, `inputs` are pairs of value position and weight.
+#[derive(Debug, Clone)]
+pub struct LoopNode<S: Scalar = f32> {
+    pub activation: Activation<S>,
+    pub bias: S,
+    pub response: S,
+    pub aggregation: Aggregation,
+    pub inputs: Vec<(usize, S)>,
+}
+
+/// Evaluates nodes one after another in topological order.
+///
+/// Values are laid out as the inputs followed by one value per entry of `nodes`, so every node only refers to positions before its own.
+#[derive(Debug, Clone)]
+pub struct LoopEvaluator<S: Scalar = f32> {
+    pub input_count: usize,
+    pub nodes: Vec<LoopNode<S>>,
+    /// Value positions of the outputs.
+    pub output_ids: Vec<usize>,
+}
+
+impl<S: Scalar> LoopEvaluator<S> {
+    pub fn input_size(&self) -> usize {
+        self.input_count
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.output_ids.len()
+    }
+
+    /// Number of values [`Self::evaluate_values`] expects.
+    pub fn value_count(&self) -> usize {
+        self.input_count + self.nodes.len()
+    }
+
+    /// Evaluates a single sample in place without allocating.
+    ///
+    /// `values` needs [`Self::value_count`] entries, the leading ones holding the input, outputs are found at [`Self::output_ids`] afterwards.
+    pub fn evaluate_values(&self, values: &mut [S]) {
+        for (position, node) in (self.input_count..).zip(&self.nodes) {
+            let aggregate = node.aggregation.aggregate(
+                node.inputs
+                    .iter()
+                    .map(|&(source, weight)| values[source] * weight),
+            );
+            values[position] = node.activation.apply(node.bias + node.response * aggregate);
+        }
+    }
+}
+
+impl<S: Scalar> Evaluator<S> for LoopEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&self, input: T) -> T {
+        let input = NetworkIO::input(input);
+        let mut output = DMatrix::zeros(input.nrows(), self.output_ids.len());
+        // the values are reused for every sample
+        let mut values = vec![S::zero(); self.value_count()];
+        for (sample, row) in input.row_iter().enumerate() {
+            for (value, &input) in values.iter_mut().zip(row.iter()) {
+                *value = input;
+            }
+            self.evaluate_values(&mut values);
+            for (column, &id) in self.output_ids.iter().enumerate() {
+                output[(sample, column)] = values[id];
+            }
+        }
+        NetworkIO::output(output)
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
+
+impl<S: Scalar> EvaluateInto<S> for LoopEvaluator<S> {
+    fn scratch(&self) -> Scratch<S> {
+        Scratch::new(self.value_count())
+    }
+
+    fn evaluate_into(&self, input: &[S], output: &mut [S], scratch: &mut Scratch<S>) {
+        scratch.current[..self.input_count].copy_from_slice(input);
+        self.evaluate_values(&mut scratch.current);
+        assert_eq!(
+            output.len(),
+            self.output_size(),
+            "output dimension does not match"
+        );
+        for (value, &id) in output.iter_mut().zip(&self.output_ids) {
+            *value = scratch.current[id];
+        }
+    }
+}
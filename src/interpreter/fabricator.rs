@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use crate::network::{
+    net::{activations::Activation, Node},
+    prune::active_edges,
+    remap::fabricable,
+    EdgeLike, FabricationError, Fabricator, NetworkLike, NodeId, NodeLike, Scalar,
+};
+
+use super::evaluator::{LoopEvaluator, LoopNode};
+
+/// Fabricates a [`LoopEvaluator`] by sorting the nodes topologically.
+#[derive(Debug)]
+pub struct LoopFabricator;
+
+impl<N, E, S, I> Fabricator<N, E, S, I> for LoopFabricator
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    type Output = LoopEvaluator<S>;
+
+    fn fabricate(net: &impl NetworkLike<N, E, S, I>) -> Result<Self::Output, FabricationError> {
+        let net = &fabricable(net);
+        let nodes = net.nodes();
+        let ids: Vec<usize> = nodes.iter().map(|node| node.id()).collect();
+
+        let mut incoming: HashMap<usize, Vec<(usize, S)>> = HashMap::new();
+        for edge in active_edges(&nodes, net.edges()) {
+            if !ids.contains(&edge.start()) {
+                return Err("edge from unknown node".into());
+            }
+            if !ids.contains(&edge.end()) {
+                return Err("edge to unknown node".into());
+            }
+            incoming
+                .entry(edge.end())
+                .or_default()
+                .push((edge.start(), edge.weight()));
+        }
+
+        // value positions of the nodes evaluated so far
+        let mut positions: HashMap<usize, usize> = net
+            .inputs_iter()
+            .enumerate()
+            .map(|(position, node)| (node.id(), position))
+            .collect();
+        let input_count = positions.len();
+
+        let mut pending: Vec<&Node<S>> = net.hidden_iter().chain(net.outputs_iter()).collect();
+        let mut loop_nodes = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let pending_count = pending.len();
+            let mut blocked = Vec::new();
+            for node in pending {
+                // nodes without incoming edges can not be computed, as in the matrix backends
+                match incoming.get(&node.id()) {
+                    Some(inputs)
+                        if inputs
+                            .iter()
+                            .all(|(start, _)| positions.contains_key(start)) =>
+                    {
+                        loop_nodes.push(LoopNode {
+                            activation: Activation::of(node),
+                            bias: node.bias(),
+                            response: node.response(),
+                            aggregation: node.aggregation(),
+                            inputs: inputs
+                                .iter()
+                                .map(|&(start, weight)| (positions[&start], weight))
+                                .collect(),
+                        });
+                        positions.insert(node.id(), input_count + loop_nodes.len() - 1);
+                    }
+                    _ => blocked.push(node),
+                }
+            }
+
+            // if no node was added no progress was made
+            if blocked.len() == pending_count {
+                let mut edges = incoming
+                    .iter()
+                    .flat_map(|(&end, inputs)| inputs.iter().map(move |&(start, _)| (start, end)))
+                    .collect::<Vec<_>>();
+                // sorted, so the same cycles are found whatever the order of the hash map
+                edges.sort_unstable();
+                let cycles = crate::validation::edge_cycles(edges);
+                if !cycles.is_empty() {
+                    return Err(FabricationError::Cycles(cycles));
+                }
+                return Err("can't resolve dependencies, net invalid".into());
+            }
+            pending = blocked;
+        }
+
+        Ok(LoopEvaluator {
+            input_count,
+            nodes: loop_nodes,
+            output_ids: net
+                .outputs_iter()
+                .map(|node| positions[&node.id()])
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::LoopFabricator;
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Net, Node},
+            Aggregation, Evaluator, FabricationError, Fabricator,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn loop_evaluator_matches_matrix() {
+        let some_net = Net::new(
+            2,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::SIGMOID).with_bias(0.5),
+                Node::new(3, activations::TANH)
+                    .with_aggregation(Aggregation::Max)
+                    .with_response(-2.0),
+                Node::new(4, activations::LINEAR).with_aggregation(Aggregation::Product),
+                Node::new(5, activations::RELU),
+            ],
+            edges!(
+                0--0.5->2,
+                1--0.5->2,
+                2--1.0->3,
+                0--0.25->3,
+                3--1.5->4,
+                1--2.0->4,
+                0--1.0->5
+            ),
+        );
+
+        let evaluator = LoopFabricator::fabricate(&some_net).unwrap();
+        let matrix = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.nodes.len(), 4);
+
+        let input = dmatrix![1.0, 2.0; -1.0, 0.5; 0.0, -3.0];
+        let difference = evaluator.evaluate(input.clone()) - matrix.evaluate(input);
+        assert!(difference.amax() < 1e-6);
+    }
+
+    #[test]
+    fn evaluate_values_in_place() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--2.0->1, 1--0.5->2));
+
+        let evaluator = LoopFabricator::fabricate(&some_net).unwrap();
+        let mut values = vec![3.0, 0.0, 0.0];
+        evaluator.evaluate_values(&mut values);
+        assert_eq!(values, vec![3.0, 6.0, 3.0]);
+        assert_eq!(evaluator.output_ids, vec![2]);
+    }
+
+    #[test]
+    fn reject_cycles() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(0--1.0->1, 2--1.0->1, 1--1.0->2),
+        );
+        assert_eq!(
+            LoopFabricator::fabricate(&some_net).unwrap_err(),
+            FabricationError::Cycles(vec![vec![1, 2]])
+        );
+    }
+}
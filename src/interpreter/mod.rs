@@ -0,0 +1,8 @@
+//! Evaluates [`crate::network::NetworkLike`] structures node by node without any matrices.
+//!
+//! Nodes are sorted topologically once on fabrication, every evaluation then loops over them and aggregates their incoming edges.
+//! For the small nets of early generations this beats the matrix backends, as there are no carries or empty entries to compute.
+
+pub mod evaluator;
+#[cfg(feature = "std")]
+pub mod fabricator;
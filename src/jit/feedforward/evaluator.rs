@@ -0,0 +1,80 @@
+use std::{fmt, mem::ManuallyDrop};
+
+use cranelift_jit::JITModule;
+use nalgebra::DMatrix;
+
+use crate::network::{EvaluateInto, Evaluator, NetworkIO, Scratch};
+
+/// Evaluates a network compiled to native code by [`super::fabricator::JitFabricator`].
+///
+/// Every row of the input is a separate sample.
+pub struct JitEvaluator {
+    pub(crate) module: ManuallyDrop<JITModule>,
+    pub(crate) function: extern "C" fn(*const f32, *mut f32),
+    pub inputs: usize,
+    pub outputs: usize,
+}
+
+impl fmt::Debug for JitEvaluator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JitEvaluator")
+            .field("inputs", &self.inputs)
+            .field("outputs", &self.outputs)
+            .finish()
+    }
+}
+
+impl Drop for JitEvaluator {
+    fn drop(&mut self) {
+        // SAFETY: the compiled function is owned by this evaluator and can not be called anymore
+        unsafe { ManuallyDrop::take(&mut self.module).free_memory() }
+    }
+}
+
+impl Evaluator for JitEvaluator {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        let input = NetworkIO::input(input);
+        assert_eq!(input.ncols(), self.inputs, "input dimension does not match");
+
+        let mut sample = vec![0.0; self.inputs];
+        let mut output = DMatrix::zeros(input.nrows(), self.outputs);
+        let mut result = vec![0.0; self.outputs];
+
+        for (sample_index, row) in input.row_iter().enumerate() {
+            for (value, input_value) in sample.iter_mut().zip(row.iter()) {
+                *value = *input_value;
+            }
+            (self.function)(sample.as_ptr(), result.as_mut_ptr());
+            for (column, value) in result.iter().enumerate() {
+                output[(sample_index, column)] = *value;
+            }
+        }
+
+        NetworkIO::output(output)
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.inputs)
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.outputs)
+    }
+}
+
+impl EvaluateInto for JitEvaluator {
+    /// The compiled function keeps its intermediate values on the stack, so no buffers are needed.
+    fn scratch(&self) -> Scratch {
+        Scratch::new(0)
+    }
+
+    fn evaluate_into(&self, input: &[f32], output: &mut [f32], _scratch: &mut Scratch) {
+        assert_eq!(input.len(), self.inputs, "input dimension does not match");
+        assert_eq!(
+            output.len(),
+            self.outputs,
+            "output dimension does not match"
+        );
+        (self.function)(input.as_ptr(), output.as_mut_ptr());
+    }
+}
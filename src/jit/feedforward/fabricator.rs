@@ -0,0 +1,300 @@
+use std::mem::ManuallyDrop;
+
+use cranelift_codegen::{
+    ir::{types, AbiParam, InstBuilder, MemFlags, UserFuncName, Value},
+    settings::{self, Configurable},
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Linkage, Module};
+
+use crate::{
+    matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+    network::{Aggregation, EdgeLike, FabricationError, Fabricator, NetworkLike, NodeId, NodeLike},
+};
+
+use super::evaluator::JitEvaluator;
+
+/// Calls an activation function on behalf of compiled code, as Rust function pointers have no stable ABI.
+extern "C" fn activate(activation: usize, value: f32) -> f32 {
+    // SAFETY: only ever called with pointers of type `fn(f32) -> f32` embedded during fabrication
+    let activation: fn(f32) -> f32 = unsafe { std::mem::transmute(activation) };
+    activation(value)
+}
+
+/// Compiles the stages found by [`MatrixFeedforwardFabricator`] into a native function.
+///
+/// Zero weights are skipped, every node value lives in a register or on the stack.
+pub struct JitFabricator;
+
+impl<N, E, I> Fabricator<N, E, f32, I> for JitFabricator
+where
+    N: NodeLike<f32, I>,
+    E: EdgeLike<f32, I>,
+    I: NodeId,
+{
+    type Output = JitEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E, f32, I>) -> Result<Self::Output, FabricationError> {
+        let plan = MatrixFeedforwardFabricator::fabricate(net)?;
+        // compiled code calls activations by address, which closures do not have
+        let functions = plan
+            .transformations
+            .iter()
+            .map(|stage| {
+                stage
+                    .iter()
+                    .map(|activation| {
+                        activation
+                            .function()
+                            .ok_or("closure activations can not be compiled")
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut flag_builder = settings::builder();
+        flag_builder
+            .set("opt_level", "speed")
+            .map_err(|_| "jit setup failed")?;
+        let isa = cranelift_native::builder()
+            .map_err(|_| "host machine not supported by jit")?
+            .finish(settings::Flags::new(flag_builder))
+            .map_err(|_| "jit setup failed")?;
+
+        let mut builder = JITBuilder::with_isa(isa, default_libcall_names());
+        builder.symbol("favannat_activate", activate as *const u8);
+        let mut module = JITModule::new(builder);
+
+        let pointer = module.target_config().pointer_type();
+
+        let mut activate_signature = module.make_signature();
+        activate_signature.params.push(AbiParam::new(pointer));
+        activate_signature.params.push(AbiParam::new(types::F32));
+        activate_signature.returns.push(AbiParam::new(types::F32));
+        let activate_id = module
+            .declare_function("favannat_activate", Linkage::Import, &activate_signature)
+            .map_err(|_| "jit compilation failed")?;
+
+        let mut signature = module.make_signature();
+        signature.params.push(AbiParam::new(pointer));
+        signature.params.push(AbiParam::new(pointer));
+        let function_id = module
+            .declare_function("evaluate", Linkage::Local, &signature)
+            .map_err(|_| "jit compilation failed")?;
+
+        let mut context = module.make_context();
+        context.func.signature = signature;
+        context.func.name = UserFuncName::user(0, function_id.as_u32());
+
+        let inputs = plan.stages.first().map_or(0, |stage| stage.nrows());
+        let outputs = plan.stages.last().map_or(0, |stage| stage.ncols());
+
+        {
+            let mut function_context = FunctionBuilderContext::new();
+            let mut b = FunctionBuilder::new(&mut context.func, &mut function_context);
+            let activate = module.declare_func_in_func(activate_id, b.func);
+
+            let block = b.create_block();
+            b.append_block_params_for_function_params(block);
+            b.switch_to_block(block);
+            let input = b.block_params(block)[0];
+            let output = b.block_params(block)[1];
+
+            let mut state: Vec<Value> = (0..inputs)
+                .map(|index| {
+                    b.ins()
+                        .load(types::F32, MemFlags::trusted(), input, (index * 4) as i32)
+                })
+                .collect();
+
+            for (((stage_matrix, transformations), biases), aggregations) in plan
+                .stages
+                .iter()
+                .zip(&functions)
+                .zip(&plan.biases)
+                .zip(&plan.aggregations)
+            {
+                let mut next = Vec::with_capacity(stage_matrix.ncols());
+
+                for (column_index, column) in stage_matrix.column_iter().enumerate() {
+                    let bias = b.ins().f32const(biases[column_index]);
+
+                    let value = if let Some((_, aggregation, weighted_inputs)) = aggregations
+                        .iter()
+                        .find(|(column, _, _)| *column == column_index)
+                    {
+                        let weighted = weighted_inputs
+                            .iter()
+                            .map(|&(row, weight)| {
+                                let weight = b.ins().f32const(weight);
+                                b.ins().fmul(state[row], weight)
+                            })
+                            .collect::<Vec<_>>();
+
+                        let aggregated = match aggregation {
+                            Aggregation::Sum | Aggregation::Mean => {
+                                let sum = weighted
+                                    .iter()
+                                    .fold(None, |acc, &value| match acc {
+                                        None => Some(value),
+                                        Some(acc) => Some(b.ins().fadd(acc, value)),
+                                    })
+                                    .unwrap_or_else(|| b.ins().f32const(0.0));
+                                if *aggregation == Aggregation::Mean && !weighted.is_empty() {
+                                    let count = b.ins().f32const(weighted.len() as f32);
+                                    b.ins().fdiv(sum, count)
+                                } else {
+                                    sum
+                                }
+                            }
+                            Aggregation::Product => weighted
+                                .iter()
+                                .fold(None, |acc, &value| match acc {
+                                    None => Some(value),
+                                    Some(acc) => Some(b.ins().fmul(acc, value)),
+                                })
+                                .unwrap_or_else(|| b.ins().f32const(1.0)),
+                            Aggregation::Max => weighted
+                                .iter()
+                                .fold(None, |acc, &value| match acc {
+                                    None => Some(value),
+                                    Some(acc) => Some(b.ins().fmax(acc, value)),
+                                })
+                                .unwrap_or_else(|| b.ins().f32const(0.0)),
+                            Aggregation::Min => weighted
+                                .iter()
+                                .fold(None, |acc, &value| match acc {
+                                    None => Some(value),
+                                    Some(acc) => Some(b.ins().fmin(acc, value)),
+                                })
+                                .unwrap_or_else(|| b.ins().f32const(0.0)),
+                        };
+                        b.ins().fadd(aggregated, bias)
+                    } else {
+                        column
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, &weight)| weight != 0.0)
+                            .fold(bias, |acc, (row, &weight)| {
+                                let weighted = if weight == 1.0 {
+                                    state[row]
+                                } else {
+                                    let weight = b.ins().f32const(weight);
+                                    b.ins().fmul(state[row], weight)
+                                };
+                                b.ins().fadd(acc, weighted)
+                            })
+                    };
+
+                    let activation = b
+                        .ins()
+                        .iconst(pointer, transformations[column_index] as usize as i64);
+                    let call = b.ins().call(activate, &[activation, value]);
+                    next.push(b.inst_results(call)[0]);
+                }
+
+                state = next;
+            }
+
+            for (index, &value) in state.iter().enumerate() {
+                b.ins()
+                    .store(MemFlags::trusted(), value, output, (index * 4) as i32);
+            }
+
+            b.ins().return_(&[]);
+            b.seal_all_blocks();
+            b.finalize();
+        }
+
+        module
+            .define_function(function_id, &mut context)
+            .map_err(|_| "jit compilation failed")?;
+        module.clear_context(&mut context);
+        module
+            .finalize_definitions()
+            .map_err(|_| "jit compilation failed")?;
+
+        let code = module.get_finalized_function(function_id);
+
+        Ok(JitEvaluator {
+            module: ManuallyDrop::new(module),
+            // SAFETY: the function was compiled with exactly this signature
+            function: unsafe {
+                std::mem::transmute::<*const u8, extern "C" fn(*const f32, *mut f32)>(code)
+            },
+            inputs,
+            outputs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::JitFabricator;
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Net, Node},
+            Aggregation, Evaluator, Fabricator,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn jit_net_evaluator_0() {
+        let some_net = Net::new(
+            1,
+            2,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(
+                0--0.5->1,
+                1--0.5->2,
+                0--0.5->3,
+                0--0.5->2
+            ),
+        );
+
+        let evaluator = JitFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(evaluator.evaluate(dmatrix![5.0]), dmatrix![3.75, 2.5]);
+    }
+
+    // compares against the matrix backend for activations, biases and aggregations on a batch
+    #[test]
+    fn jit_net_evaluator_1() {
+        let some_net = Net::new(
+            2,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::SIGMOID).with_bias(-1.0),
+                Node::new(3, activations::TANH).with_aggregation(Aggregation::Product),
+                Node::new(4, activations::RELU).with_aggregation(Aggregation::Max),
+            ],
+            edges!(
+                0--1.5->2,
+                1--1.0->2,
+                0--1.0->3,
+                2--0.7->3,
+                2--0.5->4,
+                1--0.25->4
+            ),
+        );
+
+        let evaluator = JitFabricator::fabricate(&some_net).unwrap();
+        let reference = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let input = dmatrix![
+            1.0, 2.0;
+            0.0, 0.0;
+            -2.0, 3.0
+        ];
+
+        assert_eq!(evaluator.evaluate(input.clone()), reference.evaluate(input));
+    }
+}
@@ -0,0 +1,5 @@
+//! Backend compiling the stage pipeline into native code via `cranelift`, available with the feature `jit`.
+//!
+//! Suited for tiny networks where the per call overhead of the matrix backends dominates.
+
+pub mod feedforward;
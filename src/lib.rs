@@ -4,12 +4,110 @@
 //!
 //! Networks accept any value that implements the [`network::NetworkIO`] trait.
 //!
-//! The feature `ndarray` implements `NetworkIO` from `ndarray::Array1` when enabled.
+//! Networks can be fabricated and evaluated with any [`network::Scalar`], e.g. `f64`, and default to `f32`.
+//!
+//! The default feature `std` can be disabled to use the evaluators under `#![no_std]` with `alloc`, e.g. on microcontrollers.
+//! Without it only [`network`], [`analysis`], [`cppn`], [`validation`], [`verify`], [`test_util`], the evaluators of [`matrix`], [`interpreter`], [`boolean`], [`bytecode`], [`ctrnn`], [`esn`], [`spiking`], [`quantized`] and [`neat_original`] are available, networks have to be fabricated elsewhere and transferred.
+//! All other features require `std`.
+//!
+//! The feature `derive` re-exports the derive macros of `favannat-derive` as [`network::NodeLike`], [`network::EdgeLike`] and [`network::NetworkLike`], which assign roles to fields by attributes like `#[node(id)]` or `#[edge(weight)]`.
+//!
+//! The feature `ndarray` implements `NetworkIO` for `ndarray::Array1` samples and `ndarray::Array2` batches with one sample per row.
+//!
+//! The feature `half` enables [`matrix::feedforward::half`] to store stage matrices as `f16`, halving their memory.
+//!
+//! The feature `onnx` enables the `onnx` module to export fabricated networks as ONNX models and import simple ONNX MLPs.
+//!
+//! The feature `parallel` enables the `parallel` module to evaluate many networks at once via `rayon`.
+//!
+//! The feature `graphml` enables [`export::graphml`] to write and read networks as GraphML.
+//!
+//! The feature `ffi` enables the `ffi` module with C bindings to evaluate serialized evaluators on raw buffers.
+//!
+//! The feature `gpu` enables the `gpu` backend which evaluates large batches via `wgpu` compute shaders.
+//!
+//! The feature `jit` enables the `jit` backend which compiles networks to native code via `cranelift`.
+//!
+//! The feature `petgraph` enables [`export::petgraph`] to fabricate `petgraph` graphs and convert networks into them.
+//!
+//! The feature `proptest` adds `test_util::arbitrary_net` and implements `proptest::arbitrary::Arbitrary` for [`network::net::Net`], so downstream crates can property-test against valid random nets.
+//!
+//! The feature `python` enables the `python` module with `pyo3` bindings evaluating numpy arrays.
+//!
+//! The feature `serde` makes [`network::net::Net`] and fabricated evaluators (de)serializable, their activations are stored by name.
+//!
+//! The feature `simd` accelerates the stage multiplications of [`matrix::feedforward::evaluator::MatrixFeedforwardEvaluator`] for `f32` via `wide`.
+//!
+//! The feature `faer` multiplies stages of [`matrix::feedforward::evaluator::MatrixFeedforwardEvaluator`] with at least [`matrix::feedforward::faer::MIN_ENTRIES`] entries for `f32` and `f64` via `faer`, e.g. for large HyperNEAT substrates.
+//!
+//! The feature `svg` enables [`export::svg`] to draw networks layered by their fabrication stages as SVG.
+//!
+//! The feature `tracing` emits `tracing` spans and events while [`matrix::feedforward::fabricator::MatrixFeedforwardFabricator`] resolves dependencies, creates carries and settles on the final stage shapes.
+//!
+//! The feature `wasm` enables the `wasm` module with `wasm-bindgen` bindings evaluating `Float32Array`s.
+//!
+//! All backends except `jit` compile to `wasm32-unknown-unknown`, the `gpu` backend blocks on its results and therefore needs a worker in browsers.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+// lets the derive macros refer to this crate as `::favannat` within it, too
+#[cfg(feature = "derive")]
+extern crate self as favannat;
 
+#[cfg(all(feature = "jit", target_arch = "wasm32"))]
+compile_error!("the feature `jit` generates native code and is not supported on wasm32");
+
+pub mod analysis;
+pub mod boolean;
+pub mod bytecode;
+pub mod cppn;
+pub mod ctrnn;
+pub mod esn;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod interpreter;
+#[cfg(feature = "jit")]
+pub mod jit;
 pub mod matrix;
 pub mod neat_original;
 pub mod network;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quantized;
+mod rng;
+#[cfg(feature = "std")]
+pub mod smart;
+#[cfg(feature = "std")]
 pub mod sparse_matrix;
+pub mod spiking;
+pub mod test_util;
+pub mod validation;
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use analysis::layers;
+pub use network::remap::{remap, IdMapping};
+pub use validation::{validate, ValidationReport};
+
+use alloc::vec::Vec;
 
-type Matrix = Vec<Vec<f32>>;
-type Transformations = Vec<fn(f32) -> f32>;
+#[cfg(feature = "std")]
+type Matrix<S = f32> = Vec<Vec<S>>;
+type Transformations<S = f32> = Vec<network::net::activations::Activation<S>>;
+/// Per stage list of columns whose value is not a weighted sum: (column, aggregation, [(row, weight)]).
+type Aggregations<S = f32> = Vec<(usize, network::Aggregation, Vec<(usize, S)>)>;
+/// Location of edges in fabricated stages: ((start, end), (stage, row, column)).
+type EdgeIndex = Vec<((usize, usize), (usize, usize, usize))>;
+/// Location of computed nodes in fabricated stages: (id, (stage, column)).
+#[cfg(feature = "std")]
+type NodeIndex = Vec<(usize, (usize, usize))>;
@@ -0,0 +1,175 @@
+//! Locates the first stage producing NaN or infinite values, see [`DebugEvaluator`].
+
+use std::fmt;
+
+use nalgebra::DMatrix;
+
+use crate::network::{
+    EdgeLike, Evaluator, FabricationError, NetworkIO, NetworkLike, NodeLike, Scalar,
+};
+
+use super::{evaluator::MatrixFeedforwardEvaluator, fabricator::MatrixFeedforwardFabricator};
+
+/// The first stage whose output is NaN or infinite for some sample, as found by [`DebugEvaluator::check`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NonFinite<S: Scalar = f32> {
+    /// Index of the stage among the unfused stages.
+    pub stage: usize,
+    /// Index of the first affected sample.
+    pub sample: usize,
+    /// Ids of the nodes computed by that stage whose value is not finite.
+    pub node_ids: Vec<usize>,
+    /// The input of the affected sample.
+    pub input: Vec<S>,
+}
+
+impl<S: Scalar> fmt::Display for NonFinite<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "stage {} produced non-finite values at nodes {:?} for sample {} with input {:?}",
+            self.stage, self.node_ids, self.sample, self.input
+        )
+    }
+}
+
+/// Wraps a [`MatrixFeedforwardEvaluator`] and checks the output of every stage for NaN and infinite values.
+///
+/// Stages are not fused, so every stage corresponds to one layer of [`crate::layers`].
+/// [`Evaluator::evaluate`] panics with the [`NonFinite`] report, [`Self::check`] returns it instead.
+#[derive(Debug, Clone)]
+pub struct DebugEvaluator<S: Scalar = f32> {
+    pub evaluator: MatrixFeedforwardEvaluator<S>,
+    /// Id of the node computed by every column of every stage, `None` for columns carrying values.
+    pub node_ids: Vec<Vec<Option<usize>>>,
+}
+
+impl<S: Scalar> DebugEvaluator<S> {
+    pub fn fabricate<N, E>(net: &impl NetworkLike<N, E, S>) -> Result<Self, FabricationError>
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+    {
+        let (evaluator, edges) = MatrixFeedforwardFabricator::fabricate_indexed(net)?;
+        let mut node_ids: Vec<Vec<Option<usize>>> = evaluator
+            .stages
+            .iter()
+            .map(|stage| vec![None; stage.ncols()])
+            .collect();
+        for ((_, end), (stage, _, column)) in edges {
+            node_ids[stage][column] = Some(end);
+        }
+        Ok(Self {
+            evaluator,
+            node_ids,
+        })
+    }
+
+    /// Evaluates all stages and returns the output, or where non-finite values showed up first.
+    pub fn check(&self, input: DMatrix<S>) -> Result<DMatrix<S>, NonFinite<S>> {
+        let mut states = self.evaluator.evaluate_stages(input);
+        for (stage, state) in states.iter().enumerate().skip(1) {
+            let Some(sample) = state
+                .row_iter()
+                .position(|row| row.iter().any(|value| !value.is_finite()))
+            else {
+                continue;
+            };
+            return Err(NonFinite {
+                stage: stage - 1,
+                sample,
+                node_ids: state
+                    .row(sample)
+                    .iter()
+                    .zip(&self.node_ids[stage - 1])
+                    .filter(|(value, _)| !value.is_finite())
+                    .filter_map(|(_, &id)| id)
+                    .collect(),
+                input: states[0].row(sample).iter().copied().collect(),
+            });
+        }
+        Ok(states.pop().unwrap())
+    }
+}
+
+impl<S: Scalar> Evaluator<S> for DebugEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&self, input: T) -> T {
+        match self.check(NetworkIO::input(input)) {
+            Ok(output) => NetworkIO::output(output),
+            Err(report) => panic!("{}", report),
+        }
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.evaluator.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.evaluator.output_size())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::DebugEvaluator;
+    use crate::{
+        edges,
+        network::{
+            net::{activations, Net, Node},
+            Evaluator, FabricationError,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn locate_overflow() {
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::SQUARED),
+                Node::new(2, activations::SQUARED),
+                Node::new(3, activations::LINEAR),
+                Node::new(4, activations::LINEAR),
+            ],
+            edges!(0--1.0->1, 0--1.0->2, 1--1.0->3, 2---1.0->3, 3--1.0->4),
+        );
+        let evaluator = DebugEvaluator::fabricate(&some_net).unwrap();
+
+        assert_eq!(evaluator.evaluate(dmatrix![2.0]), dmatrix![0.0]);
+
+        // both squares overflow to infinity, their difference is NaN
+        let report = evaluator.check(dmatrix![1.0; 1e20]).unwrap_err();
+        assert_eq!(report.stage, 0);
+        assert_eq!(report.sample, 1);
+        assert_eq!(report.node_ids, vec![1, 2]);
+        assert_eq!(report.input, vec![1e20]);
+        assert!(report
+            .to_string()
+            .starts_with("stage 0 produced non-finite values at nodes [1, 2] for sample 1"));
+
+        let bounded = Net::new(1, 1, nodes!('l', 's'), edges!(0--1.0->1));
+        assert!(DebugEvaluator::fabricate(&bounded)
+            .unwrap()
+            .check(dmatrix![1e20])
+            .is_ok());
+    }
+
+    #[test]
+    fn debug_cycle() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--0.5->1, 1--0.5->2, 2--0.5->1, 2--0.5->3),
+        );
+
+        assert_eq!(
+            DebugEvaluator::fabricate(&some_net).unwrap_err(),
+            FabricationError::Cycles(vec![vec![1, 2]])
+        );
+    }
+}
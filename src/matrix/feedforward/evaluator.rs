@@ -1,23 +1,647 @@
+use alloc::{string::String, vec, vec::Vec};
+use core::{cmp::Ordering, fmt};
+
 use nalgebra::DMatrix;
 
-use crate::network::{Evaluator, NetworkIO};
+use crate::{
+    matrix::Normalization,
+    network::{net::activations, Cost, EvaluateInto, Evaluator, NetworkIO, Scalar, Scratch},
+};
 
-#[derive(Debug)]
-pub struct MatrixFeedforwardEvaluator {
-    pub stages: Vec<DMatrix<f32>>,
-    pub transformations: Vec<crate::Transformations>,
+/// Evaluates a fabricated [`crate::network::NetworkLike`] structure by a sequence of matrix multiplications.
+///
+/// The input is interpreted row-wise: every row of the input matrix is a separate sample
+/// and the corresponding row of the output matrix holds the outputs for that sample.
+/// This allows to evaluate a whole batch of samples with one call.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatrixFeedforwardEvaluator<S: Scalar = f32> {
+    pub stages: Vec<DMatrix<S>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::network::serialization"))]
+    pub transformations: Vec<crate::Transformations<S>>,
+    pub biases: Vec<DMatrix<S>>,
+    pub aggregations: Vec<crate::Aggregations<S>>,
+    /// Stages as they were before [`Self::perturb_weights`], restored by [`Self::restore`].
+    #[cfg_attr(feature = "serde", serde(skip, default = "Option::default"))]
+    pub unperturbed: Option<Vec<DMatrix<S>>>,
+    /// Positions of the edges that can be changed by [`Self::set_weight`] as `((start, end), (stage, row, column))`.
+    #[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
+    pub edges: crate::EdgeIndex,
+    /// Edges that have an id as `(id, (start, end), weight)`, see [`Self::set_weight_by_id`].
+    #[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
+    pub edge_ids: Vec<(usize, (usize, usize), S)>,
+    /// Sums the weighted inputs of every node by row with compensation, see [`Self::with_compensated_summation`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub compensated: bool,
+    /// Range every biased sum is clamped into before the activation is applied, see [`Self::with_clamp`].
+    #[cfg_attr(feature = "serde", serde(default = "Option::default"))]
+    pub clamp: Option<(S, S)>,
+    /// Carries copied by index instead of multiplied, one entry per stage, see [`Self::with_gathered_carries`].
+    #[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
+    pub gathers: Vec<Gather>,
 }
 
-impl Evaluator for MatrixFeedforwardEvaluator {
-    fn evaluate<T: NetworkIO>(&self, state: T) -> T {
-        let mut state = NetworkIO::input(state);
-        // performs evaluation by sequentially matrix multiplying and transforming the state with every stage
-        for (stage_matrix, transformations) in self.stages.iter().zip(&self.transformations) {
-            state *= stage_matrix;
-            for (value, activation) in state.iter_mut().zip(transformations) {
-                *value = activation(*value);
+/// Layout of a stage whose carries were removed from its matrix by [`MatrixFeedforwardEvaluator::with_gathered_carries`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gather {
+    /// Column of the stage output every column of the stage matrix is written to.
+    pub computed: Vec<usize>,
+    /// Carried values as `(column, row)`, the input value in `row` is copied to `column` of the stage output.
+    pub carries: Vec<(usize, usize)>,
+}
+
+impl<S: Scalar> MatrixFeedforwardEvaluator<S> {
+    // uses faer for large stages, explicit SIMD for f32 and falls back to the generic multiplication otherwise
+    fn multiply(state: &DMatrix<S>, stage_matrix: &DMatrix<S>) -> DMatrix<S> {
+        #[cfg(feature = "faer")]
+        if let Some(product) = super::faer::multiply(state, stage_matrix) {
+            return product;
+        }
+        #[cfg(feature = "simd")]
+        if let Some(product) = super::simd::multiply(state, stage_matrix) {
+            return product;
+        }
+        state * stage_matrix
+    }
+}
+
+/// Columns of a stage sharing an activation function.
+type Group<S> = (fn(S) -> S, Vec<usize>);
+
+/// Sums `weights * inputs` in order with Neumaier's compensation, so rounding errors do not accumulate.
+fn compensated_sum<'a, S: Scalar>(
+    weights: impl Iterator<Item = &'a S>,
+    inputs: impl Iterator<Item = &'a S>,
+) -> S {
+    let (mut sum, mut compensation) = (S::zero(), S::zero());
+    for (&weight, &input) in weights.zip(inputs) {
+        let term = weight * input;
+        let next = sum + term;
+        compensation += if sum.abs() >= term.abs() {
+            (sum - next) + term
+        } else {
+            (term - next) + sum
+        };
+        sum = next;
+    }
+    sum + compensation
+}
+
+impl<S: Scalar> MatrixFeedforwardEvaluator<S> {
+    fn clamped(&self, value: S) -> S {
+        match self.clamp {
+            Some((min, max)) => value.clamp(min, max),
+            None => value,
+        }
+    }
+
+    /// Computes a single stage, i.e. the values of the next layer of nodes and carries.
+    fn stage(&self, index: usize, input: DMatrix<S>) -> DMatrix<S> {
+        let stage = &self.stages[index];
+        let product = if self.compensated {
+            DMatrix::from_fn(input.nrows(), stage.ncols(), |sample, column| {
+                compensated_sum(stage.column(column).iter(), input.row(sample).iter())
+            })
+        } else {
+            Self::multiply(&input, stage)
+        };
+        let mut state = match self.gathers.get(index) {
+            Some(gather) => {
+                let mut state = DMatrix::zeros(input.nrows(), self.transformations[index].len());
+                for (column, &target) in gather.computed.iter().enumerate() {
+                    state.set_column(target, &product.column(column));
+                }
+                for &(column, row) in &gather.carries {
+                    state.set_column(column, &input.column(row));
+                }
+                state
+            }
+            None => product,
+        };
+        // compute values of nodes that do not sum their inputs for every sample
+        for (column, aggregation, weighted_inputs) in &self.aggregations[index] {
+            for sample in 0..input.nrows() {
+                state[(sample, *column)] = aggregation.aggregate(
+                    weighted_inputs
+                        .iter()
+                        .map(|&(row, weight)| input[(sample, row)] * weight),
+                );
+            }
+        }
+        self.activate(index, &mut state);
+        state
+    }
+
+    /// Adds the biases and applies the activations of stage `index` column by column, i.e. per node for all samples.
+    ///
+    /// Columns sharing an activation function are grouped, so the activation is resolved once per group and not for every value.
+    fn activate(&self, index: usize, state: &mut DMatrix<S>) {
+        let mut groups: Vec<Group<S>> = Vec::new();
+        for (column, activation) in self.transformations[index].iter().enumerate() {
+            match activation {
+                activations::Activation::Function(function) => {
+                    match groups
+                        .iter_mut()
+                        .find(|(grouped, _)| core::ptr::fn_addr_eq(*grouped, *function))
+                    {
+                        Some((_, columns)) => columns.push(column),
+                        None => groups.push((*function, vec![column])),
+                    }
+                }
+                activations::Activation::Closure(closure) => {
+                    self.map_column(index, state, column, |value| (closure.0)(value))
+                }
+                activations::Activation::Table(table) => {
+                    self.map_column(index, state, column, |value| table.apply(value))
+                }
+            }
+        }
+        for (function, columns) in groups {
+            for column in columns {
+                self.map_column(index, state, column, function);
+            }
+        }
+    }
+
+    fn map_column(
+        &self,
+        index: usize,
+        state: &mut DMatrix<S>,
+        column: usize,
+        activation: impl Fn(S) -> S,
+    ) {
+        let bias = self.biases[index][column];
+        state
+            .column_mut(column)
+            .apply(|value| *value = activation(self.clamped(*value + bias)));
+    }
+
+    /// Returns the input followed by the output of every stage.
+    pub(crate) fn evaluate_stages(&self, input: DMatrix<S>) -> Vec<DMatrix<S>> {
+        let mut states = vec![input];
+        for index in 0..self.stages.len() {
+            let state = self.stage(index, states[index].clone());
+            states.push(state);
+        }
+        states
+    }
+
+    /// Computes a single stage for a single sample, `output` receives one value per column.
+    fn stage_into(&self, index: usize, input: &[S], output: &mut [S]) {
+        // nalgebra stores column-major, so every column is contiguous
+        for (column, weights) in self.stages[index].column_iter().enumerate() {
+            output[self.output_column(index, column)] = if self.compensated {
+                compensated_sum(weights.iter(), input.iter())
+            } else {
+                weights
+                    .iter()
+                    .zip(input)
+                    .fold(S::zero(), |sum, (&weight, &input)| sum + weight * input)
+            };
+        }
+        if let Some(gather) = self.gathers.get(index) {
+            for &(column, row) in &gather.carries {
+                output[column] = input[row];
+            }
+        }
+        for (column, aggregation, weighted_inputs) in &self.aggregations[index] {
+            output[*column] = aggregation.aggregate(
+                weighted_inputs
+                    .iter()
+                    .map(|&(row, weight)| input[row] * weight),
+            );
+        }
+        for ((value, activation), &bias) in output
+            .iter_mut()
+            .zip(&self.transformations[index])
+            .zip(self.biases[index].iter())
+        {
+            *value = activation.apply(self.clamped(*value + bias));
+        }
+    }
+
+    /// Evaluates all stages on the leading values of `scratch.current`, which holds the output at its start afterwards.
+    pub(crate) fn evaluate_scratch(&self, scratch: &mut Scratch<S>) {
+        for (index, (stage, columns)) in self.stages.iter().zip(&self.transformations).enumerate() {
+            self.stage_into(
+                index,
+                &scratch.current[..stage.nrows()],
+                &mut scratch.next[..columns.len()],
+            );
+            core::mem::swap(&mut scratch.current, &mut scratch.next);
+        }
+    }
+
+    /// Estimates the cost of evaluating a single sample, every stage entry is multiplied once.
+    pub fn cost(&self) -> Cost {
+        Cost {
+            multiply_adds: self.stages.iter().map(|stage| stage.len()).sum::<usize>()
+                + Cost::aggregated_inputs(&self.aggregations),
+            stages: self.stages.len(),
+            matrix_bytes: self
+                .stages
+                .iter()
+                .chain(&self.biases)
+                .map(|matrix| matrix.len())
+                .sum::<usize>()
+                * core::mem::size_of::<S>(),
+        }
+    }
+
+    /// Number of values every sample provides as input.
+    pub fn input_size(&self) -> usize {
+        self.stages.first().map_or(0, |stage| stage.nrows())
+    }
+
+    /// Number of values evaluated for every sample.
+    pub fn output_size(&self) -> usize {
+        self.transformations
+            .last()
+            .map_or(0, |columns| columns.len())
+    }
+
+    pub fn stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Dimensions of every stage as `(rows, columns)`, i.e. the number of values entering and leaving it.
+    pub fn stage_dimensions(&self) -> Vec<(usize, usize)> {
+        self.stages
+            .iter()
+            .zip(&self.transformations)
+            .map(|(stage, columns)| (stage.nrows(), columns.len()))
+            .collect()
+    }
+
+    /// Draws the stages as terminal friendly ASCII diagram, e.g. to debug without graphviz.
+    ///
+    /// Every stage lists its dimensions and one letter per column as used by the `nodes!` macro,
+    /// `=` marks carries, `?` activations without a letter and non-sum aggregations follow in parentheses, e.g. `t(max)`.
+    pub fn render_ascii(&self) -> String {
+        crate::matrix::render_stages(&self.stage_dimensions(), |index, column| {
+            self.describe(index, column)
+        })
+    }
+
+    fn describe(&self, index: usize, column: usize) -> String {
+        crate::matrix::describe_column(
+            &self.transformations[index][column],
+            self.aggregations[index]
+                .iter()
+                .find(|(aggregated, _, _)| *aggregated == column)
+                .map(|&(_, aggregation, _)| aggregation),
+            self.is_carry(index, column),
+        )
+    }
+
+    /// Normalizes the inputs as part of the evaluation, so raw values can be passed in.
+    ///
+    /// The normalization is folded into the first stage unless it aggregates inputs other than by sum,
+    /// in that case a linear stage is added in front. Folding makes edges starting at normalized inputs unaddressable.
+    pub fn with_normalization(
+        mut self,
+        normalization: &Normalization<S>,
+    ) -> Result<Self, &'static str> {
+        let (scale, offset) = normalization.affine()?;
+        if scale.len() > self.input_size() {
+            return Err("normalization given for more values than there are inputs");
+        }
+        // carries copied by index can not be scaled
+        let fold = self
+            .aggregations
+            .first()
+            .is_some_and(|aggregations| aggregations.is_empty())
+            && self
+                .gathers
+                .first()
+                .is_none_or(|gather| gather.carries.is_empty());
+        self.normalize(&scale, &offset, fold);
+        Ok(self)
+    }
+
+    /// Sums the weighted inputs of every node in the order of the rows of its stage with Neumaier's compensated summation.
+    ///
+    /// Outputs then no longer depend on how the matrix multiplication groups its sums, e.g. with the feature `simd`, and barely on how carries order the rows,
+    /// at the price of a slower evaluation without vectorization.
+    pub fn with_compensated_summation(mut self) -> Self {
+        self.compensated = true;
+        self
+    }
+
+    /// Clamps the biased sum of every node and carry into `[min, max]` before its activation is applied.
+    ///
+    /// This keeps chains of unbounded activations like squared or inverse from overflowing to infinity or NaN in deep nets.
+    /// Carried values are clamped as well, so the range should cover all values of interest.
+    pub fn with_clamp(mut self, min: S, max: S) -> Result<Self, &'static str> {
+        if !matches!(
+            min.partial_cmp(&max),
+            Some(Ordering::Less | Ordering::Equal)
+        ) {
+            return Err("clamp range needs min not above max");
+        }
+        self.clamp = Some((min, max));
+        Ok(self)
+    }
+
+    /// Approximates sigmoid, tanh and gaussian activations by linear interpolation in tables of `resolution` entries over `[-range, range]`.
+    ///
+    /// This trades precision for speed where evaluating `exp` dominates, e.g. with small nets, outside the range the values at its ends are used.
+    /// Tables are not serialized, deserialized evaluators apply the exact activations again.
+    pub fn with_lookup_tables(mut self, resolution: usize, range: S) -> Self {
+        crate::matrix::tabulate(&mut self.transformations, resolution, range);
+        self
+    }
+
+    /// Applies `input * scale + offset` to the leading inputs, either folded into the first stage or as a stage of its own.
+    pub(crate) fn normalize(&mut self, scale: &[S], offset: &[S], fold: bool) {
+        let rows = self.input_size();
+        let offsets = DMatrix::from_fn(1, rows, |_, row| {
+            offset.get(row).copied().unwrap_or_else(S::zero)
+        });
+
+        if fold {
+            // (input * scale + offset) * stage = input * (scale * stage) + offset * stage, without any perturbation
+            let unperturbed = self
+                .unperturbed
+                .as_ref()
+                .map_or(&self.stages[0], |stages| &stages[0]);
+            for (column, offset) in (&offsets * unperturbed).iter().enumerate() {
+                let column = self
+                    .gathers
+                    .first()
+                    .map_or(column, |gather| gather.computed[column]);
+                self.biases[0][column] += *offset;
+            }
+            for stages in core::iter::once(&mut self.stages).chain(self.unperturbed.as_mut()) {
+                for (row, &scale) in scale.iter().enumerate() {
+                    stages[0].row_mut(row).scale_mut(scale);
+                }
+            }
+            // weights of normalized inputs now hold the scale and determine the folded offset
+            self.edges
+                .retain(|&(_, (stage, row, _))| stage > 0 || row >= scale.len());
+        } else {
+            let mut stage = DMatrix::identity(rows, rows);
+            for (row, &scale) in scale.iter().enumerate() {
+                stage[(row, row)] = scale;
+            }
+            if let Some(unperturbed) = self.unperturbed.as_mut() {
+                unperturbed.insert(0, stage.clone());
             }
+            self.stages.insert(0, stage);
+            self.transformations.insert(
+                0,
+                vec![activations::Activation::Function(activations::linear); rows],
+            );
+            self.biases.insert(0, offsets);
+            self.aggregations.insert(0, Vec::new());
+            for (_, (stage, _, _)) in self.edges.iter_mut() {
+                *stage += 1;
+            }
+            if !self.gathers.is_empty() {
+                self.gathers.insert(
+                    0,
+                    Gather {
+                        computed: (0..rows).collect(),
+                        carries: Vec::new(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl<S: Scalar> MatrixFeedforwardEvaluator<S> {
+    /// Returns true if the column only passes on a single value, i.e. has a single unit weight, no bias and a linear activation.
+    pub(crate) fn is_carry(&self, index: usize, column: usize) -> bool {
+        if let Some(gather) = self.gathers.get(index) {
+            return gather.carries.iter().any(|&(carry, _)| carry == column);
+        }
+        let weights = self.stages[index].column(column);
+        self.biases[index][column].is_zero()
+            && weights.iter().filter(|weight| !weight.is_zero()).count() == 1
+            && weights.iter().any(|weight| weight.is_one())
+            && self.transformations[index][column].is_linear()
+    }
+
+    /// Column of the stage output the given column of the stage matrix is written to.
+    fn output_column(&self, index: usize, column: usize) -> usize {
+        self.gathers
+            .get(index)
+            .map_or(column, |gather| gather.computed[column])
+    }
+
+    /// Removes every carry from the stage matrices and copies the carried values by index instead.
+    ///
+    /// Deep and narrow nets carry many values through every stage, each a column of zeros but a single one,
+    /// so dropping them shrinks the matrices and the multiplications. Stage outputs keep their layout and edges stay addressable.
+    /// Evaluators built from the stages, e.g. by exports or other backends, expect full stage matrices and need to be built before gathering.
+    pub fn with_gathered_carries(mut self) -> Self {
+        if !self.gathers.is_empty() {
+            return self;
+        }
+        let mut gathers: Vec<Gather> = Vec::new();
+        for index in 0..self.stages.len() {
+            let mut gather = Gather::default();
+            for column in 0..self.stages[index].ncols() {
+                // nodes that look like carries keep their column, so their edges stay addressable
+                let addressed = self
+                    .edges
+                    .iter()
+                    .any(|&(_, (stage, _, end))| (stage, end) == (index, column));
+                if self.is_carry(index, column) && !addressed {
+                    gather
+                        .carries
+                        .push((column, self.stages[index].column(column).iamax()));
+                } else {
+                    gather.computed.push(column);
+                }
+            }
+            gathers.push(gather);
+        }
+
+        for (stage, gather) in self.stages.iter_mut().zip(&gathers) {
+            *stage = stage.select_columns(&gather.computed);
+        }
+        for (_, (stage, _, column)) in self.edges.iter_mut() {
+            *column = gathers[*stage]
+                .computed
+                .iter()
+                .position(|computed| computed == column)
+                .expect("edges only address computed columns");
+        }
+        if let Some(unperturbed) = self.unperturbed.as_mut() {
+            for (stage, gather) in unperturbed.iter_mut().zip(&gathers) {
+                *stage = stage.select_columns(&gather.computed);
+            }
+        }
+        self.gathers = gathers;
+        self
+    }
+
+    /// Adds `noise(stage, entry)` to every non-zero weight of the stages, `entry` is the column-major position in the stage matrix.
+    ///
+    /// Perturbations do not accumulate, every call starts from the weights before the first one.
+    /// Carries and inputs of nodes not summing them are left as they are.
+    /// Evolution strategies can evaluate e.g. antithetic samples by perturbing with negated noise before calling [`Self::restore`].
+    pub fn perturb_weights(&mut self, noise: &impl Fn(usize, usize) -> S) {
+        match &self.unperturbed {
+            Some(unperturbed) => self.stages.clone_from(unperturbed),
+            None => self.unperturbed = Some(self.stages.clone()),
+        }
+        for index in 0..self.stages.len() {
+            for column in 0..self.stages[index].ncols() {
+                if self.is_carry(index, self.output_column(index, column)) {
+                    continue;
+                }
+                let rows = self.stages[index].nrows();
+                for (row, weight) in self.stages[index].column_mut(column).iter_mut().enumerate() {
+                    if !weight.is_zero() {
+                        *weight += noise(index, column * rows + row);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reverts all perturbations made by [`Self::perturb_weights`].
+    pub fn restore(&mut self) {
+        if let Some(unperturbed) = self.unperturbed.take() {
+            self.stages = unperturbed;
+        }
+    }
+
+    /// Returns the weight of the edge from `start` to `end`, if it is listed in [`Self::edges`].
+    pub fn weight(&self, start: usize, end: usize) -> Option<S> {
+        self.position(start, end)
+            .map(|(stage, row, column)| self.stages[stage][(row, column)])
+    }
+
+    /// Changes the weight of the edge from `start` to `end` in place.
+    ///
+    /// Only edges listed in [`Self::edges`] can be changed, edges into nodes not summing their inputs,
+    /// with a response other than one or whose stage was fused with a preceding one are not.
+    pub fn set_weight(&mut self, start: usize, end: usize, weight: S) -> Result<(), &'static str> {
+        let (stage, row, column) = self
+            .position(start, end)
+            .ok_or("edge is not addressable in the stages")?;
+        self.set_entry(stage, row, column, weight);
+        Ok(())
+    }
+
+    /// Returns the weights of all edges listed in [`Self::edges`] in that order, e.g. as the search space of CMA-ES.
+    ///
+    /// Biases are not included, see [`crate::network::net::Net::parameters`] to optimize them as well.
+    pub fn parameters(&self) -> Vec<S> {
+        self.edges
+            .iter()
+            .map(|&(_, (stage, row, column))| self.stages[stage][(row, column)])
+            .collect()
+    }
+
+    /// Changes the weights of all edges listed in [`Self::edges`] in place, ordered like [`Self::parameters`].
+    pub fn set_parameters(&mut self, parameters: &[S]) -> Result<(), &'static str> {
+        if parameters.len() != self.edges.len() {
+            return Err("one parameter per addressable edge is needed");
+        }
+        for (index, &weight) in parameters.iter().enumerate() {
+            let (_, (stage, row, column)) = self.edges[index];
+            self.set_entry(stage, row, column, weight);
+        }
+        Ok(())
+    }
+
+    /// Returns the weight of the edge with the given id, parallel edges are summed in the stages but keep their own weights here.
+    pub fn weight_by_id(&self, id: usize) -> Option<S> {
+        self.edge_ids
+            .iter()
+            .find(|&&(edge, _, _)| edge == id)
+            .map(|&(_, _, weight)| weight)
+    }
+
+    /// Changes the weight of the edge with the given id in place, the weights of parallel edges are kept.
+    ///
+    /// The edge needs to be addressable like for [`Self::set_weight`].
+    pub fn set_weight_by_id(&mut self, id: usize, weight: S) -> Result<(), &'static str> {
+        let index = self
+            .edge_ids
+            .iter()
+            .position(|&(edge, _, _)| edge == id)
+            .ok_or("no edge with this id")?;
+        let ((start, end), previous) = (self.edge_ids[index].1, self.edge_ids[index].2);
+        let (stage, row, column) = self
+            .position(start, end)
+            .ok_or("edge is not addressable in the stages")?;
+        // the entry holds the sum of all parallel edges, perturbations aside
+        let stages = self.unperturbed.as_ref().unwrap_or(&self.stages);
+        let summed = stages[stage][(row, column)];
+        self.set_entry(stage, row, column, summed - previous + weight);
+        self.edge_ids[index].2 = weight;
+        Ok(())
+    }
+
+    fn position(&self, start: usize, end: usize) -> Option<(usize, usize, usize)> {
+        self.edges
+            .iter()
+            .find(|&&(edge, _)| edge == (start, end))
+            .map(|&(_, position)| position)
+    }
+
+    /// Sets a stage entry, perturbed weights keep their noise and are restored to the new weight.
+    pub(crate) fn set_entry(&mut self, stage: usize, row: usize, column: usize, weight: S) {
+        if let Some(unperturbed) = self.unperturbed.as_mut() {
+            let noise = self.stages[stage][(row, column)] - unperturbed[stage][(row, column)];
+            unperturbed[stage][(row, column)] = weight;
+            self.stages[stage][(row, column)] = weight + noise;
+        } else {
+            self.stages[stage][(row, column)] = weight;
         }
-        NetworkIO::output(state)
+    }
+}
+
+impl<S: Scalar> Evaluator<S> for MatrixFeedforwardEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&self, state: T) -> T {
+        // performs evaluation by sequentially matrix multiplying and transforming the state with every stage
+        NetworkIO::output(
+            (0..self.stages.len()).fold(NetworkIO::input(state), |state, index| {
+                self.stage(index, state)
+            }),
+        )
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
+
+impl<S: Scalar> EvaluateInto<S> for MatrixFeedforwardEvaluator<S> {
+    fn scratch(&self) -> Scratch<S> {
+        Scratch::new(
+            self.stage_dimensions()
+                .into_iter()
+                .map(|(rows, columns)| rows.max(columns))
+                .max()
+                .unwrap_or(0),
+        )
+    }
+
+    fn evaluate_into(&self, input: &[S], output: &mut [S], scratch: &mut Scratch<S>) {
+        scratch.current[..self.input_size()].copy_from_slice(input);
+        self.evaluate_scratch(scratch);
+        output.copy_from_slice(&scratch.current[..self.output_size()]);
+    }
+}
+
+/// Prints one line per stage with its dimensions and the activation of every column, columns only passing on a value are shown as carries.
+impl<S: Scalar> fmt::Display for MatrixFeedforwardEvaluator<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::matrix::fmt_stages(f, &self.stage_dimensions(), |index, column| {
+            self.describe(index, column)
+        })
     }
 }
@@ -1,11 +1,105 @@
-use crate::network::{EdgeLike, Fabricator, NetworkLike, NodeLike};
+use crate::{
+    network::{
+        module::flatten,
+        net::{activations, Edge},
+        prune::{active_edges, copy_net, prune},
+        remap::fabricable,
+        Aggregation, EdgeLike, FabricationError, Fabricator, Modular, NetworkLike, NodeId,
+        NodeLike, Scalar,
+    },
+    validation::{fabrication_warnings, FabricationWarning},
+};
 use nalgebra::{DMatrix, DVector};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 pub struct MatrixFeedforwardFabricator;
 
+/// Stage layout of a net together with the positions of its edges and nodes, see [`MatrixFeedforwardFabricator::refabricate`].
+#[derive(Debug, Clone)]
+pub struct FabricationPlan<S: Scalar = f32> {
+    evaluator: super::evaluator::MatrixFeedforwardEvaluator<S>,
+    edges: crate::EdgeIndex,
+    nodes: crate::NodeIndex,
+}
+
+/// Settings of a [`ConfiguredFabricator`], the defaults fabricate like [`Fabricator::fabricate`] does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FabricatorOptions<S: Scalar = f32> {
+    /// Removes hidden nodes that can not reach any output before fabrication, see [`prune`]. Defaults to `false`.
+    pub prune_dead_nodes: bool,
+    /// Merges stages that only combine values linearly into the following stage. Defaults to `true`.
+    pub fuse_linear_stages: bool,
+    /// See [`super::evaluator::MatrixFeedforwardEvaluator::with_compensated_summation`]. Defaults to `false`.
+    pub compensated_summation: bool,
+    /// See [`super::evaluator::MatrixFeedforwardEvaluator::with_gathered_carries`]. Defaults to `false`.
+    pub gather_carries: bool,
+    /// Range as `(min, max)`, see [`super::evaluator::MatrixFeedforwardEvaluator::with_clamp`]. Defaults to none.
+    pub clamp: Option<(S, S)>,
+    /// Resolution and range, see [`super::evaluator::MatrixFeedforwardEvaluator::with_lookup_tables`]. Defaults to none.
+    pub lookup_tables: Option<(usize, S)>,
+}
+
+impl<S: Scalar> Default for FabricatorOptions<S> {
+    fn default() -> Self {
+        Self {
+            prune_dead_nodes: false,
+            fuse_linear_stages: true,
+            compensated_summation: false,
+            gather_carries: false,
+            clamp: None,
+            lookup_tables: None,
+        }
+    }
+}
+
+/// Fabricates like [`MatrixFeedforwardFabricator`] with the settings of its [`FabricatorOptions`], see [`MatrixFeedforwardFabricator::with_options`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ConfiguredFabricator<S: Scalar = f32> {
+    pub options: FabricatorOptions<S>,
+}
+
+impl<S: Scalar> ConfiguredFabricator<S> {
+    pub fn fabricate<N, E>(
+        &self,
+        net: &impl NetworkLike<N, E, S>,
+    ) -> Result<super::evaluator::MatrixFeedforwardEvaluator<S>, FabricationError>
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+    {
+        let options = &self.options;
+        let mut evaluator = if options.prune_dead_nodes {
+            MatrixFeedforwardFabricator::fabricate_indexed(&prune(&copy_net(net)))?.0
+        } else {
+            MatrixFeedforwardFabricator::fabricate_indexed(net)?.0
+        };
+        if options.fuse_linear_stages {
+            evaluator = MatrixFeedforwardFabricator::fuse_stages(evaluator);
+        }
+        if options.compensated_summation {
+            evaluator = evaluator.with_compensated_summation();
+        }
+        if let Some((min, max)) = options.clamp {
+            evaluator = evaluator.with_clamp(min, max)?;
+        }
+        if let Some((resolution, range)) = options.lookup_tables {
+            evaluator = evaluator.with_lookup_tables(resolution, range);
+        }
+        // carries are detected by their matrix columns, so they are gathered last
+        if options.gather_carries {
+            evaluator = evaluator.with_gathered_carries();
+        }
+        Ok(evaluator)
+    }
+}
+
 impl MatrixFeedforwardFabricator {
-    fn get_matrix(dynamic_matrix: Vec<Vec<f32>>) -> DMatrix<f32> {
+    /// Returns a fabricator configured by `options`, e.g. `MatrixFeedforwardFabricator::with_options(FabricatorOptions { prune_dead_nodes: true, ..Default::default() })`.
+    pub fn with_options<S: Scalar>(options: FabricatorOptions<S>) -> ConfiguredFabricator<S> {
+        ConfiguredFabricator { options }
+    }
+
+    fn get_matrix<S: Scalar>(dynamic_matrix: Vec<Vec<S>>) -> DMatrix<S> {
         let columns = dynamic_matrix
             .into_iter()
             .map(DVector::from_vec)
@@ -13,75 +107,378 @@ impl MatrixFeedforwardFabricator {
 
         DMatrix::from_columns(&columns)
     }
+
+    /// Merges every stage that only linearly combines or carries values into the following stage.
+    ///
+    /// A stage qualifies if all its activations are [`activations::linear`], its biases are zero and all its nodes sum their inputs,
+    /// the following stage needs to sum its inputs as well. Both matrices are multiplied at fabrication time,
+    /// unless the product would have more entries than both factors together.
+    ///
+    /// Edges of the second stage stay addressable if the first stage only carries values, as their weights are copied into the product unchanged.
+    /// Edges of the first stage are folded into the product and can no longer be addressed.
+    fn fuse_stages<S: Scalar>(
+        mut evaluator: super::evaluator::MatrixFeedforwardEvaluator<S>,
+    ) -> super::evaluator::MatrixFeedforwardEvaluator<S> {
+        let mut index = 0;
+        while index + 1 < evaluator.stages.len() {
+            let (first, second) = (&evaluator.stages[index], &evaluator.stages[index + 1]);
+            let fusable = evaluator.aggregations[index].is_empty()
+                && evaluator.aggregations[index + 1].is_empty()
+                && evaluator.biases[index].iter().all(|bias| bias.is_zero())
+                && evaluator.transformations[index]
+                    .iter()
+                    .all(|activation| activation.is_linear())
+                && first.nrows() * second.ncols() <= first.len() + second.len();
+
+            if fusable {
+                // rows of the product are the rows carried by the first stage, if that is all it does
+                let carried: Option<Vec<usize>> = (0..first.ncols())
+                    .map(|column| {
+                        evaluator
+                            .is_carry(index, column)
+                            .then(|| first.column(column).iamax())
+                    })
+                    .collect();
+                evaluator.edges.retain_mut(|(_, (stage, row, _))| {
+                    if *stage == index {
+                        return false;
+                    }
+                    if *stage == index + 1 {
+                        match &carried {
+                            Some(carried) => *row = carried[*row],
+                            None => return false,
+                        }
+                    }
+                    if *stage > index {
+                        *stage -= 1;
+                    }
+                    true
+                });
+
+                let first = evaluator.stages.remove(index);
+                evaluator.stages[index] = first * &evaluator.stages[index];
+                evaluator.transformations.remove(index);
+                evaluator.biases.remove(index);
+                evaluator.aggregations.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+        evaluator
+    }
 }
 
-impl<N, E> Fabricator<N, E> for MatrixFeedforwardFabricator
+impl<N, E, S, I> Fabricator<N, E, S, I> for MatrixFeedforwardFabricator
 where
-    N: NodeLike,
-    E: EdgeLike,
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
 {
-    type Output = super::evaluator::MatrixFeedforwardEvaluator;
+    type Output = super::evaluator::MatrixFeedforwardEvaluator<S>;
+
+    fn fabricate(net: &impl NetworkLike<N, E, S, I>) -> Result<Self::Output, FabricationError> {
+        let net = &fabricable(net);
+        let evaluator = Self::fuse_stages(Self::fabricate_indexed(net)?.0);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(stages = ?evaluator.stage_dimensions(), "fabricated stages");
+        Ok(evaluator)
+    }
+}
+
+impl MatrixFeedforwardFabricator {
+    /// Resolves the stage layout of `net` once, so nets differing only in their parameters can be fabricated by [`Self::refabricate`].
+    pub fn plan<N, E, S>(
+        net: &impl NetworkLike<N, E, S>,
+    ) -> Result<FabricationPlan<S>, FabricationError>
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+        S: Scalar,
+    {
+        Self::fabricate_located(net, false).map(|(evaluator, edges, nodes)| FabricationPlan {
+            evaluator,
+            edges,
+            nodes,
+        })
+    }
+
+    /// Fabricates like [`Fabricator::fabricate`] and lists the [`FabricationWarning`]s of `net`, so they can be logged without failing.
+    pub fn fabricate_with_warnings<N, E, S>(
+        net: &impl NetworkLike<N, E, S>,
+    ) -> Result<
+        (
+            super::evaluator::MatrixFeedforwardEvaluator<S>,
+            Vec<FabricationWarning>,
+        ),
+        FabricationError,
+    >
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+        S: Scalar,
+    {
+        Ok((Self::fabricate(net)?, fabrication_warnings(net)))
+    }
+
+    /// Fabricates a [`Modular`] structure with all its modules spliced in by [`flatten`].
+    pub fn fabricate_modular<N, E, S, I>(
+        net: &impl Modular<N, E, S, I>,
+    ) -> Result<super::evaluator::MatrixFeedforwardEvaluator<S>, FabricationError>
+    where
+        N: NodeLike<S, I>,
+        E: EdgeLike<S, I>,
+        S: Scalar,
+        I: NodeId,
+    {
+        Self::fabricate(&flatten(net)?)
+    }
+
+    /// Fabricates `net` by writing its parameters into the stages of `plan`, skipping the dependency resolution.
+    ///
+    /// `net` needs the same edges and aggregations as the net `plan` was made from, weights, biases, responses and activations may differ.
+    /// Weights changing from or to zero can change which edges are evaluated, such nets are rejected and need to be fabricated.
+    pub fn refabricate<N, E, S>(
+        plan: &FabricationPlan<S>,
+        net: &impl NetworkLike<N, E, S>,
+    ) -> Result<super::evaluator::MatrixFeedforwardEvaluator<S>, &'static str>
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+        S: Scalar,
+    {
+        let net_nodes = net.nodes();
+        let nodes: BTreeMap<usize, &N> = net_nodes.iter().map(|&node| (node.id(), node)).collect();
+        let positions: BTreeMap<(usize, usize), (usize, usize, usize)> =
+            plan.edges.iter().copied().collect();
+        let edges = active_edges(&net_nodes, net.edges());
+        if edges.len() != positions.len() {
+            return Err("edges differ from plan, net needs to be fabricated");
+        }
+
+        let mut evaluator = plan.evaluator.clone();
+        // weighted inputs of columns not summing them are collected anew by `(stage, column, end)`
+        let mut weighted: BTreeMap<(usize, usize, usize), Vec<(usize, S)>> = BTreeMap::new();
+        for edge in edges {
+            let &(stage, row, column) = positions
+                .get(&(edge.start(), edge.end()))
+                .ok_or("edges differ from plan, net needs to be fabricated")?;
+            let node = nodes[&edge.end()];
+            let aggregated = evaluator.aggregations[stage]
+                .iter()
+                .any(|&(aggregated, _, _)| aggregated == column);
+            if aggregated == (node.aggregation() == Aggregation::Sum) {
+                return Err("aggregations differ from plan, net needs to be fabricated");
+            }
+
+            if aggregated {
+                weighted
+                    .entry((stage, column, edge.end()))
+                    .or_default()
+                    .push((row, edge.weight()));
+            } else {
+                evaluator.stages[stage][(row, column)] = edge.weight() * node.response();
+            }
+        }
+        for &(id, (stage, column)) in &plan.nodes {
+            let node = nodes
+                .get(&id)
+                .ok_or("nodes differ from plan, net needs to be fabricated")?;
+            evaluator.biases[stage][column] = node.bias();
+            evaluator.transformations[stage][column] = activations::Activation::of(*node);
+        }
+        for ((stage, column, end), mut weighted_inputs) in weighted {
+            let node = nodes[&end];
+            let aggregation = node
+                .aggregation()
+                .respond(&mut weighted_inputs, node.response());
+            for entry in evaluator.aggregations[stage].iter_mut() {
+                if entry.0 == column {
+                    *entry = (column, aggregation, weighted_inputs);
+                    break;
+                }
+            }
+        }
 
-    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str> {
-        // build dependency graph by collecting incoming edges per node
-        let mut dependency_graph: HashMap<usize, Vec<&E>> = HashMap::new();
+        evaluator.edges = Self::addressable(&nodes, &evaluator, &plan.edges);
+        evaluator.edge_ids = Self::identified(net);
+
+        Ok(Self::fuse_stages(evaluator))
+    }
 
-        for edge in net.edges() {
-            dependency_graph
-                .entry(edge.end())
-                .and_modify(|dependencies| dependencies.push(edge))
-                .or_insert_with(|| vec![edge]);
+    /// Lists the enabled edges having an id as `(id, (start, end), weight)`.
+    fn identified<N, E, S>(net: &impl NetworkLike<N, E, S>) -> Vec<(usize, (usize, usize), S)>
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+        S: Scalar,
+    {
+        net.edges_iter()
+            .filter(|edge| edge.enabled())
+            .filter_map(|edge| {
+                edge.id()
+                    .map(|id| (id, (edge.start(), edge.end()), edge.weight()))
+            })
+            .collect()
+    }
+
+    /// Keeps the positions of edges whose weight is stored as is, i.e. edges into summing nodes with a response of one.
+    fn addressable<N, S>(
+        nodes: &BTreeMap<usize, &N>,
+        evaluator: &super::evaluator::MatrixFeedforwardEvaluator<S>,
+        edge_index: &crate::EdgeIndex,
+    ) -> crate::EdgeIndex
+    where
+        N: NodeLike<S>,
+        S: Scalar,
+    {
+        edge_index
+            .iter()
+            .filter(|&&((_, end), (stage, _, column))| {
+                nodes.get(&end).is_some_and(|node| node.response().is_one())
+                    && evaluator.aggregations[stage]
+                        .iter()
+                        .all(|&(aggregated, _, _)| aggregated != column)
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Fabricates like [`Fabricator::fabricate`] without fusing stages and additionally locates every edge in the stages.
+    ///
+    /// Every entry holds `((start, end), (stage, row, column))`, edges into nodes not summing their inputs are located in the weighted inputs of their column.
+    /// Weights found at these positions are scaled by the response of the end node.
+    pub(crate) fn fabricate_indexed<N, E, S>(
+        net: &impl NetworkLike<N, E, S>,
+    ) -> Result<
+        (
+            super::evaluator::MatrixFeedforwardEvaluator<S>,
+            crate::EdgeIndex,
+        ),
+        FabricationError,
+    >
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+        S: Scalar,
+    {
+        Self::fabricate_located(net, false).map(|(evaluator, edges, _)| (evaluator, edges))
+    }
+
+    /// Same as [`Self::fabricate_indexed`] but also locates the column computing every node as `(id, (stage, column))`.
+    ///
+    /// Inputs and nodes computed in the last stage without being an output are not listed.
+    /// With `isolated`, hidden nodes without any edges are computed from their bias in the first stage, so they are located as well.
+    pub(crate) fn fabricate_located<N, E, S>(
+        net: &impl NetworkLike<N, E, S>,
+        isolated: bool,
+    ) -> Result<
+        (
+            super::evaluator::MatrixFeedforwardEvaluator<S>,
+            crate::EdgeIndex,
+            crate::NodeIndex,
+        ),
+        FabricationError,
+    >
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+        S: Scalar,
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("fabricate", nodes = net.nodes().len()).entered();
+
+        // build dependency graph by collecting incoming edges per node, ordered by id so the same net always fabricates the same stages
+        let mut dependency_graph: BTreeMap<usize, Vec<Edge<S>>> = BTreeMap::new();
+
+        let net_nodes = net.nodes();
+        // look up nodes by id instead of searching them for every computable node
+        let nodes: BTreeMap<usize, &N> = net_nodes.iter().map(|&node| (node.id(), node)).collect();
+        for edge in active_edges(&net_nodes, net.edges()) {
+            dependency_graph.entry(edge.end()).or_default().push(edge);
         }
 
         if dependency_graph.is_empty() {
-            return Err("no edges present, net invalid");
+            return Err("no edges present, net invalid".into());
+        }
+
+        if isolated {
+            let connected: BTreeSet<usize> = dependency_graph
+                .values()
+                .flatten()
+                .flat_map(|edge| [edge.start(), edge.end()])
+                .collect();
+            for node in net.hidden_iter() {
+                if !connected.contains(&node.id()) {
+                    dependency_graph.insert(node.id(), Vec::new());
+                }
+            }
         }
 
         // keep track of dependencies present
         let mut dependency_count = dependency_graph.len();
 
-        // println!("initial dependency_graph {:#?}", dependency_graph);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(dependencies = dependency_count, "built dependency graph");
 
         // contains list of matrices (stages) that form the computable net
-        let mut compute_stages: Vec<crate::Matrix> = Vec::new();
+        let mut compute_stages: Vec<crate::Matrix<S>> = Vec::new();
         // contains activation functions corresponding to each stage
-        let mut stage_transformations: Vec<crate::Transformations> = Vec::new();
+        let mut stage_transformations: Vec<crate::Transformations<S>> = Vec::new();
+        // contains node biases corresponding to each stage
+        let mut stage_biases: Vec<Vec<S>> = Vec::new();
+        // contains non-sum aggregations corresponding to each stage
+        let mut stage_aggregations: Vec<crate::Aggregations<S>> = Vec::new();
+        // contains location of summed edges per stage
+        let mut edge_index: crate::EdgeIndex = Vec::new();
+        // contains location of computed nodes per stage
+        let mut node_index: crate::NodeIndex = Vec::new();
         // set available nodes a.k.a net input
-        let mut available_nodes: Vec<usize> = net.inputs().iter().map(|n| n.id()).collect();
+        let mut available_nodes: Vec<usize> = net.inputs_iter().map(|n| n.id()).collect();
         // sort to guarantee each input will be processed by the same node every time
         available_nodes.sort_unstable();
 
-        // println!("available_nodes {:?}", available_nodes);
-
         // set wanted nodes a.k.a net output
-        let mut wanted_nodes: Vec<usize> = net.outputs().iter().map(|n| n.id()).collect();
+        let mut wanted_nodes: Vec<usize> = net.outputs_iter().map(|n| n.id()).collect();
         // sort to guarantee each output will appear in the same order every time
         wanted_nodes.sort_unstable();
         let wanted_nodes = wanted_nodes;
 
-        // println!("wanted_nodes {:?}", wanted_nodes);
-
         // gather compute stages by finding computable nodes and required carries until all dependencies are resolved
         while !dependency_graph.is_empty() {
             // setup new compute stage
-            let mut stage_matrix: crate::Matrix = Vec::new();
+            let mut stage_matrix: crate::Matrix<S> = Vec::new();
             // setup new transformations
-            let mut transformations: crate::Transformations = Vec::new();
+            let mut transformations: crate::Transformations<S> = Vec::new();
+            // setup new biases
+            let mut biases: Vec<S> = Vec::new();
+            // setup new aggregations
+            let mut aggregations: crate::Aggregations<S> = Vec::new();
+            // setup new edge positions as `((start, end), (row, column))`
+            let mut positions: Vec<((usize, usize), (usize, usize))> = Vec::new();
+            // computed nodes and their columns
+            let mut node_columns: Vec<(usize, usize)> = Vec::new();
             // list of nodes becoming available by compute stage
             let mut next_available_nodes: Vec<usize> = Vec::new();
 
             for (&dependent_node, dependencies) in dependency_graph.iter() {
                 // marker if all dependencies are available
                 let mut computable = true;
-                // eventual compute vector
-                let mut compute_or_carry = vec![f32::NAN; available_nodes.len()];
+                // eventual compute vector, `None` marks inputs that are not a dependency
+                let mut compute_or_carry: Vec<Option<S>> = vec![None; available_nodes.len()];
+                // weighted inputs in case the node does not sum them up
+                let mut weighted_inputs: Vec<(usize, S)> = Vec::new();
+                // edges and the rows they are read from
+                let mut edge_rows: Vec<((usize, usize), usize)> = Vec::new();
                 // check every dependency
-                for &dependency in dependencies {
+                for dependency in dependencies {
                     let mut found = false;
                     for (index, &id) in available_nodes.iter().enumerate() {
                         if dependency.start() == id {
                             // add weight to compute vector at position of input
-                            compute_or_carry[index] = dependency.weight();
+                            compute_or_carry[index] = Some(dependency.weight());
+                            weighted_inputs.push((index, dependency.weight()));
+                            edge_rows.push(((dependency.start(), dependency.end()), index));
                             found = true;
                         }
                     }
@@ -91,39 +488,62 @@ where
                     }
                 }
                 if computable {
-                    // replace NAN with 0.0
-                    for n in &mut compute_or_carry {
-                        if n.is_nan() {
-                            *n = 0.0
-                        }
+                    let node = nodes[&dependent_node];
+                    let response = node.response();
+                    if node.aggregation() == Aggregation::Sum {
+                        // replace None with zero, scale by the response and add vec to compute stage
+                        stage_matrix.push(
+                            compute_or_carry
+                                .into_iter()
+                                .map(|weight| {
+                                    weight.map_or_else(S::zero, |weight| weight * response)
+                                })
+                                .collect(),
+                        );
+                    } else {
+                        // add empty vec to compute stage, the value is aggregated from the weighted inputs instead
+                        stage_matrix.push(vec![S::zero(); available_nodes.len()]);
+                        let aggregation =
+                            node.aggregation().respond(&mut weighted_inputs, response);
+                        aggregations.push((stage_matrix.len() - 1, aggregation, weighted_inputs));
                     }
-                    // add vec to compute stage
-                    stage_matrix.push(compute_or_carry);
-                    // add activation function to stage transformations
-                    transformations.push(
-                        net.nodes()
-                            .iter()
-                            .find(|&node| node.id() == dependent_node)
-                            .unwrap()
-                            .activation(),
+                    positions.extend(
+                        edge_rows
+                            .into_iter()
+                            .map(|(edge, row)| (edge, (row, stage_matrix.len() - 1))),
                     );
+                    node_columns.push((dependent_node, stage_matrix.len() - 1));
+                    // add activation function to stage transformations
+                    transformations.push(activations::Activation::of(node));
+                    // add bias to stage biases
+                    biases.push(node.bias());
                     // mark node as available in next iteration
                     next_available_nodes.push(dependent_node);
                 } else {
                     // figure out carries
-                    for (index, &weight) in compute_or_carry.iter().enumerate() {
+                    for (index, weight) in compute_or_carry.iter().enumerate() {
                         // if there is some partial dependency that is not carried yet
                         if !next_available_nodes
                             .iter()
                             .any(|node| *node == available_nodes[index])
-                            && !weight.is_nan()
+                            && weight.is_some()
                         {
-                            let mut carry = vec![0.0; available_nodes.len()];
-                            carry[index] = 1.0;
+                            #[cfg(feature = "tracing")]
+                            tracing::trace!(
+                                node = available_nodes[index],
+                                stage = compute_stages.len(),
+                                dependent = dependent_node,
+                                "carry partial dependency"
+                            );
+                            let mut carry = vec![S::zero(); available_nodes.len()];
+                            carry[index] = S::one();
                             // add carry vector
                             stage_matrix.push(carry);
                             // add identity function for carried vector
-                            transformations.push(|val| val);
+                            transformations
+                                .push(activations::Activation::Function(activations::linear));
+                            // carried values are not biased
+                            biases.push(S::zero());
                             // add node as available
                             next_available_nodes.push(available_nodes[index]);
                         }
@@ -140,12 +560,21 @@ where
                             .iter()
                             .any(|node| *node == *available_node)
                         {
-                            let mut carry = vec![0.0; available_nodes.len()];
-                            carry[index] = 1.0;
+                            #[cfg(feature = "tracing")]
+                            tracing::trace!(
+                                node = *available_node,
+                                stage = compute_stages.len(),
+                                "carry output"
+                            );
+                            let mut carry = vec![S::zero(); available_nodes.len()];
+                            carry[index] = S::one();
                             // add carry vector
                             stage_matrix.push(carry);
                             // add identity function for carried vector
-                            transformations.push(|val| val);
+                            transformations
+                                .push(activations::Activation::Function(activations::linear));
+                            // carried values are not biased
+                            biases.push(S::zero());
                             // add node as available
                             next_available_nodes.push(*available_node);
                         }
@@ -160,31 +589,66 @@ where
 
             // if no dependency was removed no progess was made
             if dependency_graph.len() == dependency_count {
-                return Err("can't resolve dependencies, net invalid");
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    unresolved = ?dependency_graph.keys().collect::<Vec<_>>(),
+                    available = ?available_nodes,
+                    "no dependency resolved"
+                );
+                // every cycle is left unresolved, so the remaining edges contain all of them
+                let cycles = crate::validation::edge_cycles(
+                    dependency_graph
+                        .values()
+                        .flatten()
+                        .map(|edge| (edge.start(), edge.end())),
+                );
+                if !cycles.is_empty() {
+                    return Err(FabricationError::Cycles(cycles));
+                }
+                return Err("can't resolve dependencies, net invalid".into());
             } else {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    stage = compute_stages.len(),
+                    resolved = dependency_count - dependency_graph.len(),
+                    remaining = dependency_graph.len(),
+                    "resolved dependencies"
+                );
                 dependency_count = dependency_graph.len();
             }
 
-            // println!("next_available_nodes {:?}", next_available_nodes);
-
             // reorder last stage according to net output order (invalidates next_available_nodes order which wont be used after this point)
             if dependency_graph.is_empty() {
-                // println!("stage_matrix {:?}", stage_matrix);
-
                 let mut reordered_matrix = stage_matrix.clone();
                 let mut reordered_transformations = transformations.clone();
+                let mut reordered_biases = biases.clone();
+                let mut reordered_aggregations = Vec::new();
+                let mut remapped_columns = vec![None; stage_matrix.len()];
 
                 let mut matched_wanted_count = 0;
 
-                for ((available_node, column), transformation) in next_available_nodes
-                    .iter()
-                    .zip(stage_matrix.into_iter())
-                    .zip(transformations.into_iter())
+                for (old_index, (((available_node, column), transformation), bias)) in
+                    next_available_nodes
+                        .iter()
+                        .zip(stage_matrix.into_iter())
+                        .zip(transformations.into_iter())
+                        .zip(biases.into_iter())
+                        .enumerate()
                 {
                     for (index, wanted_node) in wanted_nodes.iter().enumerate() {
                         if available_node == wanted_node {
                             reordered_matrix[index] = column;
                             reordered_transformations[index] = transformation;
+                            reordered_biases[index] = bias;
+                            if let Some(position) = aggregations
+                                .iter()
+                                .position(|&(column, _, _)| column == old_index)
+                            {
+                                let (_, aggregation, weighted_inputs) =
+                                    aggregations.swap_remove(position);
+                                reordered_aggregations.push((index, aggregation, weighted_inputs));
+                            }
+                            remapped_columns[old_index] = Some(index);
                             matched_wanted_count += 1;
                             break;
                         }
@@ -193,31 +657,70 @@ where
 
                 if matched_wanted_count < wanted_nodes.len() {
                     return Err(
-                        "dependencies resolved but not all outputs computable, net invalid",
+                        "dependencies resolved but not all outputs computable, net invalid".into(),
                     );
                 }
 
-                // println!("reordered_matrix {:?}", reordered_matrix);
-
                 stage_matrix = reordered_matrix;
                 transformations = reordered_transformations;
+                biases = reordered_biases;
+                aggregations = reordered_aggregations;
+                positions = positions
+                    .into_iter()
+                    .filter_map(|(edge, (row, column))| {
+                        remapped_columns[column].map(|column| (edge, (row, column)))
+                    })
+                    .collect();
+                node_columns = node_columns
+                    .into_iter()
+                    .filter_map(|(id, column)| remapped_columns[column].map(|column| (id, column)))
+                    .collect();
             }
 
-            // add resolved dependencies and transformations to compute stages
+            // add resolved dependencies, transformations and biases to compute stages
             compute_stages.push(stage_matrix);
             stage_transformations.push(transformations);
+            stage_biases.push(biases);
+            stage_aggregations.push(aggregations);
+            edge_index.extend(
+                positions
+                    .into_iter()
+                    .map(|(edge, (row, column))| (edge, (compute_stages.len() - 1, row, column))),
+            );
+            node_index.extend(
+                node_columns
+                    .into_iter()
+                    .map(|(id, column)| (id, (compute_stages.len() - 1, column))),
+            );
 
             // set available nodes for next iteration
             available_nodes = next_available_nodes;
         }
 
-        Ok(super::evaluator::MatrixFeedforwardEvaluator {
+        let evaluator = super::evaluator::MatrixFeedforwardEvaluator {
             stages: compute_stages
                 .into_iter()
                 .map(MatrixFeedforwardFabricator::get_matrix)
                 .collect(),
             transformations: stage_transformations,
-        })
+            biases: stage_biases
+                .into_iter()
+                .map(|biases| DMatrix::from_row_slice(1, biases.len(), &biases))
+                .collect(),
+            aggregations: stage_aggregations,
+            unperturbed: None,
+            edges: Vec::new(),
+            edge_ids: Self::identified(net),
+            compensated: false,
+            clamp: None,
+            gathers: Vec::new(),
+        };
+        let evaluator = super::evaluator::MatrixFeedforwardEvaluator {
+            edges: Self::addressable(&nodes, &evaluator, &edge_index),
+            ..evaluator
+        };
+
+        Ok((evaluator, edge_index, node_index))
     }
 }
 
@@ -225,13 +728,661 @@ where
 mod tests {
     use nalgebra::dmatrix;
 
-    use super::MatrixFeedforwardFabricator;
+    use super::{FabricationWarning, FabricatorOptions, MatrixFeedforwardFabricator};
     use crate::{
         edges,
-        network::{net::Net, Evaluator, Fabricator},
+        matrix::Normalization,
+        network::{
+            net::{activations, Edge, Net, Node},
+            Aggregation, EvaluateInto, EvaluationError, Evaluator, FabricationError, Fabricator,
+        },
         nodes,
     };
 
+    #[test]
+    fn simple_net_evaluator_cost() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 's', 'l'),
+            edges!(
+                0--0.5->2,
+                1--0.5->2,
+                2--1.0->3,
+                0--0.25->3
+            ),
+        );
+
+        let cost = MatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .cost();
+        // 2x2 stage computing node 2 and carrying node 0, then a 2x1 stage
+        assert_eq!(cost.stages, 2);
+        assert_eq!(cost.multiply_adds, 6);
+        assert_eq!(cost.matrix_bytes, (6 + 3) * 4);
+    }
+
+    #[test]
+    fn simple_net_evaluator_dimensions() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 's', 'l'),
+            edges!(
+                0--0.5->2,
+                1--0.5->2,
+                2--1.0->3,
+                0--0.25->3
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.input_size(), 2);
+        assert_eq!(evaluator.output_size(), 1);
+        assert_eq!(evaluator.stage_count(), 2);
+        assert_eq!(evaluator.stage_dimensions(), vec![(2, 2), (2, 1)]);
+    }
+
+    #[test]
+    fn simple_net_evaluator_deterministic() {
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 's', 't', 'g', 'l', 'l'),
+            edges!(
+                0--0.5->2,
+                1--0.5->3,
+                0--0.25->4,
+                2--1.0->5,
+                3--1.0->5,
+                4--1.0->6,
+                2--1.0->6,
+                1--0.75->6
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        for _ in 0..10 {
+            assert_eq!(
+                MatrixFeedforwardFabricator::fabricate(&some_net)
+                    .unwrap()
+                    .stages,
+                evaluator.stages
+            );
+        }
+    }
+
+    #[test]
+    fn simple_net_evaluator_display() {
+        let some_net = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::SIGMOID),
+                Node::new(3, activations::TANH).with_aggregation(Aggregation::Max),
+            ],
+            edges!(
+                0--0.5->2,
+                1--0.5->2,
+                2--1.0->3,
+                0--0.25->3
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(
+            evaluator.to_string(),
+            "stage 0: 2 x 2 | sigmoid carry\nstage 1: 2 x 1 | tanh(max)\n"
+        );
+        assert_eq!(
+            evaluator.render_ascii(),
+            "2 inputs\n  | stage 0: 2 x 2 [s =]\n2 values\n  | stage 1: 2 x 1 [t(max)]\n1 outputs\n"
+        );
+    }
+
+    #[test]
+    fn simple_net_evaluator_closures() {
+        let leaky_relu = |slope: f32| move |val: f32| if val > 0.0 { val } else { slope * val };
+        let some_net = Net::new(
+            1,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::RELU).with_custom_activation(leaky_relu(0.1)),
+                Node::new(2, activations::RELU).with_custom_activation(leaky_relu(0.5)),
+            ],
+            edges!(0--1.0->1, 0--1.0->2),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![-2.0]), dmatrix![-0.2, -1.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![2.0]), dmatrix![2.0, 2.0]);
+        assert_eq!(evaluator.to_string(), "stage 0: 1 x 2 | custom custom\n");
+    }
+
+    #[test]
+    fn simple_net_evaluator_response() {
+        let some_net = Net::new(
+            2,
+            3,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::LINEAR)
+                    .with_bias(1.0)
+                    .with_response(2.0),
+                Node::new(3, activations::LINEAR)
+                    .with_aggregation(Aggregation::Max)
+                    .with_response(-1.0),
+                Node::new(4, activations::LINEAR)
+                    .with_aggregation(Aggregation::Product)
+                    .with_response(3.0),
+            ],
+            edges!(
+                0--1.0->2,
+                1--0.5->2,
+                0--1.0->3,
+                1--1.0->3,
+                0--1.0->4,
+                1--1.0->4
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        // 1 + 2 * (1 + 2 * 0.5), -1 * max(1, 2), 3 * 1 * 2
+        assert_eq!(
+            evaluator.evaluate(dmatrix![1.0, 2.0]),
+            dmatrix![5.0, -2.0, 6.0]
+        );
+    }
+
+    #[test]
+    fn simple_net_evaluator_normalization() {
+        let normalization = Normalization::Standard {
+            mean: vec![10.0, -4.0],
+            deviation: vec![2.0, 4.0],
+        };
+        let summing = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--2.0->2));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&summing)
+            .unwrap()
+            .with_normalization(&normalization)
+            .unwrap();
+        // folded into the only stage, (14 - 10) / 2 + 2 * (0 + 4) / 4
+        assert_eq!(evaluator.stage_count(), 1);
+        assert_eq!(evaluator.evaluate(dmatrix![14.0, 0.0]), dmatrix![4.0]);
+
+        let maximizing = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::LINEAR).with_aggregation(Aggregation::Max),
+            ],
+            edges!(0--1.0->2, 1--1.0->2),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&maximizing)
+            .unwrap()
+            .with_normalization(&Normalization::MinMax {
+                min: vec![0.0],
+                max: vec![10.0],
+            })
+            .unwrap();
+        // added as a stage of its own, only the first input is normalized
+        assert_eq!(evaluator.stage_count(), 2);
+        assert_eq!(evaluator.evaluate(dmatrix![5.0, 0.25]), dmatrix![0.5]);
+
+        assert!(MatrixFeedforwardFabricator::fabricate(&summing)
+            .unwrap()
+            .with_normalization(&Normalization::Standard {
+                mean: vec![0.0],
+                deviation: vec![0.0],
+            })
+            .is_err());
+    }
+
+    // test that edges stay addressed correctly after normalization
+    #[test]
+    fn simple_net_evaluator_normalization_weights() {
+        let summing = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--3.0->2, 1--2.0->2));
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&summing)
+            .unwrap()
+            .with_normalization(&Normalization::MinMax {
+                min: vec![0.0],
+                max: vec![2.0],
+            })
+            .unwrap();
+        // the scale is folded into the weights of the normalized input
+        assert_eq!(evaluator.weight(0, 2), None);
+        assert!(evaluator.set_weight(0, 2, 4.0).is_err());
+        assert_eq!(evaluator.weight(1, 2), Some(2.0));
+        evaluator.set_weight(1, 2, 1.0).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![2.0, 1.0]), dmatrix![4.0]);
+
+        let mixed = Net::new(
+            2,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::LINEAR).with_aggregation(Aggregation::Product),
+                Node::new(3, activations::LINEAR),
+            ],
+            edges!(0--1.0->2, 0--2.0->3, 1--3.0->3),
+        );
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&mixed)
+            .unwrap()
+            .with_normalization(&Normalization::MinMax {
+                min: vec![0.0],
+                max: vec![10.0],
+            })
+            .unwrap();
+        assert_eq!(evaluator.stage_count(), 2);
+        assert_eq!(evaluator.weight(1, 3), Some(3.0));
+        assert_eq!(evaluator.evaluate(dmatrix![5.0, 1.0]), dmatrix![0.5, 4.0]);
+        evaluator.set_weight(1, 3, 10.0).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![5.0, 1.0]), dmatrix![0.5, 11.0]);
+    }
+
+    #[test]
+    fn simple_net_evaluator_perturbation() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'r', 'l'),
+            edges!(
+                0--1.0->2,
+                2--1.0->3,
+                1--2.0->3
+            ),
+        );
+
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![3.0]);
+
+        // the carry of input 1 keeps its unit weight
+        evaluator.perturb_weights(&|_, _| 0.5);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![4.75]);
+        evaluator.perturb_weights(&|_, _| -0.5);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![1.75]);
+
+        evaluator.restore();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![3.0]);
+    }
+
+    // test that restoring perturbed weights keeps a normalization added in between
+    #[test]
+    fn simple_net_evaluator_perturbation_normalization() {
+        let summing = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--2.0->2));
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&summing).unwrap();
+        evaluator.perturb_weights(&|_, _| 0.5);
+        let mut evaluator = evaluator
+            .with_normalization(&Normalization::MinMax {
+                min: vec![0.0],
+                max: vec![2.0],
+            })
+            .unwrap();
+        evaluator.restore();
+        // folded, 0.5 * 2 + 2 * 1
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![2.5]);
+
+        let maximizing = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::LINEAR).with_aggregation(Aggregation::Max),
+            ],
+            edges!(0--1.0->2, 1--1.0->2),
+        );
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&maximizing).unwrap();
+        evaluator.perturb_weights(&|_, _| 0.5);
+        let mut evaluator = evaluator
+            .with_normalization(&Normalization::MinMax {
+                min: vec![0.0],
+                max: vec![10.0],
+            })
+            .unwrap();
+        evaluator.restore();
+        // added as a stage of its own
+        assert_eq!(evaluator.evaluate(dmatrix![5.0, 0.25]), dmatrix![0.5]);
+    }
+
+    #[test]
+    fn simple_net_evaluator_refabrication() {
+        let net = |weight: f32, bias: f32| {
+            Net::new(
+                2,
+                2,
+                vec![
+                    Node::new(0, activations::LINEAR),
+                    Node::new(1, activations::LINEAR),
+                    Node::new(2, activations::SIGMOID).with_bias(bias),
+                    Node::new(3, activations::TANH).with_aggregation(Aggregation::Max),
+                    Node::new(4, activations::LINEAR),
+                ],
+                vec![
+                    Edge::new(0, 2, weight),
+                    Edge::new(1, 2, 0.5),
+                    Edge::new(0, 3, weight),
+                    Edge::new(1, 3, 0.5),
+                    Edge::new(2, 4, 1.5),
+                ],
+            )
+        };
+
+        let plan = MatrixFeedforwardFabricator::plan(&net(1.0, 0.0)).unwrap();
+        let input = dmatrix![1.0, 2.0; -1.0, 0.5];
+        for (weight, bias) in [(1.0, 0.0), (-2.0, 0.5), (3.0, -1.0)] {
+            let refabricated = MatrixFeedforwardFabricator::refabricate(&plan, &net(weight, bias));
+            let fabricated = MatrixFeedforwardFabricator::fabricate(&net(weight, bias)).unwrap();
+            assert_eq!(
+                refabricated.unwrap().evaluate(input.clone()),
+                fabricated.evaluate(input.clone())
+            );
+        }
+
+        let other_edges = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 's', 't', 'l'),
+            edges!(0--1.0->2, 1--0.5->3, 2--1.5->4),
+        );
+        assert!(MatrixFeedforwardFabricator::refabricate(&plan, &other_edges).is_err());
+    }
+
+    #[test]
+    fn simple_net_evaluator_set_weight() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'r', 'l'),
+            edges!(
+                0--1.0->2,
+                2--1.0->3,
+                1--2.0->3
+            ),
+        );
+
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.weight(1, 3), Some(2.0));
+        evaluator.set_weight(0, 2, 3.0).unwrap();
+        evaluator.set_weight(1, 3, -1.0).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![2.0]);
+        assert!(evaluator.set_weight(0, 3, 1.0).is_err());
+
+        // both stages are fused into one
+        let linear_net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--2.0->1, 1--3.0->2));
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&linear_net).unwrap();
+        assert_eq!(evaluator.stage_count(), 1);
+        assert!(evaluator.set_weight(1, 2, 1.0).is_err());
+    }
+
+    #[test]
+    fn simple_net_evaluator_into() {
+        let some_net = Net::new(
+            2,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::RELU).with_bias(0.5),
+                Node::new(3, activations::LINEAR).with_aggregation(Aggregation::Max),
+                Node::new(4, activations::SIGMOID),
+            ],
+            edges!(
+                0--1.0->2,
+                1--2.0->2,
+                2--1.0->3,
+                0--0.5->3,
+                2---1.0->4
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let mut scratch = evaluator.scratch();
+        let mut output = [0.0; 2];
+        for input in [[1.0, 2.0], [-3.0, 0.5]] {
+            evaluator.evaluate_into(&input, &mut output, &mut scratch);
+            assert_eq!(
+                output.to_vec(),
+                evaluator
+                    .evaluate(dmatrix![input[0], input[1]])
+                    .iter()
+                    .copied()
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn simple_net_evaluator_compensated() {
+        let some_net = Net::new(
+            3,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--1.0->3, 1--1.0->3, 2--1.0->3),
+        );
+
+        // the one is lost when added to 1e8 in f32 without compensation
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_compensated_summation();
+        assert_eq!(
+            evaluator.evaluate(dmatrix![1e8, 1.0, -1e8; 1.0, 1e8, -1e8]),
+            dmatrix![1.0; 1.0]
+        );
+
+        let mut output = [0.0];
+        evaluator.evaluate_into(&[1e8, 1.0, -1e8], &mut output, &mut evaluator.scratch());
+        assert_eq!(output, [1.0]);
+    }
+
+    #[test]
+    fn simple_net_evaluator_lookup_tables() {
+        let some_net = Net::new(
+            2,
+            3,
+            nodes!('l', 'l', 's', 't', 'g'),
+            edges!(0--1.0->2, 1--0.5->2, 0---0.3->3, 1--0.2->3, 0--0.4->4),
+        );
+        let exact = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let tabulated = MatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_lookup_tables(1024, 8.0);
+        let sparse = crate::sparse_matrix::feedforward::fabricator::SparseMatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_lookup_tables(1024, 8.0);
+
+        assert_eq!(tabulated.stages[0].ncols(), 3);
+        assert_eq!(tabulated.transformations[0][0].name(), Some("sigmoid"));
+        assert!(matches!(
+            tabulated.transformations[0][0],
+            activations::Activation::Table(_)
+        ));
+
+        let input = dmatrix![0.0, 0.0; 1.0, -2.0; 0.3, 0.7; 20.0, -20.0];
+        assert!((exact.evaluate(input.clone()) - tabulated.evaluate(input.clone())).amax() < 1e-3);
+        assert!((tabulated.evaluate(input.clone()) - sparse.evaluate(input)).amax() < 1e-6);
+    }
+
+    #[test]
+    fn simple_net_evaluator_clamp() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'q', 'q', 'q'),
+            edges!(0--1.0->1, 1--1.0->2, 2--1.0->3),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1e10]), dmatrix![f32::INFINITY]);
+
+        let evaluator = evaluator.with_clamp(-10.0, 10.0).unwrap();
+        assert_eq!(
+            evaluator.evaluate(dmatrix![1e10; 2.0]),
+            dmatrix![100.0; 100.0]
+        );
+        let mut output = [0.0];
+        evaluator.evaluate_into(&[1e10], &mut output, &mut evaluator.scratch());
+        assert_eq!(output, [100.0]);
+
+        assert!(MatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_clamp(1.0, -1.0)
+            .is_err());
+    }
+
+    #[test]
+    fn simple_net_evaluator_edge_ids() {
+        // parallel edges told apart by their innovation numbers
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l'),
+            vec![
+                Edge::new(0, 1, 0.5).with_id(Some(7)),
+                Edge::new(0, 1, 0.25).with_id(Some(9)),
+            ],
+        );
+
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![4.0]), dmatrix![3.0]);
+        assert_eq!(evaluator.weight_by_id(9), Some(0.25));
+
+        evaluator.set_weight_by_id(9, 1.0).unwrap();
+        assert_eq!(evaluator.weight_by_id(7), Some(0.5));
+        assert_eq!(evaluator.weight(0, 1), Some(1.5));
+        assert_eq!(evaluator.evaluate(dmatrix![4.0]), dmatrix![6.0]);
+
+        evaluator.perturb_weights(&|_, _| 1.0);
+        evaluator.set_weight_by_id(7, 0.0).unwrap();
+        assert_eq!(evaluator.weight(0, 1), Some(2.0));
+        evaluator.restore();
+        assert_eq!(evaluator.weight(0, 1), Some(1.0));
+
+        assert_eq!(
+            evaluator.set_weight_by_id(8, 1.0).err(),
+            Some("no edge with this id")
+        );
+    }
+
+    #[test]
+    fn simple_net_evaluator_warnings() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--1.0->1, 0--2.0->2, 2--1.0->3),
+        );
+
+        let (evaluator, warnings) =
+            MatrixFeedforwardFabricator::fabricate_with_warnings(&some_net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![2.0]);
+        assert_eq!(warnings, vec![FabricationWarning::UnreachableHidden(1)]);
+    }
+
+    #[test]
+    fn simple_net_evaluator_options() {
+        // node 1 depends on node 2 which has no inputs, neither reaches the output
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--2.0->3, 2--1.0->1),
+        );
+        assert!(MatrixFeedforwardFabricator::fabricate(&some_net).is_err());
+
+        let fabricator = MatrixFeedforwardFabricator::with_options(FabricatorOptions {
+            prune_dead_nodes: true,
+            clamp: Some((-1.0, 1.0)),
+            ..Default::default()
+        });
+        let evaluator = fabricator.fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![0.25; 3.0]), dmatrix![0.5; 1.0]);
+
+        // the linear stage computing node 2 is fused unless told otherwise
+        let linear_net = Net::new(1, 1, nodes!('l', 'l', 's'), edges!(0--1.0->1, 1--1.0->2));
+        let fabricator = MatrixFeedforwardFabricator::with_options(FabricatorOptions {
+            fuse_linear_stages: false,
+            gather_carries: true,
+            ..Default::default()
+        });
+        assert_eq!(fabricator.fabricate(&linear_net).unwrap().stage_count(), 2);
+        assert_eq!(
+            MatrixFeedforwardFabricator::with_options(FabricatorOptions::default())
+                .fabricate(&linear_net)
+                .unwrap()
+                .stage_count(),
+            1
+        );
+
+        assert!(
+            MatrixFeedforwardFabricator::with_options(FabricatorOptions {
+                clamp: Some((1.0, -1.0)),
+                ..Default::default()
+            })
+            .fabricate(&linear_net)
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn simple_net_evaluator_gathered_carries() {
+        // the input is carried past a chain of four nodes to the output
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 's', 't', 's', 't', 'l'),
+            edges!(
+                0--1.0->1,
+                1--0.5->2,
+                2--2.0->3,
+                3--0.5->4,
+                4--1.0->5,
+                0--0.5->5
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let mut gathered = evaluator.clone().with_gathered_carries();
+        assert_eq!(gathered.stage_dimensions(), evaluator.stage_dimensions());
+        assert_eq!(gathered.to_string(), evaluator.to_string());
+        assert!(gathered.cost().multiply_adds < evaluator.cost().multiply_adds);
+
+        let input = dmatrix![0.3; -1.2];
+        assert_eq!(gathered.evaluate(input.clone()), evaluator.evaluate(input));
+        let (mut output, mut expected) = ([0.0], [0.0]);
+        gathered.evaluate_into(&[0.7], &mut output, &mut gathered.scratch());
+        evaluator.evaluate_into(&[0.7], &mut expected, &mut evaluator.scratch());
+        assert_eq!(output, expected);
+
+        gathered.set_weight(0, 5, 2.0).unwrap();
+        assert_eq!(gathered.weight(0, 5), Some(2.0));
+        assert_eq!(gathered.weight(1, 2), Some(0.5));
+    }
+
+    #[test]
+    fn simple_net_evaluator_try() {
+        let some_net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--1.0->2));
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(
+            evaluator.try_evaluate(dmatrix![1.0, 2.0; 3.0, 4.0]),
+            Ok(dmatrix![3.0; 7.0])
+        );
+        assert_eq!(
+            evaluator.try_evaluate(dmatrix![1.0, 2.0, 3.0]),
+            Err(EvaluationError::DimensionMismatch {
+                expected: 2,
+                got: 3
+            })
+        );
+    }
+
     // tests construction and evaluation of simplest network
     #[test]
     fn simple_net_evaluator_0() {
@@ -364,7 +1515,10 @@ mod tests {
         let some_net = Net::new(1, 1, nodes!('l', 'l'), Vec::new());
 
         if let Err(message) = MatrixFeedforwardFabricator::fabricate(&some_net) {
-            assert_eq!(message, "no edges present, net invalid");
+            assert_eq!(
+                message,
+                FabricationError::Invalid("no edges present, net invalid")
+            );
         } else {
             unreachable!();
         }
@@ -378,20 +1532,112 @@ mod tests {
         if let Err(message) = MatrixFeedforwardFabricator::fabricate(&some_net) {
             assert_eq!(
                 message,
-                "dependencies resolved but not all outputs computable, net invalid"
+                FabricationError::Invalid(
+                    "dependencies resolved but not all outputs computable, net invalid"
+                )
             );
         } else {
             unreachable!();
         }
     }
 
+    // test fusion of linear stages
+    #[test]
+    fn simple_net_evaluator_fused() {
+        let linear_chain = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--0.5->1, 1--2.0->2, 2--3.0->3),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&linear_chain).unwrap();
+        assert_eq!(evaluator.stages.len(), 1);
+        assert_eq!(evaluator.evaluate(dmatrix![2.0]), dmatrix![6.0]);
+
+        let sigmoid_chain = Net::new(1, 1, nodes!('l', 's', 'l'), edges!(0--1.0->1, 1--1.0->2));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&sigmoid_chain).unwrap();
+        assert_eq!(evaluator.stages.len(), 2);
+    }
+
+    // piecewise linear functions agree with the identity on most inputs, but are not fused
+    #[test]
+    fn simple_net_evaluator_fused_custom() {
+        let clamped_chain = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, |val: f32| val.clamp(-3.0, 3.0)),
+                Node::new(2, activations::LINEAR),
+            ],
+            edges!(0--1.0->1, 1--1.0->2),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&clamped_chain).unwrap();
+        assert_eq!(evaluator.stages.len(), 2);
+        assert_eq!(evaluator.evaluate(dmatrix![10.0]), dmatrix![3.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![-1.0]), dmatrix![-1.0]);
+    }
+
+    // test that edges folded into a product are no longer addressable
+    #[test]
+    fn simple_net_evaluator_fused_weights() {
+        let linear_chain = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--2.0->1, 1--3.0->2));
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&linear_chain).unwrap();
+        assert_eq!(evaluator.stages.len(), 1);
+
+        assert_eq!(evaluator.weight(0, 1), None);
+        assert_eq!(evaluator.weight(1, 2), None);
+        assert!(evaluator.set_weight(0, 1, 5.0).is_err());
+        assert!(evaluator.parameters().is_empty());
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![6.0]);
+
+        // the second stage keeps its edges if the first one only carries values
+        let carried_chain = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->1, 1--3.0->2));
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&carried_chain).unwrap();
+        assert_eq!(evaluator.stages.len(), 1);
+        assert_eq!(evaluator.weight(0, 1), None);
+        assert_eq!(evaluator.weight(1, 2), Some(3.0));
+        evaluator.set_weight(1, 2, 5.0).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![5.0]);
+    }
+
+    // test cyclic net
+    #[test]
+    fn simple_net_evaluator_cycle() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            vec![
+                Edge::new(0, 1, 0.5),
+                Edge::new(1, 2, 0.5),
+                Edge::new(2, 1, 0.5),
+                Edge::new(2, 3, 0.5),
+                // disabled edges are not evaluated and close no cycle
+                Edge::new(3, 2, 0.5).with_enabled(false),
+            ],
+        );
+
+        assert_eq!(
+            MatrixFeedforwardFabricator::fabricate(&some_net).unwrap_err(),
+            FabricationError::Cycles(vec![vec![1, 2]])
+        );
+        assert_eq!(
+            crate::validation::cycles(&some_net),
+            vec![vec![1, 2], vec![2, 3]]
+        );
+    }
+
     // test uncomputable output
     #[test]
     fn simple_net_evaluator_8() {
         let some_net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(1--0.5->2));
 
         if let Err(message) = MatrixFeedforwardFabricator::fabricate(&some_net) {
-            assert_eq!(message, "can't resolve dependencies, net invalid");
+            assert_eq!(
+                message,
+                FabricationError::Invalid("can't resolve dependencies, net invalid")
+            );
         } else {
             unreachable!();
         }
@@ -417,4 +1663,165 @@ mod tests {
 
         assert_eq!(result, dmatrix![2.5]);
     }
+
+    // test evaluation with f64 precision
+    #[test]
+    fn simple_net_evaluator_f64() {
+        let some_net: Net<f64> = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::linear),
+                Node::new(1, activations::linear),
+                Node::new(2, activations::linear),
+            ],
+            vec![
+                Edge::new(0, 1, 0.1),
+                Edge::new(1, 2, 0.1),
+                Edge::new(0, 2, 0.1),
+            ],
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let result = evaluator.evaluate(dmatrix![3.0]);
+
+        assert_eq!(result, dmatrix![3.0 * 0.1 * 0.1 + 3.0 * 0.1]);
+    }
+
+    // test node bias is added before activation, also for carried nodes
+    #[test]
+    fn simple_net_evaluator_bias() {
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_bias(1.0),
+                Node::new(2, activations::RELU).with_bias(-2.0),
+            ],
+            edges!(
+                0--0.5->1,
+                1--0.5->2,
+                0--0.5->2
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let result = evaluator.evaluate(dmatrix![5.0]);
+        assert_eq!(result, dmatrix![2.25]);
+
+        let result = evaluator.evaluate(dmatrix![0.0]);
+        assert_eq!(result, dmatrix![0.0]);
+    }
+
+    // test non-sum aggregations, also in the output stage
+    #[test]
+    fn simple_net_evaluator_aggregation() {
+        let some_net = Net::new(
+            2,
+            3,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::LINEAR).with_aggregation(Aggregation::Product),
+                Node::new(3, activations::LINEAR).with_aggregation(Aggregation::Max),
+                Node::new(4, activations::LINEAR)
+                    .with_aggregation(Aggregation::Mean)
+                    .with_bias(1.0),
+                Node::new(5, activations::LINEAR).with_aggregation(Aggregation::Min),
+            ],
+            edges!(
+                0--1.0->2,
+                1--0.5->2,
+                0--1.0->3,
+                2--1.0->3,
+                0--1.0->4,
+                1--1.0->4,
+                0--1.0->5,
+                1--1.0->5
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let result = evaluator.evaluate(dmatrix![2.0, 6.0]);
+        assert_eq!(result, dmatrix![6.0, 5.0, 2.0]);
+    }
+
+    // test evaluation of several samples at once
+    #[test]
+    fn simple_net_evaluator_batch() {
+        let some_net = Net::new(
+            2,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::RELU).with_bias(-1.0),
+                Node::new(3, activations::LINEAR).with_aggregation(Aggregation::Product),
+                Node::new(4, activations::LINEAR),
+            ],
+            edges!(
+                0--1.0->2,
+                1--1.0->2,
+                0--1.0->3,
+                2--1.0->3,
+                2--0.5->4
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let result = evaluator.evaluate(dmatrix![
+            1.0, 2.0;
+            0.0, 0.0;
+            2.0, 3.0
+        ]);
+        assert_eq!(
+            result,
+            dmatrix![
+                2.0, 1.0;
+                0.0, 0.0;
+                8.0, 2.0
+            ]
+        );
+
+        // batched evaluation agrees with evaluating each sample on its own
+        assert_eq!(evaluator.evaluate(dmatrix![2.0, 3.0]), dmatrix![8.0, 2.0]);
+    }
+
+    // test that a fabricated evaluator survives a round trip through serialization
+    #[cfg(feature = "serde")]
+    #[test]
+    fn simple_net_evaluator_serde() {
+        let some_net = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::SIGMOID).with_bias(0.5),
+                Node::new(3, activations::TANH).with_aggregation(Aggregation::Max),
+            ],
+            edges!(
+                0--0.5->2,
+                1--0.5->2,
+                2--1.0->3,
+                0--0.25->3
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let serialized = serde_json::to_string(&evaluator).unwrap();
+        let deserialized: crate::matrix::feedforward::evaluator::MatrixFeedforwardEvaluator =
+            serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(
+            deserialized.evaluate(dmatrix![1.0, 2.0]),
+            evaluator.evaluate(dmatrix![1.0, 2.0])
+        );
+    }
 }
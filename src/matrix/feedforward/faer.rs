@@ -0,0 +1,102 @@
+//! Stage multiplication via `faer` for large `f32` and `f64` networks, available with the feature `faer`.
+
+use std::any::Any;
+
+use faer::{
+    linalg::matmul::matmul,
+    mat::{MatMut, MatRef},
+    traits::ComplexField,
+    Accum, Par,
+};
+use nalgebra::DMatrix;
+
+use crate::network::Scalar;
+
+/// Stage matrices with fewer entries are multiplied by nalgebra, which is faster for the small stages of most evolved nets.
+pub const MIN_ENTRIES: usize = 128 * 128;
+
+/// Multiplies state and stage matrix with `faer` if the stage is large enough and the scalar is `f32` or `f64`.
+///
+/// Returns `None` otherwise so the caller can fall back to another multiplication.
+pub(crate) fn multiply<S: Scalar>(
+    state: &DMatrix<S>,
+    stage_matrix: &DMatrix<S>,
+) -> Option<DMatrix<S>> {
+    if stage_matrix.len() < MIN_ENTRIES {
+        return None;
+    }
+    let result: Box<dyn Any> = if let (Some(state), Some(stage_matrix)) = (
+        (state as &dyn Any).downcast_ref::<DMatrix<f32>>(),
+        (stage_matrix as &dyn Any).downcast_ref::<DMatrix<f32>>(),
+    ) {
+        Box::new(multiply_with(state, stage_matrix))
+    } else {
+        let state = (state as &dyn Any).downcast_ref::<DMatrix<f64>>()?;
+        let stage_matrix = (stage_matrix as &dyn Any).downcast_ref::<DMatrix<f64>>()?;
+        Box::new(multiply_with(state, stage_matrix))
+    };
+
+    result.downcast::<DMatrix<S>>().ok().map(|result| *result)
+}
+
+fn multiply_with<T: Scalar + ComplexField>(
+    state: &DMatrix<T>,
+    stage_matrix: &DMatrix<T>,
+) -> DMatrix<T> {
+    assert_eq!(
+        state.ncols(),
+        stage_matrix.nrows(),
+        "state and stage matrix dimensions do not match"
+    );
+
+    let mut result = DMatrix::zeros(state.nrows(), stage_matrix.ncols());
+    // both libraries store column major, so the buffers can be shared without copies
+    let (samples, columns) = result.shape();
+    matmul(
+        MatMut::from_column_major_slice_mut(result.as_mut_slice(), samples, columns),
+        Accum::Replace,
+        MatRef::from_column_major_slice(state.as_slice(), state.nrows(), state.ncols()),
+        MatRef::from_column_major_slice(
+            stage_matrix.as_slice(),
+            stage_matrix.nrows(),
+            stage_matrix.ncols(),
+        ),
+        T::one(),
+        Par::Seq,
+    );
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::DMatrix;
+
+    use super::MIN_ENTRIES;
+
+    #[test]
+    fn faer_multiplication_matches_generic_multiplication() {
+        let state = DMatrix::from_fn(3, 150, |r, c| ((r + c) % 7) as f64 - 3.0);
+        let stage_matrix = DMatrix::from_fn(150, 120, |r, c| ((r * c) % 5) as f64 * 0.5);
+        assert!(stage_matrix.len() >= MIN_ENTRIES);
+
+        assert_eq!(
+            super::multiply(&state, &stage_matrix).unwrap(),
+            &state * &stage_matrix
+        );
+        let state = state.map(|value| value as f32);
+        let stage_matrix = stage_matrix.map(|value| value as f32);
+        assert_eq!(
+            super::multiply(&state, &stage_matrix).unwrap(),
+            &state * &stage_matrix
+        );
+    }
+
+    #[test]
+    fn faer_multiplication_skips_small_stages() {
+        let state = DMatrix::<f32>::from_element(1, 2, 1.0);
+        let stage_matrix = DMatrix::<f32>::from_element(2, 2, 1.0);
+
+        assert!(super::multiply(&state, &stage_matrix).is_none());
+    }
+}
@@ -0,0 +1,336 @@
+//! Half precision storage for stage matrices, available with the feature `half`.
+
+use std::fmt;
+
+use ::half::f16;
+use nalgebra::{DMatrix, DVector};
+
+use super::{evaluator::MatrixFeedforwardEvaluator, fabricator::MatrixFeedforwardFabricator};
+use crate::network::{
+    Cost, EdgeLike, EvaluateInto, Evaluator, FabricationError, Fabricator, NetworkIO, NetworkLike,
+    NodeId, NodeLike, Scratch,
+};
+
+/// Evaluates a [`MatrixFeedforwardEvaluator`] whose stage matrices are stored as `f16`.
+///
+/// Stage matrices take half the memory, which matters for large substrates, e.g. in HyperNEAT.
+/// Every column of a stage is widened to `f32` right before it is multiplied, so accumulation happens in `f32`.
+/// Biases, activations, aggregations and the clamp range are kept as is.
+#[derive(Debug)]
+pub struct HalfFeedforwardEvaluator {
+    pub stages: Vec<DMatrix<f16>>,
+    pub transformations: Vec<crate::Transformations>,
+    pub biases: Vec<DMatrix<f32>>,
+    pub aggregations: Vec<crate::Aggregations>,
+    /// Range every biased sum is clamped into, see [`MatrixFeedforwardEvaluator::with_clamp`].
+    pub clamp: Option<(f32, f32)>,
+}
+
+impl HalfFeedforwardEvaluator {
+    /// Narrows all stage matrices of `evaluator` to `f16`, gathered carries and compensated summation are not supported.
+    pub fn new(evaluator: MatrixFeedforwardEvaluator) -> Result<Self, &'static str> {
+        if !evaluator.gathers.is_empty() {
+            return Err("half evaluation needs full stage matrices, not gathered carries");
+        }
+        if evaluator.compensated {
+            return Err("half evaluation does not support compensated summation");
+        }
+        Ok(Self {
+            stages: evaluator
+                .stages
+                .iter()
+                .map(|stage| stage.map(f16::from_f32))
+                .collect(),
+            transformations: evaluator.transformations,
+            biases: evaluator.biases,
+            aggregations: evaluator.aggregations,
+            clamp: evaluator.clamp,
+        })
+    }
+
+    /// Bytes taken by the stage matrices.
+    pub fn weight_bytes(&self) -> usize {
+        self.stages
+            .iter()
+            .map(|stage| stage.len() * core::mem::size_of::<f16>())
+            .sum()
+    }
+
+    /// Estimates the cost of evaluating a single sample, like [`MatrixFeedforwardEvaluator::cost`] with `f16` stages.
+    pub fn cost(&self) -> Cost {
+        Cost {
+            multiply_adds: self.stages.iter().map(|stage| stage.len()).sum::<usize>()
+                + Cost::aggregated_inputs(&self.aggregations),
+            stages: self.stages.len(),
+            matrix_bytes: self.weight_bytes()
+                + self.biases.iter().map(|biases| biases.len()).sum::<usize>()
+                    * core::mem::size_of::<f32>(),
+        }
+    }
+
+    /// Number of values every sample provides as input.
+    pub fn input_size(&self) -> usize {
+        self.stages.first().map_or(0, |stage| stage.nrows())
+    }
+
+    /// Number of values evaluated for every sample.
+    pub fn output_size(&self) -> usize {
+        self.stages.last().map_or(0, |stage| stage.ncols())
+    }
+
+    pub fn stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Dimensions of every stage as `(rows, columns)`, i.e. the number of values entering and leaving it.
+    pub fn stage_dimensions(&self) -> Vec<(usize, usize)> {
+        self.stages
+            .iter()
+            .map(|stage| (stage.nrows(), stage.ncols()))
+            .collect()
+    }
+
+    /// Draws the stages like [`MatrixFeedforwardEvaluator::render_ascii`] does.
+    pub fn render_ascii(&self) -> String {
+        crate::matrix::render_stages(&self.stage_dimensions(), |index, column| {
+            self.describe(index, column)
+        })
+    }
+
+    fn describe(&self, index: usize, column: usize) -> String {
+        let weights = self.stages[index].column(column);
+        crate::matrix::describe_column(
+            &self.transformations[index][column],
+            self.aggregations[index]
+                .iter()
+                .find(|(aggregated, _, _)| *aggregated == column)
+                .map(|&(_, aggregation, _)| aggregation),
+            self.biases[index][column] == 0.0
+                && weights
+                    .iter()
+                    .filter(|&&weight| weight != f16::ZERO)
+                    .count()
+                    == 1
+                && weights.iter().any(|&weight| weight == f16::ONE),
+        )
+    }
+
+    fn clamped(&self, value: f32) -> f32 {
+        match self.clamp {
+            Some((min, max)) => value.clamp(min, max),
+            None => value,
+        }
+    }
+
+    fn stage(&self, index: usize, input: DMatrix<f32>) -> DMatrix<f32> {
+        let stage = &self.stages[index];
+        let mut state = DMatrix::zeros(input.nrows(), stage.ncols());
+        let mut widened = DVector::zeros(stage.nrows());
+        for (column, weights) in stage.column_iter().enumerate() {
+            for (wide, weight) in widened.iter_mut().zip(weights.iter()) {
+                *wide = weight.to_f32();
+            }
+            state.column_mut(column).gemv(1.0, &input, &widened, 0.0);
+        }
+        // compute values of nodes that do not sum their inputs for every sample
+        for (column, aggregation, weighted_inputs) in &self.aggregations[index] {
+            for sample in 0..input.nrows() {
+                state[(sample, *column)] = aggregation.aggregate(
+                    weighted_inputs
+                        .iter()
+                        .map(|&(row, weight)| input[(sample, row)] * weight),
+                );
+            }
+        }
+        // add bias and apply activation per column, i.e. per node for all samples
+        for ((mut column, activation), &bias) in state
+            .column_iter_mut()
+            .zip(&self.transformations[index])
+            .zip(self.biases[index].iter())
+        {
+            for value in column.iter_mut() {
+                *value = activation.apply(self.clamped(*value + bias));
+            }
+        }
+        state
+    }
+
+    /// Computes a single stage for a single sample, widening every weight as it is multiplied.
+    fn stage_into(&self, index: usize, input: &[f32], output: &mut [f32]) {
+        for (value, weights) in output.iter_mut().zip(self.stages[index].column_iter()) {
+            *value = weights
+                .iter()
+                .zip(input)
+                .fold(0.0, |sum, (weight, &input)| sum + weight.to_f32() * input);
+        }
+        for (column, aggregation, weighted_inputs) in &self.aggregations[index] {
+            output[*column] = aggregation.aggregate(
+                weighted_inputs
+                    .iter()
+                    .map(|&(row, weight)| input[row] * weight),
+            );
+        }
+        for ((value, activation), &bias) in output
+            .iter_mut()
+            .zip(&self.transformations[index])
+            .zip(self.biases[index].iter())
+        {
+            *value = activation.apply(self.clamped(*value + bias));
+        }
+    }
+}
+
+impl Evaluator for HalfFeedforwardEvaluator {
+    fn evaluate<T: NetworkIO>(&self, state: T) -> T {
+        NetworkIO::output(
+            (0..self.stages.len()).fold(NetworkIO::input(state), |state, index| {
+                self.stage(index, state)
+            }),
+        )
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
+
+impl EvaluateInto for HalfFeedforwardEvaluator {
+    fn scratch(&self) -> Scratch {
+        Scratch::new(
+            self.stage_dimensions()
+                .into_iter()
+                .map(|(rows, columns)| rows.max(columns))
+                .max()
+                .unwrap_or(0),
+        )
+    }
+
+    fn evaluate_into(&self, input: &[f32], output: &mut [f32], scratch: &mut Scratch) {
+        scratch.current[..self.input_size()].copy_from_slice(input);
+        for (index, stage) in self.stages.iter().enumerate() {
+            self.stage_into(
+                index,
+                &scratch.current[..stage.nrows()],
+                &mut scratch.next[..stage.ncols()],
+            );
+            core::mem::swap(&mut scratch.current, &mut scratch.next);
+        }
+        output.copy_from_slice(&scratch.current[..self.output_size()]);
+    }
+}
+
+/// Prints the stages like the [`MatrixFeedforwardEvaluator`] does.
+impl fmt::Display for HalfFeedforwardEvaluator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::matrix::fmt_stages(f, &self.stage_dimensions(), |index, column| {
+            self.describe(index, column)
+        })
+    }
+}
+
+/// Fabricates a [`HalfFeedforwardEvaluator`] by narrowing the result of the [`MatrixFeedforwardFabricator`].
+#[derive(Debug)]
+pub struct HalfFeedforwardFabricator;
+
+impl<N, E, I> Fabricator<N, E, f32, I> for HalfFeedforwardFabricator
+where
+    N: NodeLike<f32, I>,
+    E: EdgeLike<f32, I>,
+    I: NodeId,
+{
+    type Output = HalfFeedforwardEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E, f32, I>) -> Result<Self::Output, FabricationError> {
+        Ok(HalfFeedforwardEvaluator::new(
+            MatrixFeedforwardFabricator::fabricate(net)?,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{HalfFeedforwardEvaluator, HalfFeedforwardFabricator};
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Net, Node},
+            Aggregation, Evaluator, Fabricator,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn half_close_to_float() {
+        let some_net = Net::new(
+            2,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::SIGMOID).with_bias(0.1),
+                Node::new(3, activations::TANH).with_aggregation(Aggregation::Max),
+                Node::new(4, activations::LINEAR),
+                Node::new(5, activations::SIGMOID),
+            ],
+            edges!(
+                0--0.3->2,
+                1--0.7->2,
+                0--1.5->3,
+                1--0.2->3,
+                2--0.9->4,
+                3--0.4->4,
+                2--1.1->5,
+                3--0.6->5
+            ),
+        );
+
+        let half = HalfFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let float = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let input = dmatrix![0.5, 1.0; -1.0, 0.25; 2.0, -0.5];
+        let difference = half.evaluate(input.clone()) - float.evaluate(input);
+        assert!(difference.amax() < 1e-3);
+    }
+
+    #[test]
+    fn half_weight_bytes() {
+        let some_net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--1.0->2));
+        let half = HalfFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(half.weight_bytes(), 2 * 2);
+        assert_eq!(half.evaluate(dmatrix![1.0, 2.0]), dmatrix![3.0]);
+    }
+
+    #[test]
+    fn half_rejects_gathered_carries() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--1.0->2, 2--1.0->3, 1--1.0->3),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert!(HalfFeedforwardEvaluator::new(evaluator.clone()).is_ok());
+        assert!(HalfFeedforwardEvaluator::new(evaluator.with_gathered_carries()).is_err());
+    }
+
+    #[test]
+    fn half_clamp() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--2.0->1));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_clamp(-1.0, 1.0)
+            .unwrap();
+
+        let half = HalfFeedforwardEvaluator::new(evaluator.clone()).unwrap();
+        assert_eq!(half.evaluate(dmatrix![2.0; 0.25]), dmatrix![1.0; 0.5]);
+
+        assert!(HalfFeedforwardEvaluator::new(evaluator.with_compensated_summation()).is_err());
+    }
+}
@@ -1,2 +1,15 @@
+#[cfg(feature = "std")]
+pub mod debug;
 pub mod evaluator;
+#[cfg(feature = "std")]
 pub mod fabricator;
+#[cfg(feature = "faer")]
+pub mod faer;
+#[cfg(feature = "half")]
+pub mod half;
+#[cfg(feature = "std")]
+pub mod probe;
+#[cfg(feature = "std")]
+pub mod record;
+#[cfg(feature = "simd")]
+mod simd;
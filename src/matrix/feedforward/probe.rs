@@ -0,0 +1,189 @@
+//! Exposes the values of hidden nodes after every evaluation, see [`ProbedEvaluator`].
+
+use nalgebra::DMatrix;
+
+use crate::network::{
+    EdgeLike, FabricationError, NetworkIO, NetworkLike, NodeLike, Scalar, StatefulEvaluator,
+};
+
+use super::{evaluator::MatrixFeedforwardEvaluator, fabricator::MatrixFeedforwardFabricator};
+
+/// Wraps a [`MatrixFeedforwardEvaluator`] and records the values of probed hidden nodes, e.g. to analyze what evolved hidden nodes encode.
+///
+/// Values are taken after the activation and kept until the next evaluation, see [`Self::probes`].
+/// Recording needs mutable access, hence it is a [`StatefulEvaluator`] although the wrapped evaluator is not.
+/// Stages are not fused, so every probed node keeps a column of its own.
+#[derive(Debug, Clone)]
+pub struct ProbedEvaluator<S: Scalar = f32> {
+    pub evaluator: MatrixFeedforwardEvaluator<S>,
+    /// Probed node ids along with the index of the state holding them, zero being the input, and their column.
+    pub positions: Vec<(usize, usize, usize)>,
+    probes: DMatrix<S>,
+}
+
+impl<S: Scalar> ProbedEvaluator<S> {
+    /// Fabricates `net` probing the hidden nodes with the given ids, in that order.
+    pub fn fabricate<N, E>(
+        net: &impl NetworkLike<N, E, S>,
+        probes: &[usize],
+    ) -> Result<Self, FabricationError>
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+    {
+        let mut evaluator = Self::fabricate_all(net)?;
+        let positions = probes
+            .iter()
+            .map(|&id| {
+                if !net.hidden_iter().any(|node| node.id() == id) {
+                    return Err("only hidden nodes can be probed".into());
+                }
+                evaluator
+                    .positions
+                    .iter()
+                    .find(|&&(node, _, _)| node == id)
+                    .copied()
+                    .ok_or(FabricationError::Invalid("probed node is not computed"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        evaluator.probes = DMatrix::zeros(0, positions.len());
+        evaluator.positions = positions;
+        Ok(evaluator)
+    }
+
+    /// Fabricates `net` probing every node, inputs first in ascending order of their ids, then all other nodes by the column computing them.
+    ///
+    /// Hidden nodes without any edges are computed from their bias as well, nodes computed in the last stage without being an output are not probed.
+    pub(crate) fn fabricate_all<N, E>(
+        net: &impl NetworkLike<N, E, S>,
+    ) -> Result<Self, FabricationError>
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+    {
+        let (evaluator, _, nodes) = MatrixFeedforwardFabricator::fabricate_located(net, true)?;
+
+        let mut inputs = net.inputs_iter().map(|node| node.id()).collect::<Vec<_>>();
+        inputs.sort_unstable();
+        let positions: Vec<_> = inputs
+            .into_iter()
+            .enumerate()
+            .map(|(column, id)| (id, 0, column))
+            // the first state is the input
+            .chain(
+                nodes
+                    .into_iter()
+                    .map(|(id, (stage, column))| (id, stage + 1, column)),
+            )
+            .collect();
+        Ok(Self {
+            evaluator,
+            probes: DMatrix::zeros(0, positions.len()),
+            positions,
+        })
+    }
+
+    /// Values of the probed nodes during the last evaluation, one row per sample and one column per probe in the order they were given.
+    pub fn probes(&self) -> &DMatrix<S> {
+        &self.probes
+    }
+
+    /// Ids of the probed nodes in the order of the columns of [`Self::probes`].
+    pub fn probed_ids(&self) -> Vec<usize> {
+        self.positions.iter().map(|&(id, _, _)| id).collect()
+    }
+}
+
+impl<S: Scalar> StatefulEvaluator<S> for ProbedEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T {
+        let mut states = self.evaluator.evaluate_stages(NetworkIO::input(input));
+        let samples = states[0].nrows();
+        self.probes = DMatrix::from_fn(samples, self.positions.len(), |sample, probe| {
+            let (_, state, column) = self.positions[probe];
+            states[state][(sample, column)]
+        });
+        NetworkIO::output(states.pop().unwrap())
+    }
+
+    /// Clears the recorded values, the wrapped evaluator holds no state.
+    fn reset_internal_state(&mut self) {
+        self.probes = DMatrix::zeros(0, self.positions.len());
+    }
+
+    fn reset_state_for(&mut self, _node_ids: &[usize]) {}
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.evaluator.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.evaluator.output_size())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::ProbedEvaluator;
+    use crate::{
+        edges,
+        network::{
+            net::{activations, Net, Node},
+            FabricationError, StatefulEvaluator,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn probe_hidden_nodes() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'r', 'l', 'l'),
+            edges!(0--1.0->1, 0--2.0->2, 1--1.0->3, 2--1.0->3),
+        );
+        let mut evaluator = ProbedEvaluator::fabricate(&some_net, &[2, 1]).unwrap();
+        assert_eq!(evaluator.probed_ids(), vec![2, 1]);
+
+        assert_eq!(evaluator.evaluate(dmatrix![-1.0; 3.0]), dmatrix![-2.0; 9.0]);
+        assert_eq!(evaluator.probes(), &dmatrix![-2.0, 0.0; 6.0, 3.0]);
+
+        assert!(ProbedEvaluator::fabricate(&some_net, &[3]).is_err());
+    }
+
+    #[test]
+    fn probe_bias_only_node() {
+        // node 1 has no edges, its value is its bias
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_bias(2.0),
+                Node::new(2, activations::LINEAR),
+                Node::new(3, activations::LINEAR),
+            ],
+            edges!(0--2.0->2, 2--1.0->3),
+        );
+        let mut evaluator = ProbedEvaluator::fabricate(&some_net, &[1, 2]).unwrap();
+
+        assert_eq!(evaluator.evaluate(dmatrix![1.0; -1.0]), dmatrix![2.0; -2.0]);
+        assert_eq!(evaluator.probes(), &dmatrix![2.0, 2.0; 2.0, -2.0]);
+    }
+
+    #[test]
+    fn probe_cycle() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--0.5->1, 1--0.5->2, 2--0.5->1, 2--0.5->3),
+        );
+
+        assert_eq!(
+            ProbedEvaluator::fabricate(&some_net, &[1]).unwrap_err(),
+            FabricationError::Cycles(vec![vec![1, 2]])
+        );
+    }
+}
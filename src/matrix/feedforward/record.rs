@@ -0,0 +1,169 @@
+//! Accumulates statistics of the values of every node across evaluations, see [`RecordingEvaluator`].
+
+use std::collections::BTreeMap;
+
+use crate::network::{
+    EdgeLike, FabricationError, NetworkIO, NetworkLike, NodeLike, Scalar, StatefulEvaluator,
+};
+
+use super::probe::ProbedEvaluator;
+
+/// Minimum, maximum, mean and variance of the values a node took, as recorded by [`RecordingEvaluator`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActivationStats<S: Scalar = f32> {
+    /// Number of recorded values, one per evaluated sample.
+    pub count: usize,
+    pub min: S,
+    pub max: S,
+    pub mean: S,
+    // sum of squared differences from the mean, updated by Welford's algorithm
+    squared_deviations: S,
+}
+
+impl<S: Scalar> ActivationStats<S> {
+    fn record(&mut self, value: S) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / nalgebra::convert(self.count as f64);
+        self.squared_deviations += delta * (value - self.mean);
+    }
+
+    /// Population variance of the recorded values, zero if none were recorded.
+    pub fn variance(&self) -> S {
+        if self.count == 0 {
+            S::zero()
+        } else {
+            self.squared_deviations / nalgebra::convert(self.count as f64)
+        }
+    }
+}
+
+impl<S: Scalar> Default for ActivationStats<S> {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            min: S::zero(),
+            max: S::zero(),
+            mean: S::zero(),
+            squared_deviations: S::zero(),
+        }
+    }
+}
+
+/// Probes every node like a [`ProbedEvaluator`] and records [`ActivationStats`] of their values over all evaluated samples.
+///
+/// Inputs are recorded as given, all other nodes after their activation.
+/// Nodes whose values hardly vary are candidates for pruning, sigmoids with a minimum and maximum close to zero and one are saturated.
+#[derive(Debug, Clone)]
+pub struct RecordingEvaluator<S: Scalar = f32> {
+    pub probed: ProbedEvaluator<S>,
+    stats: BTreeMap<usize, ActivationStats<S>>,
+}
+
+impl<S: Scalar> RecordingEvaluator<S> {
+    /// Fabricates `net` recording the inputs and every computed node.
+    pub fn fabricate<N, E>(net: &impl NetworkLike<N, E, S>) -> Result<Self, FabricationError>
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+    {
+        Ok(Self {
+            probed: ProbedEvaluator::fabricate_all(net)?,
+            stats: BTreeMap::new(),
+        })
+    }
+
+    /// Statistics of every recorded node keyed by its id, nodes that are not computed are missing.
+    pub fn report(&self) -> &BTreeMap<usize, ActivationStats<S>> {
+        &self.stats
+    }
+}
+
+impl<S: Scalar> StatefulEvaluator<S> for RecordingEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T {
+        let output = self.probed.evaluate(input);
+        for (column, id) in self.probed.probed_ids().into_iter().enumerate() {
+            let stats = self.stats.entry(id).or_default();
+            for &value in self.probed.probes().column(column).iter() {
+                stats.record(value);
+            }
+        }
+        output
+    }
+
+    /// Discards all recorded statistics.
+    fn reset_internal_state(&mut self) {
+        self.stats.clear();
+    }
+
+    fn reset_state_for(&mut self, node_ids: &[usize]) {
+        for id in node_ids {
+            self.stats.remove(id);
+        }
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        self.probed.input_dimension()
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        self.probed.output_dimension()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::RecordingEvaluator;
+    use crate::{
+        edges,
+        network::{net::Net, FabricationError, StatefulEvaluator},
+        nodes,
+    };
+
+    #[test]
+    fn record_activation_stats() {
+        let some_net = Net::new(1, 1, nodes!('l', 'r', 'l'), edges!(0--1.0->1, 1--2.0->2));
+        let mut evaluator = RecordingEvaluator::fabricate(&some_net).unwrap();
+
+        evaluator.evaluate(dmatrix![-1.0; 1.0]);
+        evaluator.evaluate(dmatrix![3.0]);
+
+        let report = evaluator.report();
+        assert_eq!(report.len(), 3);
+        assert_eq!(report[&0].count, 3);
+        assert_eq!((report[&0].min, report[&0].max), (-1.0, 3.0));
+        assert_eq!(report[&0].mean, 1.0);
+        assert!((report[&0].variance() - 8.0 / 3.0).abs() < 1e-6);
+        assert_eq!((report[&1].min, report[&1].max), (0.0, 3.0));
+        assert_eq!(report[&2].max, 6.0);
+
+        evaluator.reset_state_for(&[1]);
+        assert!(!evaluator.report().contains_key(&1));
+        evaluator.reset_internal_state();
+        assert!(evaluator.report().is_empty());
+    }
+
+    #[test]
+    fn record_cycle() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--0.5->1, 1--0.5->2, 2--0.5->1, 2--0.5->3),
+        );
+
+        assert_eq!(
+            RecordingEvaluator::fabricate(&some_net).unwrap_err(),
+            FabricationError::Cycles(vec![vec![1, 2]])
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! SIMD accelerated stage multiplication for `f32` networks, available with the feature `simd`.
+
+use std::{any::Any, convert::TryFrom};
+
+use nalgebra::DMatrix;
+use wide::f32x8;
+
+use crate::network::Scalar;
+
+/// Multiplies state and stage matrix with explicit SIMD dot products if the scalar is `f32`.
+///
+/// Returns `None` for any other scalar so the caller can fall back to the generic multiplication.
+pub(crate) fn multiply<S: Scalar>(
+    state: &DMatrix<S>,
+    stage_matrix: &DMatrix<S>,
+) -> Option<DMatrix<S>> {
+    let state = (state as &dyn Any).downcast_ref::<DMatrix<f32>>()?;
+    let stage_matrix = (stage_matrix as &dyn Any).downcast_ref::<DMatrix<f32>>()?;
+
+    let result: Box<dyn Any> = Box::new(multiply_f32(state, stage_matrix));
+
+    result.downcast::<DMatrix<S>>().ok().map(|result| *result)
+}
+
+fn multiply_f32(state: &DMatrix<f32>, stage_matrix: &DMatrix<f32>) -> DMatrix<f32> {
+    assert_eq!(
+        state.ncols(),
+        stage_matrix.nrows(),
+        "state and stage matrix dimensions do not match"
+    );
+
+    let rows = stage_matrix.nrows();
+    let mut result = DMatrix::zeros(state.nrows(), stage_matrix.ncols());
+    // storage is column major, so every column of the stage matrix is a contiguous slice
+    let columns = stage_matrix.as_slice().chunks_exact(rows.max(1));
+
+    let mut sample = vec![0.0; rows];
+    for (sample_index, state_row) in state.row_iter().enumerate() {
+        // copy sample into contiguous memory
+        for (value, state_value) in sample.iter_mut().zip(state_row.iter()) {
+            *value = *state_value;
+        }
+        for (column_index, column) in columns.clone().enumerate() {
+            result[(sample_index, column_index)] = dot(&sample, column);
+        }
+    }
+
+    result
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    let chunks_a = a.chunks_exact(8);
+    let chunks_b = b.chunks_exact(8);
+
+    let remainder: f32 = chunks_a
+        .remainder()
+        .iter()
+        .zip(chunks_b.remainder())
+        .map(|(a, b)| a * b)
+        .sum();
+
+    let mut accumulator = f32x8::ZERO;
+    for (a, b) in chunks_a.zip(chunks_b) {
+        let a = f32x8::from(<[f32; 8]>::try_from(a).unwrap());
+        let b = f32x8::from(<[f32; 8]>::try_from(b).unwrap());
+        accumulator = a.mul_add(b, accumulator);
+    }
+
+    accumulator.reduce_add() + remainder
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::DMatrix;
+
+    #[test]
+    fn simd_multiplication_matches_generic_multiplication() {
+        for &(samples, rows, columns) in &[(1, 1, 1), (1, 8, 3), (3, 19, 5), (2, 0, 2)] {
+            let state = DMatrix::from_fn(samples, rows, |r, c| (r + c % 4) as f32);
+            let stage_matrix = DMatrix::from_fn(rows, columns, |r, c| (r % 3) as f32 - c as f32);
+
+            assert_eq!(
+                super::multiply(&state, &stage_matrix).unwrap(),
+                &state * &stage_matrix
+            );
+        }
+    }
+
+    #[test]
+    fn simd_multiplication_skips_other_scalars() {
+        let state = DMatrix::<f64>::from_element(1, 2, 1.0);
+        let stage_matrix = DMatrix::<f64>::from_element(2, 2, 1.0);
+
+        assert!(super::multiply(&state, &stage_matrix).is_none());
+    }
+}
@@ -1,2 +1,189 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::network::{net::activations, Aggregation, Scalar};
+
 pub mod feedforward;
 pub mod recurrent;
+
+/// Normalizes inputs before they enter the first stage, see [`feedforward::evaluator::MatrixFeedforwardEvaluator::with_normalization`].
+///
+/// Values are given per input in evaluation order, inputs beyond them are left as they are.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Normalization<S: Scalar = f32> {
+    /// Maps every input to `(input - mean) / deviation`.
+    Standard { mean: Vec<S>, deviation: Vec<S> },
+    /// Maps every input from `[min, max]` to `[0, 1]`.
+    MinMax { min: Vec<S>, max: Vec<S> },
+}
+
+impl<S: Scalar> Normalization<S> {
+    /// Returns the normalization as `input * scale + offset` by `(scale, offset)`.
+    pub(crate) fn affine(&self) -> Result<(Vec<S>, Vec<S>), &'static str> {
+        let (shift, spread) = match self {
+            Self::Standard { mean, deviation } => (mean, deviation.clone()),
+            Self::MinMax { min, max } => {
+                if min.len() != max.len() {
+                    return Err("normalization needs as many minima as maxima");
+                }
+                (
+                    min,
+                    max.iter().zip(min).map(|(&max, &min)| max - min).collect(),
+                )
+            }
+        };
+        if shift.len() != spread.len() {
+            return Err("normalization needs as many means as deviations");
+        }
+        if spread.iter().any(|spread| spread.is_zero()) {
+            return Err("normalization can not divide by zero");
+        }
+        Ok((
+            spread.iter().map(|&spread| S::one() / spread).collect(),
+            shift
+                .iter()
+                .zip(&spread)
+                .map(|(&shift, &spread)| -shift / spread)
+                .collect(),
+        ))
+    }
+}
+
+/// Replaces sigmoid, tanh and gaussian activations by [`activations::Table`]s of `resolution` entries over `[-range, range]`.
+///
+/// All columns with the same activation share one table.
+pub(crate) fn tabulate<S: Scalar>(
+    transformations: &mut [crate::Transformations<S>],
+    resolution: usize,
+    range: S,
+) {
+    let mut tables: Vec<(&'static str, activations::Table<S>)> = Vec::new();
+    for activation in transformations.iter_mut().flatten() {
+        let (Some(function), Some(name @ ("sigmoid" | "tanh" | "gaussian"))) =
+            (activation.function(), activation.name())
+        else {
+            continue;
+        };
+        let table = match tables.iter().find(|(tabulated, _)| *tabulated == name) {
+            Some((_, table)) => table.clone(),
+            None => {
+                let table = activations::Table::new(function, resolution, range);
+                tables.push((name, table.clone()));
+                table
+            }
+        };
+        *activation = activations::Activation::Table(table);
+    }
+}
+
+/// Describes a column of a stage for the `Display` implementations of the evaluators.
+///
+/// Columns passing on a single value unchanged are shown as carries,
+/// a node with a single unit weight input, no bias and a linear activation is indistinguishable from one.
+pub(crate) fn describe_column<S: Scalar>(
+    activation: &activations::Activation<S>,
+    aggregation: Option<Aggregation>,
+    carry: bool,
+) -> String {
+    let name = activation.name().unwrap_or("custom");
+    match aggregation {
+        Some(aggregation) => alloc::format!("{}({:?})", name, aggregation).to_lowercase(),
+        None if carry && name == "linear" => "carry".to_string(),
+        None => name.to_string(),
+    }
+}
+
+/// Shortens a description of [`describe_column`] to the letter of the activation used by the `nodes!` macro.
+///
+/// Carries become `=`, activations without a letter `?`, a non-sum aggregation is kept as suffix, e.g. `t(max)`.
+fn abbreviate(description: &str) -> String {
+    let (name, aggregation) = description
+        .find('(')
+        .map_or((description, ""), |split| description.split_at(split));
+    let letter = match name {
+        "carry" => '=',
+        "linear" => 'l',
+        "sigmoid" => 's',
+        "tanh" => 't',
+        "gaussian" => 'g',
+        "relu" => 'r',
+        "squared" => 'q',
+        "inverse" => 'i',
+        "step" => 'p',
+        "sine" => 'n',
+        "cosine" => 'c',
+        "abs" => 'a',
+        _ => '?',
+    };
+    alloc::format!("{}{}", letter, aggregation)
+}
+
+/// Draws the stages as a vertical pipeline of the values passed between them, annotated with the abbreviated columns.
+pub(crate) fn render_stages(
+    dimensions: &[(usize, usize)],
+    describe: impl Fn(usize, usize) -> String,
+) -> String {
+    let mut rendered = String::new();
+    let Some(&(inputs, _)) = dimensions.first() else {
+        return rendered;
+    };
+    rendered.push_str(&alloc::format!("{} inputs\n", inputs));
+    for (index, &(rows, columns)) in dimensions.iter().enumerate() {
+        let letters = (0..columns)
+            .map(|column| abbreviate(&describe(index, column)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        rendered.push_str(&alloc::format!(
+            "  | stage {}: {} x {} [{}]\n",
+            index,
+            rows,
+            columns,
+            letters
+        ));
+        let kind = if index + 1 == dimensions.len() {
+            "outputs"
+        } else {
+            "values"
+        };
+        rendered.push_str(&alloc::format!("{} {}\n", columns, kind));
+    }
+    rendered
+}
+
+/// Writes one aligned line per stage with its dimensions and a description of every column.
+pub(crate) fn fmt_stages(
+    f: &mut fmt::Formatter<'_>,
+    dimensions: &[(usize, usize)],
+    describe: impl Fn(usize, usize) -> String,
+) -> fmt::Result {
+    let width = |values: &mut dyn Iterator<Item = usize>| {
+        values
+            .map(|value| value.to_string().len())
+            .max()
+            .unwrap_or(1)
+    };
+    let index_width = width(&mut (0..dimensions.len()));
+    let rows_width = width(&mut dimensions.iter().map(|&(rows, _)| rows));
+    let columns_width = width(&mut dimensions.iter().map(|&(_, columns)| columns));
+
+    for (index, &(rows, columns)) in dimensions.iter().enumerate() {
+        write!(
+            f,
+            "stage {:>index_width$}: {:>rows_width$} x {:<columns_width$} |",
+            index,
+            rows,
+            columns,
+            index_width = index_width,
+            rows_width = rows_width,
+            columns_width = columns_width
+        )?;
+        for column in 0..columns {
+            write!(f, " {}", describe(index, column))?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
@@ -1,39 +1,343 @@
+use alloc::vec::Vec;
+use core::fmt;
+
 use nalgebra::DMatrix;
 
 use crate::{
-    matrix::feedforward::evaluator::MatrixFeedforwardEvaluator,
-    network::{Evaluator, NetworkIO, StatefulEvaluator},
+    matrix::{feedforward::evaluator::MatrixFeedforwardEvaluator, Normalization},
+    network::{
+        Cost, EvaluateInto, Evaluator, NetworkIO, Scalar, Scratch, StatefulEvaluateInto,
+        StatefulEvaluator,
+    },
 };
 
+/// Locates an edge of the fabricated net inside the stages of [`MatrixRecurrentEvaluator::evaluator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EdgePosition {
+    pub start: usize,
+    pub end: usize,
+    pub recurrent: bool,
+    pub stage: usize,
+    pub row: usize,
+    pub column: usize,
+    /// Column of the internal state holding the modulation of the end node, see [`crate::network::Modulated`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub modulator: Option<usize>,
+}
+
+/// Hebbian plasticity rule applied to every edge after each evaluation: `w += learning_rate * pre * post - decay * w`.
+///
+/// `pre` is the value of the start node, for recurrent edges from the previous evaluation, and `post` the value of the end node.
+/// The learning rate is scaled by the modulation of the end node if it has modulatory edges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hebbian<S: Scalar = f32> {
+    pub learning_rate: S,
+    pub decay: S,
+}
+
+/// Internal state of a recurrent evaluator kept apart from it, so many agents can share one evaluator, e.g. behind an `Arc`.
+///
+/// It is created by [`MatrixRecurrentEvaluator::new_state`] and laid out like [`MatrixRecurrentEvaluator::state`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct State<S: Scalar = f32> {
+    pub internal: DMatrix<S>,
+}
+
+impl<S: Scalar> State<S> {
+    pub fn reset(&mut self) {
+        self.internal.fill(S::zero());
+    }
+}
+
+/// Prepends `input` to the internal state, which the wrapped feedforward evaluator takes as additional inputs.
+pub(crate) fn with_internal<S: Scalar>(input: DMatrix<S>, internal: &DMatrix<S>) -> DMatrix<S> {
+    DMatrix::from_iterator(
+        1,
+        input.len() + internal.len(),
+        input.iter().chain(internal.iter()).cloned(),
+    )
+}
+
 #[derive(Debug)]
-pub struct MatrixRecurrentEvaluator {
-    pub internal: DMatrix<f32>,
-    pub evaluator: MatrixFeedforwardEvaluator,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatrixRecurrentEvaluator<S: Scalar = f32> {
+    pub internal: DMatrix<S>,
+    pub evaluator: MatrixFeedforwardEvaluator<S>,
     pub outputs: usize,
+    /// Id of the node held by every column of [`Self::internal`], delay buffers and cell states of gated nodes have ids of their own.
+    #[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
+    pub memory_ids: Vec<usize>,
+    /// Edges of the fabricated net that can be adapted, edges into nodes not summing their inputs, with a response other than one or into gated cells are not included.
+    #[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
+    pub edges: Vec<EdgePosition>,
+    #[cfg_attr(feature = "serde", serde(default = "Option::default"))]
+    pub plasticity: Option<Hebbian<S>>,
 }
 
-impl StatefulEvaluator for MatrixRecurrentEvaluator {
-    fn evaluate<T: NetworkIO>(&mut self, input: T) -> T {
-        let mut input = NetworkIO::input(input);
-        input = DMatrix::from_iterator(
-            1,
-            input.len() + self.internal.len(),
-            input.iter().chain(self.internal.iter()).cloned(),
-        );
+impl<S: Scalar> MatrixRecurrentEvaluator<S> {
+    /// Returns the internal state, i.e. the values of all wrapped outputs and recurrent edge starts of the last evaluation.
+    ///
+    /// With the feature `serde` a clone of it can be stored as a checkpoint and later restored with [`Self::set_state`].
+    pub fn state(&self) -> &DMatrix<S> {
+        &self.internal
+    }
 
-        self.internal = self.evaluator.evaluate(input);
+    /// Replaces the internal state, e.g. to warm-start from a checkpoint taken by [`Self::state`].
+    pub fn set_state(&mut self, state: DMatrix<S>) -> Result<(), &'static str> {
+        if state.shape() != self.internal.shape() {
+            return Err("state shape does not match evaluator");
+        }
+        self.internal = state;
+        Ok(())
+    }
 
-        NetworkIO::output(DMatrix::from_iterator(
-            1,
-            self.outputs,
-            self.internal
-                .slice((0, 0), (1, self.outputs))
-                .iter()
-                .cloned(),
-        ))
+    /// Returns a zeroed [`State`] for [`Self::evaluate_with_state`].
+    pub fn new_state(&self) -> State<S> {
+        State {
+            internal: DMatrix::zeros(1, self.internal.len()),
+        }
+    }
+
+    /// Evaluates with the given [`State`] instead of the internal one, leaving the evaluator untouched.
+    ///
+    /// Plasticity is not applied, as it would change the weights shared by all states.
+    pub fn evaluate_with_state<T: NetworkIO<S>>(&self, state: &mut State<S>, input: T) -> T {
+        state.internal = self
+            .evaluator
+            .evaluate(with_internal(NetworkIO::input(input), &state.internal));
+        NetworkIO::output(state.internal.columns(0, self.outputs).into_owned())
+    }
+
+    /// Enables online adaption of the edge weights by the given [`Hebbian`] rule.
+    pub fn with_plasticity(mut self, plasticity: Hebbian<S>) -> Self {
+        self.plasticity = Some(plasticity);
+        self
+    }
+
+    /// Normalizes the inputs like [`MatrixFeedforwardEvaluator::with_normalization`], the internal state is passed on unchanged.
+    ///
+    /// The normalization always becomes a stage of its own, so adapted weights keep referring to the raw edges.
+    pub fn with_normalization(
+        mut self,
+        normalization: &Normalization<S>,
+    ) -> Result<Self, &'static str> {
+        let (scale, offset) = normalization.affine()?;
+        if scale.len() > self.input_size() {
+            return Err("normalization given for more values than there are inputs");
+        }
+        self.evaluator.normalize(&scale, &offset, false);
+        for edge in self.edges.iter_mut() {
+            edge.stage += 1;
+        }
+        Ok(self)
+    }
+
+    /// Perturbs the weights like [`MatrixFeedforwardEvaluator::perturb_weights`], the internal state is kept.
+    pub fn perturb_weights(&mut self, noise: &impl Fn(usize, usize) -> S) {
+        self.evaluator.perturb_weights(noise);
+    }
+
+    /// Reverts all perturbations made by [`Self::perturb_weights`].
+    pub fn restore(&mut self) {
+        self.evaluator.restore();
+    }
+
+    /// Estimates the cost of a single evaluation step, i.e. the cost of the wrapped feedforward evaluator.
+    pub fn cost(&self) -> Cost {
+        self.evaluator.cost()
+    }
+
+    /// Number of values every evaluation provides as input, the internal state is fed in by the evaluator itself.
+    pub fn input_size(&self) -> usize {
+        self.evaluator.input_size() - self.internal.len()
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.outputs
+    }
+
+    pub fn stage_count(&self) -> usize {
+        self.evaluator.stage_count()
+    }
+
+    /// Dimensions of every stage of the wrapped feedforward evaluator, including the columns of the internal state.
+    pub fn stage_dimensions(&self) -> Vec<(usize, usize)> {
+        self.evaluator.stage_dimensions()
+    }
+
+    /// Returns the current weights of the regular edges as `(start, end, weight)`.
+    pub fn weights(&self) -> Vec<(usize, usize, S)> {
+        self.current_weights(false)
+    }
+
+    /// Changes the weight of a regular edge in place, only edges listed in [`Self::edges`] can be changed.
+    pub fn set_weight(&mut self, start: usize, end: usize, weight: S) -> Result<(), &'static str> {
+        self.set_current_weight(start, end, weight, false)
+    }
+
+    /// Changes the weight of a recurrent edge in place, only edges listed in [`Self::edges`] can be changed.
+    pub fn set_recurrent_weight(
+        &mut self,
+        start: usize,
+        end: usize,
+        weight: S,
+    ) -> Result<(), &'static str> {
+        self.set_current_weight(start, end, weight, true)
+    }
+
+    fn set_current_weight(
+        &mut self,
+        start: usize,
+        end: usize,
+        weight: S,
+        recurrent: bool,
+    ) -> Result<(), &'static str> {
+        let edge = self
+            .edges
+            .iter()
+            .find(|edge| edge.start == start && edge.end == end && edge.recurrent == recurrent)
+            .ok_or("edge is not addressable in the stages")?;
+        self.evaluator
+            .set_entry(edge.stage, edge.row, edge.column, weight);
+        Ok(())
+    }
+
+    /// Returns the weights of the regular edges followed by those of the recurrent edges, in the order of [`Self::weights`] and [`Self::recurrent_weights`].
+    pub fn parameters(&self) -> Vec<S> {
+        self.weights()
+            .into_iter()
+            .chain(self.recurrent_weights())
+            .map(|(_, _, weight)| weight)
+            .collect()
+    }
+
+    /// Changes the weights of all addressable edges in place, ordered like [`Self::parameters`].
+    pub fn set_parameters(&mut self, parameters: &[S]) -> Result<(), &'static str> {
+        if parameters.len() != self.edges.len() {
+            return Err("one parameter per addressable edge is needed");
+        }
+        let edges = self
+            .edges
+            .iter()
+            .filter(|edge| !edge.recurrent)
+            .chain(self.edges.iter().filter(|edge| edge.recurrent))
+            .map(|edge| (edge.stage, edge.row, edge.column))
+            .collect::<Vec<_>>();
+        for ((stage, row, column), &weight) in edges.into_iter().zip(parameters) {
+            self.evaluator.set_entry(stage, row, column, weight);
+        }
+        Ok(())
+    }
+
+    /// Returns the current weights of the recurrent edges as `(start, end, weight)`.
+    pub fn recurrent_weights(&self) -> Vec<(usize, usize, S)> {
+        self.current_weights(true)
+    }
+
+    fn current_weights(&self, recurrent: bool) -> Vec<(usize, usize, S)> {
+        self.edges
+            .iter()
+            .filter(|edge| edge.recurrent == recurrent)
+            .map(|edge| {
+                (
+                    edge.start,
+                    edge.end,
+                    self.evaluator.stages[edge.stage][(edge.row, edge.column)],
+                )
+            })
+            .collect()
+    }
+}
+
+impl<S: Scalar> StatefulEvaluator<S> for MatrixRecurrentEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T {
+        let input = with_internal(NetworkIO::input(input), &self.internal);
+
+        if let Some(Hebbian {
+            learning_rate,
+            decay,
+        }) = self.plasticity
+        {
+            let mut states = self.evaluator.evaluate_stages(input);
+            let modulation = states.last().unwrap();
+            for edge in &self.edges {
+                let pre = states[edge.stage][(0, edge.row)];
+                let post = states[edge.stage + 1][(0, edge.column)];
+                let rate = edge.modulator.map_or(learning_rate, |column| {
+                    learning_rate * modulation[(0, column)]
+                });
+                let weight = &mut self.evaluator.stages[edge.stage][(edge.row, edge.column)];
+                *weight += rate * pre * post - decay * *weight;
+            }
+            self.internal = states.pop().unwrap();
+        } else {
+            self.internal = self.evaluator.evaluate(input);
+        }
+
+        NetworkIO::output(self.internal.columns(0, self.outputs).into_owned())
     }
 
     fn reset_internal_state(&mut self) {
-        self.internal = DMatrix::from_element(1, self.internal.len(), 0.0);
+        self.internal = DMatrix::from_element(1, self.internal.len(), S::zero());
+    }
+
+    fn reset_state_for(&mut self, node_ids: &[usize]) {
+        for (column, id) in self.memory_ids.iter().enumerate() {
+            if node_ids.contains(id) {
+                self.internal[(0, column)] = S::zero();
+            }
+        }
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
+
+impl<S: Scalar> StatefulEvaluateInto<S> for MatrixRecurrentEvaluator<S> {
+    fn scratch(&self) -> Scratch<S> {
+        self.evaluator.scratch()
+    }
+
+    /// Falls back to [`StatefulEvaluator::evaluate`] when plasticity is enabled, as adapting the weights needs the values of all stages.
+    fn evaluate_into(&mut self, input: &[S], output: &mut [S], scratch: &mut Scratch<S>) {
+        if self.plasticity.is_some() {
+            let result: DMatrix<S> =
+                StatefulEvaluator::evaluate(self, DMatrix::from_row_slice(1, input.len(), input));
+            output.copy_from_slice(result.as_slice());
+            return;
+        }
+
+        let inputs = self.input_size();
+        scratch.current[..inputs].copy_from_slice(input);
+        scratch.current[inputs..inputs + self.internal.len()]
+            .copy_from_slice(self.internal.as_slice());
+        self.evaluator.evaluate_scratch(scratch);
+        let internal = self.internal.len();
+        self.internal
+            .as_mut_slice()
+            .copy_from_slice(&scratch.current[..internal]);
+        output.copy_from_slice(&self.internal.as_slice()[..self.outputs]);
+    }
+}
+
+/// Prints the size of the internal state followed by the stages of the wrapped feedforward evaluator.
+impl<S: Scalar> fmt::Display for MatrixRecurrentEvaluator<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} inputs, {} outputs, {} state columns",
+            self.input_size(),
+            self.outputs,
+            self.internal.len()
+        )?;
+        write!(f, "{}", self.evaluator)
     }
 }
@@ -3,47 +3,286 @@ use nalgebra::DMatrix;
 use crate::{
     matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
     network::{
-        net::unroll, EdgeLike, Fabricator, NetworkLike, NodeLike, Recurrent, StatefulFabricator,
+        delayed::expand_delayed,
+        gated::expand_gated,
+        leaky::expand_leaky,
+        net::{activations, memory_ids, unroll_with_map, Edge, Net, Node},
+        prune::copy_node,
+        remap::{fabricable_modulated, fabricable_recurrent},
+        EdgeLike, FabricationError, Modulated, NetworkLike, NodeId, NodeLike, Recurrent, Scalar,
+        StatefulFabricator,
     },
 };
 
-use super::evaluator::MatrixRecurrentEvaluator;
+use super::evaluator::{EdgePosition, MatrixRecurrentEvaluator};
 
 pub struct MatrixRecurrentFabricator;
 
-impl<N, E> StatefulFabricator<N, E> for MatrixRecurrentFabricator
-where
-    N: NodeLike,
-    E: EdgeLike,
-{
-    type Output = MatrixRecurrentEvaluator;
+impl MatrixRecurrentFabricator {
+    /// Fabricates a [`Recurrent`] [`Modulated`] structure whose modulatory edges scale the plasticity of their end node.
+    ///
+    /// The modulation of a node is the weighted sum over its modulatory edges, evaluated together with the regular edges.
+    /// It multiplies the learning rate of all edges entering the node, see [`MatrixRecurrentEvaluator::with_plasticity`].
+    /// Nodes without modulatory edges keep the plain learning rate.
+    pub fn fabricate_modulated<M, N, E, S, I>(
+        net: &M,
+    ) -> Result<MatrixRecurrentEvaluator<S>, FabricationError>
+    where
+        M: Recurrent<N, E, S, I> + Modulated<N, E, S, I>,
+        N: NodeLike<S, I>,
+        E: EdgeLike<S, I>,
+        S: Scalar,
+        I: NodeId,
+    {
+        let net = &fabricable_modulated(net);
+        let expanded = expand_gated(&expand_delayed(&expand_leaky(net)?)?)?;
+
+        let mut modulated = net
+            .modulatory_edges()
+            .iter()
+            .map(|edge| edge.end())
+            .collect::<Vec<_>>();
+        modulated.sort_unstable();
+        modulated.dedup();
+
+        // every modulated node gets an additional output summing its modulatory edges
+        // the ids are larger than any other, so these outputs are sorted right after the original outputs
+        let first_id = expanded
+            .nodes()
+            .iter()
+            .map(|node| node.id() + 1)
+            .max()
+            .unwrap_or(0);
+        let copy_edge = |edge: &&Edge<S>| Edge::new(edge.start(), edge.end(), edge.weight());
+        let nodes = expanded
+            .nodes()
+            .iter()
+            .map(|node| copy_node(*node))
+            .chain(
+                (0..modulated.len()).map(|index| Node::new(first_id + index, activations::linear)),
+            )
+            .collect();
+        let edges = expanded
+            .edges()
+            .iter()
+            .map(copy_edge)
+            .chain(net.modulatory_edges().iter().map(|edge| {
+                let index = modulated.binary_search(&edge.end()).unwrap();
+                Edge::new(edge.start(), first_id + index, edge.weight())
+            }))
+            .collect();
+        let mut modulated_net = Net::new(
+            expanded.inputs().len(),
+            expanded.outputs().len() + modulated.len(),
+            nodes,
+            edges,
+        );
+        modulated_net
+            .set_recurrent_edges(expanded.recurrent_edges().iter().map(copy_edge).collect());
+
+        let outputs = net.outputs().len();
+        Self::fabricate_expanded(net, &modulated_net, |end| {
+            modulated
+                .binary_search(&end)
+                .ok()
+                .map(|index| outputs + index)
+        })
+    }
 
-    fn fabricate(net: &impl Recurrent<N, E>) -> Result<Self::Output, &'static str> {
-        let unrolled = unroll(net);
-        let evaluator = MatrixFeedforwardFabricator::fabricate(&unrolled)?;
+    /// Fabricates the evaluator of `net` from its gate expanded form, `modulator` gives the state column modulating a node.
+    fn fabricate_expanded<N, E, S>(
+        net: &impl Recurrent<N, E, S>,
+        expanded: &Net<S>,
+        modulator: impl Fn(usize) -> Option<usize>,
+    ) -> Result<MatrixRecurrentEvaluator<S>, FabricationError>
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+        S: Scalar,
+    {
+        let (unrolled, unroll_map) = unroll_with_map(expanded)?;
+        let (evaluator, _) = MatrixFeedforwardFabricator::fabricate_indexed(&unrolled)?;
         let memory = unrolled.outputs().len();
 
         assert!(unrolled.inputs().len() - net.inputs().len() == memory);
 
+        // locate original edges, recurrent ones start at the input wrapping their start
+        let locate = |start: usize, end: usize, recurrent: bool| {
+            let unrolled_start = if recurrent {
+                *unroll_map.get(&start)?
+            } else {
+                start
+            };
+            evaluator
+                .edges
+                .iter()
+                .find(|&&(edge, _)| edge == (unrolled_start, end))
+                .map(|&(_, (stage, row, column))| EdgePosition {
+                    start,
+                    end,
+                    recurrent,
+                    stage,
+                    row,
+                    column,
+                    modulator: modulator(end),
+                })
+        };
+        let edges = net
+            .edges()
+            .into_iter()
+            .filter_map(|edge| locate(edge.start(), edge.end(), false))
+            .chain(
+                net.recurrent_edges()
+                    .into_iter()
+                    .filter_map(|edge| locate(edge.start(), edge.end(), true)),
+            )
+            .collect();
+
         Ok(MatrixRecurrentEvaluator {
-            internal: DMatrix::from_element(1, memory, 0.0),
+            internal: DMatrix::from_element(1, memory, S::zero()),
             evaluator,
             outputs: net.outputs().len(),
+            memory_ids: memory_ids(&unrolled),
+            edges,
+            plasticity: None,
         })
     }
 }
 
+impl<N, E, S, I> StatefulFabricator<N, E, S, I> for MatrixRecurrentFabricator
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    type Output = MatrixRecurrentEvaluator<S>;
+
+    fn fabricate(net: &impl Recurrent<N, E, S, I>) -> Result<Self::Output, FabricationError> {
+        let net = &fabricable_recurrent(net);
+        Self::fabricate_expanded(
+            net,
+            &expand_gated(&expand_delayed(&expand_leaky(net)?)?)?,
+            |_| None,
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use nalgebra::dmatrix;
 
     use crate::{
         edges,
+        matrix::recurrent::evaluator::Hebbian,
         matrix::recurrent::fabricator::MatrixRecurrentFabricator,
-        network::{net::Net, StatefulEvaluator, StatefulFabricator},
+        matrix::Normalization,
+        network::{
+            net::{activations, Net, Node},
+            EvaluationError, StatefulEvaluateInto, StatefulEvaluator, StatefulFabricator,
+        },
         nodes,
     };
 
+    #[test]
+    fn stateful_net_evaluator_normalization() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--1.0->1));
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_normalization(&Normalization::Standard {
+                mean: vec![1.0],
+                deviation: vec![2.0],
+            })
+            .unwrap();
+        assert_eq!(evaluator.input_size(), 1);
+        assert_eq!(evaluator.evaluate(dmatrix![3.0]), dmatrix![1.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![3.0]), dmatrix![2.0]);
+        assert_eq!(evaluator.weights(), vec![(0, 1, 1.0)]);
+    }
+
+    #[test]
+    fn stateful_net_evaluator_set_weight() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--1.0->1));
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        evaluator.set_weight(0, 1, 2.0).unwrap();
+        evaluator.set_recurrent_weight(1, 1, 0.5).unwrap();
+        assert!(evaluator.set_recurrent_weight(0, 1, 1.0).is_err());
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![2.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![3.0]);
+        assert_eq!(evaluator.recurrent_weights(), vec![(1, 1, 0.5)]);
+    }
+
+    #[test]
+    fn stateful_net_evaluator_into() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--0.5->1));
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        let mut scratch = evaluator.scratch();
+        let mut output = [0.0];
+        for expected in [2.0, 3.0, 3.5] {
+            evaluator.evaluate_into(&[2.0], &mut output, &mut scratch);
+            assert_eq!(output, [expected]);
+        }
+        // the internal state is shared with evaluate
+        assert_eq!(evaluator.evaluate(dmatrix![2.0]), dmatrix![3.75]);
+    }
+
+    #[test]
+    fn stateful_net_evaluator_try() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--1.0->1));
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(
+            evaluator.try_evaluate(vec![1.0, 1.0]),
+            Err(EvaluationError::DimensionMismatch {
+                expected: 1,
+                got: 2
+            })
+        );
+        // the failed evaluation did not change the internal state
+        assert_eq!(evaluator.try_evaluate(vec![1.0]), Ok(vec![1.0]));
+        assert_eq!(evaluator.try_evaluate(vec![1.0]), Ok(vec![2.0]));
+    }
+
+    #[test]
+    fn stateful_net_evaluator_shared() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--1.0->1));
+
+        let evaluator =
+            std::sync::Arc::new(MatrixRecurrentFabricator::fabricate(&some_net).unwrap());
+        let mut first = evaluator.new_state();
+        let mut second = evaluator.new_state();
+
+        assert_eq!(
+            evaluator.evaluate_with_state(&mut first, dmatrix![1.0]),
+            dmatrix![1.0]
+        );
+        assert_eq!(
+            evaluator.evaluate_with_state(&mut first, dmatrix![1.0]),
+            dmatrix![2.0]
+        );
+        // every state keeps its own memory
+        assert_eq!(
+            evaluator.evaluate_with_state(&mut second, dmatrix![5.0]),
+            dmatrix![5.0]
+        );
+        assert_eq!(
+            evaluator.evaluate_with_state(&mut first, dmatrix![1.0]),
+            dmatrix![3.0]
+        );
+        assert_eq!(evaluator.state(), &dmatrix![0.0]);
+
+        first.reset();
+        assert_eq!(first, evaluator.new_state());
+    }
+
     #[test]
     fn stateful_net_evaluator_0() {
         let mut some_net = Net::new(
@@ -74,4 +313,209 @@ mod tests {
         let result = evaluator.evaluate(dmatrix![0.0, 0.0]);
         assert_eq!(result, dmatrix![0.0, 5.0]);
     }
+
+    #[test]
+    fn sequence() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--1.0->1));
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+
+        let inputs = vec![dmatrix![1.0], dmatrix![2.0], dmatrix![3.0]];
+        let running_sum = vec![dmatrix![1.0], dmatrix![3.0], dmatrix![6.0]];
+
+        assert_eq!(evaluator.evaluate_sequence(inputs.clone()), running_sum);
+        assert_eq!(
+            evaluator.evaluate_sequence(vec![dmatrix![0.0]]),
+            vec![dmatrix![6.0]]
+        );
+        assert_eq!(evaluator.reset_and_evaluate_sequence(inputs), running_sum);
+    }
+
+    #[test]
+    fn stateful_dimensions() {
+        let mut some_net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--1.0->2));
+        some_net.set_recurrent_edges(edges!(2--1.0->2));
+        let evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(evaluator.input_size(), 2);
+        assert_eq!(evaluator.output_size(), 1);
+        assert_eq!(evaluator.stage_count(), evaluator.stage_dimensions().len());
+    }
+
+    #[test]
+    fn state_checkpoint() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--1.0->1));
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        let mut restored = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+
+        evaluator.evaluate_sequence(vec![dmatrix![1.0], dmatrix![2.0]]);
+        let checkpoint = evaluator.state().clone();
+        assert!(restored.set_state(dmatrix![1.0, 2.0, 3.0]).is_err());
+
+        #[cfg(feature = "serde")]
+        let checkpoint: nalgebra::DMatrix<f32> =
+            serde_json::from_str(&serde_json::to_string(&checkpoint).unwrap()).unwrap();
+
+        restored.set_state(checkpoint).unwrap();
+        assert_eq!(
+            restored.evaluate(dmatrix![3.0]),
+            evaluator.evaluate(dmatrix![3.0])
+        );
+        assert_eq!(restored.state(), &dmatrix![6.0]);
+    }
+
+    #[test]
+    fn partial_reset() {
+        let mut some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(
+                0--1.0->2,
+                1--1.0->3
+            ),
+        );
+        some_net.set_recurrent_edges(edges!(
+            2--1.0->2,
+            3--1.0->3
+        ));
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+
+        evaluator.evaluate(dmatrix![1.0, 1.0]);
+        evaluator.evaluate(dmatrix![1.0, 1.0]);
+        evaluator.reset_state_for(&[2]);
+
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![1.0, 3.0]);
+    }
+
+    #[test]
+    fn hebbian_plasticity() {
+        let mut some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0--0.5->1,
+                1--1.0->2,
+                0--0.5->2
+            ),
+        );
+        some_net.set_recurrent_edges(edges!(2--0.2->1));
+
+        let (learning_rate, decay) = (0.1, 0.01);
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_plasticity(Hebbian {
+                learning_rate,
+                decay,
+            });
+
+        let hebbian =
+            |weight: f32, pre: f32, post: f32| weight + learning_rate * pre * post - decay * weight;
+        let (mut w01, mut w12, mut w02, mut r21) = (0.5, 1.0, 0.5, 0.2);
+        let mut previous = 0.0;
+        for x in [1.0, 0.5, -1.0, 2.0] {
+            let hidden = w01 * x + r21 * previous;
+            let output = w12 * hidden + w02 * x;
+            w01 = hebbian(w01, x, hidden);
+            w12 = hebbian(w12, hidden, output);
+            w02 = hebbian(w02, x, output);
+            r21 = hebbian(r21, previous, hidden);
+            previous = output;
+
+            let result = evaluator.evaluate(dmatrix![x]);
+            assert!((result[0] - output).abs() < 1e-6);
+        }
+
+        let mut weights = evaluator.weights();
+        weights.sort_by_key(|&(start, end, _)| (start, end));
+        for ((start, end, weight), expected) in
+            weights
+                .into_iter()
+                .zip([(0, 1, w01), (0, 2, w02), (1, 2, w12)])
+        {
+            assert_eq!((start, end), (expected.0, expected.1));
+            assert!((weight - expected.2).abs() < 1e-6);
+        }
+        let recurrent_weights = evaluator.recurrent_weights();
+        assert_eq!(recurrent_weights.len(), 1);
+        assert!((recurrent_weights[0].2 - r21).abs() < 1e-6);
+    }
+
+    #[test]
+    fn modulated_plasticity() {
+        let mut some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l'),
+            edges!(
+                0--0.5->2,
+                1--0.5->2
+            ),
+        );
+        some_net.set_modulatory_edges(edges!(1--2.0->2));
+
+        let learning_rate = 0.1;
+        let mut evaluator = MatrixRecurrentFabricator::fabricate_modulated(&some_net)
+            .unwrap()
+            .with_plasticity(Hebbian {
+                learning_rate,
+                decay: 0.0,
+            });
+
+        let (mut w02, mut w12) = (0.5, 0.5);
+        for (x0, x1) in [(1.0, 0.0), (1.0, 0.5), (-1.0, 1.0)] {
+            let output = w02 * x0 + w12 * x1;
+            let rate: f32 = learning_rate * 2.0 * x1;
+            w02 += rate * x0 * output;
+            w12 += rate * x1 * output;
+
+            let result = evaluator.evaluate(dmatrix![x0, x1]);
+            assert_eq!(result.len(), 1);
+            assert!((result[0] - output).abs() < 1e-6);
+        }
+
+        let mut weights = evaluator.weights();
+        weights.sort_by_key(|&(start, end, _)| (start, end));
+        assert!((weights[0].2 - w02).abs() < 1e-6);
+        assert!((weights[1].2 - w12).abs() < 1e-6);
+    }
+
+    #[test]
+    fn custom_activation() {
+        let mut some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_custom_activation(|x| x * 10.0),
+            ],
+            edges!(0--1.0->1),
+        );
+        some_net.set_recurrent_edges(edges!(1--0.5->1));
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![10.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![60.0]);
+    }
+
+    #[test]
+    fn modulated_response() {
+        let mut some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_response(2.0),
+            ],
+            edges!(0--1.0->1),
+        );
+        some_net.set_modulatory_edges(edges!(0--1.0->1));
+
+        let mut plain = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        let mut modulated = MatrixRecurrentFabricator::fabricate_modulated(&some_net).unwrap();
+        assert_eq!(plain.evaluate(dmatrix![1.0]), dmatrix![2.0]);
+        assert_eq!(modulated.evaluate(dmatrix![1.0]), dmatrix![2.0]);
+    }
 }
@@ -1,2 +1,3 @@
 pub mod evaluator;
+#[cfg(feature = "std")]
 pub mod fabricator;
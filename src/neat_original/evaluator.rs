@@ -1,10 +1,15 @@
+use alloc::vec::Vec;
+
 use nalgebra::DMatrix;
 
-use crate::network::{NetworkIO, StatefulEvaluator};
+use crate::network::{Aggregation, NetworkIO, StatefulEvaluator};
 
 #[derive(Debug)]
 pub struct DependentNode {
     pub activation_function: fn(f32) -> f32,
+    pub bias: f32,
+    pub response: f32,
+    pub aggregation: Aggregation,
     pub inputs: Vec<(usize, f32, bool)>,
     pub is_active: bool,
 }
@@ -17,9 +22,19 @@ pub struct NeatOriginalEvaluator {
     pub node_input_sum: Vec<f32>,
     // [0] is current output, [1] it output before that
     pub node_active_output: Vec<[f32; 2]>,
+    /// Ids of the nodes as given to the fabricator.
+    pub node_ids: Vec<usize>,
 }
 
 impl NeatOriginalEvaluator {
+    pub fn input_size(&self) -> usize {
+        self.input_ids.len()
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.output_ids.len()
+    }
+
     fn outputs_off(&self) -> bool {
         for &id in self.output_ids.iter() {
             if !self.nodes[id].is_active {
@@ -44,20 +59,23 @@ impl StatefulEvaluator for NeatOriginalEvaluator {
         while self.outputs_off() || !onetime {
             for id in 0..self.nodes.len() {
                 if !self.input_ids.contains(&id) {
-                    self.node_input_sum[id] = 0.0;
                     self.nodes[id].is_active = false;
 
                     let inputs = self.nodes[id].inputs.clone();
+                    let mut weighted_inputs = Vec::with_capacity(inputs.len());
                     for &(dep_id, weight, recurrent) in inputs.iter() {
                         if !recurrent {
                             if self.nodes[dep_id].is_active {
                                 self.nodes[id].is_active = true;
                             }
-                            self.node_input_sum[id] += self.node_active_output[dep_id][0] * weight;
+                            weighted_inputs.push(self.node_active_output[dep_id][0] * weight);
                         } else {
-                            self.node_input_sum[id] += self.node_active_output[dep_id][1] * weight;
+                            weighted_inputs.push(self.node_active_output[dep_id][1] * weight);
                         }
                     }
+                    self.node_input_sum[id] = self.nodes[id].bias
+                        + self.nodes[id].response
+                            * self.nodes[id].aggregation.aggregate(weighted_inputs);
                 }
             }
 
@@ -91,4 +109,21 @@ impl StatefulEvaluator for NeatOriginalEvaluator {
             *value = [0.0; 2];
         }
     }
+
+    fn reset_state_for(&mut self, node_ids: &[usize]) {
+        for (index, id) in self.node_ids.iter().enumerate() {
+            if node_ids.contains(id) {
+                self.node_input_sum[index] = 0.0;
+                self.node_active_output[index] = [0.0; 2];
+            }
+        }
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
 }
@@ -1,39 +1,47 @@
 use std::collections::HashMap;
 
-use crate::network::{EdgeLike, NodeLike, StatefulFabricator};
+use crate::network::{
+    prune::active_edges, remap::fabricable_recurrent, EdgeLike, FabricationError, NetworkLike,
+    NodeId, NodeLike, Recurrent, StatefulFabricator,
+};
 
 use super::evaluator::{DependentNode, NeatOriginalEvaluator};
 
 #[derive(Debug)]
 pub struct NeatOriginalFabricator {}
 
-impl<N, E> StatefulFabricator<N, E> for NeatOriginalFabricator
+impl<N, E, I> StatefulFabricator<N, E, f32, I> for NeatOriginalFabricator
 where
-    N: NodeLike,
-    E: EdgeLike,
+    N: NodeLike<f32, I>,
+    E: EdgeLike<f32, I>,
+    I: NodeId,
 {
     type Output = super::evaluator::NeatOriginalEvaluator;
 
-    fn fabricate(net: &impl crate::network::Recurrent<N, E>) -> Result<Self::Output, &'static str> {
+    fn fabricate(net: &impl Recurrent<N, E, f32, I>) -> Result<Self::Output, FabricationError> {
+        let net = &fabricable_recurrent(net);
         let mut nodes: Vec<DependentNode> = Vec::new();
 
-        let node_input_sum: Vec<f32> = vec![0.0; net.nodes().len()];
-        let node_active_output: Vec<[f32; 2]> = vec![[0.0; 2]; net.nodes().len()];
+        let node_input_sum: Vec<f32> = vec![0.0; net.nodes_iter().count()];
+        let node_active_output: Vec<[f32; 2]> = vec![[0.0; 2]; net.nodes_iter().count()];
 
         let mut id_gen = 0_usize..;
         let mut id_map: HashMap<usize, usize> = HashMap::new();
 
-        for node in net.nodes() {
+        for node in net.nodes_iter() {
             id_map.insert(node.id(), id_gen.next().unwrap());
 
             nodes.push(DependentNode {
                 activation_function: node.activation(),
+                bias: node.bias(),
+                response: node.response(),
+                aggregation: node.aggregation(),
                 inputs: Vec::new(),
                 is_active: false,
             });
         }
 
-        for edge in net.edges() {
+        for edge in active_edges(&net.nodes(), net.edges()) {
             nodes[*id_map.get(&edge.end()).unwrap()].inputs.push((
                 *id_map.get(&edge.start()).unwrap(),
                 edge.weight(),
@@ -41,7 +49,7 @@ where
             ))
         }
 
-        for edge in net.recurrent_edges() {
+        for edge in active_edges(&net.nodes(), net.recurrent_edges()) {
             nodes[*id_map.get(&edge.end()).unwrap()].inputs.push((
                 *id_map.get(&edge.start()).unwrap(),
                 edge.weight(),
@@ -63,6 +71,7 @@ where
             nodes,
             node_input_sum,
             node_active_output,
+            node_ids: net.nodes_iter().map(|node| node.id()).collect(),
         })
     }
 }
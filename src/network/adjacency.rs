@@ -0,0 +1,239 @@
+//! Network stored as adjacency lists, suited to be mutated edge by edge.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    net::{Edge, Node},
+    prune::{copy_edge, copy_node},
+    EdgeLike, NetworkLike, NodeLike, Recurrent, Scalar,
+};
+
+/// Alternative to [`super::net::Net`] keeping the outgoing edges of every node in a list of their own.
+///
+/// Adding an edge takes constant time and the edges of a node are iterated without visiting any other edge,
+/// which suits genomes that are mutated many times between evaluations.
+/// Edges are listed in the order of their start nodes, inputs before hidden nodes before outputs, so fabrication is deterministic.
+#[derive(Debug)]
+pub struct AdjNet<S: Scalar = f32> {
+    inputs: Vec<Node<S>>,
+    hidden: Vec<Node<S>>,
+    outputs: Vec<Node<S>>,
+    ids: HashSet<usize>,
+    outgoing: HashMap<usize, Vec<Edge<S>>>,
+    recurrent: HashMap<usize, Vec<Edge<S>>>,
+}
+
+impl<S: Scalar> AdjNet<S> {
+    /// Creates a net without hidden nodes and edges, ids need to be unique.
+    pub fn new(inputs: Vec<Node<S>>, outputs: Vec<Node<S>>) -> Result<Self, &'static str> {
+        let ids: HashSet<usize> = inputs
+            .iter()
+            .chain(&outputs)
+            .map(|node| node.id())
+            .collect();
+        if ids.len() != inputs.len() + outputs.len() {
+            return Err("node ids need to be unique");
+        }
+        Ok(Self {
+            inputs,
+            hidden: Vec::new(),
+            outputs,
+            ids,
+            outgoing: HashMap::new(),
+            recurrent: HashMap::new(),
+        })
+    }
+
+    /// Copies any recurrent structure, its edges need to connect its nodes.
+    pub fn from_recurrent<R, N, E>(recurrent: &R) -> Result<Self, &'static str>
+    where
+        R: Recurrent<N, E, S>,
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+    {
+        let mut net = Self::new(
+            recurrent.inputs_iter().map(copy_node).collect(),
+            recurrent.outputs_iter().map(copy_node).collect(),
+        )?;
+        for node in recurrent.hidden_iter() {
+            net.add_hidden(copy_node(node))?;
+        }
+        for edge in recurrent.edges_iter() {
+            net.add_edge(copy_edge(edge))?;
+        }
+        for edge in recurrent.recurrent_edges_iter() {
+            net.add_recurrent_edge(copy_edge(edge))?;
+        }
+        Ok(net)
+    }
+
+    /// Adds a hidden node, its id needs to be unused.
+    pub fn add_hidden(&mut self, node: Node<S>) -> Result<(), &'static str> {
+        if !self.ids.insert(node.id()) {
+            return Err("node ids need to be unique");
+        }
+        self.hidden.push(node);
+        Ok(())
+    }
+
+    /// Adds an edge between two nodes of the net.
+    pub fn add_edge(&mut self, edge: Edge<S>) -> Result<(), &'static str> {
+        self.check(&edge)?;
+        self.outgoing.entry(edge.start()).or_default().push(edge);
+        Ok(())
+    }
+
+    /// Adds a recurrent edge between two nodes of the net.
+    pub fn add_recurrent_edge(&mut self, edge: Edge<S>) -> Result<(), &'static str> {
+        self.check(&edge)?;
+        self.recurrent.entry(edge.start()).or_default().push(edge);
+        Ok(())
+    }
+
+    fn check(&self, edge: &Edge<S>) -> Result<(), &'static str> {
+        if self.ids.contains(&edge.start()) && self.ids.contains(&edge.end()) {
+            Ok(())
+        } else {
+            Err("edges need to connect nodes of the net")
+        }
+    }
+
+    /// Removes the first edge from `start` to `end` and returns it, e.g. to split it by a new hidden node.
+    pub fn remove_edge(&mut self, start: usize, end: usize) -> Option<Edge<S>> {
+        let edges = self.outgoing.get_mut(&start)?;
+        let position = edges.iter().position(|edge| edge.end() == end)?;
+        Some(edges.remove(position))
+    }
+
+    /// Removes the first recurrent edge from `start` to `end` and returns it.
+    pub fn remove_recurrent_edge(&mut self, start: usize, end: usize) -> Option<Edge<S>> {
+        let edges = self.recurrent.get_mut(&start)?;
+        let position = edges.iter().position(|edge| edge.end() == end)?;
+        Some(edges.remove(position))
+    }
+
+    /// Iterates the edges starting at node `id`.
+    pub fn outgoing(&self, id: usize) -> impl Iterator<Item = &Edge<S>> {
+        self.outgoing.get(&id).into_iter().flatten()
+    }
+
+    /// Iterates the recurrent edges starting at node `id`.
+    pub fn outgoing_recurrent(&self, id: usize) -> impl Iterator<Item = &Edge<S>> {
+        self.recurrent.get(&id).into_iter().flatten()
+    }
+}
+
+impl<S: Scalar> NetworkLike<Node<S>, Edge<S>, S> for AdjNet<S> {
+    fn edges(&self) -> Vec<&Edge<S>> {
+        self.edges_iter().collect()
+    }
+    fn inputs(&self) -> Vec<&Node<S>> {
+        self.inputs.iter().collect()
+    }
+    fn hidden(&self) -> Vec<&Node<S>> {
+        self.hidden.iter().collect()
+    }
+    fn outputs(&self) -> Vec<&Node<S>> {
+        self.outputs.iter().collect()
+    }
+
+    fn edges_iter<'a>(&'a self) -> impl Iterator<Item = &'a Edge<S>>
+    where
+        Edge<S>: 'a,
+    {
+        self.nodes_iter()
+            .flat_map(move |node| self.outgoing(node.id()))
+    }
+    fn inputs_iter<'a>(&'a self) -> impl Iterator<Item = &'a Node<S>>
+    where
+        Node<S>: 'a,
+    {
+        self.inputs.iter()
+    }
+    fn hidden_iter<'a>(&'a self) -> impl Iterator<Item = &'a Node<S>>
+    where
+        Node<S>: 'a,
+    {
+        self.hidden.iter()
+    }
+    fn outputs_iter<'a>(&'a self) -> impl Iterator<Item = &'a Node<S>>
+    where
+        Node<S>: 'a,
+    {
+        self.outputs.iter()
+    }
+    fn nodes_iter<'a>(&'a self) -> impl Iterator<Item = &'a Node<S>>
+    where
+        Node<S>: 'a,
+    {
+        self.inputs.iter().chain(&self.hidden).chain(&self.outputs)
+    }
+}
+
+impl<S: Scalar> Recurrent<Node<S>, Edge<S>, S> for AdjNet<S> {
+    fn recurrent_edges(&self) -> Vec<&Edge<S>> {
+        self.recurrent_edges_iter().collect()
+    }
+
+    fn recurrent_edges_iter<'a>(&'a self) -> impl Iterator<Item = &'a Edge<S>>
+    where
+        Edge<S>: 'a,
+    {
+        self.nodes_iter()
+            .flat_map(move |node| self.outgoing_recurrent(node.id()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::AdjNet;
+    use crate::{
+        edges,
+        matrix::{
+            feedforward::fabricator::MatrixFeedforwardFabricator,
+            recurrent::fabricator::MatrixRecurrentFabricator,
+        },
+        network::{
+            net::{activations, Edge, Net, Node},
+            EdgeLike, Evaluator, Fabricator, StatefulEvaluator, StatefulFabricator,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn split_edge() {
+        let mut net = AdjNet::new(
+            vec![Node::new(0, activations::LINEAR)],
+            vec![Node::new(1, activations::LINEAR)],
+        )
+        .unwrap();
+        net.add_edge(Edge::new(0, 1, 2.0)).unwrap();
+        assert!(net.add_edge(Edge::new(0, 2, 1.0)).is_err());
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![3.0]), dmatrix![6.0]);
+
+        // mutate like NEAT, splitting the edge by a new node
+        let split = net.remove_edge(0, 1).unwrap();
+        net.add_hidden(Node::new(2, activations::LINEAR)).unwrap();
+        net.add_edge(Edge::new(0, 2, 1.0)).unwrap();
+        net.add_edge(Edge::new(2, 1, split.weight())).unwrap();
+        assert_eq!(net.outgoing(2).count(), 1);
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![3.0]), dmatrix![6.0]);
+    }
+
+    #[test]
+    fn copy_recurrent() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--0.5->1));
+        let net = AdjNet::from_recurrent(&some_net).unwrap();
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![2.0]), dmatrix![2.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![2.0]), dmatrix![3.0]);
+    }
+}
@@ -0,0 +1,127 @@
+//! Supports genome encodings with an always-on bias input, see [`with_bias_input`].
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use super::{
+    net::Net,
+    prune::{copy_edge, copy_node},
+    Aggregation, EdgeLike, NodeLike, Recurrent, Scalar,
+};
+
+/// Returns a copy of `recurrent` where the node `bias_id` is treated as an input constantly holding one.
+///
+/// Edges starting at `bias_id`, regular or recurrent, are folded into the bias of their end node, scaled by its response like any other input.
+/// The bias node itself is removed from the inputs if present, it may as well be missing and only be referred to by edges.
+/// The resulting net is evaluated without a value for the bias input.
+/// Fails if a bias edge ends at a node not summing its inputs, as the constant would then change its aggregation.
+pub fn with_bias_input<R, N, E, S>(recurrent: &R, bias_id: usize) -> Result<Net<S>, &'static str>
+where
+    R: Recurrent<N, E, S>,
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut folded: BTreeMap<usize, S> = BTreeMap::new();
+    for edge in recurrent
+        .edges_iter()
+        .chain(recurrent.recurrent_edges_iter())
+        .filter(|edge| edge.start() == bias_id && edge.enabled())
+    {
+        *folded.entry(edge.end()).or_insert_with(S::zero) += edge.weight();
+    }
+
+    let mut nodes = Vec::new();
+    for node in recurrent.nodes_iter().filter(|node| node.id() != bias_id) {
+        let copy = copy_node(node);
+        nodes.push(match folded.get(&node.id()) {
+            Some(_) if node.aggregation() != Aggregation::Sum => {
+                return Err("bias input connects to a node not summing its inputs")
+            }
+            Some(&weight) => copy.with_bias(node.bias() + weight * node.response()),
+            None => copy,
+        });
+    }
+
+    let kept = |edges: Vec<&E>| {
+        edges
+            .into_iter()
+            .filter(|edge| edge.start() != bias_id)
+            .map(copy_edge)
+            .collect::<Vec<_>>()
+    };
+
+    let inputs = recurrent
+        .inputs_iter()
+        .filter(|node| node.id() != bias_id)
+        .count();
+    let mut net = Net::new(
+        inputs,
+        recurrent.outputs_iter().count(),
+        nodes,
+        kept(recurrent.edges()),
+    );
+    net.set_recurrent_edges(kept(recurrent.recurrent_edges()));
+    Ok(net)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::with_bias_input;
+    use crate::{
+        edges,
+        matrix::{
+            feedforward::fabricator::MatrixFeedforwardFabricator,
+            recurrent::fabricator::MatrixRecurrentFabricator,
+        },
+        network::{
+            net::{activations, Net, Node},
+            Aggregation, Evaluator, Fabricator, NetworkLike, StatefulEvaluator, StatefulFabricator,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn fold_bias_input() {
+        // node 1 is the bias input of the genome
+        let mut some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--1.0->2, 1--0.5->2, 2--2.0->3, 1---1.0->3),
+        );
+
+        let folded = with_bias_input(&some_net, 1).unwrap();
+        assert_eq!(folded.inputs().len(), 1);
+
+        let with_bias = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&folded).unwrap();
+        assert_eq!(
+            evaluator.evaluate(dmatrix![3.0]),
+            with_bias.evaluate(dmatrix![3.0, 1.0])
+        );
+
+        some_net.set_recurrent_edges(edges!(1--0.25->2));
+        let folded = with_bias_input(&some_net, 1).unwrap();
+
+        // the recurrent bias edge adds another quarter to node 2
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&folded).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![3.0]), dmatrix![6.5]);
+        assert_eq!(evaluator.evaluate(dmatrix![3.0]), dmatrix![6.5]);
+    }
+
+    #[test]
+    fn reject_non_sum_targets() {
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_aggregation(Aggregation::Max),
+            ],
+            edges!(0--1.0->1, 5--1.0->1),
+        );
+        assert!(with_bias_input(&some_net, 5).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! Constructs a [`Net`] step by step and validates it on completion.
+
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::fmt;
+
+use super::{
+    net::{Edge, Net, Node},
+    EdgeLike, NodeLike, Scalar,
+};
+
+/// Reasons for [`NetBuilder::build`] to reject a net.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// No input node was added.
+    NoInputs,
+    /// No output node was added.
+    NoOutputs,
+    /// More than one node was added with this id.
+    DuplicateId(usize),
+    /// An edge as `(start, end)` connects at least one id that is not a node.
+    DanglingEdge(usize, usize),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoInputs => write!(f, "net has no inputs"),
+            Self::NoOutputs => write!(f, "net has no outputs"),
+            Self::DuplicateId(id) => write!(f, "node id {} is used more than once", id),
+            Self::DanglingEdge(start, end) => {
+                write!(f, "edge from {} to {} connects an unknown node", start, end)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BuildError {}
+
+/// Builds a [`Net`] without relying on the positional convention of [`Net::new`].
+///
+/// Nodes added by activation get the next free id, i.e. one above the largest id added so far, starting at zero.
+/// Nodes may be added in any order, the net lists inputs first, then hidden nodes and outputs last.
+#[derive(Debug)]
+pub struct NetBuilder<S: Scalar = f32> {
+    inputs: Vec<Node<S>>,
+    hidden: Vec<Node<S>>,
+    outputs: Vec<Node<S>>,
+    edges: Vec<Edge<S>>,
+    recurrent_edges: Vec<Edge<S>>,
+    next_id: usize,
+}
+
+impl<S: Scalar> Default for NetBuilder<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Scalar> NetBuilder<S> {
+    pub fn new() -> Self {
+        Self {
+            inputs: Vec::new(),
+            hidden: Vec::new(),
+            outputs: Vec::new(),
+            edges: Vec::new(),
+            recurrent_edges: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    fn track(&mut self, node: &Node<S>) {
+        self.next_id = self.next_id.max(node.id() + 1);
+    }
+
+    pub fn input(self, activation: fn(S) -> S) -> Self {
+        let id = self.next_id;
+        self.input_node(Node::new(id, activation))
+    }
+
+    pub fn hidden(self, activation: fn(S) -> S) -> Self {
+        let id = self.next_id;
+        self.hidden_node(Node::new(id, activation))
+    }
+
+    pub fn output(self, activation: fn(S) -> S) -> Self {
+        let id = self.next_id;
+        self.output_node(Node::new(id, activation))
+    }
+
+    /// Adds a fully configured input node, e.g. with a label.
+    pub fn input_node(mut self, node: Node<S>) -> Self {
+        self.track(&node);
+        self.inputs.push(node);
+        self
+    }
+
+    /// Adds a fully configured hidden node, e.g. with a bias or another aggregation.
+    pub fn hidden_node(mut self, node: Node<S>) -> Self {
+        self.track(&node);
+        self.hidden.push(node);
+        self
+    }
+
+    /// Adds a fully configured output node, e.g. with a bias or another aggregation.
+    pub fn output_node(mut self, node: Node<S>) -> Self {
+        self.track(&node);
+        self.outputs.push(node);
+        self
+    }
+
+    pub fn edge(self, start: usize, end: usize, weight: S) -> Self {
+        self.custom_edge(Edge::new(start, end, weight))
+    }
+
+    pub fn recurrent_edge(self, start: usize, end: usize, weight: S) -> Self {
+        self.custom_recurrent_edge(Edge::new(start, end, weight))
+    }
+
+    /// Adds a fully configured edge, e.g. with a delay or disabled.
+    pub fn custom_edge(mut self, edge: Edge<S>) -> Self {
+        self.edges.push(edge);
+        self
+    }
+
+    /// Adds a fully configured recurrent edge, e.g. with a gate.
+    pub fn custom_recurrent_edge(mut self, edge: Edge<S>) -> Self {
+        self.recurrent_edges.push(edge);
+        self
+    }
+
+    /// Checks ids and counts and assembles the [`Net`].
+    pub fn build(self) -> Result<Net<S>, BuildError> {
+        if self.inputs.is_empty() {
+            return Err(BuildError::NoInputs);
+        }
+        if self.outputs.is_empty() {
+            return Err(BuildError::NoOutputs);
+        }
+
+        let mut ids = BTreeSet::new();
+        for node in self.inputs.iter().chain(&self.hidden).chain(&self.outputs) {
+            if !ids.insert(node.id()) {
+                return Err(BuildError::DuplicateId(node.id()));
+            }
+        }
+        for edge in self.edges.iter().chain(&self.recurrent_edges) {
+            if !ids.contains(&edge.start()) || !ids.contains(&edge.end()) {
+                return Err(BuildError::DanglingEdge(edge.start(), edge.end()));
+            }
+        }
+
+        let (inputs, outputs) = (self.inputs.len(), self.outputs.len());
+        let mut net = Net::new(
+            inputs,
+            outputs,
+            self.inputs
+                .into_iter()
+                .chain(self.hidden)
+                .chain(self.outputs)
+                .collect(),
+            self.edges,
+        );
+        net.set_recurrent_edges(self.recurrent_edges);
+        Ok(net)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{BuildError, NetBuilder};
+    use crate::{
+        matrix::recurrent::fabricator::MatrixRecurrentFabricator,
+        network::{
+            net::{activations, Node},
+            NetworkLike, NodeLike, StatefulEvaluator, StatefulFabricator,
+        },
+    };
+
+    #[test]
+    fn build_net() {
+        let net = NetBuilder::new()
+            .input(activations::LINEAR)
+            .input(activations::LINEAR)
+            .output(activations::LINEAR)
+            .hidden_node(Node::new(5, activations::LINEAR).with_bias(1.0))
+            .edge(0, 5, 1.0)
+            .edge(1, 5, 2.0)
+            .edge(5, 2, 1.0)
+            .recurrent_edge(2, 2, 1.0)
+            .build()
+            .unwrap();
+
+        assert_eq!(net.inputs().len(), 2);
+        assert_eq!(net.hidden()[0].id(), 5);
+        assert_eq!(net.outputs()[0].id(), 2);
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![4.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![8.0]);
+    }
+
+    #[test]
+    fn reject_invalid_net() {
+        let builder = || {
+            NetBuilder::new()
+                .input(activations::LINEAR)
+                .output(activations::LINEAR)
+        };
+
+        assert_eq!(
+            NetBuilder::<f32>::new()
+                .output(activations::LINEAR)
+                .build()
+                .unwrap_err(),
+            BuildError::NoInputs
+        );
+        assert_eq!(
+            builder()
+                .hidden_node(Node::new(1, activations::LINEAR))
+                .build()
+                .unwrap_err(),
+            BuildError::DuplicateId(1)
+        );
+        assert_eq!(
+            builder().edge(0, 7, 1.0).build().unwrap_err(),
+            BuildError::DanglingEdge(0, 7)
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Composes evaluators, e.g. a planner feeding a low-level controller.
+
+use super::{Evaluator, NetworkIO, Scalar, StatefulEvaluator};
+
+/// Feeds the outputs of the first evaluator as inputs into the second one.
+///
+/// Both evaluators are either an [`Evaluator`] or a [`StatefulEvaluator`], chains can be nested to compose more than two.
+/// [`StatefulEvaluator::reset_state_for`] passes the node ids on to both, as each net has ids of its own.
+#[derive(Debug)]
+pub struct Chained<A, B>(pub A, pub B);
+
+impl<A, B> Chained<A, B> {
+    /// Chains two [`Evaluator`]s, their dimensions need to match where both are known.
+    pub fn new<S: Scalar>(first: A, second: B) -> Result<Self, &'static str>
+    where
+        A: Evaluator<S>,
+        B: Evaluator<S>,
+    {
+        Self::checked(first.output_dimension(), second.input_dimension())?;
+        Ok(Self(first, second))
+    }
+
+    /// Chains two [`StatefulEvaluator`]s, their dimensions need to match where both are known.
+    pub fn new_stateful<S: Scalar>(first: A, second: B) -> Result<Self, &'static str>
+    where
+        A: StatefulEvaluator<S>,
+        B: StatefulEvaluator<S>,
+    {
+        Self::checked(first.output_dimension(), second.input_dimension())?;
+        Ok(Self(first, second))
+    }
+
+    fn checked(outputs: Option<usize>, inputs: Option<usize>) -> Result<(), &'static str> {
+        match (outputs, inputs) {
+            (Some(outputs), Some(inputs)) if outputs != inputs => {
+                Err("outputs of the first evaluator do not match the inputs of the second")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<A: Evaluator<S>, B: Evaluator<S>, S: Scalar> Evaluator<S> for Chained<A, B> {
+    fn evaluate<T: NetworkIO<S>>(&self, input: T) -> T {
+        NetworkIO::output(self.1.evaluate(self.0.evaluate(NetworkIO::input(input))))
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        self.0.input_dimension()
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        self.1.output_dimension()
+    }
+}
+
+impl<A: StatefulEvaluator<S>, B: StatefulEvaluator<S>, S: Scalar> StatefulEvaluator<S>
+    for Chained<A, B>
+{
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T {
+        NetworkIO::output(self.1.evaluate(self.0.evaluate(NetworkIO::input(input))))
+    }
+
+    fn reset_internal_state(&mut self) {
+        self.0.reset_internal_state();
+        self.1.reset_internal_state();
+    }
+
+    fn reset_state_for(&mut self, node_ids: &[usize]) {
+        self.0.reset_state_for(node_ids);
+        self.1.reset_state_for(node_ids);
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        self.0.input_dimension()
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        self.1.output_dimension()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::Chained;
+    use crate::{
+        edges,
+        matrix::{
+            feedforward::fabricator::MatrixFeedforwardFabricator,
+            recurrent::fabricator::MatrixRecurrentFabricator,
+        },
+        network::{net::Net, Evaluator, Fabricator, StatefulEvaluator, StatefulFabricator},
+        nodes,
+    };
+
+    #[test]
+    fn chain_evaluators() {
+        let planner = Net::new(1, 2, nodes!('l', 'l', 'l'), edges!(0--1.0->1, 0--2.0->2));
+        let controller = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--1.0->2));
+
+        let chained = Chained::new(
+            MatrixFeedforwardFabricator::fabricate(&planner).unwrap(),
+            MatrixFeedforwardFabricator::fabricate(&controller).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(chained.evaluate(dmatrix![1.0; 2.0]), dmatrix![3.0; 6.0]);
+        assert_eq!(chained.input_dimension(), Some(1));
+
+        assert!(Chained::new(
+            MatrixFeedforwardFabricator::fabricate(&controller).unwrap(),
+            MatrixFeedforwardFabricator::fabricate(&controller).unwrap(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn chain_stateful_evaluators() {
+        let mut accumulator = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        accumulator.set_recurrent_edges(edges!(1--1.0->1));
+
+        let mut chained = Chained::new_stateful(
+            MatrixRecurrentFabricator::fabricate(&accumulator).unwrap(),
+            MatrixRecurrentFabricator::fabricate(&accumulator).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(chained.evaluate(dmatrix![1.0]), dmatrix![1.0]);
+        assert_eq!(chained.evaluate(dmatrix![1.0]), dmatrix![3.0]);
+
+        chained.reset_internal_state();
+        assert_eq!(chained.evaluate(dmatrix![1.0]), dmatrix![1.0]);
+    }
+}
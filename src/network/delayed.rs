@@ -0,0 +1,116 @@
+//! Expands recurrent edges delayed by more than one evaluation, see [`EdgeLike::delay`].
+
+use alloc::vec::Vec;
+
+use super::{
+    net::{activations, Edge, Net, Node},
+    prune::{active_edges, copy_node},
+    EdgeLike, NodeLike, Recurrent, Scalar,
+};
+
+/// Replaces every recurrent edge with a delay of `k > 1` by a chain of `k - 1` relay nodes connected by recurrent edges.
+///
+/// Each relay holds the value of the start node one evaluation longer, hence the internal state of the recurrent evaluators acts as a buffer per delayed edge.
+/// The weight and gate of the original edge are kept on the last link of the chain.
+/// Recurrent edges with a delay of zero are rejected.
+pub fn expand_delayed<R, N, E, S>(recurrent: &R) -> Result<Net<S>, &'static str>
+where
+    R: Recurrent<N, E, S>,
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut next_id = recurrent
+        .nodes()
+        .iter()
+        .map(|node| node.id() + 1)
+        .max()
+        .unwrap_or(0);
+
+    let mut relays = Vec::new();
+    let mut recurrent_edges = Vec::new();
+
+    let nodes = recurrent.nodes();
+    for edge in active_edges(&nodes, recurrent.recurrent_edges()) {
+        if edge.delay() == 0 {
+            return Err("recurrent edges need a delay of at least one");
+        }
+        let mut start = edge.start();
+        for _ in 1..edge.delay() {
+            relays.push(Node::new(next_id, activations::linear));
+            recurrent_edges.push(Edge::new(start, next_id, S::one()));
+            start = next_id;
+            next_id += 1;
+        }
+        recurrent_edges.push(Edge::new(start, edge.end(), edge.weight()).with_gate(edge.gate()));
+    }
+
+    let mut net = Net::new(
+        recurrent.inputs().len(),
+        recurrent.outputs().len(),
+        recurrent
+            .inputs()
+            .into_iter()
+            .chain(recurrent.hidden())
+            .map(copy_node)
+            .chain(relays)
+            .chain(recurrent.outputs().into_iter().map(copy_node))
+            .collect(),
+        active_edges(&nodes, recurrent.edges()),
+    );
+    net.set_recurrent_edges(recurrent_edges);
+    Ok(net)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::expand_delayed;
+    use crate::{
+        matrix::recurrent::fabricator::MatrixRecurrentFabricator,
+        network::{
+            net::{activations, Edge, Net, Node},
+            StatefulEvaluator, StatefulFabricator,
+        },
+        sparse_matrix::recurrent::fabricator::SparseMatrixRecurrentFabricator,
+    };
+
+    fn echo(delay: usize) -> Net {
+        let mut net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+            ],
+            vec![Edge::new(0, 1, 1.0)],
+        );
+        net.set_recurrent_edges(vec![Edge::new(0, 1, 0.5).with_delay(delay)]);
+        net
+    }
+
+    #[test]
+    fn delayed_echo() {
+        let net = echo(3);
+        let mut matrix_evaluator = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+        let mut sparse_evaluator = SparseMatrixRecurrentFabricator::fabricate(&net).unwrap();
+
+        let inputs = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        for (step, &x) in inputs.iter().enumerate() {
+            let expected = x + if step >= 3 {
+                0.5 * inputs[step - 3]
+            } else {
+                0.0
+            };
+
+            assert_eq!(matrix_evaluator.evaluate(dmatrix![x]), dmatrix![expected]);
+            assert_eq!(sparse_evaluator.evaluate(dmatrix![x]), dmatrix![expected]);
+        }
+    }
+
+    #[test]
+    fn zero_delay() {
+        assert!(expand_delayed(&echo(0)).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+//! Expands gated recurrent cells into plain nodes, see [`NodeKind`].
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+use super::{
+    net::{activations, Edge, Net, Node},
+    prune::copy_node,
+    Aggregation, EdgeLike, Gate, NodeKind, NodeLike, Recurrent, Scalar,
+};
+
+/// Where the edges of every gate of a cell end, separately for regular and recurrent edges.
+struct Routes {
+    kind: NodeKind,
+    gates: Vec<(Gate, (usize, usize))>,
+}
+
+/// Replaces every [`NodeKind::Lstm`] and [`NodeKind::Gru`] node by a subnetwork of plain nodes computing the gating equations.
+///
+/// A cell keeps its id for its output `h`, so edges leaving it are unchanged.
+/// Edges entering it are routed to the gate given by [`EdgeLike::gate`].
+/// The activation and bias of a cell apply to its candidate, gates use [`activations::sigmoid`].
+/// Cell states and previous outputs are carried by recurrent edges, hence the result needs to be fabricated by a recurrent fabricator.
+///
+/// LSTM: `c = f * c' + i * a(candidate)` and `h = o * a(c)`.
+/// GRU: `n = a(candidate + r * recurrent candidate)` and `h = (1 - z) * n + z * h'`.
+pub fn expand_gated<R, N, E, S>(recurrent: &R) -> Result<Net<S>, &'static str>
+where
+    R: Recurrent<N, E, S>,
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut next_id = recurrent
+        .nodes()
+        .iter()
+        .map(|node| node.id() + 1)
+        .max()
+        .unwrap_or(0);
+    let mut fresh = || {
+        next_id += 1;
+        next_id - 1
+    };
+
+    let mut hidden = Vec::new();
+    let mut outputs = Vec::new();
+    let mut edges = Vec::new();
+    let mut recurrent_edges = Vec::new();
+    let mut routes: BTreeMap<usize, Routes> = BTreeMap::new();
+
+    let output_ids = recurrent
+        .outputs()
+        .iter()
+        .map(|node| node.id())
+        .collect::<Vec<_>>();
+
+    for node in recurrent.hidden().into_iter().chain(recurrent.outputs()) {
+        let id = node.id();
+        let (cell, mut parts) = match node.kind() {
+            NodeKind::Plain => (copy_node(node), Vec::new()),
+            NodeKind::Lstm => {
+                let (input, forget, output) = (fresh(), fresh(), fresh());
+                let (candidate, input_candidate, forget_state) = (fresh(), fresh(), fresh());
+                let (state, activated_state) = (fresh(), fresh());
+
+                for (start, end) in [
+                    (input, input_candidate),
+                    (candidate, input_candidate),
+                    (forget, forget_state),
+                    (input_candidate, state),
+                    (forget_state, state),
+                    (state, activated_state),
+                    (output, id),
+                    (activated_state, id),
+                ] {
+                    edges.push(Edge::new(start, end, S::one()));
+                }
+                recurrent_edges.push(Edge::new(state, forget_state, S::one()));
+
+                routes.insert(
+                    id,
+                    Routes {
+                        kind: NodeKind::Lstm,
+                        gates: vec![
+                            (Gate::Input, (input, input)),
+                            (Gate::Forget, (forget, forget)),
+                            (Gate::Output, (output, output)),
+                            (Gate::Candidate, (candidate, candidate)),
+                        ],
+                    },
+                );
+
+                (
+                    Node::new(id, activations::linear).with_aggregation(Aggregation::Product),
+                    vec![
+                        Node::new(input, activations::sigmoid),
+                        Node::new(forget, activations::sigmoid),
+                        Node::new(output, activations::sigmoid),
+                        Node::new(candidate, node.activation())
+                            .with_bias(node.bias())
+                            .with_response(node.response())
+                            .with_closure(node.custom_activation()),
+                        Node::new(input_candidate, activations::linear)
+                            .with_aggregation(Aggregation::Product),
+                        Node::new(forget_state, activations::linear)
+                            .with_aggregation(Aggregation::Product),
+                        Node::new(state, activations::linear),
+                        Node::new(activated_state, node.activation())
+                            .with_closure(node.custom_activation()),
+                    ],
+                )
+            }
+            NodeKind::Gru => {
+                let (update, reset, recurrent_candidate) = (fresh(), fresh(), fresh());
+                let (reset_candidate, candidate, keep) = (fresh(), fresh(), fresh());
+                let (new_part, old_part) = (fresh(), fresh());
+
+                for (start, end, weight) in [
+                    (reset, reset_candidate, S::one()),
+                    (recurrent_candidate, reset_candidate, S::one()),
+                    (reset_candidate, candidate, S::one()),
+                    (update, keep, -S::one()),
+                    (keep, new_part, S::one()),
+                    (candidate, new_part, S::one()),
+                    (update, old_part, S::one()),
+                    (new_part, id, S::one()),
+                    (old_part, id, S::one()),
+                ] {
+                    edges.push(Edge::new(start, end, weight));
+                }
+                recurrent_edges.push(Edge::new(id, old_part, S::one()));
+
+                routes.insert(
+                    id,
+                    Routes {
+                        kind: NodeKind::Gru,
+                        gates: vec![
+                            (Gate::Update, (update, update)),
+                            (Gate::Reset, (reset, reset)),
+                            (Gate::Candidate, (candidate, recurrent_candidate)),
+                        ],
+                    },
+                );
+
+                (
+                    Node::new(id, activations::linear),
+                    vec![
+                        Node::new(update, activations::sigmoid),
+                        Node::new(reset, activations::sigmoid),
+                        Node::new(recurrent_candidate, activations::linear),
+                        Node::new(reset_candidate, activations::linear)
+                            .with_aggregation(Aggregation::Product),
+                        Node::new(candidate, node.activation())
+                            .with_bias(node.bias())
+                            .with_response(node.response())
+                            .with_closure(node.custom_activation()),
+                        // 1 - z
+                        Node::new(keep, activations::linear).with_bias(S::one()),
+                        Node::new(new_part, activations::linear)
+                            .with_aggregation(Aggregation::Product),
+                        Node::new(old_part, activations::linear)
+                            .with_aggregation(Aggregation::Product),
+                    ],
+                )
+            }
+        };
+
+        hidden.append(&mut parts);
+        if output_ids.contains(&id) {
+            outputs.push(cell);
+        } else {
+            hidden.push(cell);
+        }
+    }
+
+    let route = |edge: &E, is_recurrent: bool| -> Result<Edge<S>, &'static str> {
+        let end = match routes.get(&edge.end()) {
+            None => edge.end(),
+            Some(routes) => {
+                let &(_, (regular, recurrent)) = routes
+                    .gates
+                    .iter()
+                    .find(|(gate, _)| *gate == edge.gate())
+                    .ok_or(match routes.kind {
+                        NodeKind::Lstm => {
+                            "lstm cells only have input, forget, output and candidate gates"
+                        }
+                        _ => "gru cells only have update, reset and candidate gates",
+                    })?;
+                if is_recurrent {
+                    recurrent
+                } else {
+                    regular
+                }
+            }
+        };
+        Ok(Edge::new(edge.start(), end, edge.weight()))
+    };
+
+    for edge in recurrent.edges() {
+        edges.push(route(edge, false)?);
+    }
+    for edge in recurrent.recurrent_edges() {
+        recurrent_edges.push(route(edge, true)?);
+    }
+
+    let inputs = recurrent
+        .inputs()
+        .into_iter()
+        .map(copy_node)
+        .collect::<Vec<_>>();
+    let (inputs_count, outputs_count) = (inputs.len(), outputs.len());
+
+    let mut net = Net::new(
+        inputs_count,
+        outputs_count,
+        inputs.into_iter().chain(hidden).chain(outputs).collect(),
+        edges,
+    );
+    net.set_recurrent_edges(recurrent_edges);
+    Ok(net)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::expand_gated;
+    use crate::{
+        matrix::recurrent::fabricator::MatrixRecurrentFabricator,
+        network::{
+            net::{activations, Edge, Net, Node},
+            Gate, NodeKind, StatefulEvaluator, StatefulFabricator,
+        },
+    };
+
+    fn cell(kind: NodeKind, gates: &[(Gate, f64)], recurrent: &[(Gate, f64)]) -> Net<f64> {
+        let mut net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::linear),
+                Node::new(1, activations::tanh)
+                    .with_bias(0.1)
+                    .with_kind(kind),
+            ],
+            gates
+                .iter()
+                .map(|&(gate, weight)| Edge::new(0, 1, weight).with_gate(gate))
+                .collect(),
+        );
+        net.set_recurrent_edges(
+            recurrent
+                .iter()
+                .map(|&(gate, weight)| Edge::new(1, 1, weight).with_gate(gate))
+                .collect(),
+        );
+        net
+    }
+
+    #[test]
+    fn lstm_cell() {
+        let net = cell(
+            NodeKind::Lstm,
+            &[
+                (Gate::Input, 0.5),
+                (Gate::Forget, 0.3),
+                (Gate::Output, 0.8),
+                (Gate::Candidate, 1.2),
+            ],
+            &[(Gate::Candidate, -0.7), (Gate::Forget, 0.2)],
+        );
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+
+        let (sigmoid, tanh) = (activations::sigmoid::<f64>, activations::tanh::<f64>);
+        let (mut h, mut c) = (0.0, 0.0);
+        for x in [1.0, -0.5, 2.0] {
+            let i = sigmoid(0.5 * x);
+            let f = sigmoid(0.3 * x + 0.2 * h);
+            let o = sigmoid(0.8 * x);
+            let g = tanh(1.2 * x - 0.7 * h + 0.1);
+            c = f * c + i * g;
+            h = o * tanh(c);
+
+            let output = evaluator.evaluate(dmatrix![x]);
+            assert!((output[0] - h).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn gru_cell() {
+        let net = cell(
+            NodeKind::Gru,
+            &[
+                (Gate::Update, 0.5),
+                (Gate::Reset, -0.4),
+                (Gate::Candidate, 1.2),
+            ],
+            &[(Gate::Candidate, 0.9), (Gate::Update, 0.3)],
+        );
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+
+        let (sigmoid, tanh) = (activations::sigmoid::<f64>, activations::tanh::<f64>);
+        let mut h = 0.0;
+        for x in [1.0, -0.5, 2.0] {
+            let z = sigmoid(0.5 * x + 0.3 * h);
+            let r = sigmoid(-0.4 * x);
+            let n = tanh(1.2 * x + r * 0.9 * h + 0.1);
+            h = (1.0 - z) * n + z * h;
+
+            let output = evaluator.evaluate(dmatrix![x]);
+            assert!((output[0] - h).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn invalid_gate() {
+        let net = cell(NodeKind::Lstm, &[(Gate::Update, 1.0)], &[]);
+        assert!(expand_gated(&net).is_err());
+    }
+}
@@ -1,47 +1,136 @@
+use alloc::vec::Vec;
+
 use nalgebra::{DMatrix, DVector};
 
+use super::Scalar;
+
 /// Data structures implementing this trait can be used as input and output of networks.
-pub trait NetworkIO {
-    fn input(input: Self) -> DMatrix<f32>;
-    fn output(output: DMatrix<f32>) -> Self;
+///
+/// Evaluators work on matrices holding one sample per row and one value per column, i.e. per input or output node.
+/// Matrices, `DMatrix` as well as `ndarray::Array2` with the feature `ndarray`, keep this layout and are evaluated as batches of samples.
+/// Vectors are single samples, their output has as many values as the net has outputs.
+pub trait NetworkIO<S: Scalar = f32> {
+    fn input(input: Self) -> DMatrix<S>;
+    fn output(output: DMatrix<S>) -> Self;
 }
 
-impl NetworkIO for DMatrix<f32> {
-    fn input(input: Self) -> DMatrix<f32> {
+impl<S: Scalar> NetworkIO<S> for DMatrix<S> {
+    fn input(input: Self) -> DMatrix<S> {
         input
     }
-    fn output(output: DMatrix<f32>) -> Self {
+    fn output(output: DMatrix<S>) -> Self {
         output
     }
 }
 
-impl NetworkIO for DVector<f32> {
-    fn input(input: Self) -> DMatrix<f32> {
+impl<S: Scalar> NetworkIO<S> for DVector<S> {
+    fn input(input: Self) -> DMatrix<S> {
         DMatrix::from_iterator(1, input.len(), input.into_iter().cloned())
     }
-    fn output(output: DMatrix<f32>) -> Self {
-        DVector::from(output.into_iter().cloned().collect::<Vec<f32>>())
+    fn output(output: DMatrix<S>) -> Self {
+        DVector::from(output.into_iter().cloned().collect::<Vec<S>>())
     }
 }
 
-impl NetworkIO for Vec<f32> {
-    fn input(input: Self) -> DMatrix<f32> {
+impl<S: Scalar> NetworkIO<S> for Vec<S> {
+    fn input(input: Self) -> DMatrix<S> {
         DMatrix::from_iterator(1, input.len(), input.into_iter())
     }
-    fn output(output: DMatrix<f32>) -> Self {
-        output.into_iter().cloned().collect::<Vec<f32>>()
+    fn output(output: DMatrix<S>) -> Self {
+        output.into_iter().cloned().collect::<Vec<S>>()
     }
 }
 
 #[cfg(feature = "ndarray")]
-use ndarray::Array1;
+use ndarray::{Array1, Array2};
 
 #[cfg(feature = "ndarray")]
-impl NetworkIO for Array1<f32> {
-    fn input(input: Self) -> DMatrix<f32> {
-        DMatrix::from_iterator(1, input.len(), input.into_iter)
+impl<S: Scalar> NetworkIO<S> for Array1<S> {
+    fn input(input: Self) -> DMatrix<S> {
+        DMatrix::from_iterator(1, input.len(), input.into_iter())
     }
-    fn output(output: DMatrix<f32>) -> Self {
+    fn output(output: DMatrix<S>) -> Self {
         Array1::from_iter(output.into_iter().cloned())
     }
 }
+
+#[cfg(feature = "ndarray")]
+impl<S: Scalar> NetworkIO<S> for Array2<S> {
+    fn input(input: Self) -> DMatrix<S> {
+        // ndarray iterates in logical order, i.e. row by row, regardless of the memory layout
+        DMatrix::from_row_iterator(input.nrows(), input.ncols(), input.into_iter())
+    }
+    fn output(output: DMatrix<S>) -> Self {
+        Array2::from_shape_fn(output.shape(), |index| output[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{dmatrix, DMatrix, DVector};
+
+    use super::NetworkIO;
+
+    #[test]
+    fn matrix_rows_are_samples() {
+        let input = dmatrix![1.0, 2.0; 3.0, 4.0; 5.0, 6.0];
+        assert_eq!(NetworkIO::input(input.clone()), input);
+
+        let sample: DMatrix<f32> = NetworkIO::input(vec![1.0, 2.0]);
+        assert_eq!(sample, dmatrix![1.0, 2.0]);
+        assert_eq!(
+            <DVector<f32> as NetworkIO>::output(dmatrix![1.0, 2.0]),
+            DVector::from_vec(vec![1.0, 2.0])
+        );
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn ndarray_batches() {
+        use ndarray::{array, Array1, Array2};
+
+        let input: Array2<f32> = array![[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]];
+        assert_eq!(
+            NetworkIO::input(input.clone()),
+            dmatrix![1.0, 2.0; 3.0, 4.0; 5.0, 6.0]
+        );
+        // transposed views are copied in logical order as well
+        assert_eq!(
+            NetworkIO::input(input.t().to_owned()),
+            dmatrix![1.0, 3.0, 5.0; 2.0, 4.0, 6.0]
+        );
+        assert_eq!(
+            <Array2<f32> as NetworkIO>::output(NetworkIO::input(input.clone())),
+            input
+        );
+
+        let sample: DMatrix<f32> = NetworkIO::input(array![1.0, 2.0]);
+        assert_eq!(sample, dmatrix![1.0, 2.0]);
+        assert_eq!(
+            <Array1<f32> as NetworkIO>::output(dmatrix![1.0, 2.0]),
+            array![1.0, 2.0]
+        );
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn evaluate_ndarray_batches() {
+        use ndarray::array;
+
+        use crate::{
+            edges,
+            matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+            network::{net::Net, Evaluator, Fabricator},
+            nodes,
+        };
+
+        let some_net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--2.0->2));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert_eq!(
+            evaluator.evaluate(array![[1.0, 1.0], [2.0, -1.0]]),
+            array![[3.0], [0.0]]
+        );
+        assert_eq!(evaluator.evaluate(array![1.0, 1.0]), array![3.0]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Expands nodes with a time constant into leaky integrators, see [`NodeLike::time_constant`].
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use super::{
+    net::{activations, Edge, Net, Node},
+    prune::copy_node,
+    EdgeLike, NodeLike, Recurrent, Scalar,
+};
+
+/// Replaces every non-input node with a time constant `τ` other than one by a leaky integrator `y_t = (1 - α) y_{t-1} + α f(x)` with `α = 1 / τ`.
+///
+/// The node keeps its id but only sums the value `f(x)` of a new node with weight `α` and its own previous value by a recurrent edge with weight `1 - α`.
+/// The new node takes over the activation, bias, aggregation, response, kind and all incoming edges, hence edges into leaky nodes are not addressable after fabrication.
+/// Time constants of one keep the node unchanged, time constants that are not positive are rejected.
+pub fn expand_leaky<R, N, E, S>(recurrent: &R) -> Result<Net<S>, &'static str>
+where
+    R: Recurrent<N, E, S>,
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut next_id = recurrent
+        .nodes_iter()
+        .map(|node| node.id() + 1)
+        .max()
+        .unwrap_or(0);
+
+    let inputs: Vec<usize> = recurrent.inputs_iter().map(|node| node.id()).collect();
+    // maps every leaky node to the node computing its unsmoothed value
+    let mut integrated: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut inner_nodes = Vec::new();
+    let mut leak_edges = Vec::new();
+    let mut recurrent_edges = Vec::new();
+
+    let mut copy = |node: &N| -> Result<Node<S>, &'static str> {
+        let time_constant = node.time_constant();
+        if time_constant <= S::zero() {
+            return Err("time constants need to be positive");
+        }
+        if time_constant == S::one() || inputs.contains(&node.id()) {
+            return Ok(copy_node(node));
+        }
+
+        let alpha = S::one() / time_constant;
+        inner_nodes.push(
+            Node::new(next_id, node.activation())
+                .with_bias(node.bias())
+                .with_aggregation(node.aggregation())
+                .with_response(node.response())
+                .with_kind(node.kind())
+                .with_closure(node.custom_activation()),
+        );
+        leak_edges.push(Edge::new(next_id, node.id(), alpha));
+        recurrent_edges.push(Edge::new(node.id(), node.id(), S::one() - alpha));
+        integrated.insert(node.id(), next_id);
+        next_id += 1;
+
+        let integrator = Node::new(node.id(), activations::linear);
+        Ok(match node.label() {
+            Some(label) => integrator.with_label(String::from(label)),
+            None => integrator,
+        })
+    };
+
+    let copied_inputs = recurrent
+        .inputs_iter()
+        .map(&mut copy)
+        .collect::<Result<Vec<_>, _>>()?;
+    let copied_hidden = recurrent
+        .hidden_iter()
+        .map(&mut copy)
+        .collect::<Result<Vec<_>, _>>()?;
+    let copied_outputs = recurrent
+        .outputs_iter()
+        .map(&mut copy)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let redirect = |edge: &E| {
+        let end = integrated.get(&edge.end()).copied().unwrap_or(edge.end());
+        Edge::new(edge.start(), end, edge.weight())
+            .with_gate(edge.gate())
+            .with_delay(edge.delay())
+            .with_enabled(edge.enabled())
+    };
+
+    let mut net = Net::new(
+        copied_inputs.len(),
+        copied_outputs.len(),
+        copied_inputs
+            .into_iter()
+            .chain(copied_hidden)
+            .chain(inner_nodes)
+            .chain(copied_outputs)
+            .collect(),
+        recurrent
+            .edges_iter()
+            .map(redirect)
+            .chain(leak_edges)
+            .collect(),
+    );
+    net.set_recurrent_edges(
+        recurrent
+            .recurrent_edges_iter()
+            .map(redirect)
+            .chain(recurrent_edges)
+            .collect(),
+    );
+    Ok(net)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::expand_leaky;
+    use crate::{
+        matrix::recurrent::fabricator::MatrixRecurrentFabricator,
+        network::{
+            net::{activations, Edge, Net, Node},
+            StatefulEvaluator, StatefulFabricator,
+        },
+        sparse_matrix::recurrent::fabricator::SparseMatrixRecurrentFabricator,
+    };
+
+    fn smoother(time_constant: f32) -> Net {
+        Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_time_constant(time_constant),
+            ],
+            vec![Edge::new(0, 1, 1.0)],
+        )
+    }
+
+    #[test]
+    fn leaky_integration() {
+        let net = smoother(2.0);
+        let mut matrix_evaluator = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+        let mut sparse_evaluator = SparseMatrixRecurrentFabricator::fabricate(&net).unwrap();
+
+        // moves halfway towards the input every evaluation
+        for expected in [0.5, 0.75, 0.875] {
+            assert_eq!(matrix_evaluator.evaluate(dmatrix![1.0]), dmatrix![expected]);
+            assert_eq!(sparse_evaluator.evaluate(dmatrix![1.0]), dmatrix![expected]);
+        }
+
+        let mut plain = MatrixRecurrentFabricator::fabricate(&smoother(1.0)).unwrap();
+        assert_eq!(plain.evaluate(dmatrix![1.0]), dmatrix![1.0]);
+
+        assert!(expand_leaky(&smoother(0.0)).is_err());
+    }
+}
@@ -1,25 +1,209 @@
 //! Defines vocabulary and interfaces for this crate.
 
+use alloc::{boxed::Box, vec, vec::Vec};
+
+use nalgebra::DMatrix;
+
 pub use self::io::NetworkIO;
+#[cfg(feature = "derive")]
+pub use favannat_derive::{EdgeLike, NetworkLike, NodeLike};
 
+#[cfg(feature = "std")]
+pub mod adjacency;
+pub mod bias;
+pub mod builder;
+pub mod chain;
+pub mod delayed;
+pub mod gated;
 mod io;
+pub mod leaky;
+pub mod module;
+#[cfg(feature = "std")]
+pub mod named;
+mod parse;
+pub mod prune;
+pub mod remap;
+#[cfg(feature = "serde")]
+pub(crate) mod serialization;
+pub mod transform;
+
+/// Declares a type to be usable as the scalar networks are fabricated and evaluated with.
+///
+/// It is implemented for every [`nalgebra::RealField`] that is [`Copy`], most notably `f32` and `f64`.
+/// All traits in this crate default to `f32`.
+pub trait Scalar: nalgebra::RealField + Copy {}
+
+impl<S: nalgebra::RealField + Copy> Scalar for S {}
+
+/// Declares a type to be usable as node id, e.g. `u64` innovation numbers or `(layer, index)` tuples.
+///
+/// It is implemented for every type that is [`Copy`], [`Ord`], [`Hash`](core::hash::Hash), [`Debug`](core::fmt::Debug) and `'static`.
+/// All traits in this crate default to `usize`, which evaluators address nodes by.
+/// Fabricators keep `usize` ids and compact any other ids like [`remap::remap`] does, whose mapping then translates the ids of the evaluator.
+pub trait NodeId: Copy + Ord + core::hash::Hash + core::fmt::Debug + 'static {}
+
+impl<I: Copy + Ord + core::hash::Hash + core::fmt::Debug + 'static> NodeId for I {}
+
+/// Describes how the weighted values arriving over incoming edges are combined into a single value per node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Aggregation {
+    #[default]
+    Sum,
+    Product,
+    Max,
+    Min,
+    Mean,
+}
+
+impl Aggregation {
+    pub fn aggregate<S: Scalar>(&self, values: impl IntoIterator<Item = S>) -> S {
+        let values = values.into_iter();
+        match self {
+            Aggregation::Sum => values.fold(S::zero(), |acc, value| acc + value),
+            Aggregation::Product => values.fold(S::one(), |acc, value| acc * value),
+            Aggregation::Max => values
+                .reduce(|acc, value| acc.max(value))
+                .unwrap_or_else(S::zero),
+            Aggregation::Min => values
+                .reduce(|acc, value| acc.min(value))
+                .unwrap_or_else(S::zero),
+            Aggregation::Mean => {
+                let (sum, count) = values.fold((S::zero(), S::zero()), |(sum, count), value| {
+                    (sum + value, count + S::one())
+                });
+                if count > S::zero() {
+                    sum / count
+                } else {
+                    sum
+                }
+            }
+        }
+    }
+
+    /// Folds `response` into the weights of `weighted_inputs`, so aggregating them yields the original aggregate times `response`.
+    ///
+    /// Returns the aggregation to use instead, maximum and minimum swap for negative responses.
+    /// Products only scale their first input.
+    #[cfg(feature = "std")]
+    pub(crate) fn respond<S: Scalar>(
+        self,
+        weighted_inputs: &mut [(usize, S)],
+        response: S,
+    ) -> Self {
+        if response.is_one() {
+            return self;
+        }
+        let scaled = match self {
+            Aggregation::Product => weighted_inputs.len().min(1),
+            _ => weighted_inputs.len(),
+        };
+        for (_, weight) in weighted_inputs[..scaled].iter_mut() {
+            *weight *= response;
+        }
+        match self {
+            Aggregation::Max if response < S::zero() => Aggregation::Min,
+            Aggregation::Min if response < S::zero() => Aggregation::Max,
+            aggregation => aggregation,
+        }
+    }
+}
+
+/// Declares a node to be a plain neuron or a gated recurrent cell.
+///
+/// Gated cells are expanded into plain nodes by [`gated::expand_gated`], which the recurrent fabricators do before unrolling.
+/// Other fabricators treat every node as [`NodeKind::Plain`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NodeKind {
+    #[default]
+    Plain,
+    Lstm,
+    Gru,
+}
+
+/// Declares which part of a gated cell an edge feeds, see [`NodeKind`].
+///
+/// [`Gate::Input`], [`Gate::Forget`] and [`Gate::Output`] apply to [`NodeKind::Lstm`], [`Gate::Update`] and [`Gate::Reset`] to [`NodeKind::Gru`].
+/// Edges into plain nodes ignore their gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Gate {
+    #[default]
+    Candidate,
+    Input,
+    Forget,
+    Output,
+    Update,
+    Reset,
+}
 
 /// Declares a structure to have [`NodeLike`] properties.
 ///
 /// [`NodeLike`] provides the plumbing to accept user-defined structures and use them as nodes in this crates context.
-/// The implemntation of [`NodeLike::id`] needs to provide a unique identifier per node.
-pub trait NodeLike: Ord {
-    fn id(&self) -> usize;
-    fn activation(&self) -> fn(f32) -> f32;
+/// The implemntation of [`NodeLike::id`] needs to provide a unique identifier per node, which can be any [`NodeId`].
+/// [`NodeLike::bias`] is added to the aggregated incoming edges before the activation is applied and defaults to zero.
+/// [`NodeLike::aggregation`] selects how incoming edges are aggregated and defaults to [`Aggregation::Sum`].
+/// [`NodeLike::response`] multiplies the aggregated incoming edges before the bias is added, like the response of NEAT-Python, and defaults to one.
+/// [`NodeLike::time_constant`] is used by continuous-time backends like [`crate::ctrnn`], the recurrent evaluators smooth node outputs by it, see [`leaky::expand_leaky`], and it defaults to one.
+/// [`NodeLike::kind`] declares gated recurrent cells and defaults to [`NodeKind::Plain`].
+/// [`NodeLike::label`] names a node, e.g. to address inputs and outputs by [`named::NamedIO`], and defaults to none.
+/// [`NodeLike::activation_kind`] identifies the activation as [`net::activations::ActivationKind`].
+/// [`NodeLike::custom_activation`] provides a closure, e.g. capturing parameters of the node, which the matrix based fabricators use instead of [`NodeLike::activation`].
+/// It defaults to none, backends not supporting closures fall back to [`NodeLike::activation`].
+pub trait NodeLike<S: Scalar = f32, I: NodeId = usize>: Ord {
+    fn id(&self) -> I;
+    fn activation(&self) -> fn(S) -> S;
+    fn activation_kind(&self) -> net::activations::ActivationKind<S> {
+        self.activation().into()
+    }
+    fn custom_activation(&self) -> Option<net::activations::Closure<S>> {
+        None
+    }
+    fn bias(&self) -> S {
+        S::zero()
+    }
+    fn aggregation(&self) -> Aggregation {
+        Aggregation::Sum
+    }
+    fn time_constant(&self) -> S {
+        S::one()
+    }
+    fn response(&self) -> S {
+        S::one()
+    }
+    fn kind(&self) -> NodeKind {
+        NodeKind::Plain
+    }
+    fn label(&self) -> Option<&str> {
+        None
+    }
 }
 
 /// Declares a structure to have [`EdgeLike`] properties.
 ///
 /// [`EdgeLike`] provides the plumbing to accept user-defined structures and use them as edges in this crates context.
-pub trait EdgeLike {
-    fn start(&self) -> usize;
-    fn end(&self) -> usize;
-    fn weight(&self) -> f32;
+/// [`EdgeLike::gate`] selects the part of a gated cell the edge feeds and defaults to [`Gate::Candidate`].
+/// [`EdgeLike::delay`] is the number of evaluations a recurrent edge holds back its value and defaults to one, it is ignored for regular edges.
+/// [`EdgeLike::enabled`] defaults to true, fabricators skip disabled edges as well as zero-weight edges that do not change the result, see [`prune::prune_disabled`].
+/// Several edges between the same nodes act like a single edge with the sum of their weights, see [`prune::dedup_edges`].
+/// [`EdgeLike::id`] defaults to none, e.g. an innovation number tells such parallel edges apart after fabrication.
+pub trait EdgeLike<S: Scalar = f32, I: NodeId = usize> {
+    fn start(&self) -> I;
+    fn end(&self) -> I;
+    fn weight(&self) -> S;
+    fn gate(&self) -> Gate {
+        Gate::Candidate
+    }
+    fn delay(&self) -> usize {
+        1
+    }
+    fn enabled(&self) -> bool {
+        true
+    }
+    fn id(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Declares a structure to have network-like properties.
@@ -28,18 +212,50 @@ pub trait EdgeLike {
 /// Together with [`NodeLike`] and [`EdgeLike`] it provides the interface to start using this crate.
 /// Structures that are `NetworkLike` can be fabricated and evaluated by the different implementations of the
 /// [`Fabricator`], [`Evaluator`], [`StatefulFabricator`] and [`StatefulEvaluator`] traits.
-pub trait NetworkLike<N: NodeLike, E: EdgeLike> {
+///
+/// The `_iter` methods are used by fabricators where possible, they default to iterating the collected vectors.
+/// Structures storing their nodes and edges should override them to avoid allocating on every call.
+pub trait NetworkLike<N: NodeLike<S, I>, E: EdgeLike<S, I>, S: Scalar = f32, I: NodeId = usize> {
     fn edges(&self) -> Vec<&E>;
     fn inputs(&self) -> Vec<&N>;
     fn hidden(&self) -> Vec<&N>;
     fn outputs(&self) -> Vec<&N>;
 
     fn nodes(&self) -> Vec<&N> {
-        self.inputs()
-            .into_iter()
-            .chain(self.hidden().into_iter())
-            .chain(self.outputs().into_iter())
-            .collect()
+        self.nodes_iter().collect()
+    }
+
+    fn edges_iter<'a>(&'a self) -> impl Iterator<Item = &'a E>
+    where
+        E: 'a,
+    {
+        self.edges().into_iter()
+    }
+    fn inputs_iter<'a>(&'a self) -> impl Iterator<Item = &'a N>
+    where
+        N: 'a,
+    {
+        self.inputs().into_iter()
+    }
+    fn hidden_iter<'a>(&'a self) -> impl Iterator<Item = &'a N>
+    where
+        N: 'a,
+    {
+        self.hidden().into_iter()
+    }
+    fn outputs_iter<'a>(&'a self) -> impl Iterator<Item = &'a N>
+    where
+        N: 'a,
+    {
+        self.outputs().into_iter()
+    }
+    fn nodes_iter<'a>(&'a self) -> impl Iterator<Item = &'a N>
+    where
+        N: 'a,
+    {
+        self.inputs_iter()
+            .chain(self.hidden_iter())
+            .chain(self.outputs_iter())
     }
 }
 
@@ -47,182 +263,1042 @@ pub trait NetworkLike<N: NodeLike, E: EdgeLike> {
 ///
 /// Recurrent edges act like memory cells in a network.
 /// They imply that internal state has to be preserved.
-pub trait Recurrent<N: NodeLike, E: EdgeLike>: NetworkLike<N, E> {
+pub trait Recurrent<N: NodeLike<S, I>, E: EdgeLike<S, I>, S: Scalar = f32, I: NodeId = usize>:
+    NetworkLike<N, E, S, I>
+{
     fn recurrent_edges(&self) -> Vec<&E>;
+
+    fn recurrent_edges_iter<'a>(&'a self) -> impl Iterator<Item = &'a E>
+    where
+        E: 'a,
+    {
+        self.recurrent_edges().into_iter()
+    }
+}
+
+/// Declares a [`NetworkLike`] structure to have modulatory edges.
+///
+/// Modulatory edges do not contribute to the value of their end node.
+/// Instead their weighted sum scales the plasticity of the edges entering the end node,
+/// see [`crate::matrix::recurrent::fabricator::MatrixRecurrentFabricator::fabricate_modulated`].
+pub trait Modulated<N: NodeLike<S, I>, E: EdgeLike<S, I>, S: Scalar = f32, I: NodeId = usize>:
+    NetworkLike<N, E, S, I>
+{
+    fn modulatory_edges(&self) -> Vec<&E>;
+}
+
+/// Describes a subnetwork reused inside a [`Modular`] structure.
+///
+/// [`ModuleLike::sources`] lists the outer node ids whose values replace the module inputs, in the order of [`NetworkLike::inputs`] of the module.
+/// [`ModuleLike::targets`] lists the outer node ids every module output is connected to with weight one, in the order of [`NetworkLike::outputs`] of the module.
+pub trait ModuleLike<N: NodeLike<S, I>, E: EdgeLike<S, I>, S: Scalar = f32, I: NodeId = usize> {
+    type Network: NetworkLike<N, E, S, I>;
+
+    fn network(&self) -> &Self::Network;
+    fn sources(&self) -> Vec<I>;
+    fn targets(&self) -> Vec<I>;
+}
+
+/// Declares a [`NetworkLike`] structure to contain modules, which are spliced in by [`module::flatten`].
+///
+/// Fabricators only see the outer structure, see [`crate::matrix::feedforward::fabricator::MatrixFeedforwardFabricator::fabricate_modular`].
+pub trait Modular<N: NodeLike<S, I>, E: EdgeLike<S, I>, S: Scalar = f32, I: NodeId = usize>:
+    NetworkLike<N, E, S, I>
+{
+    type Module: ModuleLike<N, E, S, I>;
+
+    fn modules(&self) -> Vec<&Self::Module>;
+}
+
+/// Error returned by [`Evaluator::try_evaluate`] and [`StatefulEvaluator::try_evaluate`] instead of panicking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvaluationError {
+    /// Samples provide `got` values while the evaluator expects `expected` inputs.
+    DimensionMismatch { expected: usize, got: usize },
+}
+
+impl core::fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::DimensionMismatch { expected, got } => {
+                write!(f, "expected {} input values, got {}", expected, got)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EvaluationError {}
+
+/// Error returned by [`Fabricator::fabricate`] and [`StatefulFabricator::fabricate`].
+///
+/// Converts into the plain message of the `&'static str` errors used elsewhere, so `?` keeps working in functions returning those.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FabricationError {
+    /// Evaluated edges form loops, each listed by the node ids along it starting at the smallest, see [`crate::validation::cycles`].
+    Cycles(Vec<Vec<usize>>),
+    /// The net can not be fabricated for the given reason.
+    Invalid(&'static str),
+}
+
+impl core::fmt::Display for FabricationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Cycles(cycles) => write!(
+                f,
+                "can't resolve dependencies, net contains cycles {:?}",
+                cycles
+            ),
+            Self::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FabricationError {}
+
+impl From<&'static str> for FabricationError {
+    fn from(message: &'static str) -> Self {
+        Self::Invalid(message)
+    }
+}
+
+impl From<FabricationError> for &'static str {
+    fn from(error: FabricationError) -> Self {
+        match error {
+            FabricationError::Cycles(_) => "can't resolve dependencies, net contains a cycle",
+            FabricationError::Invalid(message) => message,
+        }
+    }
 }
 
 /// A facade behind which evaluation of a fabricated [`NetworkLike`] structure is implemented.
-pub trait Evaluator {
-    fn evaluate<T: NetworkIO>(&self, input: T) -> T;
+///
+/// [`Evaluator::evaluate`] panics on inputs not matching the fabricated net, [`Evaluator::try_evaluate`] checks them first.
+/// [`Evaluator::input_dimension`] and [`Evaluator::output_dimension`] default to none, in which case every input is passed on unchecked.
+pub trait Evaluator<S: Scalar = f32> {
+    fn evaluate<T: NetworkIO<S>>(&self, input: T) -> T;
+
+    /// Number of values every sample needs to provide, i.e. the number of columns of the input.
+    fn input_dimension(&self) -> Option<usize> {
+        None
+    }
+
+    /// Number of values evaluated for every sample, i.e. the number of columns of the output.
+    fn output_dimension(&self) -> Option<usize> {
+        None
+    }
+
+    fn try_evaluate<T: NetworkIO<S>>(&self, input: T) -> Result<T, EvaluationError> {
+        let input = NetworkIO::input(input);
+        match self.input_dimension() {
+            Some(expected) if input.ncols() != expected => {
+                Err(EvaluationError::DimensionMismatch {
+                    expected,
+                    got: input.ncols(),
+                })
+            }
+            _ => Ok(NetworkIO::output(self.evaluate(input))),
+        }
+    }
+
+    /// Wraps the evaluator into [`Stateless`], so it can be used where a [`StatefulEvaluator`] is expected.
+    fn into_stateful(self) -> Stateless<Self>
+    where
+        Self: Sized,
+    {
+        Stateless(self)
+    }
+}
+
+/// Estimated cost of evaluating a single sample, e.g. to penalize expensive topologies in a fitness function.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cost {
+    /// Multiplications of a weight with a value, including those with zero weights in dense stages.
+    pub multiply_adds: usize,
+    /// Number of stages evaluated one after another.
+    pub stages: usize,
+    /// Bytes taken by all stage and bias matrices.
+    pub matrix_bytes: usize,
+}
+
+impl Cost {
+    /// Counts the weighted inputs of nodes not summing their inputs, they are computed apart from the stage matrices.
+    pub(crate) fn aggregated_inputs<S: Scalar>(aggregations: &[crate::Aggregations<S>]) -> usize {
+        aggregations
+            .iter()
+            .flatten()
+            .map(|(_, _, weighted_inputs)| weighted_inputs.len())
+            .sum()
+    }
 }
 
 /// A facade behind which evaluation of a fabricated [`Recurrent`] [`NetworkLike`] structure is implemented.
 ///
 /// Due to its statefulness it needs mutable access and provides a way to reset the internal state.
-pub trait StatefulEvaluator {
-    fn evaluate<T: NetworkIO>(&mut self, input: T) -> T;
+/// [`StatefulEvaluator::reset_state_for`] resets only the state of the given nodes, ids without state are ignored.
+/// [`StatefulEvaluator::evaluate_sequence`] steps through a whole time series, continuing from the current state,
+/// while [`StatefulEvaluator::reset_and_evaluate_sequence`] starts from a reset state.
+/// [`StatefulEvaluator::try_evaluate`] checks the input like [`Evaluator::try_evaluate`] does, the internal state is left untouched on errors.
+pub trait StatefulEvaluator<S: Scalar = f32> {
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T;
     fn reset_internal_state(&mut self);
+    fn reset_state_for(&mut self, node_ids: &[usize]);
+
+    /// Number of values every evaluation needs to provide, the internal state is not counted.
+    fn input_dimension(&self) -> Option<usize> {
+        None
+    }
+
+    /// Number of values every evaluation returns.
+    fn output_dimension(&self) -> Option<usize> {
+        None
+    }
+
+    /// Only a single sample is evaluated at a time, so all values of the input count.
+    fn try_evaluate<T: NetworkIO<S>>(&mut self, input: T) -> Result<T, EvaluationError> {
+        let input = NetworkIO::input(input);
+        match self.input_dimension() {
+            Some(expected) if input.len() != expected => Err(EvaluationError::DimensionMismatch {
+                expected,
+                got: input.len(),
+            }),
+            _ => Ok(NetworkIO::output(self.evaluate(input))),
+        }
+    }
+
+    fn evaluate_sequence<T: NetworkIO<S>>(
+        &mut self,
+        inputs: impl IntoIterator<Item = T>,
+    ) -> Vec<T> {
+        inputs
+            .into_iter()
+            .map(|input| self.evaluate(input))
+            .collect()
+    }
+    fn reset_and_evaluate_sequence<T: NetworkIO<S>>(
+        &mut self,
+        inputs: impl IntoIterator<Item = T>,
+    ) -> Vec<T> {
+        self.reset_internal_state();
+        self.evaluate_sequence(inputs)
+    }
+}
+
+/// Object-safe core of [`Evaluator`], e.g. to keep different backends in a `Vec<Box<dyn DynEvaluator>>`.
+///
+/// Every [`Evaluator`] implements it, boxed trait objects implement [`Evaluator`] again, so the generic methods remain available on them.
+pub trait DynEvaluator<S: Scalar = f32> {
+    /// Evaluates a single sample.
+    fn evaluate_vec(&self, input: Vec<S>) -> Vec<S>;
+    /// Evaluates a batch with one sample per row.
+    fn evaluate_matrix(&self, input: DMatrix<S>) -> DMatrix<S>;
+    /// Same as [`Evaluator::input_dimension`].
+    fn expected_inputs(&self) -> Option<usize>;
+    /// Same as [`Evaluator::output_dimension`].
+    fn expected_outputs(&self) -> Option<usize>;
+}
+
+impl<S: Scalar, V: Evaluator<S>> DynEvaluator<S> for V {
+    fn evaluate_vec(&self, input: Vec<S>) -> Vec<S> {
+        self.evaluate(input)
+    }
+    fn evaluate_matrix(&self, input: DMatrix<S>) -> DMatrix<S> {
+        self.evaluate(input)
+    }
+    fn expected_inputs(&self) -> Option<usize> {
+        self.input_dimension()
+    }
+    fn expected_outputs(&self) -> Option<usize> {
+        self.output_dimension()
+    }
+}
+
+// calls go through the trait object, the box itself implements the dynamic traits as well
+impl<S: Scalar> Evaluator<S> for Box<dyn DynEvaluator<S> + '_> {
+    fn evaluate<T: NetworkIO<S>>(&self, input: T) -> T {
+        NetworkIO::output((**self).evaluate_matrix(NetworkIO::input(input)))
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        (**self).expected_inputs()
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        (**self).expected_outputs()
+    }
+}
+
+/// Object-safe core of [`StatefulEvaluator`], e.g. to keep different backends in a `Vec<Box<dyn DynStatefulEvaluator>>`.
+///
+/// Methods are named apart from those of [`StatefulEvaluator`], so both traits can be in scope at once.
+pub trait DynStatefulEvaluator<S: Scalar = f32> {
+    /// Evaluates a single sample and advances the internal state.
+    fn evaluate_vec(&mut self, input: Vec<S>) -> Vec<S>;
+    fn evaluate_matrix(&mut self, input: DMatrix<S>) -> DMatrix<S>;
+    /// Same as [`StatefulEvaluator::reset_internal_state`].
+    fn reset(&mut self);
+    /// Same as [`StatefulEvaluator::reset_state_for`].
+    fn reset_nodes(&mut self, node_ids: &[usize]);
+    /// Same as [`StatefulEvaluator::input_dimension`].
+    fn expected_inputs(&self) -> Option<usize>;
+    /// Same as [`StatefulEvaluator::output_dimension`].
+    fn expected_outputs(&self) -> Option<usize>;
+}
+
+impl<S: Scalar, V: StatefulEvaluator<S>> DynStatefulEvaluator<S> for V {
+    fn evaluate_vec(&mut self, input: Vec<S>) -> Vec<S> {
+        self.evaluate(input)
+    }
+    fn evaluate_matrix(&mut self, input: DMatrix<S>) -> DMatrix<S> {
+        self.evaluate(input)
+    }
+    fn reset(&mut self) {
+        self.reset_internal_state();
+    }
+    fn reset_nodes(&mut self, node_ids: &[usize]) {
+        self.reset_state_for(node_ids);
+    }
+    fn expected_inputs(&self) -> Option<usize> {
+        self.input_dimension()
+    }
+    fn expected_outputs(&self) -> Option<usize> {
+        self.output_dimension()
+    }
+}
+
+impl<S: Scalar> StatefulEvaluator<S> for Box<dyn DynStatefulEvaluator<S> + '_> {
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T {
+        NetworkIO::output((**self).evaluate_matrix(NetworkIO::input(input)))
+    }
+
+    fn reset_internal_state(&mut self) {
+        (**self).reset();
+    }
+
+    fn reset_state_for(&mut self, node_ids: &[usize]) {
+        (**self).reset_nodes(node_ids);
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        (**self).expected_inputs()
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        (**self).expected_outputs()
+    }
+}
+
+/// Buffers reused by [`EvaluateInto`] and [`StatefulEvaluateInto`], so evaluating a single sample does not allocate.
+///
+/// Get one sized for an evaluator from [`EvaluateInto::scratch`], scratches of other evaluators may be too small.
+#[derive(Debug, Clone, Default)]
+pub struct Scratch<S: Scalar = f32> {
+    pub(crate) current: Vec<S>,
+    pub(crate) next: Vec<S>,
+}
+
+impl<S: Scalar> Scratch<S> {
+    /// Allocates two buffers of `size` values each.
+    pub fn new(size: usize) -> Self {
+        Self {
+            current: vec![S::zero(); size],
+            next: vec![S::zero(); size],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.current.len()
+    }
+}
+
+/// Evaluates a single sample from slice to slice, reusing a [`Scratch`] instead of allocating matrices, e.g. every frame of a game loop.
+///
+/// `input` and `output` need exactly as many values as the evaluator has inputs and outputs, otherwise evaluation panics.
+pub trait EvaluateInto<S: Scalar = f32> {
+    /// Returns a [`Scratch`] large enough for the fabricated stages.
+    fn scratch(&self) -> Scratch<S>;
+    fn evaluate_into(&self, input: &[S], output: &mut [S], scratch: &mut Scratch<S>);
+}
+
+/// Like [`EvaluateInto`] for [`StatefulEvaluator`]s, every call advances the internal state like [`StatefulEvaluator::evaluate`] does.
+pub trait StatefulEvaluateInto<S: Scalar = f32> {
+    /// Returns a [`Scratch`] large enough for the fabricated stages.
+    fn scratch(&self) -> Scratch<S>;
+    fn evaluate_into(&mut self, input: &[S], output: &mut [S], scratch: &mut Scratch<S>);
+}
+
+/// Adapts an [`Evaluator`] of a feedforward net to [`StatefulEvaluator`], e.g. for agents generic over whether their genome has recurrent edges.
+///
+/// There is no internal state, so resetting does nothing and every evaluation only depends on its input.
+#[derive(Debug, Clone)]
+pub struct Stateless<V>(pub V);
+
+impl<S: Scalar, V: Evaluator<S>> StatefulEvaluator<S> for Stateless<V> {
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T {
+        self.0.evaluate(input)
+    }
+
+    fn reset_internal_state(&mut self) {}
+
+    fn reset_state_for(&mut self, _node_ids: &[usize]) {}
+
+    fn input_dimension(&self) -> Option<usize> {
+        self.0.input_dimension()
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        self.0.output_dimension()
+    }
+}
+
+impl<S: Scalar, V: EvaluateInto<S>> StatefulEvaluateInto<S> for Stateless<V> {
+    fn scratch(&self) -> Scratch<S> {
+        self.0.scratch()
+    }
+
+    fn evaluate_into(&mut self, input: &[S], output: &mut [S], scratch: &mut Scratch<S>) {
+        self.0.evaluate_into(input, output, scratch);
+    }
 }
 
 /// A facade behind which the fabrication of a [`NetworkLike`] structure is implemented.
 ///
 /// Fabrication means transforming a description of a network, the [`NetworkLike`] structure, into an executable form of its encoded function, an [`Evaluator`].
-pub trait Fabricator<N: NodeLike, E: EdgeLike> {
-    type Output: Evaluator;
+/// Structures with ids other than `usize` are fabricated from a copy with compacted ids, see [`NodeId`].
+pub trait Fabricator<N: NodeLike<S, I>, E: EdgeLike<S, I>, S: Scalar = f32, I: NodeId = usize> {
+    type Output: Evaluator<S>;
 
-    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str>;
+    fn fabricate(net: &impl NetworkLike<N, E, S, I>) -> Result<Self::Output, FabricationError>;
 }
 
 /// A facade behind which the fabrication of a [`Recurrent`] [`NetworkLike`] structure is implemented.
 ///
 /// Fabrication means transforming a description of a network, the [`Recurrent`] [`NetworkLike`] structure, into an executable form of its encoded function, a [`StatefulEvaluator`].
-pub trait StatefulFabricator<N: NodeLike, E: EdgeLike> {
-    type Output: StatefulEvaluator;
+/// Structures with ids other than `usize` are fabricated from a copy with compacted ids, see [`NodeId`].
+pub trait StatefulFabricator<
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar = f32,
+    I: NodeId = usize,
+>
+{
+    type Output: StatefulEvaluator<S>;
 
-    fn fabricate(net: &impl Recurrent<N, E>) -> Result<Self::Output, &'static str>;
+    fn fabricate(net: &impl Recurrent<N, E, S, I>) -> Result<Self::Output, FabricationError>;
 }
 
 /// Contains an example of a [`Recurrent`] [`NetworkLike`] structure.
 pub mod net {
-    use std::{collections::HashMap, ops::Shr};
+    use alloc::{collections::BTreeMap, string::String, vec::Vec};
 
-    use super::{EdgeLike, NetworkLike, NodeLike, Recurrent};
+    use nalgebra::DMatrix;
+
+    use super::{
+        Aggregation, EdgeLike, Gate, Modulated, NetworkLike, NodeKind, NodeLike, Recurrent, Scalar,
+    };
 
     #[derive(Debug)]
-    pub struct Node {
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Node<S: Scalar = f32> {
         id: usize,
-        activation: fn(f32) -> f32,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::network::serialization::activation")
+        )]
+        activation: fn(S) -> S,
+        #[cfg_attr(feature = "serde", serde(default = "S::zero"))]
+        bias: S,
+        #[cfg_attr(feature = "serde", serde(default))]
+        aggregation: Aggregation,
+        #[cfg_attr(feature = "serde", serde(default = "S::one"))]
+        time_constant: S,
+        #[cfg_attr(feature = "serde", serde(default = "S::one"))]
+        response: S,
+        #[cfg_attr(feature = "serde", serde(default))]
+        kind: NodeKind,
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        label: Option<String>,
+        // closures can not be serialized, the activation above is stored instead
+        #[cfg_attr(feature = "serde", serde(skip, default = "Option::default"))]
+        custom_activation: Option<activations::Closure<S>>,
     }
 
-    impl Node {
-        pub fn new(id: usize, activation: fn(f32) -> f32) -> Self {
-            Self { id, activation }
+    impl<S: Scalar> Node<S> {
+        pub fn new(id: usize, activation: fn(S) -> S) -> Self {
+            Self {
+                id,
+                activation,
+                bias: S::zero(),
+                aggregation: Aggregation::Sum,
+                time_constant: S::one(),
+                response: S::one(),
+                kind: NodeKind::Plain,
+                label: None,
+                custom_activation: None,
+            }
+        }
+        pub fn with_bias(mut self, bias: S) -> Self {
+            self.bias = bias;
+            self
+        }
+        pub fn with_aggregation(mut self, aggregation: Aggregation) -> Self {
+            self.aggregation = aggregation;
+            self
+        }
+        pub fn with_time_constant(mut self, time_constant: S) -> Self {
+            self.time_constant = time_constant;
+            self
+        }
+        pub fn with_response(mut self, response: S) -> Self {
+            self.response = response;
+            self
+        }
+        pub fn with_kind(mut self, kind: NodeKind) -> Self {
+            self.kind = kind;
+            self
+        }
+        pub fn with_label(mut self, label: impl Into<String>) -> Self {
+            self.label = Some(label.into());
+            self
+        }
+        /// Uses `activation` in the matrix based backends, others keep using the activation given to [`Node::new`].
+        pub fn with_custom_activation(
+            mut self,
+            activation: impl Fn(S) -> S + Send + Sync + 'static,
+        ) -> Self {
+            self.custom_activation = Some(activations::Closure::new(activation));
+            self
+        }
+        pub(crate) fn with_closure(mut self, activation: Option<activations::Closure<S>>) -> Self {
+            self.custom_activation = activation;
+            self
         }
     }
 
-    impl NodeLike for Node {
+    impl<S: Scalar> NodeLike<S> for Node<S> {
         fn id(&self) -> usize {
             self.id
         }
-        fn activation(&self) -> fn(f32) -> f32 {
+        fn activation(&self) -> fn(S) -> S {
             self.activation
         }
+        fn bias(&self) -> S {
+            self.bias
+        }
+        fn aggregation(&self) -> Aggregation {
+            self.aggregation
+        }
+        fn time_constant(&self) -> S {
+            self.time_constant
+        }
+        fn response(&self) -> S {
+            self.response
+        }
+        fn kind(&self) -> NodeKind {
+            self.kind
+        }
+        fn label(&self) -> Option<&str> {
+            self.label.as_deref()
+        }
+        fn custom_activation(&self) -> Option<activations::Closure<S>> {
+            self.custom_activation.clone()
+        }
     }
 
-    impl PartialEq for Node {
+    impl<S: Scalar> PartialEq for Node<S> {
         fn eq(&self, other: &Self) -> bool {
             self.id() == other.id()
         }
     }
 
-    impl Eq for Node {}
+    impl<S: Scalar> Eq for Node<S> {}
 
-    impl PartialOrd for Node {
-        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    impl<S: Scalar> PartialOrd for Node<S> {
+        fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
             Some(self.cmp(other))
         }
     }
 
-    impl Ord for Node {
-        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    impl<S: Scalar> Ord for Node<S> {
+        fn cmp(&self, other: &Self) -> core::cmp::Ordering {
             self.id().cmp(&other.id())
         }
     }
 
     #[derive(Debug)]
-    pub struct Edge {
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Edge<S: Scalar = f32> {
         start: usize,
         end: usize,
-        weight: f32,
+        weight: S,
+        #[cfg_attr(feature = "serde", serde(default))]
+        gate: Gate,
+        #[cfg_attr(feature = "serde", serde(default = "one"))]
+        delay: usize,
+        #[cfg_attr(feature = "serde", serde(default = "enabled"))]
+        enabled: bool,
+        #[cfg_attr(feature = "serde", serde(default))]
+        id: Option<usize>,
+    }
+
+    #[cfg(feature = "serde")]
+    fn one() -> usize {
+        1
     }
 
-    impl Edge {
-        pub fn new(start: usize, end: usize, weight: f32) -> Self {
-            Self { start, end, weight }
+    #[cfg(feature = "serde")]
+    fn enabled() -> bool {
+        true
+    }
+
+    impl<S: Scalar> Edge<S> {
+        pub fn new(start: usize, end: usize, weight: S) -> Self {
+            Self {
+                start,
+                end,
+                weight,
+                gate: Gate::Candidate,
+                delay: 1,
+                enabled: true,
+                id: None,
+            }
+        }
+        pub fn with_gate(mut self, gate: Gate) -> Self {
+            self.gate = gate;
+            self
+        }
+        pub fn with_delay(mut self, delay: usize) -> Self {
+            self.delay = delay;
+            self
+        }
+        pub fn with_enabled(mut self, enabled: bool) -> Self {
+            self.enabled = enabled;
+            self
+        }
+        pub fn with_id(mut self, id: Option<usize>) -> Self {
+            self.id = id;
+            self
         }
     }
 
-    impl EdgeLike for Edge {
+    impl<S: Scalar> EdgeLike<S> for Edge<S> {
         fn start(&self) -> usize {
             self.start
         }
         fn end(&self) -> usize {
             self.end
         }
-        fn weight(&self) -> f32 {
+        fn weight(&self) -> S {
             self.weight
         }
+        fn gate(&self) -> Gate {
+            self.gate
+        }
+        fn delay(&self) -> usize {
+            self.delay
+        }
+        fn enabled(&self) -> bool {
+            self.enabled
+        }
+        fn id(&self) -> Option<usize> {
+            self.id
+        }
     }
 
     /// [`Net`] is an example of a [`Recurrent`] [`NetworkLike`] structure and also used as an intermediate representation to perform the [`unroll`] operation on [`Recurrent`] [`NetworkLike`] structures.
+    ///
+    /// With the feature `serde` it (de)serializes to a format tagged with [`NET_FORMAT_VERSION`], activations are stored by name.
     #[derive(Debug)]
-    pub struct Net {
+    pub struct Net<S: Scalar = f32> {
         inputs: usize,
         outputs: usize,
-        nodes: Vec<Node>,
-        edges: Vec<Edge>,
-        recurrent_edges: Vec<Edge>,
+        nodes: Vec<Node<S>>,
+        edges: Vec<Edge<S>>,
+        recurrent_edges: Vec<Edge<S>>,
+        modulatory_edges: Vec<Edge<S>>,
     }
 
-    impl NetworkLike<Node, Edge> for Net {
-        fn edges(&self) -> Vec<&Edge> {
-            self.edges.iter().collect()
+    impl<S: Scalar> NetworkLike<Node<S>, Edge<S>, S> for Net<S> {
+        fn edges(&self) -> Vec<&Edge<S>> {
+            self.edges_iter().collect()
         }
-        fn inputs(&self) -> Vec<&Node> {
-            self.nodes.iter().take(self.inputs).collect()
+        fn inputs(&self) -> Vec<&Node<S>> {
+            self.inputs_iter().collect()
         }
-        fn hidden(&self) -> Vec<&Node> {
+        fn hidden(&self) -> Vec<&Node<S>> {
+            self.hidden_iter().collect()
+        }
+
+        fn outputs(&self) -> Vec<&Node<S>> {
+            self.outputs_iter().collect()
+        }
+
+        fn nodes(&self) -> Vec<&Node<S>> {
+            self.nodes_iter().collect()
+        }
+
+        fn edges_iter<'a>(&'a self) -> impl Iterator<Item = &'a Edge<S>>
+        where
+            Edge<S>: 'a,
+        {
+            self.edges.iter()
+        }
+        fn inputs_iter<'a>(&'a self) -> impl Iterator<Item = &'a Node<S>>
+        where
+            Node<S>: 'a,
+        {
+            self.nodes.iter().take(self.inputs)
+        }
+        fn hidden_iter<'a>(&'a self) -> impl Iterator<Item = &'a Node<S>>
+        where
+            Node<S>: 'a,
+        {
             self.nodes
                 .iter()
                 .skip(self.inputs)
                 .take(self.nodes.len() - self.inputs - self.outputs)
-                .collect()
         }
+        fn outputs_iter<'a>(&'a self) -> impl Iterator<Item = &'a Node<S>>
+        where
+            Node<S>: 'a,
+        {
+            self.nodes.iter().skip(self.nodes.len() - self.outputs)
+        }
+        fn nodes_iter<'a>(&'a self) -> impl Iterator<Item = &'a Node<S>>
+        where
+            Node<S>: 'a,
+        {
+            self.nodes.iter()
+        }
+    }
 
-        fn outputs(&self) -> Vec<&Node> {
-            self.nodes
-                .iter()
-                .skip(self.nodes().len() - self.outputs)
-                .collect()
+    impl<S: Scalar> Recurrent<Node<S>, Edge<S>, S> for Net<S> {
+        fn recurrent_edges(&self) -> Vec<&Edge<S>> {
+            self.recurrent_edges_iter().collect()
         }
 
-        fn nodes(&self) -> Vec<&Node> {
-            self.nodes.iter().collect()
+        fn recurrent_edges_iter<'a>(&'a self) -> impl Iterator<Item = &'a Edge<S>>
+        where
+            Edge<S>: 'a,
+        {
+            self.recurrent_edges.iter()
         }
     }
 
-    impl Recurrent<Node, Edge> for Net {
-        fn recurrent_edges(&self) -> Vec<&Edge> {
-            self.recurrent_edges.iter().collect()
+    impl<S: Scalar> Modulated<Node<S>, Edge<S>, S> for Net<S> {
+        fn modulatory_edges(&self) -> Vec<&Edge<S>> {
+            self.modulatory_edges.iter().collect()
         }
     }
 
-    impl Net {
-        pub fn new(inputs: usize, outputs: usize, nodes: Vec<Node>, edges: Vec<Edge>) -> Self {
+    impl<S: Scalar> Net<S> {
+        pub fn new(
+            inputs: usize,
+            outputs: usize,
+            nodes: Vec<Node<S>>,
+            edges: Vec<Edge<S>>,
+        ) -> Self {
             Net {
                 inputs,
                 outputs,
                 nodes,
                 edges,
                 recurrent_edges: Vec::new(),
+                modulatory_edges: Vec::new(),
             }
         }
-        pub fn set_recurrent_edges(&mut self, edges: Vec<Edge>) {
+        pub fn set_recurrent_edges(&mut self, edges: Vec<Edge<S>>) {
             self.recurrent_edges = edges
         }
+        pub fn set_modulatory_edges(&mut self, edges: Vec<Edge<S>>) {
+            self.modulatory_edges = edges
+        }
+
+        /// Returns all weights and biases as one vector, e.g. as the search space of CMA-ES.
+        ///
+        /// The weights of the edges come first, then those of the recurrent edges, each in the order of their list, followed by the biases of the nodes in the order of [`NetworkLike::nodes`].
+        pub fn parameters(&self) -> Vec<S> {
+            self.edges
+                .iter()
+                .chain(&self.recurrent_edges)
+                .map(|edge| edge.weight)
+                .chain(self.nodes.iter().map(|node| node.bias))
+                .collect()
+        }
+
+        /// Changes all weights and biases, ordered like [`Net::parameters`].
+        pub fn set_parameters(&mut self, parameters: &[S]) -> Result<(), &'static str> {
+            if parameters.len() != self.edges.len() + self.recurrent_edges.len() + self.nodes.len()
+            {
+                return Err("one parameter per edge, recurrent edge and node is needed");
+            }
+            let mut parameters = parameters.iter().copied();
+            for edge in self.edges.iter_mut().chain(&mut self.recurrent_edges) {
+                edge.weight = parameters.next().unwrap();
+            }
+            for node in &mut self.nodes {
+                node.bias = parameters.next().unwrap();
+            }
+            Ok(())
+        }
+
+        /// Creates a fully connected layered net with `layers[i]` nodes in layer `i`, e.g. as baseline to compare evolved topologies against.
+        ///
+        /// Ids count up layer by layer, the first layer holds the inputs with linear activation and the last one the outputs.
+        /// All other nodes use `activation`, all weights are one and all biases zero.
+        pub fn mlp(layers: &[usize], activation: fn(S) -> S) -> Result<Self, &'static str> {
+            if layers.len() < 2 {
+                return Err("mlp needs an input and an output layer");
+            }
+            if layers.contains(&0) {
+                return Err("mlp layers need at least one node");
+            }
+
+            let mut nodes = Vec::new();
+            let mut edges = Vec::new();
+            let mut previous = 0..0;
+            for (index, &size) in layers.iter().enumerate() {
+                let layer = nodes.len()..nodes.len() + size;
+                let layer_activation = if index == 0 {
+                    activations::linear
+                } else {
+                    activation
+                };
+                for end in layer.clone() {
+                    nodes.push(Node::new(end, layer_activation));
+                    for start in previous.clone() {
+                        edges.push(Edge::new(start, end, S::one()));
+                    }
+                }
+                previous = layer;
+            }
+            Ok(Net::new(layers[0], layers[layers.len() - 1], nodes, edges))
+        }
+
+        /// Creates a net from a square matrix holding the weight of the edge from node `i` to node `j` at `(i, j)`, zero meaning no edge.
+        ///
+        /// Node `i` gets id `i` and `activations[i]`, the first `inputs` nodes are inputs and the last `outputs` nodes are outputs.
+        pub fn from_adjacency(
+            matrix: DMatrix<S>,
+            activations: &[fn(S) -> S],
+            inputs: usize,
+            outputs: usize,
+        ) -> Result<Self, &'static str> {
+            let size = matrix.nrows();
+            if matrix.ncols() != size {
+                return Err("adjacency matrix needs to be square");
+            }
+            if activations.len() != size {
+                return Err("adjacency matrix needs one activation per node");
+            }
+            if inputs + outputs > size {
+                return Err("adjacency matrix has fewer nodes than inputs and outputs");
+            }
+
+            let nodes = activations
+                .iter()
+                .enumerate()
+                .map(|(id, &activation)| Node::new(id, activation))
+                .collect();
+            let mut edges = Vec::new();
+            for start in 0..size {
+                for end in 0..size {
+                    let weight = matrix[(start, end)];
+                    if weight != S::zero() {
+                        edges.push(Edge::new(start, end, weight));
+                    }
+                }
+            }
+            Ok(Net::new(inputs, outputs, nodes, edges))
+        }
+
+        /// Returns the square matrix holding the weight of the edge from the `i`th to the `j`th node at `(i, j)`, the inverse of [`Net::from_adjacency`].
+        ///
+        /// Nodes are in the order of [`NetworkLike::nodes`], weights of edges connecting the same nodes are summed.
+        /// Disabled, dangling, recurrent and modulatory edges are not represented.
+        pub fn to_adjacency(&self) -> DMatrix<S> {
+            let position: BTreeMap<usize, usize> = self
+                .nodes
+                .iter()
+                .enumerate()
+                .map(|(position, node)| (node.id(), position))
+                .collect();
+            let mut matrix = DMatrix::zeros(self.nodes.len(), self.nodes.len());
+            for edge in self.edges.iter().filter(|edge| edge.enabled()) {
+                if let (Some(&start), Some(&end)) =
+                    (position.get(&edge.start()), position.get(&edge.end()))
+                {
+                    matrix[(start, end)] += edge.weight();
+                }
+            }
+            matrix
+        }
+    }
+
+    /// Version of the serialized format of [`Net`], formats of other versions are rejected on deserialization.
+    #[cfg(feature = "serde")]
+    pub const NET_FORMAT_VERSION: u32 = 1;
+
+    #[cfg(feature = "serde")]
+    #[derive(serde::Serialize)]
+    struct NetFormatRef<'a, S: Scalar> {
+        version: u32,
+        inputs: usize,
+        outputs: usize,
+        nodes: &'a [Node<S>],
+        edges: &'a [Edge<S>],
+        recurrent_edges: &'a [Edge<S>],
+        #[serde(skip_serializing_if = "<[_]>::is_empty")]
+        modulatory_edges: &'a [Edge<S>],
+    }
+
+    #[cfg(feature = "serde")]
+    #[derive(serde::Deserialize)]
+    struct NetFormat<S: Scalar> {
+        version: u32,
+        inputs: usize,
+        outputs: usize,
+        nodes: Vec<Node<S>>,
+        edges: Vec<Edge<S>>,
+        #[serde(default = "Vec::new")]
+        recurrent_edges: Vec<Edge<S>>,
+        #[serde(default = "Vec::new")]
+        modulatory_edges: Vec<Edge<S>>,
+    }
+
+    #[cfg(feature = "serde")]
+    impl<S: Scalar + serde::Serialize> serde::Serialize for Net<S> {
+        fn serialize<Z: serde::Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+            NetFormatRef {
+                version: NET_FORMAT_VERSION,
+                inputs: self.inputs,
+                outputs: self.outputs,
+                nodes: &self.nodes,
+                edges: &self.edges,
+                recurrent_edges: &self.recurrent_edges,
+                modulatory_edges: &self.modulatory_edges,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de, S: Scalar + serde::Deserialize<'de>> serde::Deserialize<'de> for Net<S> {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            use serde::de::Error;
+
+            let format = NetFormat::deserialize(deserializer)?;
+            if format.version != NET_FORMAT_VERSION {
+                return Err(D::Error::custom(format!(
+                    "unsupported net format version {}, expected {}",
+                    format.version, NET_FORMAT_VERSION
+                )));
+            }
+            if format.inputs + format.outputs > format.nodes.len() {
+                return Err(D::Error::custom("more inputs and outputs than nodes"));
+            }
+
+            Ok(Net {
+                inputs: format.inputs,
+                outputs: format.outputs,
+                nodes: format.nodes,
+                edges: format.edges,
+                recurrent_edges: format.recurrent_edges,
+                modulatory_edges: format.modulatory_edges,
+            })
+        }
     }
 
     /// unroll is an essential operation in order to evaluate [`Recurrent`] [`NetworkLike`] structures.
     ///
     /// It restructures the edges and nodes to be evaluatable in a feedforward manner.
     /// The evaluation further depends on the implementations in [`crate::matrix::recurrent::evaluator`] and [`crate::sparse_matrix::recurrent::evaluator`] which handle the internal state.
-    pub fn unroll<R: Recurrent<N, E>, N: NodeLike, E: EdgeLike>(recurrent: &R) -> Net {
+    /// Every recurrent edge is treated as a delay of one evaluation, longer delays are expanded beforehand by [`crate::network::delayed::expand_delayed`].
+    /// [`unroll_with_map`] additionally returns the ids of the nodes wrapping the recurrent connections.
+    /// Structures using any of the [`RESERVED_IDS`] are rejected.
+    pub fn unroll<R: Recurrent<N, E, S>, N: NodeLike<S>, E: EdgeLike<S>, S: Scalar>(
+        recurrent: &R,
+    ) -> Result<Net<S>, &'static str> {
+        Ok(unroll_with_map(recurrent)?.0)
+    }
+
+    /// Ids of nodes added by [`unroll`] start here, the upper half of `usize` is reserved for them.
+    pub const FIRST_WRAPPER_ID: usize = usize::MAX >> 1;
+
+    /// Ids [`unroll`] reserves for its wrapper nodes, structures using them can be compacted by [`crate::network::remap::remap_recurrent`] beforehand.
+    pub const RESERVED_IDS: core::ops::RangeFrom<usize> = FIRST_WRAPPER_ID..;
+
+    /// Returns the id of the node whose value is held by every column of the state carried between evaluations of an [`unroll`]ed structure.
+    ///
+    /// Columns follow the ascending ids of the unrolled outputs, wrapping outputs hold the value of the start of their only incoming edge.
+    #[cfg(feature = "std")]
+    pub(crate) fn memory_ids<S: Scalar>(unrolled: &Net<S>) -> Vec<usize> {
+        let mut ids = unrolled
+            .outputs()
+            .iter()
+            .map(|node| node.id())
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.into_iter()
+            .map(|id| {
+                if id < FIRST_WRAPPER_ID {
+                    return id;
+                }
+                unrolled
+                    .edges
+                    .iter()
+                    .find(|edge| edge.end == id)
+                    .map_or(id, |edge| edge.start)
+            })
+            .collect()
+    }
+
+    /// Same as [`unroll`] but also returns the ids of the inputs wrapping every original output and start of a recurrent edge.
+    ///
+    /// The map goes from original node ids to wrapper input ids, the value of a node from the previous evaluation is fed into its wrapper input.
+    /// Wrapper ids are assigned counting up from [`FIRST_WRAPPER_ID`]:
+    /// first one wrapper input per output, in the order of [`NetworkLike::outputs`],
+    /// then for every other start of a recurrent edge, in the order of [`Recurrent::recurrent_edges`], a wrapper input followed by a wrapper output.
+    /// The wrapper output is connected to the start of the recurrent edge with weight one and exposes its value to be fed back.
+    ///
+    /// The unrolled net lists the original inputs followed by the wrapper inputs as inputs and the original outputs followed by the wrapper outputs as outputs.
+    /// Every recurrent edge becomes an edge from the wrapper input of its start to its end.
+    /// Custom stateful evaluators can thereby evaluate the unrolled net feedforward and route its outputs back into the wrapper inputs themselves.
+    /// Structures with node or edge ids among the [`RESERVED_IDS`] are rejected, as their nodes would be mistaken for wrappers.
+    pub fn unroll_with_map<R: Recurrent<N, E, S>, N: NodeLike<S>, E: EdgeLike<S>, S: Scalar>(
+        recurrent: &R,
+    ) -> Result<(Net<S>, BTreeMap<usize, usize>), &'static str> {
+        let reserved = recurrent
+            .nodes_iter()
+            .map(|node| node.id())
+            .chain(
+                recurrent
+                    .edges_iter()
+                    .chain(recurrent.recurrent_edges())
+                    .flat_map(|edge| [edge.start(), edge.end()]),
+            )
+            .any(|id| RESERVED_IDS.contains(&id));
+        if reserved {
+            return Err("node ids collide with the ids reserved for unroll wrappers");
+        }
+
         let mut known_inputs = recurrent
             .inputs()
             .iter()
             .map(|n| Node {
                 id: n.id(),
                 activation: n.activation(),
+                bias: n.bias(),
+                aggregation: n.aggregation(),
+                time_constant: n.time_constant(),
+                response: n.response(),
+                kind: n.kind(),
+                label: n.label().map(String::from),
+                custom_activation: n.custom_activation(),
             })
             .collect::<Vec<_>>();
 
@@ -232,6 +1308,13 @@ pub mod net {
             .map(|n| Node {
                 id: n.id(),
                 activation: n.activation(),
+                bias: n.bias(),
+                aggregation: n.aggregation(),
+                time_constant: n.time_constant(),
+                response: n.response(),
+                kind: n.kind(),
+                label: n.label().map(String::from),
+                custom_activation: n.custom_activation(),
             })
             .collect::<Vec<_>>();
 
@@ -242,12 +1325,15 @@ pub mod net {
                 start: e.start(),
                 end: e.end(),
                 weight: e.weight(),
+                gate: e.gate(),
+                delay: 1,
+                enabled: e.enabled(),
+                id: e.id(),
             })
             .collect::<Vec<_>>();
 
-        let mut unroll_map: HashMap<usize, usize> = HashMap::new();
-        // WARN: upper half of usize is used for wrappping node ids
-        let mut tmp_ids = usize::MAX.shr(1)..usize::MAX;
+        let mut unroll_map: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut tmp_ids = RESERVED_IDS;
 
         // create wrapping input for all original outputs, regardless of if they are used
         // this is to simplify the state transfer inside the stateful matrix evaluator
@@ -256,7 +1342,14 @@ pub mod net {
 
             let wrapper_input_node = Node {
                 id: wrapper_input_id,
-                activation: |val| val,
+                activation: activations::linear,
+                bias: S::zero(),
+                aggregation: Aggregation::Sum,
+                time_constant: S::one(),
+                response: S::one(),
+                kind: NodeKind::Plain,
+                label: None,
+                custom_activation: None,
             };
 
             known_inputs.push(wrapper_input_node);
@@ -271,18 +1364,36 @@ pub mod net {
 
                 let wrapper_input_node = Node {
                     id: wrapper_input_id,
-                    activation: |val| val,
+                    activation: activations::linear,
+                    bias: S::zero(),
+                    aggregation: Aggregation::Sum,
+                    time_constant: S::one(),
+                    response: S::one(),
+                    kind: NodeKind::Plain,
+                    label: None,
+                    custom_activation: None,
                 };
                 let wrapper_output_node = Node {
                     id: tmp_ids.next().unwrap(),
-                    activation: |val| val,
+                    activation: activations::linear,
+                    bias: S::zero(),
+                    aggregation: Aggregation::Sum,
+                    time_constant: S::one(),
+                    response: S::one(),
+                    kind: NodeKind::Plain,
+                    label: None,
+                    custom_activation: None,
                 };
 
                 // used to carry value into next evaluation
                 let outward_wrapping_edge = Edge {
                     start: recurrent_edge.start(),
-                    weight: 1.0,
+                    weight: S::one(),
                     end: wrapper_output_node.id(),
+                    gate: Gate::Candidate,
+                    delay: 1,
+                    enabled: true,
+                    id: None,
                 };
 
                 // add nodes for wrapping
@@ -299,6 +1410,10 @@ pub mod net {
                 start: *recurrent_input,
                 end: recurrent_edge.end(),
                 weight: recurrent_edge.weight(),
+                gate: recurrent_edge.gate(),
+                delay: 1,
+                enabled: recurrent_edge.enabled(),
+                id: recurrent_edge.id(),
             };
 
             known_edges.push(inward_wrapping_connection);
@@ -311,28 +1426,336 @@ pub mod net {
             .chain(recurrent.hidden().iter().map(|n| Node {
                 id: n.id(),
                 activation: n.activation(),
+                bias: n.bias(),
+                aggregation: n.aggregation(),
+                time_constant: n.time_constant(),
+                response: n.response(),
+                kind: n.kind(),
+                label: n.label().map(String::from),
+                custom_activation: n.custom_activation(),
             }))
             .chain(known_outputs.into_iter())
             .collect::<Vec<_>>();
         let edges = known_edges;
 
-        Net::new(inputs_count, outputs_count, nodes, edges)
+        Ok((
+            Net::new(inputs_count, outputs_count, nodes, edges),
+            unroll_map,
+        ))
     }
 
     pub mod activations {
-        pub const LINEAR: fn(f32) -> f32 = |val| val;
+        use alloc::sync::Arc;
+
+        use crate::network::Scalar;
+
+        pub const LINEAR: fn(f32) -> f32 = linear;
         // pub const SIGMOID: fn(f32) -> f32 = |val| 1.0 / (1.0 + (-1.0 * val).exp());
-        pub const SIGMOID: fn(f32) -> f32 = |val| 1.0 / (1.0 + (-4.9 * val).exp());
-        pub const TANH: fn(f32) -> f32 = |val| 2.0 * SIGMOID(2.0 * val) - 1.0;
+        pub const SIGMOID: fn(f32) -> f32 = sigmoid;
+        pub const TANH: fn(f32) -> f32 = tanh;
+        pub const GAUSSIAN: fn(f32) -> f32 = gaussian;
+        pub const STEP: fn(f32) -> f32 = step;
+        pub const SINE: fn(f32) -> f32 = sine;
+        pub const COSINE: fn(f32) -> f32 = cosine;
+        pub const INVERSE: fn(f32) -> f32 = inverse;
+        pub const ABS: fn(f32) -> f32 = abs;
+        pub const RELU: fn(f32) -> f32 = relu;
+        pub const SQUARED: fn(f32) -> f32 = squared;
+
+        // the generic versions below back the `f32` constants above and can be used with any other [`Scalar`], e.g. `sigmoid::<f64>`
+        // they are never inlined, so every crate keeps a single instance per scalar whose pointer [`name`] recognizes
+
+        #[inline(never)]
+        pub fn linear<S: Scalar>(val: S) -> S {
+            val
+        }
+        #[inline(never)]
+        pub fn sigmoid<S: Scalar>(val: S) -> S {
+            S::one() / (S::one() + (nalgebra::convert::<f64, S>(-4.9) * val).exp())
+        }
+        #[inline(never)]
+        pub fn tanh<S: Scalar>(val: S) -> S {
+            let two = nalgebra::convert::<f64, S>(2.0);
+            two * sigmoid(two * val) - S::one()
+        }
         // a = 1, b = 0, c = 1
-        pub const GAUSSIAN: fn(f32) -> f32 = |val| (val * val / -2.0).exp();
-        // pub const STEP: fn(f32) -> f32 = |val| if val > 0.0 { 1.0 } else { 0.0 };
-        // pub const SINE: fn(f32) -> f32 = |val| (val * std::f32::consts::PI).sin();
-        // pub const COSINE: fn(f32) -> f32 = |val| (val * std::f32::consts::PI).cos();
-        pub const INVERSE: fn(f32) -> f32 = |val| -val;
-        // pub const ABSOLUTE: fn(f32) -> f32 = |val| val.abs();
-        pub const RELU: fn(f32) -> f32 = |val| 0f32.max(val);
-        pub const SQUARED: fn(f32) -> f32 = |val| val * val;
+        #[inline(never)]
+        pub fn gaussian<S: Scalar>(val: S) -> S {
+            (val * val / nalgebra::convert::<f64, S>(-2.0)).exp()
+        }
+        #[inline(never)]
+        pub fn inverse<S: Scalar>(val: S) -> S {
+            -val
+        }
+        #[inline(never)]
+        pub fn relu<S: Scalar>(val: S) -> S {
+            S::zero().max(val)
+        }
+        #[inline(never)]
+        pub fn squared<S: Scalar>(val: S) -> S {
+            val * val
+        }
+        #[inline(never)]
+        pub fn step<S: Scalar>(val: S) -> S {
+            if val > S::zero() {
+                S::one()
+            } else {
+                S::zero()
+            }
+        }
+        #[inline(never)]
+        pub fn sine<S: Scalar>(val: S) -> S {
+            (val * S::pi()).sin()
+        }
+        #[inline(never)]
+        pub fn cosine<S: Scalar>(val: S) -> S {
+            (val * S::pi()).cos()
+        }
+        #[inline(never)]
+        pub fn abs<S: Scalar>(val: S) -> S {
+            val.abs()
+        }
+
+        const NAMES: [&str; 11] = [
+            "linear", "sigmoid", "tanh", "gaussian", "inverse", "relu", "squared", "step", "sine",
+            "cosine", "abs",
+        ];
+
+        /// Looks up an activation of this module by its lowercase name, e.g. `"sigmoid"`.
+        pub fn from_name<S: Scalar>(name: &str) -> Option<fn(S) -> S> {
+            match name {
+                "linear" => Some(linear),
+                "sigmoid" => Some(sigmoid),
+                "tanh" => Some(tanh),
+                "gaussian" => Some(gaussian),
+                "inverse" => Some(inverse),
+                "relu" => Some(relu),
+                "squared" => Some(squared),
+                "step" => Some(step),
+                "sine" => Some(sine),
+                "cosine" => Some(cosine),
+                "abs" => Some(abs),
+                _ => None,
+            }
+        }
+
+        /// Names an activation if it is one of this module.
+        ///
+        /// Activations are recognized by their function pointer, so other functions are never named, even if they compute the same values like `|val| val`.
+        pub fn name<S: Scalar>(activation: fn(S) -> S) -> Option<&'static str> {
+            NAMES.iter().copied().find(|name| {
+                from_name::<S>(name)
+                    .is_some_and(|candidate| core::ptr::fn_addr_eq(activation, candidate))
+            })
+        }
+
+        /// A shared closure used as activation, see [`crate::network::NodeLike::custom_activation`].
+        #[derive(Clone)]
+        pub struct Closure<S: Scalar = f32>(pub Arc<dyn Fn(S) -> S + Send + Sync>);
+
+        impl<S: Scalar> Closure<S> {
+            pub fn new(activation: impl Fn(S) -> S + Send + Sync + 'static) -> Self {
+                Self(Arc::new(activation))
+            }
+        }
+
+        impl<S: Scalar> core::fmt::Debug for Closure<S> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str("Closure")
+            }
+        }
+
+        /// A function sampled evenly over `[-range, range]` and interpolated linearly in between, values outside are clamped.
+        #[derive(Debug, Clone)]
+        pub struct Table<S: Scalar = f32> {
+            pub function: fn(S) -> S,
+            pub range: S,
+            pub values: Arc<[S]>,
+        }
+
+        impl<S: Scalar> Table<S> {
+            /// Samples `function` at `resolution` points, at least two.
+            pub fn new(function: fn(S) -> S, resolution: usize, range: S) -> Self {
+                let resolution = resolution.max(2);
+                let step = (range + range) / nalgebra::convert::<f64, S>((resolution - 1) as f64);
+                Self {
+                    function,
+                    range,
+                    values: (0..resolution)
+                        .map(|index| {
+                            function(nalgebra::convert::<f64, S>(index as f64) * step - range)
+                        })
+                        .collect(),
+                }
+            }
+
+            pub fn apply(&self, val: S) -> S {
+                let last = self.values.len() - 1;
+                let position = (val.clamp(-self.range, self.range) + self.range)
+                    / (self.range + self.range)
+                    * nalgebra::convert::<f64, S>(last as f64);
+                // NaN turns into index zero but stays NaN by the fraction
+                let index = (position.floor().to_subset().unwrap_or(0.0) as usize).min(last - 1);
+                let fraction = position - nalgebra::convert::<f64, S>(index as f64);
+                self.values[index] + (self.values[index + 1] - self.values[index]) * fraction
+            }
+        }
+
+        /// An activation as applied by the matrix based evaluators, either a function, a [`Closure`] or a [`Table`] approximating a function.
+        #[derive(Debug, Clone)]
+        pub enum Activation<S: Scalar = f32> {
+            Function(fn(S) -> S),
+            Closure(Closure<S>),
+            Table(Table<S>),
+        }
+
+        impl<S: Scalar> Activation<S> {
+            /// Prefers the [`crate::network::NodeLike::custom_activation`] of `node` over its function.
+            pub fn of(node: &impl crate::network::NodeLike<S>) -> Self {
+                node.custom_activation()
+                    .map_or_else(|| Self::Function(node.activation()), Self::Closure)
+            }
+
+            pub fn apply(&self, val: S) -> S {
+                match self {
+                    Self::Function(activation) => activation(val),
+                    Self::Closure(activation) => (activation.0)(val),
+                    Self::Table(table) => table.apply(val),
+                }
+            }
+
+            /// Returns the function unless this is a closure, tables return the exact function they approximate.
+            pub fn function(&self) -> Option<fn(S) -> S> {
+                match self {
+                    Self::Function(activation) => Some(*activation),
+                    Self::Closure(_) => None,
+                    Self::Table(table) => Some(table.function),
+                }
+            }
+
+            /// Names the function like [`name`], closures are never named.
+            pub fn name(&self) -> Option<&'static str> {
+                self.function().and_then(name)
+            }
+
+            /// Whether this is exactly the function [`linear`], tables of it are not as they clamp their input.
+            pub fn is_linear(&self) -> bool {
+                matches!(self, Self::Function(activation) if core::ptr::fn_addr_eq(*activation, linear::<S> as fn(S) -> S))
+            }
+        }
+
+        impl<S: Scalar> From<fn(S) -> S> for Activation<S> {
+            fn from(activation: fn(S) -> S) -> Self {
+                Self::Function(activation)
+            }
+        }
+
+        /// Names the activations of this module as values that can be compared, displayed and, with the feature `serde`, (de)serialized.
+        ///
+        /// Other functions are kept as [`ActivationKind::Custom`], they are displayed as `custom` and can not be serialized.
+        #[derive(Debug, Clone, Copy)]
+        pub enum ActivationKind<S: Scalar = f32> {
+            Linear,
+            Sigmoid,
+            Tanh,
+            Gaussian,
+            Relu,
+            Squared,
+            Inverse,
+            Step,
+            Sine,
+            Cosine,
+            Abs,
+            Custom(fn(S) -> S),
+        }
+
+        impl<S: Scalar> ActivationKind<S> {
+            /// Returns the lowercase name used by [`from_name`], `None` for custom activations.
+            pub fn name(&self) -> Option<&'static str> {
+                Some(match self {
+                    Self::Linear => "linear",
+                    Self::Sigmoid => "sigmoid",
+                    Self::Tanh => "tanh",
+                    Self::Gaussian => "gaussian",
+                    Self::Relu => "relu",
+                    Self::Squared => "squared",
+                    Self::Inverse => "inverse",
+                    Self::Step => "step",
+                    Self::Sine => "sine",
+                    Self::Cosine => "cosine",
+                    Self::Abs => "abs",
+                    Self::Custom(_) => return None,
+                })
+            }
+
+            pub fn from_name(name: &str) -> Option<Self> {
+                Some(match name {
+                    "linear" => Self::Linear,
+                    "sigmoid" => Self::Sigmoid,
+                    "tanh" => Self::Tanh,
+                    "gaussian" => Self::Gaussian,
+                    "relu" => Self::Relu,
+                    "squared" => Self::Squared,
+                    "inverse" => Self::Inverse,
+                    "step" => Self::Step,
+                    "sine" => Self::Sine,
+                    "cosine" => Self::Cosine,
+                    "abs" => Self::Abs,
+                    _ => return None,
+                })
+            }
+
+            pub fn function(&self) -> fn(S) -> S {
+                match self {
+                    Self::Custom(activation) => *activation,
+                    kind => from_name(kind.name().unwrap()).unwrap(),
+                }
+            }
+        }
+
+        /// Recognizes the activations of this module like [`name`] does.
+        impl<S: Scalar> From<fn(S) -> S> for ActivationKind<S> {
+            fn from(activation: fn(S) -> S) -> Self {
+                name(activation)
+                    .and_then(Self::from_name)
+                    .unwrap_or(Self::Custom(activation))
+            }
+        }
+
+        impl<S: Scalar> From<ActivationKind<S>> for fn(S) -> S {
+            fn from(kind: ActivationKind<S>) -> Self {
+                kind.function()
+            }
+        }
+
+        impl<S: Scalar> PartialEq for ActivationKind<S> {
+            fn eq(&self, other: &Self) -> bool {
+                match (self, other) {
+                    (Self::Custom(a), Self::Custom(b)) => core::ptr::fn_addr_eq(*a, *b),
+                    (a, b) => a.name().is_some() && a.name() == b.name(),
+                }
+            }
+        }
+
+        impl<S: Scalar> core::fmt::Display for ActivationKind<S> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(self.name().unwrap_or("custom"))
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<S: Scalar> serde::Serialize for ActivationKind<S> {
+            fn serialize<Z: serde::Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+                crate::network::serialization::activation::serialize(&self.function(), serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de, S: Scalar> serde::Deserialize<'de> for ActivationKind<S> {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                crate::network::serialization::activation::deserialize(deserializer).map(Self::from)
+            }
+        }
     }
 
     #[macro_export]
@@ -341,36 +1764,515 @@ pub mod net {
             {
                 vec![
                     $(
-                        crate::network::net::Edge::new($start, $end, $weight),
+                        $crate::network::net::Edge::new($start, $end, $weight),
+                    )*
+                ]
+            }
+        };
+    }
+
+    /// Builds recurrent edges like `edges!`, every edge may be followed by `@ delay`, e.g. `2--0.9->1 @ 3`.
+    #[macro_export]
+    macro_rules! recurrent_edges {
+        ( $( $start:literal -- $weight:literal -> $end:literal $( @ $delay:literal )? ),* ) => {
+            {
+                vec![
+                    $(
+                        $crate::network::net::Edge::new($start, $end, $weight)
+                            $( .with_delay($delay) )?,
                     )*
                 ]
             }
         };
     }
 
+    /// Resolves an activation from a letter like `'s'`, a name like `sigmoid` or any other function in scope.
+    ///
+    /// The letters are `l` linear, `s` sigmoid, `t` tanh, `g` gaussian, `r` relu, `q` squared and `i` inverse, other letters fail to compile.
+    #[macro_export]
+    macro_rules! activation {
+        ('l') => {
+            $crate::network::net::activations::LINEAR
+        };
+        ('s') => {
+            $crate::network::net::activations::SIGMOID
+        };
+        ('t') => {
+            $crate::network::net::activations::TANH
+        };
+        ('g') => {
+            $crate::network::net::activations::GAUSSIAN
+        };
+        ('r') => {
+            $crate::network::net::activations::RELU
+        };
+        ('q') => {
+            $crate::network::net::activations::SQUARED
+        };
+        ('i') => {
+            $crate::network::net::activations::INVERSE
+        };
+        (linear) => {
+            $crate::network::net::activations::LINEAR
+        };
+        (sigmoid) => {
+            $crate::network::net::activations::SIGMOID
+        };
+        (tanh) => {
+            $crate::network::net::activations::TANH
+        };
+        (gaussian) => {
+            $crate::network::net::activations::GAUSSIAN
+        };
+        (relu) => {
+            $crate::network::net::activations::RELU
+        };
+        (squared) => {
+            $crate::network::net::activations::SQUARED
+        };
+        (inverse) => {
+            $crate::network::net::activations::INVERSE
+        };
+        (step) => {
+            $crate::network::net::activations::STEP
+        };
+        (sine) => {
+            $crate::network::net::activations::SINE
+        };
+        (cosine) => {
+            $crate::network::net::activations::COSINE
+        };
+        (abs) => {
+            $crate::network::net::activations::ABS
+        };
+        ($letter:literal) => {
+            compile_error!(concat!("unknown activation ", stringify!($letter)))
+        };
+        ($activation:tt) => {
+            $activation
+        };
+    }
+
+    /// Builds nodes with ascending ids from activations as accepted by `activation!` or paths to functions.
     #[macro_export]
     macro_rules! nodes {
-        ( $( $activation:literal ),* ) => {
+        (@collect [ $( $activation:expr, )* ]) => {
             {
-            let mut nodes = Vec::new();
+                let activations = [ $( $activation, )* ];
+                activations
+                    .iter()
+                    .enumerate()
+                    .map(|(id, &activation)| $crate::network::net::Node::new(id, activation))
+                    .collect::<Vec<_>>()
+            }
+        };
+        (@collect [ $( $done:expr, )* ] $activation:tt $( , $( $rest:tt )* )? ) => {
+            $crate::nodes!(@collect [ $( $done, )* $crate::activation!($activation), ] $( $( $rest )* )?)
+        };
+        (@collect [ $( $done:expr, )* ] $activation:path $( , $( $rest:tt )* )? ) => {
+            $crate::nodes!(@collect [ $( $done, )* $activation, ] $( $( $rest )* )?)
+        };
+        ( $( $activations:tt )* ) => {
+            $crate::nodes!(@collect [] $( $activations )*)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{
+        net::{
+            activations::{self, ActivationKind},
+            unroll_with_map, Edge, Net, Node, FIRST_WRAPPER_ID,
+        },
+        remap::remap_recurrent,
+        DynEvaluator, DynStatefulEvaluator, EdgeLike, Evaluator, Fabricator, NetworkLike, NodeLike,
+        StatefulEvaluateInto, StatefulEvaluator, StatefulFabricator,
+    };
+    use crate::{
+        edges,
+        interpreter::fabricator::LoopFabricator,
+        matrix::{
+            feedforward::fabricator::MatrixFeedforwardFabricator,
+            recurrent::fabricator::MatrixRecurrentFabricator,
+        },
+        nodes, recurrent_edges,
+        sparse_matrix::{
+            feedforward::fabricator::SparseMatrixFeedforwardFabricator,
+            recurrent::fabricator::SparseMatrixRecurrentFabricator,
+        },
+    };
+
+    // only provides the collected vectors and relies on the default iterators
+    struct Collected(Net);
+
+    impl NetworkLike<Node, Edge> for Collected {
+        fn edges(&self) -> Vec<&Edge> {
+            self.0.edges()
+        }
+        fn inputs(&self) -> Vec<&Node> {
+            self.0.inputs()
+        }
+        fn hidden(&self) -> Vec<&Node> {
+            self.0.hidden()
+        }
+        fn outputs(&self) -> Vec<&Node> {
+            self.0.outputs()
+        }
+    }
+
+    #[test]
+    fn name_only_builtins() {
+        assert_eq!(activations::name(activations::LINEAR), Some("linear"));
+        assert_eq!(activations::name(activations::relu::<f64>), Some("relu"));
+
+        // equal values at any point do not make a function one of the module
+        let identity: fn(f32) -> f32 = |val| val;
+        let clamped: fn(f32) -> f32 = |val| val.clamp(-3.0, 3.0);
+        let rectified: fn(f32) -> f32 = |val| val.max(0.0);
+        assert_eq!(activations::name(identity), None);
+        assert_eq!(activations::name(clamped), None);
+        assert_eq!(activations::name(rectified), None);
+    }
+
+    #[test]
+    fn activation_kinds() {
+        let custom: fn(f32) -> f32 = |val| val * 3.0;
+
+        for name in [
+            "linear", "sigmoid", "tanh", "gaussian", "relu", "squared", "inverse", "step", "sine",
+            "cosine", "abs",
+        ] {
+            let kind = ActivationKind::<f32>::from_name(name).unwrap();
+            assert_eq!(kind.to_string(), name);
+            assert_eq!(ActivationKind::from(kind.function()), kind);
+        }
+        assert_eq!(ActivationKind::from(custom), ActivationKind::Custom(custom));
+        assert_eq!(ActivationKind::Custom(custom).to_string(), "custom");
+        assert_ne!(
+            ActivationKind::Custom(custom),
+            ActivationKind::<f32>::Linear
+        );
+        assert_eq!(
+            Node::new(0, activations::STEP).activation_kind(),
+            ActivationKind::Step
+        );
+        assert_eq!(activations::STEP(0.0), 0.0);
+        assert_eq!(activations::ABS(-2.0), 2.0);
+
+        #[cfg(feature = "serde")]
+        {
+            let serialized = serde_json::to_string(&ActivationKind::<f32>::Sine).unwrap();
+            assert_eq!(serialized, "\"sine\"");
+            let deserialized: ActivationKind = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized, ActivationKind::Sine);
+            assert!(serde_json::to_string(&ActivationKind::Custom(custom)).is_err());
+        }
+    }
+
+    #[test]
+    fn dynamic_evaluators() {
+        let mut some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 's', 'l'),
+            edges!(0--0.5->2, 1--0.5->2, 2--1.0->3),
+        );
+
+        let evaluators: Vec<Box<dyn DynEvaluator>> = vec![
+            Box::new(MatrixFeedforwardFabricator::fabricate(&some_net).unwrap()),
+            Box::new(SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap()),
+            Box::new(LoopFabricator::fabricate(&some_net).unwrap()),
+        ];
+        for evaluator in &evaluators {
+            assert_eq!(evaluator.evaluate_vec(vec![0.0, 0.0]), vec![0.5]);
+            assert_eq!(evaluator.evaluate(dmatrix![0.0, 0.0]), dmatrix![0.5]);
+            assert!(evaluator.try_evaluate(dmatrix![0.0]).is_err());
+        }
+
+        some_net.set_recurrent_edges(edges!(3--1.0->2));
+        let mut evaluators: Vec<Box<dyn DynStatefulEvaluator>> = vec![
+            Box::new(MatrixRecurrentFabricator::fabricate(&some_net).unwrap()),
+            Box::new(SparseMatrixRecurrentFabricator::fabricate(&some_net).unwrap()),
+        ];
+        for evaluator in evaluators.iter_mut() {
+            assert_eq!(evaluator.evaluate_vec(vec![0.0, 0.0]), vec![0.5]);
+            assert_ne!(evaluator.evaluate_vec(vec![0.0, 0.0]), vec![0.5]);
+            evaluator.reset();
+            assert_eq!(evaluator.evaluate(vec![0.0, 0.0]), vec![0.5]);
+        }
+    }
+
+    #[test]
+    fn stateless_adapter() {
+        fn run(agent: &mut impl StatefulEvaluator, steps: usize) -> Vec<Vec<f32>> {
+            agent.reset_internal_state();
+            (0..steps).map(|_| agent.evaluate(vec![1.0])).collect()
+        }
+
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--0.5->1));
+        let mut stateless = MatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .into_stateful();
+        assert_eq!(run(&mut stateless, 2), vec![vec![0.5], vec![0.5]]);
+        assert_eq!(stateless.input_dimension(), Some(1));
+        assert!(stateless.try_evaluate(vec![1.0, 2.0]).is_err());
+        let mut output = [0.0];
+        stateless.evaluate_into(&[4.0], &mut output, &mut stateless.scratch());
+        assert_eq!(output, [2.0]);
+
+        some_net.set_recurrent_edges(edges!(1--1.0->1));
+        let mut recurrent = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(run(&mut recurrent, 2), vec![vec![0.5], vec![1.0]]);
+    }
+
+    #[test]
+    fn default_iterators() {
+        let net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 's', 'l'),
+            edges!(0--0.5->2, 1--0.5->2, 2--1.0->3),
+        );
+        let ids = |nodes: Vec<&Node>| nodes.iter().map(|node| node.id()).collect::<Vec<_>>();
+        assert_eq!(ids(net.nodes_iter().collect()), vec![0, 1, 2, 3]);
+        assert_eq!(ids(net.hidden_iter().collect()), vec![2]);
 
-            $(
-                nodes.push(
-                    crate::network::net::Node::new(nodes.len(), match $activation {
-                        'l' => crate::network::net::activations::LINEAR,
-                        's' => crate::network::net::activations::SIGMOID,
-                        't' => crate::network::net::activations::TANH,
-                        'g' => crate::network::net::activations::GAUSSIAN,
-                        'r' => crate::network::net::activations::RELU,
-                        'q' => crate::network::net::activations::SQUARED,
-                        'i' => crate::network::net::activations::INVERSE,
-                        _ => crate::network::net::activations::SIGMOID }
-                    )
-                );
-            )*
-
-            nodes
+        let collected = Collected(net);
+        assert_eq!(ids(collected.nodes()), vec![0, 1, 2, 3]);
+        assert_eq!(collected.edges_iter().count(), 3);
+        assert_eq!(
+            MatrixFeedforwardFabricator::fabricate(&collected)
+                .unwrap()
+                .evaluate(dmatrix![1.0, 1.0]),
+            MatrixFeedforwardFabricator::fabricate(&collected.0)
+                .unwrap()
+                .evaluate(dmatrix![1.0, 1.0])
+        );
+    }
+
+    #[test]
+    fn unroll_mapping() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->1, 1--1.0->2));
+        some_net.set_recurrent_edges(edges!(1--0.5->1, 2--2.0->1));
+
+        let (unrolled, map) = unroll_with_map(&some_net).unwrap();
+        // the output is wrapped first, the hidden node gets a wrapper input and output
+        assert_eq!(map[&2], FIRST_WRAPPER_ID);
+        assert_eq!(map[&1], FIRST_WRAPPER_ID + 1);
+        assert_eq!(map.len(), 2);
+
+        let ids = |nodes: Vec<&Node>| nodes.iter().map(|node| node.id()).collect::<Vec<_>>();
+        assert_eq!(
+            ids(unrolled.inputs()),
+            vec![0, FIRST_WRAPPER_ID, FIRST_WRAPPER_ID + 1]
+        );
+        assert_eq!(ids(unrolled.outputs()), vec![2, FIRST_WRAPPER_ID + 2]);
+        assert!(unrolled
+            .edges_iter()
+            .any(|edge| edge.start() == 1 && edge.end() == FIRST_WRAPPER_ID + 2));
+        assert!(unrolled
+            .edges_iter()
+            .any(|edge| edge.start() == FIRST_WRAPPER_ID && edge.end() == 1));
+    }
+
+    #[test]
+    fn unroll_reserved_ids() {
+        let mut some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(FIRST_WRAPPER_ID, activations::LINEAR),
+            ],
+            vec![Edge::new(0, FIRST_WRAPPER_ID, 1.0)],
+        );
+        some_net.set_recurrent_edges(vec![Edge::new(FIRST_WRAPPER_ID, FIRST_WRAPPER_ID, 0.5)]);
+        assert!(unroll_with_map(&some_net).is_err());
+        assert!(MatrixRecurrentFabricator::fabricate(&some_net).is_err());
+
+        let (compacted, _) = remap_recurrent(&some_net);
+        assert!(unroll_with_map(&compacted).is_ok());
+    }
+
+    #[test]
+    fn macro_activations() {
+        fn halve(val: f32) -> f32 {
+            val / 2.0
+        }
+
+        let nodes = nodes!('l', relu, activations::TANH, halve, 'q');
+        let probe = |node: &Node| node.activation()(-2.0);
+        assert_eq!(
+            nodes.iter().map(probe).collect::<Vec<_>>(),
+            vec![-2.0, 0.0, activations::TANH(-2.0), -1.0, 4.0]
+        );
+        assert_eq!(nodes[4].id(), 4);
+
+        let edges = recurrent_edges!(2--0.9->1, 1---1.0->1 @ 3);
+        assert_eq!(edges[0].delay(), 1);
+        assert_eq!(edges[1].delay(), 3);
+        assert_eq!(edges[1].weight(), -1.0);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_network_traits() {
+        use crate::network::{EdgeLike, NetworkLike, NodeLike};
+
+        #[derive(NodeLike)]
+        #[node(scalar = f64)]
+        struct NodeGene {
+            #[node(id)]
+            innovation: usize,
+            #[node(activation)]
+            activation: fn(f64) -> f64,
+        }
+
+        impl PartialEq for NodeGene {
+            fn eq(&self, other: &Self) -> bool {
+                self.innovation == other.innovation
+            }
+        }
+
+        impl Eq for NodeGene {}
+
+        impl PartialOrd for NodeGene {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for NodeGene {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.innovation.cmp(&other.innovation)
             }
+        }
+
+        #[derive(EdgeLike)]
+        struct ConnectionGene {
+            #[edge(start)]
+            from: usize,
+            #[edge(end)]
+            to: usize,
+            #[edge(weight)]
+            weight: f64,
+            #[edge(enabled)]
+            expressed: bool,
+        }
+
+        #[derive(NetworkLike)]
+        struct Genome {
+            #[network(inputs)]
+            inputs: Vec<NodeGene>,
+            #[network(hidden)]
+            hidden: Vec<NodeGene>,
+            #[network(outputs)]
+            outputs: Vec<NodeGene>,
+            #[network(edges)]
+            connections: Vec<ConnectionGene>,
+            #[network(recurrent_edges)]
+            recurrent_connections: Vec<ConnectionGene>,
+        }
+
+        let gene = |innovation| NodeGene {
+            innovation,
+            activation: activations::linear,
+        };
+        let connection = |from, to, weight, expressed| ConnectionGene {
+            from,
+            to,
+            weight,
+            expressed,
+        };
+        let genome = Genome {
+            inputs: vec![gene(0)],
+            hidden: vec![],
+            outputs: vec![gene(1)],
+            connections: vec![connection(0, 1, 2.0, true), connection(0, 1, 5.0, false)],
+            recurrent_connections: vec![connection(1, 1, 0.5, true)],
         };
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&genome).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0_f64]), dmatrix![2.0]);
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&genome).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0_f64]), dmatrix![2.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0_f64]), dmatrix![3.0]);
+    }
+
+    #[test]
+    fn adjacency_round_trip() {
+        let matrix = dmatrix![
+            0.0, 0.0, 0.5, 0.0;
+            0.0, 0.0, 1.0, 2.0;
+            0.0, 0.0, 0.0, 3.0;
+            0.0, 0.0, 0.0, 0.0
+        ];
+        let net = Net::from_adjacency(
+            matrix.clone(),
+            &[
+                activations::LINEAR,
+                activations::LINEAR,
+                activations::RELU,
+                activations::LINEAR,
+            ],
+            2,
+            1,
+        )
+        .unwrap();
+        assert_eq!(net.to_adjacency(), matrix);
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![2.0, -1.0]), dmatrix![-2.0]);
+
+        assert!(Net::from_adjacency(matrix, &[activations::LINEAR], 2, 1).is_err());
+    }
+
+    #[test]
+    fn mlp() {
+        let net = Net::mlp(&[3, 4, 2], activations::RELU).unwrap();
+        assert_eq!(net.inputs().len(), 3);
+        assert_eq!(net.hidden().len(), 4);
+        assert_eq!(net.outputs().len(), 2);
+        assert_eq!(net.edges().len(), 3 * 4 + 4 * 2);
+        assert!(net
+            .edges_iter()
+            .any(|edge| edge.start() == 6 && edge.end() == 8));
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        assert_eq!(
+            evaluator.evaluate(dmatrix![1.0, 2.0, -1.0]),
+            dmatrix![8.0, 8.0]
+        );
+
+        assert!(Net::mlp(&[3], activations::RELU).is_err());
+        assert!(Net::mlp(&[3, 0, 1], activations::RELU).is_err());
+    }
+
+    #[test]
+    fn parameters() {
+        let mut net = Net::mlp(&[2, 1], activations::LINEAR).unwrap();
+        net.set_recurrent_edges(edges!(2--0.5->2));
+        assert_eq!(net.parameters(), vec![1.0, 1.0, 0.5, 0.0, 0.0, 0.0]);
+
+        net.set_parameters(&[2.0, 3.0, 0.0, 0.0, 0.0, 1.0]).unwrap();
+        assert!(net.set_parameters(&[1.0]).is_err());
+        let mut evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![6.0]);
+
+        assert_eq!(evaluator.parameters(), vec![2.0, 3.0]);
+        evaluator.set_parameters(&[1.0, -1.0]).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![1.0]);
+        assert!(evaluator.set_parameters(&[]).is_err());
+
+        net.set_parameters(&[2.0, 3.0, 0.5, 0.0, 0.0, 0.0]).unwrap();
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.parameters(), vec![2.0, 3.0, 0.5]);
+        evaluator.set_parameters(&[1.0, 1.0, 1.0]).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![2.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![4.0]);
     }
 }
@@ -0,0 +1,220 @@
+//! Splices reusable subnetworks into a [`Net`], see [`Modular`].
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use super::{
+    net::{Edge, Net, Node},
+    prune::{active_edges, copy_edge, copy_node},
+    remap::{fabricable, fabricable_with_ids},
+    EdgeLike, Modular, ModuleLike, NetworkLike, NodeId, NodeLike, Scalar,
+};
+
+/// A [`Net`] used as module, e.g. an evolved building block.
+#[derive(Debug)]
+pub struct Module<S: Scalar = f32> {
+    pub net: Net<S>,
+    pub sources: Vec<usize>,
+    pub targets: Vec<usize>,
+}
+
+impl<S: Scalar> Module<S> {
+    pub fn new(net: Net<S>, sources: Vec<usize>, targets: Vec<usize>) -> Self {
+        Self {
+            net,
+            sources,
+            targets,
+        }
+    }
+}
+
+impl<S: Scalar> ModuleLike<Node<S>, Edge<S>, S> for Module<S> {
+    type Network = Net<S>;
+
+    fn network(&self) -> &Net<S> {
+        &self.net
+    }
+
+    fn sources(&self) -> Vec<usize> {
+        self.sources.clone()
+    }
+
+    fn targets(&self) -> Vec<usize> {
+        self.targets.clone()
+    }
+}
+
+/// A [`Net`] together with the [`Module`]s it contains.
+#[derive(Debug)]
+pub struct ModularNet<S: Scalar = f32> {
+    pub net: Net<S>,
+    pub modules: Vec<Module<S>>,
+}
+
+impl<S: Scalar> ModularNet<S> {
+    pub fn new(net: Net<S>, modules: Vec<Module<S>>) -> Self {
+        Self { net, modules }
+    }
+}
+
+impl<S: Scalar> NetworkLike<Node<S>, Edge<S>, S> for ModularNet<S> {
+    fn edges(&self) -> Vec<&Edge<S>> {
+        self.net.edges()
+    }
+
+    fn inputs(&self) -> Vec<&Node<S>> {
+        self.net.inputs()
+    }
+
+    fn hidden(&self) -> Vec<&Node<S>> {
+        self.net.hidden()
+    }
+
+    fn outputs(&self) -> Vec<&Node<S>> {
+        self.net.outputs()
+    }
+}
+
+impl<S: Scalar> Modular<Node<S>, Edge<S>, S> for ModularNet<S> {
+    type Module = Module<S>;
+
+    fn modules(&self) -> Vec<&Module<S>> {
+        self.modules.iter().collect()
+    }
+}
+
+/// Copies the outer structure and splices in every module with fresh ids above all outer ids.
+///
+/// Edges starting at a module input start at the corresponding source instead, module inputs themselves are dropped.
+/// All other module nodes become hidden nodes and every module output gets an edge of weight one to its target, a linear target without other edges simply passes the value on.
+/// Modules of modules need to be flattened first, labels and recurrent edges of modules are not kept.
+///
+/// Ids other than `usize` are compacted like fabricators do, see [`super::NodeId`].
+pub fn flatten<M, N, E, S, I>(modular: &M) -> Result<Net<S>, &'static str>
+where
+    M: Modular<N, E, S, I>,
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    let (modular_net, dense) = fabricable_with_ids(modular);
+    let outer: Vec<usize> = modular_net.nodes_iter().map(|node| node.id()).collect();
+    let mut next_id = outer.iter().map(|id| id + 1).max().unwrap_or(0);
+
+    let mut spliced_nodes = Vec::new();
+    let mut spliced_edges = Vec::new();
+    for module in modular.modules() {
+        let network = &fabricable(module.network());
+        let bound = |ids: Vec<I>| {
+            ids.into_iter()
+                .map(|id| dense.dense(id).filter(|id| outer.contains(id)))
+                .collect::<Option<Vec<_>>>()
+                .ok_or("module bound to unknown node")
+        };
+        let (sources, targets) = (bound(module.sources())?, bound(module.targets())?);
+        if sources.len() != network.inputs_iter().count()
+            || targets.len() != network.outputs_iter().count()
+        {
+            return Err("module bindings do not match its inputs and outputs");
+        }
+
+        let mut ids: BTreeMap<usize, usize> = network
+            .inputs_iter()
+            .map(|node| node.id())
+            .zip(sources)
+            .collect();
+        for node in network.hidden_iter().chain(network.outputs_iter()) {
+            ids.insert(node.id(), next_id);
+            spliced_nodes.push(
+                Node::new(next_id, node.activation())
+                    .with_bias(node.bias())
+                    .with_aggregation(node.aggregation())
+                    .with_time_constant(node.time_constant())
+                    .with_response(node.response())
+                    .with_kind(node.kind())
+                    .with_closure(node.custom_activation()),
+            );
+            next_id += 1;
+        }
+
+        for edge in active_edges(&network.nodes(), network.edges()) {
+            let (Some(&start), Some(&end)) = (ids.get(&edge.start()), ids.get(&edge.end())) else {
+                return Err("module edge between unknown nodes");
+            };
+            spliced_edges.push(Edge::new(start, end, edge.weight()).with_gate(edge.gate()));
+        }
+        for (output, target) in network.outputs_iter().zip(targets) {
+            spliced_edges.push(Edge::new(ids[&output.id()], target, S::one()));
+        }
+    }
+
+    Ok(Net::new(
+        modular_net.inputs_iter().count(),
+        modular_net.outputs_iter().count(),
+        modular_net
+            .inputs_iter()
+            .chain(modular_net.hidden_iter())
+            .map(copy_node)
+            .chain(spliced_nodes)
+            .chain(modular_net.outputs_iter().map(copy_node))
+            .collect(),
+        modular_net
+            .edges_iter()
+            .map(copy_edge)
+            .chain(spliced_edges)
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{flatten, Modular, ModularNet, Module};
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{net::Net, Evaluator, NetworkLike},
+        nodes,
+    };
+
+    fn difference() -> Net {
+        // relu(a - b)
+        Net::new(2, 1, nodes!('l', 'l', 'r'), edges!(0--1.0->2, 1---1.0->2))
+    }
+
+    #[test]
+    fn splice_modules() {
+        let modular = ModularNet::new(
+            Net::new(3, 2, nodes!('l', 'l', 'l', 'l', 'l'), edges!(0--1.0->4)),
+            vec![
+                Module::new(difference(), vec![0, 1], vec![3]),
+                Module::new(difference(), vec![1, 2], vec![4]),
+            ],
+        );
+        assert_eq!(modular.modules().len(), 2);
+
+        let flat = flatten(&modular).unwrap();
+        assert_eq!(flat.hidden().len(), 2);
+        assert_eq!(flat.edges().len(), 7);
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate_modular(&modular).unwrap();
+        assert_eq!(
+            evaluator.evaluate(dmatrix![3.0, 1.0, 2.0]),
+            dmatrix![2.0, 3.0]
+        );
+        assert_eq!(
+            evaluator.evaluate(dmatrix![1.0, 2.0, 0.0]),
+            dmatrix![0.0, 3.0]
+        );
+
+        let unbound = ModularNet::new(
+            Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1)),
+            vec![Module::new(difference(), vec![0], vec![1])],
+        );
+        assert_eq!(
+            flatten(&unbound).err(),
+            Some("module bindings do not match its inputs and outputs")
+        );
+    }
+}
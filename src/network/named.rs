@@ -0,0 +1,148 @@
+//! Addresses inputs and outputs by the labels of their nodes instead of their position.
+
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
+
+use super::{EdgeLike, NetworkIO, NetworkLike, NodeLike, Scalar};
+
+/// Translates between labeled values and the positional input and output of evaluators, see [`NodeLike::label`].
+///
+/// The matrix based evaluators order inputs and outputs by ascending node id, so does [`NamedIO`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedIO {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+}
+
+impl NamedIO {
+    /// Collects the labels of all inputs and outputs, which need to be present and unique.
+    pub fn new<N, E, S>(net: &impl NetworkLike<N, E, S>) -> Result<Self, &'static str>
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+        S: Scalar,
+    {
+        let labels = |mut nodes: Vec<&N>| -> Result<Vec<String>, &'static str> {
+            nodes.sort_by_key(|node| node.id());
+            nodes
+                .into_iter()
+                .map(|node| {
+                    node.label()
+                        .map(String::from)
+                        .ok_or("inputs and outputs need labels")
+                })
+                .collect()
+        };
+        let inputs = labels(net.inputs())?;
+        let outputs = labels(net.outputs())?;
+
+        let mut all = inputs.iter().chain(&outputs).collect::<Vec<_>>();
+        all.sort_unstable();
+        if all.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err("labels of inputs and outputs need to be unique");
+        }
+
+        Ok(Self { inputs, outputs })
+    }
+
+    /// Labels of the inputs in evaluation order.
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    /// Labels of the outputs in evaluation order.
+    pub fn outputs(&self) -> &[String] {
+        &self.outputs
+    }
+
+    /// Arranges labeled values as a single sample, every input label needs a value.
+    pub fn input<T: NetworkIO<S>, S: Scalar>(
+        &self,
+        values: &HashMap<String, S>,
+    ) -> Result<T, &'static str> {
+        let row = self
+            .inputs
+            .iter()
+            .map(|label| values.get(label).copied().ok_or("missing value for input"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(NetworkIO::output(DMatrix::from_row_slice(
+            1,
+            row.len(),
+            &row,
+        )))
+    }
+
+    /// Labels the values of a single sample.
+    pub fn output<T: NetworkIO<S>, S: Scalar>(&self, output: T) -> HashMap<String, S> {
+        self.outputs
+            .iter()
+            .cloned()
+            .zip(NetworkIO::input(output).iter().copied())
+            .collect()
+    }
+
+    /// Evaluates labeled values with any evaluation function, e.g. of an [`super::Evaluator`] or [`super::StatefulEvaluator`].
+    pub fn evaluate<S: Scalar>(
+        &self,
+        values: &HashMap<String, S>,
+        evaluate: impl FnOnce(DMatrix<S>) -> DMatrix<S>,
+    ) -> Result<HashMap<String, S>, &'static str> {
+        Ok(self.output(evaluate(self.input(values)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::NamedIO;
+    use crate::{
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Edge, Net, Node},
+            Evaluator, Fabricator,
+        },
+    };
+
+    #[test]
+    fn labeled_evaluation() {
+        let net = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR).with_label("speed"),
+                Node::new(1, activations::LINEAR).with_label("angle"),
+                Node::new(2, activations::LINEAR).with_label("thrust"),
+            ],
+            vec![Edge::new(0, 2, 1.0), Edge::new(1, 2, 2.0)],
+        );
+        let named = NamedIO::new(&net).unwrap();
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+
+        let mut input = HashMap::new();
+        input.insert("angle".to_string(), 1.0);
+        input.insert("speed".to_string(), 3.0);
+        let output = named
+            .evaluate(&input, |input| evaluator.evaluate(input))
+            .unwrap();
+        assert_eq!(output["thrust"], 5.0);
+
+        input.remove("speed");
+        assert!(named.evaluate(&input, |input| input).is_err());
+    }
+
+    #[test]
+    fn missing_label() {
+        let net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR).with_label("x"),
+                Node::new(1, activations::LINEAR),
+            ],
+            vec![Edge::new(0, 1, 1.0)],
+        );
+        assert!(NamedIO::new(&net).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! Reads a [`Net`] from the text syntax of the `nodes!` and `edges!` macros, see [`Net::parse`].
+
+use alloc::vec::Vec;
+
+use super::{
+    net::{activations, Edge, Net, Node},
+    Scalar,
+};
+
+type Activations<S> = Vec<fn(S) -> S>;
+
+/// Resolves the letters of the `nodes!` macro, longer words are looked up as activation names.
+fn activation<S: Scalar>(word: &str) -> Result<fn(S) -> S, &'static str> {
+    let name = match word {
+        "l" => "linear",
+        "s" => "sigmoid",
+        "t" => "tanh",
+        "g" => "gaussian",
+        "r" => "relu",
+        "q" => "squared",
+        "i" => "inverse",
+        name => name,
+    };
+    activations::from_name(name).ok_or("unknown activation")
+}
+
+/// Parses `start --weight-> end`, a negative weight shows up as a third dash like in `2---1.0->4`.
+fn edge<S: Scalar>(text: &str) -> Result<Edge<S>, &'static str> {
+    let (start, rest) = text.split_once("--").ok_or("edge is missing `--`")?;
+    let (weight, end) = rest.rsplit_once("->").ok_or("edge is missing `->`")?;
+    let start = start.trim().parse().map_err(|_| "invalid edge start")?;
+    let end = end.trim().parse().map_err(|_| "invalid edge end")?;
+    let weight: f64 = weight.trim().parse().map_err(|_| "invalid edge weight")?;
+    Ok(Edge::new(start, end, nalgebra::convert(weight)))
+}
+
+fn edges<S: Scalar>(text: &str) -> Result<Vec<Edge<S>>, &'static str> {
+    text.split(',')
+        .filter(|edge| !edge.trim().is_empty())
+        .map(edge)
+        .collect()
+}
+
+impl<S: Scalar> Net<S> {
+    /// Parses a net from sections like `"inputs: l l; hidden: s; outputs: l; edges: 0 --0.5-> 2, 1 --0.3-> 2; recurrent: 2 --0.9-> 1"`.
+    ///
+    /// Node sections list activations by the letters of the `nodes!` macro or by their names, ids are assigned in the order inputs, hidden, outputs.
+    /// Edges are written like in the `edges!` macro and separated by commas.
+    /// The sections `hidden`, `edges` and `recurrent` are optional, whitespace and line breaks are ignored.
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut nodes: [Option<Activations<S>>; 3] = [None, None, None];
+        let mut regular = Vec::new();
+        let mut recurrent = Vec::new();
+
+        for section in text.split(';').filter(|section| !section.trim().is_empty()) {
+            let (name, content) = section.split_once(':').ok_or("section is missing `:`")?;
+            let kind = match name.trim() {
+                "inputs" => 0,
+                "hidden" => 1,
+                "outputs" => 2,
+                "edges" => {
+                    regular.extend(edges(content)?);
+                    continue;
+                }
+                "recurrent" => {
+                    recurrent.extend(edges(content)?);
+                    continue;
+                }
+                _ => return Err("unknown section"),
+            };
+            if nodes[kind].is_some() {
+                return Err("node section appears twice");
+            }
+            nodes[kind] = Some(
+                content
+                    .split_whitespace()
+                    .map(activation)
+                    .collect::<Result<_, _>>()?,
+            );
+        }
+
+        let [Some(inputs), hidden, Some(outputs)] = nodes else {
+            return Err("inputs and outputs need to be given");
+        };
+        let (input_count, output_count) = (inputs.len(), outputs.len());
+        let nodes = inputs
+            .into_iter()
+            .chain(hidden.unwrap_or_default())
+            .chain(outputs)
+            .enumerate()
+            .map(|(id, activation)| Node::new(id, activation))
+            .collect();
+
+        let mut net = Net::new(input_count, output_count, nodes, regular);
+        net.set_recurrent_edges(recurrent);
+        Ok(net)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use crate::{
+        edges,
+        matrix::recurrent::fabricator::MatrixRecurrentFabricator,
+        network::{
+            net::Net, EdgeLike, NetworkLike, Recurrent, StatefulEvaluator, StatefulFabricator,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn parse_net() {
+        let parsed: Net = Net::parse(
+            "inputs: l l; hidden: s; outputs: l;
+             edges: 0 --0.5-> 2, 1 --0.3-> 2, 2---1.0->3; recurrent: 2 --0.9-> 2",
+        )
+        .unwrap();
+        let mut built = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 's', 'l'),
+            edges!(0--0.5->2, 1--0.3->2, 2---1.0->3),
+        );
+        built.set_recurrent_edges(edges!(2--0.9->2));
+
+        assert_eq!(parsed.hidden().len(), 1);
+        assert_eq!(parsed.edges()[2].weight(), -1.0);
+        assert_eq!(parsed.recurrent_edges()[0].start(), 2);
+
+        let mut parsed = MatrixRecurrentFabricator::fabricate(&parsed).unwrap();
+        let mut built = MatrixRecurrentFabricator::fabricate(&built).unwrap();
+        for _ in 0..3 {
+            assert_eq!(
+                parsed.evaluate(dmatrix![1.0, 2.0]),
+                built.evaluate(dmatrix![1.0, 2.0])
+            );
+        }
+    }
+
+    #[test]
+    fn reject_invalid_text() {
+        let parse = |text| Net::<f32>::parse(text).err();
+
+        assert_eq!(parse("inputs: l; outputs: x"), Some("unknown activation"));
+        assert_eq!(
+            parse("inputs: l; outputs: relu; edges: 0 -> 1"),
+            Some("edge is missing `--`")
+        );
+        assert_eq!(
+            parse("inputs: l; edges: 0 --1.0-> 1"),
+            Some("inputs and outputs need to be given")
+        );
+        assert_eq!(parse("inputs l"), Some("section is missing `:`"));
+        assert_eq!(
+            parse("inputs: l; outputs: l; edges: 0 --a-> 1"),
+            Some("invalid edge weight")
+        );
+    }
+}
@@ -0,0 +1,416 @@
+//! Removes structure that does not contribute to any output.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    vec::Vec,
+};
+
+use super::{
+    net::{Edge, Net, Node},
+    Aggregation, EdgeLike, NodeLike, Recurrent, Scalar,
+};
+
+/// Filters the edges fabricators evaluate.
+///
+/// Enabled edges connecting the same nodes with equal gate and delay are merged into one edge summing their weights, see [`dedup_edges`].
+/// Disabled edges are skipped, so are zero-weight edges into summing nodes with at least one other non-zero incoming edge.
+/// Zero-weight edges are kept otherwise, as they still change aggregations other than the sum and keep nodes computable.
+pub(crate) fn active_edges<N, E, S>(nodes: &[&N], edges: Vec<&E>) -> Vec<Edge<S>>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let edges = merge_duplicates(&edges);
+    let summing: BTreeSet<usize> = nodes
+        .iter()
+        .filter(|node| node.aggregation() == Aggregation::Sum)
+        .map(|node| node.id())
+        .collect();
+    let weighted: BTreeSet<usize> = edges
+        .iter()
+        .filter(|edge| edge.enabled() && edge.weight() != S::zero())
+        .map(|edge| edge.end())
+        .collect();
+
+    edges
+        .into_iter()
+        .filter(|edge| {
+            edge.enabled()
+                && (edge.weight() != S::zero()
+                    || !(summing.contains(&edge.end()) && weighted.contains(&edge.end())))
+        })
+        .collect()
+}
+
+/// Merges enabled edges with equal start, end, gate and delay into the first of them, which carries the sum of their weights and keeps its id.
+fn merge_duplicates<E: EdgeLike<S>, S: Scalar>(edges: &[&E]) -> Vec<Edge<S>> {
+    let mut merged: Vec<(&E, S)> = Vec::with_capacity(edges.len());
+    // gates can not be ordered, so edges sharing start, end and delay are told apart by a search
+    let mut positions: BTreeMap<(usize, usize, usize), Vec<usize>> = BTreeMap::new();
+    for &edge in edges {
+        if !edge.enabled() {
+            merged.push((edge, edge.weight()));
+            continue;
+        }
+        let candidates = positions
+            .entry((edge.start(), edge.end(), edge.delay()))
+            .or_default();
+        match candidates
+            .iter()
+            .find(|&&position| merged[position].0.gate() == edge.gate())
+        {
+            Some(&position) => merged[position].1 += edge.weight(),
+            None => {
+                candidates.push(merged.len());
+                merged.push((edge, edge.weight()));
+            }
+        }
+    }
+    merged
+        .into_iter()
+        .map(|(edge, weight)| {
+            Edge::new(edge.start(), edge.end(), weight)
+                .with_gate(edge.gate())
+                .with_delay(edge.delay())
+                .with_enabled(edge.enabled())
+                .with_id(edge.id())
+        })
+        .collect()
+}
+
+/// Returns a copy of `recurrent` whose edges between the same nodes are merged into one edge with the sum of their weights.
+///
+/// Fabricators evaluate duplicate edges like this anyway, regular and recurrent edges are merged apart and only if their gates and delays are equal.
+/// Disabled edges are kept as they are.
+pub fn dedup_edges<R, N, E, S>(recurrent: &R) -> Net<S>
+where
+    R: Recurrent<N, E, S>,
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let mut net = Net::new(
+        recurrent.inputs().len(),
+        recurrent.outputs().len(),
+        recurrent.nodes().into_iter().map(copy_node).collect(),
+        merge_duplicates(&recurrent.edges()),
+    );
+    net.set_recurrent_edges(merge_duplicates(&recurrent.recurrent_edges()));
+    net
+}
+
+pub(crate) fn copy_node<N: NodeLike<S>, S: Scalar>(node: &N) -> Node<S> {
+    let copy = Node::new(node.id(), node.activation())
+        .with_bias(node.bias())
+        .with_aggregation(node.aggregation())
+        .with_time_constant(node.time_constant())
+        .with_response(node.response())
+        .with_kind(node.kind())
+        .with_closure(node.custom_activation());
+    match node.label() {
+        Some(label) => copy.with_label(String::from(label)),
+        None => copy,
+    }
+}
+
+pub(crate) fn copy_edge<E: EdgeLike<S>, S: Scalar>(edge: &E) -> Edge<S> {
+    Edge::new(edge.start(), edge.end(), edge.weight())
+        .with_gate(edge.gate())
+        .with_delay(edge.delay())
+        .with_enabled(edge.enabled())
+        .with_id(edge.id())
+}
+
+/// Copies any feedforward structure into a [`Net`], e.g. to apply functions of this module that expect [`Recurrent`] structures.
+#[cfg(feature = "std")]
+pub(crate) fn copy_net<N, E, S>(net: &impl super::NetworkLike<N, E, S>) -> Net<S>
+where
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    Net::new(
+        net.inputs_iter().count(),
+        net.outputs_iter().count(),
+        net.nodes_iter().map(copy_node).collect(),
+        net.edges_iter().map(copy_edge).collect(),
+    )
+}
+
+/// Returns a copy of `recurrent` without the hidden nodes that can not reach any output and their edges.
+///
+/// Reachability follows regular and recurrent edges, inputs and outputs are always kept.
+/// Pruned nets fabricate into smaller stages and no longer fail on dead ends that can not be computed.
+pub fn prune<R, N, E, S>(recurrent: &R) -> Net<S>
+where
+    R: Recurrent<N, E, S>,
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let edges = recurrent.edges();
+    let recurrent_edges = recurrent.recurrent_edges();
+
+    // walk edges backwards starting at the outputs
+    let mut contributing: BTreeSet<usize> =
+        recurrent.outputs().iter().map(|node| node.id()).collect();
+    let mut pending: Vec<usize> = contributing.iter().copied().collect();
+    while let Some(id) = pending.pop() {
+        for edge in edges.iter().chain(recurrent_edges.iter()) {
+            if edge.end() == id && contributing.insert(edge.start()) {
+                pending.push(edge.start());
+            }
+        }
+    }
+    contributing.extend(recurrent.inputs().iter().map(|node| node.id()));
+
+    let kept = |edges: &[&E]| {
+        edges
+            .iter()
+            .filter(|edge| {
+                contributing.contains(&edge.start()) && contributing.contains(&edge.end())
+            })
+            .map(|edge| copy_edge(*edge))
+            .collect::<Vec<_>>()
+    };
+
+    let mut net = Net::new(
+        recurrent.inputs().len(),
+        recurrent.outputs().len(),
+        recurrent
+            .inputs()
+            .iter()
+            .chain(
+                recurrent
+                    .hidden()
+                    .iter()
+                    .filter(|node| contributing.contains(&node.id())),
+            )
+            .chain(recurrent.outputs().iter())
+            .map(|node| copy_node(*node))
+            .collect(),
+        kept(&edges),
+    );
+    net.set_recurrent_edges(kept(&recurrent_edges));
+    net
+}
+
+/// Returns a copy of `recurrent` without disabled edges and hidden nodes left without incoming edges by them.
+///
+/// Fabricators skip disabled edges on their own, a node whose incoming edges are all disabled can not be computed though.
+/// Here such nodes are treated as absent and removed together with their outgoing edges, which may in turn remove further nodes.
+pub fn prune_disabled<R, N, E, S>(recurrent: &R) -> Net<S>
+where
+    R: Recurrent<N, E, S>,
+    N: NodeLike<S>,
+    E: EdgeLike<S>,
+    S: Scalar,
+{
+    let nodes = recurrent.nodes();
+    let edges = active_edges(&nodes, recurrent.edges());
+    let recurrent_edges = active_edges(&nodes, recurrent.recurrent_edges());
+
+    let mut incoming: BTreeMap<usize, usize> = BTreeMap::new();
+    for edge in recurrent
+        .edges()
+        .into_iter()
+        .chain(recurrent.recurrent_edges())
+    {
+        incoming.insert(edge.end(), 0);
+    }
+    for edge in edges.iter().chain(recurrent_edges.iter()) {
+        *incoming.entry(edge.end()).or_default() += 1;
+    }
+
+    // remove hidden nodes that lost all of their incoming edges until no more are found
+    let hidden: BTreeSet<usize> = recurrent.hidden().iter().map(|node| node.id()).collect();
+    let mut absent: BTreeSet<usize> = BTreeSet::new();
+    let mut pending: Vec<usize> = incoming
+        .iter()
+        .filter(|&(id, &count)| count == 0 && hidden.contains(id))
+        .map(|(&id, _)| id)
+        .collect();
+    while let Some(id) = pending.pop() {
+        if !absent.insert(id) {
+            continue;
+        }
+        for edge in edges.iter().chain(recurrent_edges.iter()) {
+            if edge.start() == id && !absent.contains(&edge.end()) {
+                let count = incoming.get_mut(&edge.end()).unwrap();
+                *count -= 1;
+                if *count == 0 && hidden.contains(&edge.end()) {
+                    pending.push(edge.end());
+                }
+            }
+        }
+    }
+
+    let kept = |edges: Vec<Edge<S>>| {
+        edges
+            .into_iter()
+            .filter(|edge| !absent.contains(&edge.start()) && !absent.contains(&edge.end()))
+            .collect::<Vec<_>>()
+    };
+
+    let mut net = Net::new(
+        recurrent.inputs().len(),
+        recurrent.outputs().len(),
+        nodes
+            .iter()
+            .filter(|node| !absent.contains(&node.id()))
+            .map(|node| copy_node(*node))
+            .collect(),
+        kept(edges),
+    );
+    net.set_recurrent_edges(kept(recurrent_edges));
+    net
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{dedup_edges, prune, prune_disabled};
+    use crate::{
+        edges,
+        interpreter::fabricator::LoopFabricator,
+        matrix::{
+            feedforward::fabricator::MatrixFeedforwardFabricator,
+            recurrent::fabricator::MatrixRecurrentFabricator,
+        },
+        network::{
+            net::{Edge, Net},
+            EdgeLike, Evaluator, Fabricator, NetworkLike, Recurrent, StatefulEvaluator,
+            StatefulFabricator,
+        },
+        nodes,
+        sparse_matrix::feedforward::fabricator::SparseMatrixFeedforwardFabricator,
+    };
+
+    #[test]
+    fn sum_duplicate_edges() {
+        let mut some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l'),
+            vec![
+                Edge::new(0, 1, 0.5),
+                Edge::new(0, 1, 0.25),
+                Edge::new(1, 2, 1.0),
+                Edge::new(0, 2, 2.0).with_enabled(false),
+                Edge::new(1, 2, 1.0),
+            ],
+        );
+
+        let input = dmatrix![4.0];
+        assert_eq!(
+            MatrixFeedforwardFabricator::fabricate(&some_net)
+                .unwrap()
+                .evaluate(input.clone()),
+            dmatrix![6.0]
+        );
+        assert_eq!(
+            SparseMatrixFeedforwardFabricator::fabricate(&some_net)
+                .unwrap()
+                .evaluate(input.clone()),
+            dmatrix![6.0]
+        );
+        assert_eq!(
+            LoopFabricator::fabricate(&some_net)
+                .unwrap()
+                .evaluate(input),
+            dmatrix![6.0]
+        );
+
+        some_net.set_recurrent_edges(vec![Edge::new(2, 1, 0.5), Edge::new(2, 1, 0.5)]);
+        let deduped = dedup_edges(&some_net);
+        let weights = |edges: Vec<&Edge>| {
+            edges
+                .iter()
+                .map(|edge| (edge.start(), edge.end(), edge.weight()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            weights(deduped.edges()),
+            vec![(0, 1, 0.75), (1, 2, 2.0), (0, 2, 2.0)]
+        );
+        assert_eq!(weights(deduped.recurrent_edges()), vec![(2, 1, 1.0)]);
+
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&some_net).unwrap();
+        let mut expected = MatrixRecurrentFabricator::fabricate(&deduped).unwrap();
+        for _ in 0..3 {
+            assert_eq!(evaluator.evaluate(vec![1.0]), expected.evaluate(vec![1.0]));
+        }
+    }
+
+    #[test]
+    fn prune_dead_ends() {
+        let mut some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l', 'l'),
+            edges!(
+                0--1.0->1,
+                1--1.0->4,
+                0--1.0->2,
+                3--1.0->2
+            ),
+        );
+        some_net.set_recurrent_edges(edges!(2--1.0->3));
+
+        // node 2 depends on node 3 which is never computable
+        assert!(MatrixFeedforwardFabricator::fabricate(&some_net).is_err());
+
+        let pruned = prune(&some_net);
+        assert_eq!(pruned.hidden().len(), 1);
+        assert_eq!(pruned.edges().len(), 2);
+        assert!(pruned.recurrent_edges().is_empty());
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&pruned).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![2.0]), dmatrix![2.0]);
+    }
+
+    #[test]
+    fn skip_disabled_edges() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l'),
+            vec![
+                Edge::new(0, 2, 1.0),
+                Edge::new(1, 2, 5.0).with_enabled(false),
+                Edge::new(1, 2, 0.0),
+            ],
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0, 1.0]), dmatrix![1.0]);
+    }
+
+    #[test]
+    fn prune_disabled_nodes() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            vec![
+                Edge::new(0, 1, 1.0).with_enabled(false),
+                Edge::new(1, 2, 1.0),
+                Edge::new(2, 3, 1.0),
+                Edge::new(0, 3, 1.0),
+            ],
+        );
+
+        // nodes 1 and 2 lost their inputs
+        assert!(MatrixFeedforwardFabricator::fabricate(&some_net).is_err());
+
+        let pruned = prune_disabled(&some_net);
+        assert!(pruned.hidden().is_empty());
+        assert_eq!(pruned.edges().len(), 1);
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&pruned).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![2.0]), dmatrix![2.0]);
+    }
+}
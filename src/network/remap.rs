@@ -0,0 +1,513 @@
+//! Compacts arbitrary node ids into dense `usize` ids, as fabricators do with ids other than `usize`.
+
+use alloc::{string::String, vec::Vec};
+use core::any::{Any, TypeId};
+
+#[cfg(feature = "std")]
+use super::Modulated;
+use super::{
+    net::{Edge, Net, Node},
+    EdgeLike, NetworkLike, NodeId, NodeLike, Recurrent, Scalar,
+};
+
+/// Translates between the original ids of a structure and the dense ids of its [`remap`]ped copy.
+///
+/// Dense ids follow the ascending order of the original ids, so the relative order of nodes is kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdMapping<I: NodeId = usize> {
+    // sorted, the position of an id is its dense id
+    ids: Vec<I>,
+}
+
+impl<I: NodeId> IdMapping<I> {
+    fn new(ids: impl Iterator<Item = I>) -> Self {
+        let mut ids = ids.collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+        IdMapping { ids }
+    }
+
+    /// Returns the dense id of an original id, none if the id was not part of the structure.
+    pub fn dense(&self, id: I) -> Option<usize> {
+        self.ids.binary_search(&id).ok()
+    }
+
+    /// Returns the original id of a dense id.
+    pub fn original(&self, dense: usize) -> Option<I> {
+        self.ids.get(dense).copied()
+    }
+
+    /// Number of distinct ids, dense ids range from zero up to it.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+/// Copies `net` into a [`Net`] whose node ids are compacted to `0..n`, along with the mapping back to the original ids.
+///
+/// Ids of any [`NodeId`] type are accepted, e.g. sparse innovation numbers or tuples.
+/// Fabricators compact such ids the same way, so the mapping also translates the node ids of their evaluators.
+/// Ids only referenced by edges get dense ids as well, so dangling edges stay dangling instead of being attached to another node.
+/// Recurrent edges are dropped, see [`remap_recurrent`].
+pub fn remap<N, E, S, I>(net: &impl NetworkLike<N, E, S, I>) -> (Net<S>, IdMapping<I>)
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    let mapping = IdMapping::new(ids(net));
+    (copy(net, &|id| mapping.dense(id).unwrap()), mapping)
+}
+
+/// Same as [`remap`] but keeps the recurrent edges, their ids are part of the mapping.
+pub fn remap_recurrent<R, N, E, S, I>(recurrent: &R) -> (Net<S>, IdMapping<I>)
+where
+    R: Recurrent<N, E, S, I>,
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    let mapping = IdMapping::new(ids(recurrent).chain(recurrent_ids(recurrent)));
+    (
+        copy_recurrent(recurrent, &|id| mapping.dense(id).unwrap()),
+        mapping,
+    )
+}
+
+/// Ids fabricators address the nodes of a structure by, `usize` ids are kept and any other ids are compacted like by [`remap`].
+pub(crate) struct DenseIds<I: NodeId>(Option<IdMapping<I>>);
+
+impl<I: NodeId> DenseIds<I> {
+    pub(crate) fn new(ids: impl Iterator<Item = I>) -> Self {
+        if TypeId::of::<I>() == TypeId::of::<usize>() {
+            DenseIds(None)
+        } else {
+            DenseIds(Some(IdMapping::new(ids)))
+        }
+    }
+
+    pub(crate) fn dense(&self, id: I) -> Option<usize> {
+        match &self.0 {
+            Some(mapping) => mapping.dense(id),
+            None => (&id as &dyn Any).downcast_ref::<usize>().copied(),
+        }
+    }
+}
+
+/// Copies `net` into the [`Net`] fabricators work on, see [`DenseIds`].
+pub(crate) fn fabricable<N, E, S, I>(net: &impl NetworkLike<N, E, S, I>) -> Net<S>
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    fabricable_with_ids(net).0
+}
+
+/// Same as [`fabricable`] but also returns the ids, e.g. to translate ids stored elsewhere.
+pub(crate) fn fabricable_with_ids<N, E, S, I>(
+    net: &impl NetworkLike<N, E, S, I>,
+) -> (Net<S>, DenseIds<I>)
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    let dense = DenseIds::new(ids(net));
+    (copy(net, &|id| dense.dense(id).unwrap()), dense)
+}
+
+/// Same as [`fabricable`] but keeps the recurrent edges.
+#[cfg(feature = "std")]
+pub(crate) fn fabricable_recurrent<R, N, E, S, I>(recurrent: &R) -> Net<S>
+where
+    R: Recurrent<N, E, S, I>,
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    let dense = DenseIds::new(ids(recurrent).chain(recurrent_ids(recurrent)));
+    copy_recurrent(recurrent, &|id| dense.dense(id).unwrap())
+}
+
+/// Same as [`fabricable_recurrent`] but keeps the modulatory edges as well.
+#[cfg(feature = "std")]
+pub(crate) fn fabricable_modulated<M, N, E, S, I>(modulated: &M) -> Net<S>
+where
+    M: Recurrent<N, E, S, I> + Modulated<N, E, S, I>,
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    let dense = DenseIds::new(
+        ids(modulated).chain(recurrent_ids(modulated)).chain(
+            modulated
+                .modulatory_edges()
+                .into_iter()
+                .flat_map(|edge| [edge.start(), edge.end()]),
+        ),
+    );
+    let dense = |id| dense.dense(id).unwrap();
+    let mut net = copy_recurrent(modulated, &dense);
+    net.set_modulatory_edges(
+        modulated
+            .modulatory_edges()
+            .into_iter()
+            .map(|edge| remap_edge(edge, &dense))
+            .collect(),
+    );
+    net
+}
+
+/// Ids of all nodes followed by the ids referenced by edges.
+fn ids<'a, N, E, S, I>(net: &'a impl NetworkLike<N, E, S, I>) -> impl Iterator<Item = I> + 'a
+where
+    N: NodeLike<S, I> + 'a,
+    E: EdgeLike<S, I> + 'a,
+    S: Scalar,
+    I: NodeId,
+{
+    net.nodes_iter()
+        .map(|node| node.id())
+        .chain(net.edges_iter().flat_map(|edge| [edge.start(), edge.end()]))
+}
+
+fn recurrent_ids<'a, R, N, E, S, I>(recurrent: &'a R) -> impl Iterator<Item = I> + 'a
+where
+    R: Recurrent<N, E, S, I>,
+    N: NodeLike<S, I> + 'a,
+    E: EdgeLike<S, I> + 'a,
+    S: Scalar,
+    I: NodeId,
+{
+    recurrent
+        .recurrent_edges_iter()
+        .flat_map(|edge| [edge.start(), edge.end()])
+}
+
+fn copy<N, E, S, I>(net: &impl NetworkLike<N, E, S, I>, dense: &impl Fn(I) -> usize) -> Net<S>
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    Net::new(
+        net.inputs_iter().count(),
+        net.outputs_iter().count(),
+        net.nodes_iter()
+            .map(|node| remap_node(node, dense))
+            .collect(),
+        net.edges_iter()
+            .map(|edge| remap_edge(edge, dense))
+            .collect(),
+    )
+}
+
+fn copy_recurrent<R, N, E, S, I>(recurrent: &R, dense: &impl Fn(I) -> usize) -> Net<S>
+where
+    R: Recurrent<N, E, S, I>,
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    let mut net = copy(recurrent, dense);
+    net.set_recurrent_edges(
+        recurrent
+            .recurrent_edges_iter()
+            .map(|edge| remap_edge(edge, dense))
+            .collect(),
+    );
+    net
+}
+
+fn remap_node<N: NodeLike<S, I>, S: Scalar, I: NodeId>(
+    node: &N,
+    dense: &impl Fn(I) -> usize,
+) -> Node<S> {
+    let copy = Node::new(dense(node.id()), node.activation())
+        .with_bias(node.bias())
+        .with_aggregation(node.aggregation())
+        .with_time_constant(node.time_constant())
+        .with_response(node.response())
+        .with_kind(node.kind())
+        .with_closure(node.custom_activation());
+    match node.label() {
+        Some(label) => copy.with_label(String::from(label)),
+        None => copy,
+    }
+}
+
+fn remap_edge<E: EdgeLike<S, I>, S: Scalar, I: NodeId>(
+    edge: &E,
+    dense: &impl Fn(I) -> usize,
+) -> Edge<S> {
+    Edge::new(dense(edge.start()), dense(edge.end()), edge.weight())
+        .with_gate(edge.gate())
+        .with_delay(edge.delay())
+        .with_enabled(edge.enabled())
+        .with_id(edge.id())
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{remap, remap_recurrent};
+    use crate::{
+        interpreter::fabricator::LoopFabricator,
+        matrix::{
+            feedforward::fabricator::MatrixFeedforwardFabricator,
+            recurrent::{evaluator::Hebbian, fabricator::MatrixRecurrentFabricator},
+        },
+        network::{
+            net::{activations, Edge, Net, Node},
+            EdgeLike, Evaluator, Fabricator, Modular, Modulated, ModuleLike, NetworkLike, NodeLike,
+            Recurrent, StatefulEvaluator, StatefulFabricator,
+        },
+    };
+
+    #[test]
+    fn compact_sparse_ids() {
+        let high = usize::MAX - 1;
+        let mut net = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(7, activations::LINEAR),
+                Node::new(high, activations::LINEAR),
+                Node::new(42, activations::LINEAR),
+            ],
+            vec![Edge::new(7, 42, 0.5), Edge::new(high, 42, 1.0)],
+        );
+
+        let (compact, mapping) = remap(&net);
+        assert_eq!(mapping.len(), 3);
+        assert_eq!(mapping.dense(high), Some(2));
+        assert_eq!(mapping.original(1), Some(42));
+        assert_eq!(mapping.dense(0), None);
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&compact).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![2.0, 1.0]), dmatrix![2.0]);
+
+        net.set_recurrent_edges(vec![Edge::new(42, 42, 0.5)]);
+        let (compact, _) = remap_recurrent(&net);
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&compact).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![2.0, 1.0]), dmatrix![2.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![2.0, 1.0]), dmatrix![3.0]);
+    }
+
+    #[test]
+    fn keep_usize_ids() {
+        let net = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(7, activations::LINEAR),
+                Node::new(100, activations::LINEAR),
+                Node::new(42, activations::LINEAR),
+            ],
+            vec![Edge::new(7, 42, 0.5), Edge::new(100, 42, 1.0)],
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.weight(7, 42), Some(0.5));
+        assert_eq!(evaluator.weight(100, 42), Some(1.0));
+    }
+
+    // innovation pairs as keys, hidden and output nodes are not listed in the order of their keys
+    type Key = (u32, u32);
+
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct KeyedNode(Key);
+    struct KeyedEdge(Key, Key, f32);
+
+    #[derive(Default)]
+    struct KeyedNet {
+        inputs: usize,
+        outputs: usize,
+        nodes: Vec<KeyedNode>,
+        edges: Vec<KeyedEdge>,
+        recurrent_edges: Vec<KeyedEdge>,
+        modulatory_edges: Vec<KeyedEdge>,
+        modules: Vec<KeyedModule>,
+    }
+
+    struct KeyedModule(KeyedNet, Vec<Key>, Vec<Key>);
+
+    impl NodeLike<f32, Key> for KeyedNode {
+        fn id(&self) -> Key {
+            self.0
+        }
+        fn activation(&self) -> fn(f32) -> f32 {
+            activations::LINEAR
+        }
+    }
+
+    impl EdgeLike<f32, Key> for KeyedEdge {
+        fn start(&self) -> Key {
+            self.0
+        }
+        fn end(&self) -> Key {
+            self.1
+        }
+        fn weight(&self) -> f32 {
+            self.2
+        }
+    }
+
+    impl NetworkLike<KeyedNode, KeyedEdge, f32, Key> for KeyedNet {
+        fn edges(&self) -> Vec<&KeyedEdge> {
+            self.edges.iter().collect()
+        }
+        fn inputs(&self) -> Vec<&KeyedNode> {
+            self.nodes[..self.inputs].iter().collect()
+        }
+        fn hidden(&self) -> Vec<&KeyedNode> {
+            self.nodes[self.inputs..self.nodes.len() - self.outputs]
+                .iter()
+                .collect()
+        }
+        fn outputs(&self) -> Vec<&KeyedNode> {
+            self.nodes[self.nodes.len() - self.outputs..]
+                .iter()
+                .collect()
+        }
+    }
+
+    impl Recurrent<KeyedNode, KeyedEdge, f32, Key> for KeyedNet {
+        fn recurrent_edges(&self) -> Vec<&KeyedEdge> {
+            self.recurrent_edges.iter().collect()
+        }
+    }
+
+    impl Modulated<KeyedNode, KeyedEdge, f32, Key> for KeyedNet {
+        fn modulatory_edges(&self) -> Vec<&KeyedEdge> {
+            self.modulatory_edges.iter().collect()
+        }
+    }
+
+    impl ModuleLike<KeyedNode, KeyedEdge, f32, Key> for KeyedModule {
+        type Network = KeyedNet;
+
+        fn network(&self) -> &KeyedNet {
+            &self.0
+        }
+        fn sources(&self) -> Vec<Key> {
+            self.1.clone()
+        }
+        fn targets(&self) -> Vec<Key> {
+            self.2.clone()
+        }
+    }
+
+    impl Modular<KeyedNode, KeyedEdge, f32, Key> for KeyedNet {
+        type Module = KeyedModule;
+
+        fn modules(&self) -> Vec<&KeyedModule> {
+            self.modules.iter().collect()
+        }
+    }
+
+    fn keyed_net() -> KeyedNet {
+        // out = 0.5 * (a - 2 * b) + a
+        KeyedNet {
+            inputs: 2,
+            outputs: 1,
+            nodes: vec![
+                KeyedNode((1, 3)),
+                KeyedNode((9, 0)),
+                KeyedNode((4, 4)),
+                KeyedNode((2, 1)),
+            ],
+            edges: vec![
+                KeyedEdge((1, 3), (4, 4), 1.0),
+                KeyedEdge((9, 0), (4, 4), -2.0),
+                KeyedEdge((4, 4), (2, 1), 0.5),
+                KeyedEdge((1, 3), (2, 1), 1.0),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fabricate_keyed_ids() {
+        let mut net = keyed_net();
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![4.0, 1.0]), dmatrix![5.0]);
+        let evaluator = LoopFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![4.0, 1.0]), dmatrix![5.0]);
+
+        net.recurrent_edges = vec![KeyedEdge((2, 1), (4, 4), 1.0)];
+        let mut evaluator = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![4.0, 1.0]), dmatrix![5.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![4.0, 1.0]), dmatrix![7.5]);
+
+        // modulatory edges are translated like the edges of the copy
+        net.modulatory_edges = vec![KeyedEdge((1, 3), (4, 4), 1.0)];
+        let (mut compact, mapping) = remap_recurrent(&net);
+        let dense = |key| mapping.dense(key).unwrap();
+        compact.set_modulatory_edges(vec![Edge::new(dense((1, 3)), dense((4, 4)), 1.0)]);
+
+        let plasticity = Hebbian {
+            learning_rate: 0.1,
+            decay: 0.0,
+        };
+        let mut keyed = MatrixRecurrentFabricator::fabricate_modulated(&net)
+            .unwrap()
+            .with_plasticity(plasticity);
+        let mut reference = MatrixRecurrentFabricator::fabricate_modulated(&compact)
+            .unwrap()
+            .with_plasticity(plasticity);
+        for _ in 0..2 {
+            assert_eq!(
+                keyed.evaluate(dmatrix![4.0, 1.0]),
+                reference.evaluate(dmatrix![4.0, 1.0])
+            );
+        }
+        assert_eq!(keyed.weights(), reference.weights());
+    }
+
+    #[test]
+    fn fabricate_keyed_modules() {
+        // out = a - 2 * b spliced in between the outer inputs and output
+        let module = KeyedNet {
+            inputs: 2,
+            outputs: 1,
+            nodes: vec![KeyedNode((0, 1)), KeyedNode((0, 2)), KeyedNode((0, 0))],
+            edges: vec![
+                KeyedEdge((0, 1), (0, 0), 1.0),
+                KeyedEdge((0, 2), (0, 0), -2.0),
+            ],
+            ..Default::default()
+        };
+        let mut modular = KeyedNet {
+            inputs: 2,
+            outputs: 1,
+            nodes: vec![KeyedNode((1, 3)), KeyedNode((9, 0)), KeyedNode((2, 1))],
+            modules: vec![KeyedModule(module, vec![(1, 3), (9, 0)], vec![(2, 1)])],
+            ..Default::default()
+        };
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate_modular(&modular).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![4.0, 1.0]), dmatrix![2.0]);
+
+        modular.modules[0].2 = vec![(5, 5)];
+        assert_eq!(
+            super::super::module::flatten(&modular).err(),
+            Some("module bound to unknown node")
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Serializes activation functions by name, as function pointers can not be stored.
+//!
+//! Only the functions in [`crate::network::net::activations`] can be (de)serialized, see [`activations::name`].
+//! Closures are never serialized.
+
+use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::network::{net::activations, Scalar};
+
+pub(crate) fn serialize<S: Scalar, Z: Serializer>(
+    transformations: &[crate::Transformations<S>],
+    serializer: Z,
+) -> Result<Z::Ok, Z::Error> {
+    transformations
+        .iter()
+        .map(|stage| {
+            stage
+                .iter()
+                .map(|activation| {
+                    activation.name().ok_or_else(|| {
+                        Z::Error::custom(
+                            "only activations from network::net::activations can be serialized",
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .serialize(serializer)
+}
+
+pub(crate) fn deserialize<'de, S: Scalar, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<crate::Transformations<S>>, D::Error> {
+    Vec::<Vec<String>>::deserialize(deserializer)?
+        .iter()
+        .map(|stage| {
+            stage
+                .iter()
+                .map(|name| {
+                    activations::from_name(name)
+                        .map(activations::Activation::Function)
+                        .ok_or_else(|| D::Error::custom(format!("unknown activation `{}`", name)))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// (De)serializes a single activation function by name, used for [`crate::network::net::Node`].
+pub(crate) mod activation {
+    use serde::{
+        de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer,
+    };
+
+    use crate::network::{net::activations, Scalar};
+
+    pub(crate) fn serialize<S: Scalar, Z: Serializer>(
+        activation: &fn(S) -> S,
+        serializer: Z,
+    ) -> Result<Z::Ok, Z::Error> {
+        activations::name(*activation)
+            .ok_or_else(|| {
+                Z::Error::custom(
+                    "only activations from network::net::activations can be serialized",
+                )
+            })?
+            .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, S: Scalar, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<fn(S) -> S, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        activations::from_name(&name)
+            .ok_or_else(|| D::Error::custom(format!("unknown activation `{}`", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Edge, Net, Node},
+            Aggregation, Evaluator, Fabricator, NetworkLike, Recurrent,
+        },
+    };
+    use nalgebra::dmatrix;
+
+    #[test]
+    fn net_round_trip() {
+        let mut some_net = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::RELU).with_bias(0.5),
+                Node::new(3, activations::SIGMOID).with_aggregation(Aggregation::Mean),
+            ],
+            edges!(
+                0--0.5->2,
+                1--1.5->2,
+                2--1.0->3,
+                0--0.25->3
+            ),
+        );
+        some_net.set_recurrent_edges(edges!(3--0.5->2));
+
+        let serialized = serde_json::to_string(&some_net).unwrap();
+        let deserialized: Net = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(deserialized.nodes().len(), 4);
+        assert_eq!(deserialized.recurrent_edges().len(), 1);
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let restored = MatrixFeedforwardFabricator::fabricate(&deserialized).unwrap();
+
+        assert_eq!(
+            restored.evaluate(dmatrix![1.0, 2.0]),
+            evaluator.evaluate(dmatrix![1.0, 2.0])
+        );
+    }
+
+    #[test]
+    fn reject_custom_activations() {
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, |val: f32| val.clamp(-3.0, 3.0)),
+            ],
+            vec![Edge::new(0, 1, 1.0)],
+        );
+        assert!(serde_json::to_string(&some_net).is_err());
+    }
+
+    #[test]
+    fn net_format_is_versioned() {
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::TANH),
+            ],
+            vec![Edge::new(0, 1, 1.0)],
+        );
+
+        let serialized = serde_json::to_value(&some_net).unwrap();
+        assert_eq!(serialized["version"], 1);
+        assert_eq!(serialized["nodes"][1]["activation"], "tanh");
+
+        // missing bias and aggregation fall back to their defaults
+        let minimal = r#"{
+            "version": 1,
+            "inputs": 1,
+            "outputs": 1,
+            "nodes": [{ "id": 0, "activation": "linear" }, { "id": 1, "activation": "relu" }],
+            "edges": [{ "start": 0, "end": 1, "weight": 2.0 }]
+        }"#;
+        let deserialized: Net = serde_json::from_str(minimal).unwrap();
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&deserialized).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.5]), dmatrix![3.0]);
+
+        let future = minimal.replace("\"version\": 1", "\"version\": 2");
+        assert!(serde_json::from_str::<Net>(&future).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+//! Post-processes the outputs of evaluators, e.g. to turn them into class probabilities.
+
+use nalgebra::DMatrix;
+
+use super::{
+    EdgeLike, Evaluator, FabricationError, Fabricator, NetworkIO, NetworkLike, NodeLike, Recurrent,
+    Scalar, StatefulEvaluator, StatefulFabricator,
+};
+
+/// Post-processing applied to every sample of an output, i.e. every row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputTransform<S: Scalar = f32> {
+    /// Exponentiates the values and divides them by their sum, so they add up to one.
+    Softmax,
+    /// Sets the largest value to one and all others to zero, the first of equal values wins.
+    Argmax,
+    /// Limits every value to `[min, max]`.
+    Clamp(S, S),
+}
+
+impl<S: Scalar> OutputTransform<S> {
+    pub fn apply(&self, output: &mut DMatrix<S>) {
+        for mut sample in output.row_iter_mut() {
+            match *self {
+                Self::Softmax => {
+                    // shift by the maximum so the largest exponent is zero and can not overflow
+                    let max = sample.max();
+                    sample.apply(|value| *value = (*value - max).exp());
+                    let sum = sample.sum();
+                    sample /= sum;
+                }
+                Self::Argmax => {
+                    let mut largest = 0;
+                    for (index, &value) in sample.iter().enumerate() {
+                        if value > sample[largest] {
+                            largest = index;
+                        }
+                    }
+                    sample.fill(S::zero());
+                    sample[largest] = S::one();
+                }
+                Self::Clamp(min, max) => sample.apply(|value| *value = value.max(min).min(max)),
+            }
+        }
+    }
+}
+
+/// Wraps an [`Evaluator`] or [`StatefulEvaluator`] and applies an [`OutputTransform`] to everything it evaluates.
+#[derive(Debug)]
+pub struct Transformed<V, S: Scalar = f32> {
+    pub evaluator: V,
+    pub transform: OutputTransform<S>,
+}
+
+impl<V, S: Scalar> Transformed<V, S> {
+    pub fn new(evaluator: V, transform: OutputTransform<S>) -> Self {
+        Self {
+            evaluator,
+            transform,
+        }
+    }
+
+    /// Fabricates `net` with the [`Fabricator`] `F` and wraps the result.
+    pub fn fabricate<F, N, E>(
+        net: &impl NetworkLike<N, E, S>,
+        transform: OutputTransform<S>,
+    ) -> Result<Self, FabricationError>
+    where
+        F: Fabricator<N, E, S, Output = V>,
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+    {
+        F::fabricate(net).map(|evaluator| Self::new(evaluator, transform))
+    }
+
+    /// Fabricates `net` with the [`StatefulFabricator`] `F` and wraps the result.
+    pub fn fabricate_stateful<F, N, E>(
+        net: &impl Recurrent<N, E, S>,
+        transform: OutputTransform<S>,
+    ) -> Result<Self, FabricationError>
+    where
+        F: StatefulFabricator<N, E, S, Output = V>,
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+    {
+        F::fabricate(net).map(|evaluator| Self::new(evaluator, transform))
+    }
+}
+
+impl<V: Evaluator<S>, S: Scalar> Evaluator<S> for Transformed<V, S> {
+    fn evaluate<T: NetworkIO<S>>(&self, input: T) -> T {
+        let mut output = self.evaluator.evaluate(NetworkIO::input(input));
+        self.transform.apply(&mut output);
+        NetworkIO::output(output)
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        self.evaluator.input_dimension()
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        self.evaluator.output_dimension()
+    }
+}
+
+impl<V: StatefulEvaluator<S>, S: Scalar> StatefulEvaluator<S> for Transformed<V, S> {
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T {
+        let mut output = self.evaluator.evaluate(NetworkIO::input(input));
+        self.transform.apply(&mut output);
+        NetworkIO::output(output)
+    }
+
+    fn reset_internal_state(&mut self) {
+        self.evaluator.reset_internal_state();
+    }
+
+    fn reset_state_for(&mut self, node_ids: &[usize]) {
+        self.evaluator.reset_state_for(node_ids);
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        self.evaluator.input_dimension()
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        self.evaluator.output_dimension()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::{OutputTransform, Transformed};
+    use crate::{
+        edges,
+        matrix::{
+            feedforward::fabricator::MatrixFeedforwardFabricator,
+            recurrent::fabricator::MatrixRecurrentFabricator,
+        },
+        network::{net::Net, Evaluator, StatefulEvaluator},
+        nodes,
+    };
+
+    #[test]
+    fn transform_outputs() {
+        let mut output = dmatrix![0.0, 0.0; 1.0, 1.0];
+        OutputTransform::Softmax.apply(&mut output);
+        assert_eq!(output, dmatrix![0.5, 0.5; 0.5, 0.5]);
+
+        let mut output = dmatrix![0.5, 2.0, 2.0; -1.0, -3.0, -2.0];
+        OutputTransform::Argmax.apply(&mut output);
+        assert_eq!(output, dmatrix![0.0, 1.0, 0.0; 1.0, 0.0, 0.0]);
+
+        let mut output = dmatrix![-2.0, 0.5, 2.0];
+        OutputTransform::Clamp(-1.0, 1.0).apply(&mut output);
+        assert_eq!(output, dmatrix![-1.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn transformed_evaluators() {
+        let mut some_net = Net::new(1, 2, nodes!('l', 'l', 'l'), edges!(0--1.0->1, 0--2.0->2));
+
+        let evaluator = Transformed::fabricate::<MatrixFeedforwardFabricator, _, _>(
+            &some_net,
+            OutputTransform::Argmax,
+        )
+        .unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![0.0, 1.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![-1.0]), dmatrix![1.0, 0.0]);
+
+        some_net.set_recurrent_edges(edges!(1--1.0->1));
+        let mut evaluator = Transformed::fabricate_stateful::<MatrixRecurrentFabricator, _, _>(
+            &some_net,
+            OutputTransform::Clamp(0.0, 2.5),
+        )
+        .unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![1.0, 2.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![2.0, 2.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![2.5, 2.0]);
+    }
+}
@@ -0,0 +1,465 @@
+use prost::Message;
+
+use crate::matrix::feedforward::evaluator::MatrixFeedforwardEvaluator;
+
+use super::proto::{
+    Dimension, GraphProto, ModelProto, NodeProto, OperatorSetIdProto, TensorProto,
+    TensorShapeProto, TensorTypeProto, TypeProto, ValueInfoProto, TENSOR_BOOL, TENSOR_FLOAT,
+};
+
+const OPSET_VERSION: i64 = 13;
+const IR_VERSION: i64 = 7;
+
+/// Converts a [`MatrixFeedforwardEvaluator`] into an ONNX model of `MatMul`, `Add` and activation operators.
+///
+/// The model has one input `input` of shape `[batch, inputs]` and one output `output` of shape `[batch, outputs]`,
+/// matching the row-wise sample layout of the evaluator.
+/// Stages mixing several activations select per column via `Where`, a clamp range is applied by `Clip` before the activations.
+///
+/// Only activations from [`activations`] and nodes aggregating by sum can be exported,
+/// gathered carries need to be exported before [`MatrixFeedforwardEvaluator::with_gathered_carries`].
+/// Compensated summation can not be expressed and is rejected.
+pub fn to_onnx(evaluator: &MatrixFeedforwardEvaluator) -> Result<Vec<u8>, &'static str> {
+    if !evaluator.gathers.is_empty() {
+        return Err("gathered carries can not be exported to onnx");
+    }
+    if evaluator.compensated {
+        return Err("compensated summation can not be exported to onnx");
+    }
+    if evaluator
+        .aggregations
+        .iter()
+        .any(|aggregations| !aggregations.is_empty())
+    {
+        return Err("only sum aggregation can be exported to onnx");
+    }
+
+    let inputs = evaluator.stages.first().ok_or("no stages")?.nrows();
+    let outputs = evaluator.stages.last().ok_or("no stages")?.ncols();
+
+    let mut graph = GraphBuilder::default();
+    let mut state = "input".to_owned();
+
+    for (index, ((stage_matrix, transformations), biases)) in evaluator
+        .stages
+        .iter()
+        .zip(&evaluator.transformations)
+        .zip(&evaluator.biases)
+        .enumerate()
+    {
+        let weights = graph.initializer(
+            format!("stage{}_weights", index),
+            vec![stage_matrix.nrows() as i64, stage_matrix.ncols() as i64],
+            // ONNX expects row-major data, nalgebra stores column-major
+            stage_matrix.transpose().iter().copied().collect(),
+        );
+        let product = graph.node("MatMul", &[&state, &weights]);
+        let bias = graph.initializer(
+            format!("stage{}_biases", index),
+            vec![1, biases.ncols() as i64],
+            biases.iter().copied().collect(),
+        );
+        let mut sum = graph.node("Add", &[&product, &bias]);
+        if let Some((min, max)) = evaluator.clamp {
+            let (min, max) = (graph.scalar(min), graph.scalar(max));
+            sum = graph.node("Clip", &[&sum, &min, &max]);
+        }
+
+        let names = transformations
+            .iter()
+            .map(|activation| {
+                activation
+                    .name()
+                    .ok_or("activation can not be exported to onnx")
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut distinct = names.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+
+        state = match distinct.as_slice() {
+            [name] => graph.activation(name, &sum),
+            _ => {
+                // every column takes the value of its own activation, later ones overwrite zeros
+                let mut selected = graph.initializer(
+                    format!("stage{}_zeros", index),
+                    vec![1, names.len() as i64],
+                    vec![0.0; names.len()],
+                );
+                for name in distinct {
+                    let activated = graph.activation(name, &sum);
+                    let mask = graph.mask(
+                        format!("stage{}_{}_mask", index, name),
+                        names.iter().map(|&other| other == name).collect(),
+                    );
+                    selected = graph.node("Where", &[&mask, &activated, &selected]);
+                }
+                selected
+            }
+        };
+    }
+
+    graph.nodes.push(NodeProto {
+        input: vec![state],
+        output: vec!["output".to_owned()],
+        name: "output".to_owned(),
+        op_type: "Identity".to_owned(),
+        attribute: Vec::new(),
+    });
+
+    let model = ModelProto {
+        ir_version: IR_VERSION,
+        producer_name: env!("CARGO_PKG_NAME").to_owned(),
+        producer_version: env!("CARGO_PKG_VERSION").to_owned(),
+        graph: Some(GraphProto {
+            node: graph.nodes,
+            name: "favannat".to_owned(),
+            initializer: graph.initializers,
+            input: vec![value_info("input", inputs)],
+            output: vec![value_info("output", outputs)],
+        }),
+        opset_import: vec![OperatorSetIdProto {
+            domain: String::new(),
+            version: OPSET_VERSION,
+        }],
+    };
+
+    Ok(model.encode_to_vec())
+}
+
+fn value_info(name: &str, features: usize) -> ValueInfoProto {
+    ValueInfoProto {
+        name: name.to_owned(),
+        r#type: Some(TypeProto {
+            tensor_type: Some(TensorTypeProto {
+                elem_type: TENSOR_FLOAT,
+                shape: Some(TensorShapeProto {
+                    dim: vec![
+                        Dimension {
+                            dim_value: None,
+                            dim_param: Some("batch".to_owned()),
+                        },
+                        Dimension {
+                            dim_value: Some(features as i64),
+                            dim_param: None,
+                        },
+                    ],
+                }),
+            }),
+        }),
+    }
+}
+
+/// Collects nodes and initializers and hands out unique names for intermediate values.
+#[derive(Default)]
+struct GraphBuilder {
+    nodes: Vec<NodeProto>,
+    initializers: Vec<TensorProto>,
+}
+
+impl GraphBuilder {
+    fn node(&mut self, op_type: &str, inputs: &[&str]) -> String {
+        let output = format!("{}_{}", op_type.to_lowercase(), self.nodes.len());
+        self.nodes.push(NodeProto {
+            input: inputs.iter().map(|&input| input.to_owned()).collect(),
+            output: vec![output.clone()],
+            name: output.clone(),
+            op_type: op_type.to_owned(),
+            attribute: Vec::new(),
+        });
+        output
+    }
+
+    fn initializer(&mut self, name: String, dims: Vec<i64>, float_data: Vec<f32>) -> String {
+        self.initializers.push(TensorProto {
+            dims,
+            data_type: TENSOR_FLOAT,
+            float_data,
+            name: name.clone(),
+            ..Default::default()
+        });
+        name
+    }
+
+    fn scalar(&mut self, value: f32) -> String {
+        let name = format!("constant_{}", self.initializers.len());
+        self.initializer(name, Vec::new(), vec![value])
+    }
+
+    fn mask(&mut self, name: String, mask: Vec<bool>) -> String {
+        self.initializers.push(TensorProto {
+            dims: vec![1, mask.len() as i64],
+            data_type: TENSOR_BOOL,
+            int32_data: mask.into_iter().map(i32::from).collect(),
+            name: name.clone(),
+            ..Default::default()
+        });
+        name
+    }
+
+    /// Expresses the activations of [`activations`] by ONNX operators.
+    fn activation(&mut self, name: &str, input: &str) -> String {
+        match name {
+            "linear" => input.to_owned(),
+            "sigmoid" => {
+                let steepness = self.scalar(4.9);
+                let scaled = self.node("Mul", &[input, &steepness]);
+                self.node("Sigmoid", &[&scaled])
+            }
+            // 2 * sigmoid(2 * x) - 1 with the steepened sigmoid equals tanh(4.9 * x)
+            "tanh" => {
+                let steepness = self.scalar(4.9);
+                let scaled = self.node("Mul", &[input, &steepness]);
+                self.node("Tanh", &[&scaled])
+            }
+            "gaussian" => {
+                let squared = self.node("Mul", &[input, input]);
+                let factor = self.scalar(-0.5);
+                let scaled = self.node("Mul", &[&squared, &factor]);
+                self.node("Exp", &[&scaled])
+            }
+            "inverse" => self.node("Neg", &[input]),
+            "relu" => self.node("Relu", &[input]),
+            "squared" => self.node("Mul", &[input, input]),
+            // the sign is -1, 0 or 1, clipping it at zero yields the step
+            "step" => {
+                let sign = self.node("Sign", &[input]);
+                self.node("Relu", &[&sign])
+            }
+            "sine" | "cosine" => {
+                let pi = self.scalar(std::f32::consts::PI);
+                let scaled = self.node("Mul", &[input, &pi]);
+                self.node(if name == "sine" { "Sin" } else { "Cos" }, &[&scaled])
+            }
+            "abs" => self.node("Abs", &[input]),
+            _ => unreachable!("activation names are taken from activations::name"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use nalgebra::{dmatrix, DMatrix};
+    use prost::Message;
+
+    use super::to_onnx;
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Net, Node},
+            Aggregation, Evaluator, Fabricator,
+        },
+        nodes,
+        onnx::proto::{ModelProto, TENSOR_BOOL},
+    };
+
+    // interprets the operators emitted by the exporter, broadcasting row vectors and scalars
+    fn run(model: &ModelProto, input: DMatrix<f32>) -> DMatrix<f32> {
+        let graph = model.graph.as_ref().unwrap();
+        let mut values: HashMap<String, DMatrix<f32>> = HashMap::new();
+        let mut masks: HashMap<String, Vec<bool>> = HashMap::new();
+        for tensor in &graph.initializer {
+            if tensor.data_type == TENSOR_BOOL {
+                masks.insert(
+                    tensor.name.clone(),
+                    tensor.int32_data.iter().map(|&value| value != 0).collect(),
+                );
+            } else if tensor.dims.is_empty() {
+                values.insert(tensor.name.clone(), dmatrix![tensor.float_data[0]]);
+            } else {
+                values.insert(
+                    tensor.name.clone(),
+                    DMatrix::from_row_slice(
+                        tensor.dims[0] as usize,
+                        tensor.dims[1] as usize,
+                        &tensor.float_data,
+                    ),
+                );
+            }
+        }
+        values.insert("input".to_owned(), input);
+
+        let broadcast = |a: &DMatrix<f32>, b: &DMatrix<f32>, op: fn(f32, f32) -> f32| {
+            DMatrix::from_fn(
+                a.nrows().max(b.nrows()),
+                a.ncols().max(b.ncols()),
+                |r, c| {
+                    op(
+                        a[(r % a.nrows(), c % a.ncols())],
+                        b[(r % b.nrows(), c % b.ncols())],
+                    )
+                },
+            )
+        };
+
+        for node in &graph.node {
+            let arg = |index: usize| values[&node.input[index]].clone();
+            let result = match node.op_type.as_str() {
+                "MatMul" => arg(0) * arg(1),
+                "Add" => broadcast(&arg(0), &arg(1), |a, b| a + b),
+                "Mul" => broadcast(&arg(0), &arg(1), |a, b| a * b),
+                "Sigmoid" => arg(0).map(|x| 1.0 / (1.0 + (-x).exp())),
+                "Tanh" => arg(0).map(f32::tanh),
+                "Exp" => arg(0).map(f32::exp),
+                "Neg" => arg(0).map(|x| -x),
+                "Relu" => arg(0).map(|x| x.max(0.0)),
+                "Sign" => arg(0).map(|x| if x == 0.0 { 0.0 } else { x.signum() }),
+                "Sin" => arg(0).map(f32::sin),
+                "Cos" => arg(0).map(f32::cos),
+                "Abs" => arg(0).map(f32::abs),
+                "Identity" => arg(0),
+                "Clip" => {
+                    let (min, max) = (arg(1)[0], arg(2)[0]);
+                    arg(0).map(|x| x.clamp(min, max))
+                }
+                "Where" => {
+                    let mask = &masks[&node.input[0]];
+                    let (then, otherwise) = (arg(1), arg(2));
+                    DMatrix::from_fn(then.nrows(), then.ncols(), |r, c| {
+                        if mask[c] {
+                            then[(r, c)]
+                        } else {
+                            otherwise[(r % otherwise.nrows(), c)]
+                        }
+                    })
+                }
+                op => panic!("unexpected operator {}", op),
+            };
+            values.insert(node.output[0].clone(), result);
+        }
+
+        values.remove("output").unwrap()
+    }
+
+    #[test]
+    fn onnx_export_matches_evaluator() {
+        let some_net = Net::new(
+            2,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::SIGMOID).with_bias(0.5),
+                Node::new(3, activations::GAUSSIAN),
+                Node::new(4, activations::TANH),
+                Node::new(5, activations::RELU).with_bias(-0.25),
+            ],
+            edges!(
+                0--0.5->2,
+                1--1.5->2,
+                0--0.75->3,
+                2--1.0->4,
+                3--0.5->4,
+                1--1.0->5,
+                3--0.5->5
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let model = ModelProto::decode(to_onnx(&evaluator).unwrap().as_slice()).unwrap();
+
+        assert_eq!(model.opset_import[0].version, 13);
+        assert!(model
+            .graph
+            .as_ref()
+            .unwrap()
+            .node
+            .iter()
+            .any(|node| node.op_type == "Where"));
+
+        let input = dmatrix![
+            1.0, 2.0;
+            -0.5, 0.25
+        ];
+        let expected = evaluator.evaluate(input.clone());
+        let actual = run(&model, input);
+
+        for (expected, actual) in expected.iter().zip(actual.iter()) {
+            assert!((expected - actual).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn onnx_export_periodic_activations() {
+        let some_net = Net::new(
+            1,
+            4,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::STEP),
+                Node::new(2, activations::SINE),
+                Node::new(3, activations::COSINE),
+                Node::new(4, activations::ABS),
+            ],
+            edges!(0--1.0->1, 0--0.5->2, 0--0.5->3, 0--1.0->4),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let model = ModelProto::decode(to_onnx(&evaluator).unwrap().as_slice()).unwrap();
+
+        let input = dmatrix![1.0; -0.5; 0.0];
+        let expected = evaluator.evaluate(input.clone());
+        let actual = run(&model, input);
+
+        for (expected, actual) in expected.iter().zip(actual.iter()) {
+            assert!((expected - actual).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn onnx_export_rejects_aggregations() {
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_aggregation(Aggregation::Max),
+            ],
+            edges!(0--1.0->1),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        assert!(to_onnx(&evaluator).is_err());
+    }
+
+    #[test]
+    fn onnx_export_rejects_gathered_carries() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--1.0->2, 2--1.0->3, 1--1.0->3),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert!(to_onnx(&evaluator).is_ok());
+        assert!(to_onnx(&evaluator.with_gathered_carries()).is_err());
+    }
+
+    #[test]
+    fn onnx_export_clamp() {
+        let some_net = Net::new(1, 2, nodes!('l', 'l', 's'), edges!(0--2.0->1, 0--1.0->2));
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_clamp(-1.0, 0.5)
+            .unwrap();
+        let model = ModelProto::decode(to_onnx(&evaluator).unwrap().as_slice()).unwrap();
+
+        let input = dmatrix![1.0; -2.0; 0.1];
+        let expected = evaluator.evaluate(input.clone());
+        let actual = run(&model, input);
+
+        assert_eq!(expected[(0, 0)], 0.5);
+        for (expected, actual) in expected.iter().zip(actual.iter()) {
+            assert!((expected - actual).abs() < 1e-5);
+        }
+
+        assert!(to_onnx(&evaluator.with_compensated_summation()).is_err());
+    }
+}
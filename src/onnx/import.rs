@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+
+use nalgebra::{DMatrix, DVector};
+use prost::Message;
+
+use crate::network::net::{activations, Edge, Net, Node};
+
+use super::proto::{GraphProto, ModelProto, NodeProto, TensorProto, TENSOR_FLOAT};
+
+/// The steepness built into [`activations::sigmoid`] and [`activations::tanh`], standard ONNX activations are rescaled by it.
+const STEEPNESS: f32 = 4.9;
+
+/// Reads a simple ONNX MLP, given as `ModelProto` bytes, into a [`Net`].
+///
+/// The graph needs to be a single chain from its input to its output made of
+/// `MatMul`, `Gemm`, `Add`, `Mul` by constants and the activations `Relu`, `Sigmoid`, `Tanh`, `Neg` and `Identity`.
+/// Consecutive affine operators are merged into one layer of nodes, every activation starts a new layer.
+/// Weights may be stored as float data or raw data, or be produced by `Constant` operators.
+pub fn from_onnx(bytes: &[u8]) -> Result<Net, &'static str> {
+    let model = ModelProto::decode(bytes).map_err(|_| "invalid onnx model")?;
+    let graph = model.graph.ok_or("onnx model without graph")?;
+
+    let mut constants = graph
+        .initializer
+        .iter()
+        .map(|tensor| Ok((tensor.name.clone(), Constant::from_tensor(tensor)?)))
+        .collect::<Result<HashMap<_, _>, &'static str>>()?;
+
+    let input = graph
+        .input
+        .iter()
+        .find(|input| !constants.contains_key(&input.name))
+        .ok_or("onnx graph without input")?;
+    let inputs = input_width(&graph, &input.name, &constants)?;
+
+    let mut builder = NetBuilder::new(inputs);
+    let mut current = input.name.clone();
+
+    for node in &graph.node {
+        if node.op_type == "Constant" {
+            let value = node
+                .attribute
+                .iter()
+                .find(|attribute| attribute.name == "value")
+                .and_then(|attribute| attribute.t.as_ref())
+                .ok_or("unsupported constant")?;
+            constants.insert(
+                node.output
+                    .first()
+                    .ok_or("constant without output")?
+                    .clone(),
+                Constant::from_tensor(value)?,
+            );
+            continue;
+        }
+
+        // the operand that is not the running value, if any
+        let operand = match node.input.iter().position(|input| *input == current) {
+            Some(0) => node.input.get(1),
+            Some(_) => node.input.first(),
+            None => return Err("only sequential onnx graphs are supported"),
+        }
+        .map(|name| constants.get(name).ok_or("operands need to be constant"))
+        .transpose()?;
+
+        match (node.op_type.as_str(), operand) {
+            ("MatMul", Some(weights)) => {
+                if node.input[0] != current {
+                    return Err("running value needs to be the left operand of MatMul");
+                }
+                builder.multiply(&weights.matrix()?)?;
+            }
+            ("Gemm", Some(weights)) => {
+                if node.input[0] != current || int_attribute(node, "transA") != 0 {
+                    return Err("transposed input of Gemm is unsupported");
+                }
+                let mut weights = weights.matrix()?;
+                if int_attribute(node, "transB") != 0 {
+                    weights = weights.transpose();
+                }
+                builder.multiply(&(weights * float_attribute(node, "alpha")))?;
+                if let Some(bias) = node.input.get(2) {
+                    let bias = constants.get(bias).ok_or("operands need to be constant")?;
+                    builder.add(&(bias.vector()? * float_attribute(node, "beta")))?;
+                }
+            }
+            ("Add", Some(bias)) => builder.add(&bias.vector()?)?,
+            ("Mul", Some(factor)) => builder.scale(factor.scalar()?),
+            ("Relu", None) => builder.activate(activations::RELU, 1.0),
+            ("Sigmoid", None) => builder.activate(activations::SIGMOID, STEEPNESS),
+            ("Tanh", None) => builder.activate(activations::TANH, STEEPNESS),
+            ("Neg", None) => builder.activate(activations::INVERSE, 1.0),
+            ("Identity", None) => (),
+            _ => return Err("unsupported onnx operator"),
+        }
+
+        current = node
+            .output
+            .first()
+            .ok_or("operator without output")?
+            .clone();
+    }
+
+    if !graph.output.iter().any(|output| output.name == current) {
+        return Err("onnx graph output is not produced by the chain");
+    }
+
+    builder.finish()
+}
+
+fn input_width(
+    graph: &GraphProto,
+    input: &str,
+    constants: &HashMap<String, Constant>,
+) -> Result<usize, &'static str> {
+    let declared = graph
+        .input
+        .iter()
+        .find(|value| value.name == input)
+        .and_then(|value| value.r#type.as_ref())
+        .and_then(|r#type| r#type.tensor_type.as_ref())
+        .and_then(|tensor| tensor.shape.as_ref())
+        .and_then(|shape| shape.dim.last())
+        .and_then(|dim| dim.dim_value);
+
+    match declared {
+        Some(width) => Ok(width as usize),
+        // fall back to the weights consumed by the first operator
+        None => graph
+            .node
+            .iter()
+            .find(|node| node.input.first().map(String::as_str) == Some(input))
+            .and_then(|node| {
+                let weights = constants.get(node.input.get(1)?)?;
+                let transposed = node.op_type == "Gemm" && int_attribute(node, "transB") != 0;
+                let dims = &weights.dims;
+                if transposed {
+                    dims.get(1)
+                } else {
+                    dims.first()
+                }
+                .map(|&width| width as usize)
+            })
+            .ok_or("can not infer input width"),
+    }
+}
+
+fn int_attribute(node: &NodeProto, name: &str) -> i64 {
+    node.attribute
+        .iter()
+        .find(|attribute| attribute.name == name)
+        .map_or(0, |attribute| attribute.i)
+}
+
+fn float_attribute(node: &NodeProto, name: &str) -> f32 {
+    node.attribute
+        .iter()
+        .find(|attribute| attribute.name == name)
+        .map_or(1.0, |attribute| attribute.f)
+}
+
+/// A constant float tensor in row-major order.
+struct Constant {
+    dims: Vec<i64>,
+    data: Vec<f32>,
+}
+
+impl Constant {
+    fn from_tensor(tensor: &TensorProto) -> Result<Self, &'static str> {
+        if tensor.data_type != TENSOR_FLOAT {
+            return Err("only float tensors are supported");
+        }
+        let data = if tensor.float_data.is_empty() {
+            tensor
+                .raw_data
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect()
+        } else {
+            tensor.float_data.clone()
+        };
+        Ok(Self {
+            dims: tensor.dims.clone(),
+            data,
+        })
+    }
+
+    fn matrix(&self) -> Result<DMatrix<f32>, &'static str> {
+        match self.dims.as_slice() {
+            &[rows, columns] => Ok(DMatrix::from_row_slice(
+                rows as usize,
+                columns as usize,
+                &self.data,
+            )),
+            _ => Err("weights need to be two dimensional"),
+        }
+    }
+
+    fn vector(&self) -> Result<DVector<f32>, &'static str> {
+        if self.dims.iter().rev().skip(1).any(|&dim| dim != 1) {
+            return Err("biases need to be one dimensional");
+        }
+        Ok(DVector::from_vec(self.data.clone()))
+    }
+
+    fn scalar(&self) -> Result<f32, &'static str> {
+        match self.data.as_slice() {
+            &[value] => Ok(value),
+            _ => Err("factors need to be scalar"),
+        }
+    }
+}
+
+/// Accumulates affine operators into a pending layer and turns it into nodes and edges on every activation.
+struct NetBuilder {
+    inputs: usize,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    layer: Vec<usize>,
+    // weights from the last layer to the pending one (rows are sources) and the pending biases
+    pending: Option<(DMatrix<f32>, DVector<f32>)>,
+}
+
+impl NetBuilder {
+    fn new(inputs: usize) -> Self {
+        Self {
+            inputs,
+            nodes: (0..inputs)
+                .map(|id| Node::new(id, activations::LINEAR))
+                .collect(),
+            edges: Vec::new(),
+            layer: (0..inputs).collect(),
+            pending: None,
+        }
+    }
+
+    fn pending(&mut self) -> &mut (DMatrix<f32>, DVector<f32>) {
+        let width = self.layer.len();
+        self.pending
+            .get_or_insert_with(|| (DMatrix::identity(width, width), DVector::zeros(width)))
+    }
+
+    fn multiply(&mut self, weights: &DMatrix<f32>) -> Result<(), &'static str> {
+        let (pending_weights, pending_biases) = self.pending();
+        if pending_weights.ncols() != weights.nrows() {
+            return Err("weight dimensions do not match");
+        }
+        *pending_weights = &*pending_weights * weights;
+        *pending_biases = weights.tr_mul(pending_biases);
+        Ok(())
+    }
+
+    fn add(&mut self, biases: &DVector<f32>) -> Result<(), &'static str> {
+        let (_, pending_biases) = self.pending();
+        match biases.len() {
+            1 => pending_biases.add_scalar_mut(biases[0]),
+            len if len == pending_biases.len() => *pending_biases += biases,
+            _ => return Err("bias dimensions do not match"),
+        }
+        Ok(())
+    }
+
+    fn scale(&mut self, factor: f32) {
+        let (pending_weights, pending_biases) = self.pending();
+        *pending_weights *= factor;
+        *pending_biases *= factor;
+    }
+
+    // `steepness` compensates activations that are steeper than their ONNX counterparts
+    fn activate(&mut self, activation: fn(f32) -> f32, steepness: f32) {
+        let (weights, biases) = self.pending.take().unwrap_or_else(|| {
+            let width = self.layer.len();
+            (DMatrix::identity(width, width), DVector::zeros(width))
+        });
+
+        let mut layer = Vec::with_capacity(weights.ncols());
+        for (column, weights) in weights.column_iter().enumerate() {
+            let id = self.nodes.len();
+            self.nodes
+                .push(Node::new(id, activation).with_bias(biases[column] / steepness));
+            for (row, &weight) in weights.iter().enumerate() {
+                if weight != 0.0 {
+                    self.edges
+                        .push(Edge::new(self.layer[row], id, weight / steepness));
+                }
+            }
+            layer.push(id);
+        }
+        self.layer = layer;
+    }
+
+    fn finish(mut self) -> Result<Net, &'static str> {
+        if self.pending.is_some() {
+            self.activate(activations::LINEAR, 1.0);
+        }
+        if self.nodes.len() == self.inputs {
+            return Err("onnx graph without layers");
+        }
+        Ok(Net::new(
+            self.inputs,
+            self.layer.len(),
+            self.nodes,
+            self.edges,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+    use prost::Message;
+
+    use super::from_onnx;
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Net, Node},
+            Evaluator, Fabricator, NetworkLike,
+        },
+        onnx::{
+            proto::{
+                AttributeProto, GraphProto, ModelProto, NodeProto, TensorProto, ValueInfoProto,
+            },
+            to_onnx,
+        },
+    };
+
+    fn node(op_type: &str, input: &[&str], output: &str) -> NodeProto {
+        NodeProto {
+            input: input.iter().map(|&input| input.to_owned()).collect(),
+            output: vec![output.to_owned()],
+            name: output.to_owned(),
+            op_type: op_type.to_owned(),
+            attribute: Vec::new(),
+        }
+    }
+
+    fn tensor(name: &str, dims: Vec<i64>, data: &[f32]) -> TensorProto {
+        TensorProto {
+            dims,
+            data_type: 1,
+            raw_data: data.iter().flat_map(|value| value.to_le_bytes()).collect(),
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn value(name: &str) -> ValueInfoProto {
+        ValueInfoProto {
+            name: name.to_owned(),
+            r#type: None,
+        }
+    }
+
+    #[test]
+    fn onnx_round_trip() {
+        let some_net = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::SIGMOID).with_bias(0.5),
+                Node::new(3, activations::SIGMOID),
+                Node::new(4, activations::TANH).with_bias(-0.25),
+            ],
+            edges!(
+                0--0.5->2,
+                1--1.5->2,
+                0--0.75->3,
+                1--0.25->3,
+                2--1.0->4,
+                3--0.5->4
+            ),
+        );
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let imported = from_onnx(&to_onnx(&evaluator).unwrap()).unwrap();
+        let imported_evaluator = MatrixFeedforwardFabricator::fabricate(&imported).unwrap();
+
+        assert_eq!(imported.nodes().len(), 5);
+
+        let input = dmatrix![
+            1.0, 2.0;
+            -0.5, 0.25
+        ];
+        let expected = evaluator.evaluate(input.clone());
+        let actual = imported_evaluator.evaluate(input);
+
+        for (expected, actual) in expected.iter().zip(actual.iter()) {
+            assert!((expected - actual).abs() < 1e-6);
+        }
+    }
+
+    // mimics a typical export of a linear layer followed by the standard sigmoid
+    #[test]
+    fn onnx_import_gemm() {
+        let mut gemm = node("Gemm", &["x", "weights", "biases"], "hidden");
+        gemm.attribute.push(AttributeProto {
+            name: "transB".to_owned(),
+            i: 1,
+            r#type: 2,
+            ..Default::default()
+        });
+
+        let model = ModelProto {
+            ir_version: 7,
+            graph: Some(GraphProto {
+                node: vec![gemm, node("Sigmoid", &["hidden"], "y")],
+                name: "mlp".to_owned(),
+                // stored as [outputs, inputs] because of transB
+                initializer: vec![
+                    tensor("weights", vec![1, 2], &[2.0, -1.0]),
+                    tensor("biases", vec![1], &[0.5]),
+                ],
+                input: vec![value("x")],
+                output: vec![value("y")],
+            }),
+            ..Default::default()
+        };
+
+        let net = from_onnx(&model.encode_to_vec()).unwrap();
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+
+        let expected = 1.0 / (1.0 + (-(2.0 * 1.0 - 1.0 * 3.0 + 0.5f32)).exp());
+        let actual = evaluator.evaluate(dmatrix![1.0, 3.0])[0];
+
+        assert!((expected - actual).abs() < 1e-6);
+        assert!(from_onnx(b"not a model").is_err());
+    }
+}
@@ -0,0 +1,9 @@
+//! Converts fabricated networks to the ONNX format and simple ONNX models to [`crate::network::net::Net`], available with the feature `onnx`.
+//!
+//! Models are encoded as `ModelProto` bytes, ready to be written to an `.onnx` file.
+
+mod export;
+mod import;
+mod proto;
+
+pub use self::{export::to_onnx, import::from_onnx};
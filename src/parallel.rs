@@ -0,0 +1,117 @@
+//! Evaluation of many fabricated networks in parallel, e.g. a whole population per generation.
+//!
+//! Available with the feature `parallel`.
+
+use rayon::prelude::*;
+
+use crate::network::{Evaluator, NetworkIO, Scalar, StatefulEvaluator};
+
+/// Evaluates every evaluator with the same input in parallel.
+///
+/// The outputs are in the order of the evaluators.
+pub fn evaluate<E, T, S>(evaluators: &[E], input: &T) -> Vec<T>
+where
+    E: Evaluator<S> + Sync,
+    T: NetworkIO<S> + Clone + Send + Sync,
+    S: Scalar,
+{
+    evaluators
+        .par_iter()
+        .map(|evaluator| evaluator.evaluate(input.clone()))
+        .collect()
+}
+
+/// Evaluates every evaluator with its corresponding input in parallel.
+///
+/// Evaluators and inputs are paired by position, surplus evaluators or inputs are ignored.
+pub fn evaluate_pairwise<E, T, S>(evaluators: &[E], inputs: Vec<T>) -> Vec<T>
+where
+    E: Evaluator<S> + Sync,
+    T: NetworkIO<S> + Send,
+    S: Scalar,
+{
+    evaluators
+        .par_iter()
+        .zip(inputs)
+        .map(|(evaluator, input)| evaluator.evaluate(input))
+        .collect()
+}
+
+/// Evaluates every stateful evaluator with its corresponding input in parallel.
+///
+/// Evaluators and inputs are paired by position, surplus evaluators or inputs are ignored.
+pub fn evaluate_stateful_pairwise<E, T, S>(evaluators: &mut [E], inputs: Vec<T>) -> Vec<T>
+where
+    E: StatefulEvaluator<S> + Send,
+    T: NetworkIO<S> + Send,
+    S: Scalar,
+{
+    evaluators
+        .par_iter_mut()
+        .zip(inputs)
+        .map(|(evaluator, input)| evaluator.evaluate(input))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use crate::{
+        edges,
+        matrix::{
+            feedforward::fabricator::MatrixFeedforwardFabricator,
+            recurrent::fabricator::MatrixRecurrentFabricator,
+        },
+        network::{
+            net::{Edge, Net},
+            Fabricator, StatefulFabricator,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn evaluate_population() {
+        let evaluators = (1..=4)
+            .map(|weight| {
+                MatrixFeedforwardFabricator::fabricate(&Net::new(
+                    1,
+                    1,
+                    nodes!('l', 'l'),
+                    vec![Edge::new(0, 1, weight as f32)],
+                ))
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            super::evaluate(&evaluators, &dmatrix![2.0]),
+            vec![dmatrix![2.0], dmatrix![4.0], dmatrix![6.0], dmatrix![8.0]]
+        );
+
+        assert_eq!(
+            super::evaluate_pairwise(&evaluators, vec![vec![1.0], vec![1.0], vec![0.5]]),
+            vec![vec![1.0], vec![2.0], vec![1.5]]
+        );
+    }
+
+    #[test]
+    fn evaluate_stateful_population() {
+        let mut some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1));
+        some_net.set_recurrent_edges(edges!(1--1.0->1));
+
+        let mut evaluators = vec![
+            MatrixRecurrentFabricator::fabricate(&some_net).unwrap(),
+            MatrixRecurrentFabricator::fabricate(&some_net).unwrap(),
+        ];
+
+        assert_eq!(
+            super::evaluate_stateful_pairwise(&mut evaluators, vec![vec![1.0], vec![2.0]]),
+            vec![vec![1.0], vec![2.0]]
+        );
+        assert_eq!(
+            super::evaluate_stateful_pairwise(&mut evaluators, vec![vec![1.0], vec![2.0]]),
+            vec![vec![2.0], vec![4.0]]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Python bindings via `pyo3`, available with the feature `python`.
+//!
+//! Build the extension module with `cargo rustc --lib --release --features python --crate-type cdylib`
+//! and rename the resulting library to `favannat.so` (`favannat.pyd` on Windows).
+//!
+//! ```python
+//! import numpy as np
+//! import favannat
+//!
+//! net = favannat.Net(2, 1, [(0, "linear"), (1, "linear"), (2, "sigmoid", 0.5)], [(0, 2, 0.5), (1, 2, -1.0)])
+//! evaluator = favannat.fabricate(net)
+//! evaluator.evaluate(np.array([[1.0, 2.0]], dtype=np.float32))
+//! ```
+
+use nalgebra::DMatrix;
+use numpy::{
+    ndarray::{Array2, ArrayView2},
+    IntoPyArray, PyArray2, PyReadonlyArray2,
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::{
+    matrix::feedforward::{
+        evaluator::MatrixFeedforwardEvaluator, fabricator::MatrixFeedforwardFabricator,
+    },
+    network::{
+        net::{activations, Edge, Net, Node},
+        Evaluator, Fabricator,
+    },
+};
+
+/// A [`Net`] built from Python, nodes are given as `(id, activation)` or `(id, activation, bias)` tuples
+/// with activations named like in [`activations`] and edges as `(start, end, weight)` tuples.
+#[pyclass(name = "Net", module = "favannat")]
+pub struct PyNet {
+    net: Net,
+}
+
+#[pymethods]
+impl PyNet {
+    #[new]
+    fn new(
+        inputs: usize,
+        outputs: usize,
+        nodes: Vec<Bound<'_, PyAny>>,
+        edges: Vec<(usize, usize, f32)>,
+    ) -> PyResult<Self> {
+        let nodes = nodes
+            .iter()
+            .map(|node| {
+                node.extract::<(usize, String, f32)>().or_else(|_| {
+                    node.extract::<(usize, String)>()
+                        .map(|(id, activation)| (id, activation, 0.0))
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        Ok(Self {
+            net: to_net(inputs, outputs, nodes, edges)?,
+        })
+    }
+}
+
+/// Builds a [`Net`] from the extracted node and edge tuples of [`PyNet::new`].
+fn to_net(
+    inputs: usize,
+    outputs: usize,
+    nodes: Vec<(usize, String, f32)>,
+    edges: Vec<(usize, usize, f32)>,
+) -> PyResult<Net> {
+    let nodes = nodes
+        .into_iter()
+        .map(|(id, activation, bias)| {
+            let activation = activations::from_name(&activation).ok_or_else(|| {
+                PyValueError::new_err(format!("unknown activation `{}`", activation))
+            })?;
+            Ok(Node::new(id, activation).with_bias(bias))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+
+    if inputs + outputs > nodes.len() {
+        return Err(PyValueError::new_err("more inputs and outputs than nodes"));
+    }
+
+    let edges = edges
+        .into_iter()
+        .map(|(start, end, weight)| Edge::new(start, end, weight))
+        .collect();
+
+    Ok(Net::new(inputs, outputs, nodes, edges))
+}
+
+/// Copies a row major numpy array into a [`DMatrix`], which stores its values column major.
+fn to_matrix(array: ArrayView2<'_, f32>) -> DMatrix<f32> {
+    DMatrix::from_fn(array.nrows(), array.ncols(), |row, column| {
+        array[(row, column)]
+    })
+}
+
+/// Copies a [`DMatrix`] into an array convertible to numpy, the inverse of [`to_matrix`].
+fn to_array(matrix: &DMatrix<f32>) -> Array2<f32> {
+    Array2::from_shape_fn((matrix.nrows(), matrix.ncols()), |index| matrix[index])
+}
+
+/// A [`MatrixFeedforwardEvaluator`] taking and returning 2D `float32` numpy arrays, one sample per row.
+#[pyclass(name = "Evaluator", module = "favannat")]
+pub struct PyEvaluator {
+    evaluator: MatrixFeedforwardEvaluator,
+}
+
+#[pymethods]
+impl PyEvaluator {
+    fn evaluate<'py>(
+        &self,
+        py: Python<'py>,
+        input: PyReadonlyArray2<'py, f32>,
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let input = input.as_array();
+        let expected = self.evaluator.input_size();
+        if input.ncols() != expected {
+            return Err(PyValueError::new_err(format!(
+                "expected {} columns, got {}",
+                expected,
+                input.ncols()
+            )));
+        }
+
+        let input = to_matrix(input);
+        let output = py.detach(|| self.evaluator.evaluate(input));
+
+        Ok(to_array(&output).into_pyarray(py))
+    }
+}
+
+/// Fabricates a [`PyNet`] with the [`MatrixFeedforwardFabricator`].
+#[pyfunction]
+fn fabricate(net: &PyNet) -> PyResult<PyEvaluator> {
+    MatrixFeedforwardFabricator::fabricate(&net.net)
+        .map(|evaluator| PyEvaluator { evaluator })
+        .map_err(|error| PyValueError::new_err(error.to_string()))
+}
+
+#[pymodule]
+fn favannat(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyNet>()?;
+    module.add_class::<PyEvaluator>()?;
+    module.add_function(wrap_pyfunction!(fabricate, module)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+    use numpy::ndarray::array;
+
+    use super::{to_array, to_matrix, to_net};
+    use crate::{
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{Evaluator, Fabricator, NetworkLike, NodeLike},
+    };
+
+    #[test]
+    fn convert_net() {
+        let net = to_net(
+            2,
+            1,
+            vec![
+                (0, "linear".into(), 0.0),
+                (1, "linear".into(), 0.0),
+                (2, "relu".into(), 0.5),
+            ],
+            vec![(0, 2, 0.5), (1, 2, -1.0)],
+        )
+        .unwrap();
+        assert_eq!(net.outputs()[0].bias(), 0.5);
+        assert_eq!(net.edges().len(), 2);
+
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![2.0, 1.0]), dmatrix![0.5]);
+
+        assert!(to_net(
+            1,
+            1,
+            vec![(0, "linear".into(), 0.0), (1, "nope".into(), 0.0)],
+            vec![]
+        )
+        .is_err());
+        assert!(to_net(1, 1, vec![(0, "linear".into(), 0.0)], vec![]).is_err());
+    }
+
+    #[test]
+    fn convert_arrays() {
+        // rows of the numpy array stay samples although nalgebra stores columns contiguously
+        let array = array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        let matrix = to_matrix(array.view());
+        assert_eq!(matrix, dmatrix![1.0, 2.0, 3.0; 4.0, 5.0, 6.0]);
+        assert_eq!(to_array(&matrix), array);
+    }
+}
@@ -0,0 +1,262 @@
+use alloc::vec::Vec;
+
+use nalgebra::DMatrix;
+
+use crate::{
+    matrix::feedforward::evaluator::MatrixFeedforwardEvaluator,
+    network::{net::activations, Cost, EvaluateInto, Evaluator, NetworkIO, Scratch},
+};
+
+/// Pre-activations outside of `[-LUT_RANGE, LUT_RANGE]` are clamped before the table lookup.
+pub const LUT_RANGE: f32 = 8.0;
+/// Number of entries of every lookup table.
+pub const LUT_SIZE: usize = 256;
+
+/// Applies an activation either directly or by interpolating a lookup table.
+#[derive(Debug, Clone)]
+pub enum QuantizedActivation {
+    Linear,
+    Table(Vec<f32>),
+    Direct(activations::Activation),
+}
+
+impl QuantizedActivation {
+    /// Tabulates [`activations::SIGMOID`], [`activations::TANH`] and [`activations::GAUSSIAN`], which saturate within [`LUT_RANGE`].
+    pub fn new(activation: &activations::Activation) -> Self {
+        match activation.name() {
+            Some("linear") => Self::Linear,
+            Some("sigmoid" | "tanh" | "gaussian") => Self::Table(
+                (0..LUT_SIZE)
+                    .map(|index| {
+                        activation.apply(
+                            index as f32 / (LUT_SIZE - 1) as f32 * 2.0 * LUT_RANGE - LUT_RANGE,
+                        )
+                    })
+                    .collect(),
+            ),
+            _ => Self::Direct(activation.clone()),
+        }
+    }
+
+    pub fn apply(&self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Table(table) => {
+                let position = (value.clamp(-LUT_RANGE, LUT_RANGE) + LUT_RANGE) / (2.0 * LUT_RANGE)
+                    * (LUT_SIZE - 1) as f32;
+                // interpolate linearly between the neighbouring entries
+                let index = (position as usize).min(LUT_SIZE - 2);
+                let fraction = position - index as f32;
+                table[index] * (1.0 - fraction) + table[index + 1] * fraction
+            }
+            Self::Direct(activation) => activation.apply(value),
+        }
+    }
+}
+
+/// A stage with its weights quantized column by column, `weights[column * rows + row]` approximates `weight / scale`.
+#[derive(Debug, Clone)]
+pub struct QuantizedStage {
+    pub rows: usize,
+    pub columns: usize,
+    pub weights: Vec<i8>,
+    pub scale: f32,
+    pub biases: Vec<f32>,
+    pub activations: Vec<QuantizedActivation>,
+}
+
+/// Evaluates a quantized [`MatrixFeedforwardEvaluator`] with `i8` weights and `i32` accumulation.
+///
+/// The input is interpreted row-wise like in [`MatrixFeedforwardEvaluator`].
+#[derive(Debug, Clone)]
+pub struct QuantizedFeedforwardEvaluator {
+    pub stages: Vec<QuantizedStage>,
+    /// Range every biased sum is clamped into, see [`MatrixFeedforwardEvaluator::with_clamp`].
+    pub clamp: Option<(f32, f32)>,
+}
+
+fn quantize(value: f32, scale: f32) -> i8 {
+    nalgebra::ComplexField::round(value / scale).clamp(-127.0, 127.0) as i8
+}
+
+/// Scale mapping the largest absolute value to 127.
+fn scale<'a>(values: impl Iterator<Item = &'a f32>) -> f32 {
+    let max = values.fold(0.0_f32, |max, value| max.max(value.abs()));
+    if max > 0.0 {
+        max / 127.0
+    } else {
+        1.0
+    }
+}
+
+impl QuantizedFeedforwardEvaluator {
+    /// Quantizes every stage, nodes that do not sum their inputs, gathered carries and compensated summation are not supported.
+    pub fn quantize(evaluator: &MatrixFeedforwardEvaluator<f32>) -> Result<Self, &'static str> {
+        if !evaluator.gathers.is_empty() {
+            return Err("quantized evaluation needs full stage matrices, not gathered carries");
+        }
+        if evaluator.compensated {
+            return Err("quantized evaluation does not support compensated summation");
+        }
+        if evaluator
+            .aggregations
+            .iter()
+            .any(|aggregations| !aggregations.is_empty())
+        {
+            return Err("quantized evaluation only supports summing nodes");
+        }
+
+        let stages = evaluator
+            .stages
+            .iter()
+            .zip(&evaluator.biases)
+            .zip(&evaluator.transformations)
+            .map(|((stage, biases), transformations)| {
+                let scale = scale(stage.iter());
+                QuantizedStage {
+                    rows: stage.nrows(),
+                    columns: stage.ncols(),
+                    // nalgebra stores column-major, so every column is contiguous
+                    weights: stage
+                        .iter()
+                        .map(|&weight| quantize(weight, scale))
+                        .collect(),
+                    scale,
+                    biases: biases.iter().copied().collect(),
+                    activations: transformations
+                        .iter()
+                        .map(QuantizedActivation::new)
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            stages,
+            clamp: evaluator.clamp,
+        })
+    }
+
+    fn clamped(&self, value: f32) -> f32 {
+        match self.clamp {
+            Some((min, max)) => value.clamp(min, max),
+            None => value,
+        }
+    }
+
+    /// Bytes taken by the quantized weights of all stages.
+    pub fn weight_bytes(&self) -> usize {
+        self.stages.iter().map(|stage| stage.weights.len()).sum()
+    }
+
+    /// Estimates the cost of evaluating a single sample, the scales of the stages are not counted as matrix bytes.
+    pub fn cost(&self) -> Cost {
+        Cost {
+            multiply_adds: self.stages.iter().map(|stage| stage.weights.len()).sum(),
+            stages: self.stages.len(),
+            matrix_bytes: self.weight_bytes()
+                + self
+                    .stages
+                    .iter()
+                    .map(|stage| stage.biases.len())
+                    .sum::<usize>()
+                    * core::mem::size_of::<f32>(),
+        }
+    }
+
+    /// Number of values every sample provides as input.
+    pub fn input_size(&self) -> usize {
+        self.stages.first().map_or(0, |stage| stage.rows)
+    }
+
+    /// Number of values evaluated for every sample.
+    pub fn output_size(&self) -> usize {
+        self.stages.last().map_or(0, |stage| stage.columns)
+    }
+
+    pub fn stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Dimensions of every stage as `(rows, columns)`, i.e. the number of values entering and leaving it.
+    pub fn stage_dimensions(&self) -> Vec<(usize, usize)> {
+        self.stages
+            .iter()
+            .map(|stage| (stage.rows, stage.columns))
+            .collect()
+    }
+}
+
+impl Evaluator for QuantizedFeedforwardEvaluator {
+    fn evaluate<T: NetworkIO>(&self, input: T) -> T {
+        let mut state = NetworkIO::input(input);
+        let mut quantized = Vec::new();
+
+        for stage in &self.stages {
+            let mut next = DMatrix::zeros(state.nrows(), stage.columns);
+            for sample in 0..state.nrows() {
+                let row = state.row(sample);
+                let state_scale = scale(row.iter());
+                quantized.clear();
+                quantized.extend(row.iter().map(|&value| quantize(value, state_scale)));
+
+                for column in 0..stage.columns {
+                    let weights = &stage.weights[column * stage.rows..(column + 1) * stage.rows];
+                    let sum: i32 = quantized
+                        .iter()
+                        .zip(weights)
+                        .map(|(&value, &weight)| value as i32 * weight as i32)
+                        .sum();
+                    next[(sample, column)] = stage.activations[column].apply(
+                        self.clamped(sum as f32 * state_scale * stage.scale + stage.biases[column]),
+                    );
+                }
+            }
+            state = next;
+        }
+
+        NetworkIO::output(state)
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
+
+impl EvaluateInto for QuantizedFeedforwardEvaluator {
+    fn scratch(&self) -> Scratch {
+        Scratch::new(
+            self.stage_dimensions()
+                .into_iter()
+                .map(|(rows, columns)| rows.max(columns))
+                .max()
+                .unwrap_or(0),
+        )
+    }
+
+    /// Quantizes the state while multiplying, instead of buffering it like [`Evaluator::evaluate`] does.
+    fn evaluate_into(&self, input: &[f32], output: &mut [f32], scratch: &mut Scratch) {
+        scratch.current[..self.input_size()].copy_from_slice(input);
+        for stage in &self.stages {
+            let state = &scratch.current[..stage.rows];
+            let state_scale = scale(state.iter());
+            for (column, value) in scratch.next[..stage.columns].iter_mut().enumerate() {
+                let weights = &stage.weights[column * stage.rows..(column + 1) * stage.rows];
+                let sum: i32 = state
+                    .iter()
+                    .zip(weights)
+                    .map(|(&value, &weight)| quantize(value, state_scale) as i32 * weight as i32)
+                    .sum();
+                *value = stage.activations[column].apply(
+                    self.clamped(sum as f32 * state_scale * stage.scale + stage.biases[column]),
+                );
+            }
+            core::mem::swap(&mut scratch.current, &mut scratch.next);
+        }
+        output.copy_from_slice(&scratch.current[..self.output_size()]);
+    }
+}
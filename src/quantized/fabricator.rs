@@ -0,0 +1,118 @@
+use crate::{
+    matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+    network::{EdgeLike, FabricationError, Fabricator, NetworkLike, NodeId, NodeLike},
+};
+
+use super::evaluator::QuantizedFeedforwardEvaluator;
+
+/// Fabricates a [`QuantizedFeedforwardEvaluator`] by quantizing the result of the [`MatrixFeedforwardFabricator`].
+#[derive(Debug)]
+pub struct QuantizedFeedforwardFabricator;
+
+impl<N, E, I> Fabricator<N, E, f32, I> for QuantizedFeedforwardFabricator
+where
+    N: NodeLike<f32, I>,
+    E: EdgeLike<f32, I>,
+    I: NodeId,
+{
+    type Output = QuantizedFeedforwardEvaluator;
+
+    fn fabricate(net: &impl NetworkLike<N, E, f32, I>) -> Result<Self::Output, FabricationError> {
+        Ok(QuantizedFeedforwardEvaluator::quantize(
+            &MatrixFeedforwardFabricator::fabricate(net)?,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::dmatrix;
+
+    use super::QuantizedFeedforwardFabricator;
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Net, Node},
+            Aggregation, Evaluator, Fabricator,
+        },
+        nodes,
+        quantized::evaluator::QuantizedFeedforwardEvaluator,
+    };
+
+    #[test]
+    fn quantized_close_to_float() {
+        let some_net = Net::new(
+            2,
+            2,
+            nodes!('l', 'l', 's', 't', 'l', 's'),
+            edges!(
+                0--0.3->2,
+                1--0.7->2,
+                0--1.5->3,
+                1--0.2->3,
+                2--0.9->4,
+                3--0.4->4,
+                2--1.1->5,
+                3--0.6->5
+            ),
+        );
+
+        let quantized = QuantizedFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let float = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let input = dmatrix![0.5, 1.0; -1.0, 0.25; 2.0, -0.5];
+        let difference = quantized.evaluate(input.clone()) - float.evaluate(input);
+        assert!(difference.amax() < 0.05);
+    }
+
+    #[test]
+    fn unsupported_aggregation() {
+        let some_net = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::LINEAR).with_aggregation(Aggregation::Max),
+            ],
+            edges!(0--1.0->2, 1--1.0->2),
+        );
+        assert!(QuantizedFeedforwardFabricator::fabricate(&some_net).is_err());
+    }
+
+    #[test]
+    fn unsupported_gathered_carries() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(0--1.0->2, 2--1.0->3, 1--1.0->3),
+        );
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        assert!(QuantizedFeedforwardEvaluator::quantize(&evaluator).is_ok());
+        assert!(
+            QuantizedFeedforwardEvaluator::quantize(&evaluator.with_gathered_carries()).is_err()
+        );
+    }
+
+    #[test]
+    fn quantized_clamp() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--2.0->1));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_clamp(-1.0, 1.0)
+            .unwrap();
+
+        let quantized = QuantizedFeedforwardEvaluator::quantize(&evaluator).unwrap();
+        assert_eq!(
+            quantized.evaluate(dmatrix![2.0; -0.25]),
+            dmatrix![1.0; -0.5]
+        );
+
+        assert!(
+            QuantizedFeedforwardEvaluator::quantize(&evaluator.with_compensated_summation())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,9 @@
+//! Evaluates [`crate::network::NetworkLike`] structures with 8 bit integer arithmetic.
+//!
+//! Stage matrices of a [`crate::matrix::feedforward::evaluator::MatrixFeedforwardEvaluator`] are quantized to `i8` with one scale per stage,
+//! the state is quantized per sample before every stage and accumulated in `i32`.
+//! Saturating activations are read from lookup tables, trading precision for memory and speed on embedded targets.
+
+pub mod evaluator;
+#[cfg(feature = "std")]
+pub mod fabricator;
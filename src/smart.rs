@@ -0,0 +1,198 @@
+//! Picks the backend evaluating a [`NetworkLike`] structure from its size and density, see [`SmartFabricator`].
+
+use crate::{
+    interpreter::fabricator::LoopFabricator,
+    matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+    network::{
+        prune::active_edges, remap::fabricable, DynEvaluator, EdgeLike, FabricationError,
+        Fabricator, NetworkLike, NodeId, NodeLike, Scalar,
+    },
+    sparse_matrix::feedforward::fabricator::SparseMatrixFeedforwardFabricator,
+};
+
+/// Nets with at most this many nodes are evaluated by the loop backend, matrices do not pay off for them.
+pub const LOOP_NODES: usize = 32;
+
+/// Larger nets having at most this share of all possible edges are evaluated by the sparse matrix backend.
+pub const SPARSE_DENSITY: f64 = 0.1;
+
+/// Backends [`SmartFabricator`] chooses from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// [`LoopFabricator`]
+    Loop,
+    /// [`MatrixFeedforwardFabricator`]
+    Dense,
+    /// [`SparseMatrixFeedforwardFabricator`]
+    Sparse,
+}
+
+/// Fabricates a boxed evaluator of the backend [`SmartFabricator::backend`] chooses, so topologies need not be benchmarked one by one.
+///
+/// Small nets are evaluated by the loop backend, larger ones by the dense or sparse matrix backend depending on their density.
+/// The density is the share of active edges among all edges from a non-output to a non-input node.
+#[derive(Debug)]
+pub struct SmartFabricator;
+
+impl SmartFabricator {
+    pub fn backend<N, E, S>(net: &impl NetworkLike<N, E, S>) -> Backend
+    where
+        N: NodeLike<S>,
+        E: EdgeLike<S>,
+        S: Scalar,
+    {
+        let nodes = net.nodes();
+        if nodes.len() <= LOOP_NODES {
+            return Backend::Loop;
+        }
+        let possible =
+            (nodes.len() - net.outputs_iter().count()) * (nodes.len() - net.inputs_iter().count());
+        let edges = active_edges(&nodes, net.edges()).len();
+        if edges as f64 <= SPARSE_DENSITY * possible.max(1) as f64 {
+            Backend::Sparse
+        } else {
+            Backend::Dense
+        }
+    }
+}
+
+impl<N, E, S, I> Fabricator<N, E, S, I> for SmartFabricator
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    type Output = Box<dyn DynEvaluator<S>>;
+
+    fn fabricate(net: &impl NetworkLike<N, E, S, I>) -> Result<Self::Output, FabricationError> {
+        let net = &fabricable(net);
+        Ok(match Self::backend(net) {
+            Backend::Loop => Box::new(LoopFabricator::fabricate(net)?),
+            Backend::Dense => Box::new(MatrixFeedforwardFabricator::fabricate(net)?),
+            Backend::Sparse => Box::new(SparseMatrixFeedforwardFabricator::fabricate(net)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::DMatrix;
+
+    use super::{Backend, SmartFabricator, LOOP_NODES, SPARSE_DENSITY};
+    use crate::{
+        edges,
+        interpreter::fabricator::LoopFabricator,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{
+            net::{activations, Edge, Net, Node},
+            Evaluator, Fabricator, NetworkLike,
+        },
+        nodes,
+        sparse_matrix::feedforward::fabricator::SparseMatrixFeedforwardFabricator,
+        test_util::random_net,
+        verify::equivalent,
+    };
+
+    // `width` inputs, hidden nodes and outputs, each layer connected to the next by edges from every node to `fan_in` nodes
+    fn layered(width: usize, fan_in: usize) -> Net {
+        let nodes = (0..3 * width)
+            .map(|id| Node::new(id, activations::sigmoid))
+            .collect();
+        let mut edges = Vec::new();
+        for layer in 0..2 {
+            for start in 0..width {
+                for offset in 0..fan_in {
+                    let end = (layer + 1) * width + (start + offset) % width;
+                    edges.push(Edge::new(layer * width + start, end, 0.1 * offset as f32));
+                }
+            }
+        }
+        Net::new(width, width, nodes, edges)
+    }
+
+    // the first `count` edges from lower to higher ids, never starting at outputs or ending at inputs
+    fn counted(inputs: usize, hidden: usize, outputs: usize, count: usize) -> Net {
+        let nodes = inputs + hidden + outputs;
+        let edges = (0..inputs + hidden)
+            .flat_map(|start| (inputs.max(start + 1)..nodes).map(move |end| (start, end)))
+            .take(count)
+            .map(|(start, end)| Edge::new(start, end, 1.0))
+            .collect::<Vec<_>>();
+        assert_eq!(edges.len(), count);
+        Net::new(
+            inputs,
+            outputs,
+            (0..nodes)
+                .map(|id| Node::new(id, activations::LINEAR))
+                .collect(),
+            edges,
+        )
+    }
+
+    #[test]
+    fn choose_backend() {
+        let small = Net::new(2, 1, nodes!('l', 'l', 's'), edges!(0--0.5->2, 1--0.5->2));
+        assert_eq!(SmartFabricator::backend(&small), Backend::Loop);
+
+        let dense = layered(20, 10);
+        assert_eq!(SmartFabricator::backend(&dense), Backend::Dense);
+        let sparse = layered(20, 2);
+        assert_eq!(SmartFabricator::backend(&sparse), Backend::Sparse);
+
+        let input = DMatrix::from_fn(2, 20, |sample, column| (sample + column) as f32 * 0.1);
+        for net in [small, dense, sparse] {
+            let input = input.columns(0, net.inputs().len()).into_owned();
+            let expected = MatrixFeedforwardFabricator::fabricate(&net)
+                .unwrap()
+                .evaluate(input.clone());
+            let evaluator = SmartFabricator::fabricate(&net).unwrap();
+            assert!((evaluator.evaluate(input) - expected).amax() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn backend_thresholds() {
+        // fully connected nets are still small enough for the loop backend
+        assert_eq!(
+            SmartFabricator::backend(&counted(8, 16, 8, 400)),
+            Backend::Loop
+        );
+        assert_eq!(LOOP_NODES, 8 + 16 + 8);
+        assert_eq!(
+            SmartFabricator::backend(&counted(8, 17, 8, 400)),
+            Backend::Dense
+        );
+
+        // 30 non-outputs times 30 non-inputs possible edges
+        let threshold = (SPARSE_DENSITY * 900.0) as usize;
+        assert_eq!(threshold, 90);
+        assert_eq!(
+            SmartFabricator::backend(&counted(10, 20, 10, threshold)),
+            Backend::Sparse
+        );
+        assert_eq!(
+            SmartFabricator::backend(&counted(10, 20, 10, threshold + 1)),
+            Backend::Dense
+        );
+    }
+
+    #[test]
+    fn backends_agree() {
+        for (net, backend) in [
+            (random_net(4, 2, 10, 0.5, 0.0, 1), Backend::Loop),
+            (random_net(8, 4, 30, 0.5, 0.0, 2), Backend::Dense),
+            (random_net(8, 4, 40, 0.02, 0.0, 3), Backend::Sparse),
+        ] {
+            assert_eq!(SmartFabricator::backend(&net), backend);
+
+            let reference = LoopFabricator::fabricate(&net).unwrap();
+            let dense = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+            let sparse = SparseMatrixFeedforwardFabricator::fabricate(&net).unwrap();
+            let smart = SmartFabricator::fabricate(&net).unwrap();
+            assert_eq!(equivalent(&reference, &dense, 16, 1e-5), Ok(None));
+            assert_eq!(equivalent(&reference, &sparse, 16, 1e-5), Ok(None));
+            assert_eq!(equivalent(&reference, &smart, 16, 1e-5), Ok(None));
+        }
+    }
+}
@@ -1,39 +1,215 @@
+use std::fmt;
+
 use nalgebra::DMatrix;
-use nalgebra_sparse::{CscMatrix, SparseEntry, SparseEntryMut};
+use nalgebra_sparse::CsrMatrix;
 
-use crate::network::{Evaluator, NetworkIO};
+use crate::network::{Cost, EvaluateInto, Evaluator, NetworkIO, Scalar, Scratch};
 
+/// Evaluates a fabricated [`crate::network::NetworkLike`] structure by a sequence of sparse matrix vector products.
+///
+/// Every stage is stored transposed in compressed sparse rows, row `j` holds the incoming weights of column `j`.
+/// Evaluating a stage therefore takes time in the number of stored weights, which pays off for large nets with few edges per node.
+/// Like the dense evaluators, every row of the input is a separate sample.
 #[derive(Debug)]
-pub struct SparseMatrixFeedforwardEvaluator {
-    pub stages: Vec<CscMatrix<f32>>,
-    pub transformations: Vec<crate::Transformations>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SparseMatrixFeedforwardEvaluator<S: Scalar = f32> {
+    pub stages: Vec<CsrMatrix<S>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::network::serialization"))]
+    pub transformations: Vec<crate::Transformations<S>>,
+    pub biases: Vec<DMatrix<S>>,
+    pub aggregations: Vec<crate::Aggregations<S>>,
 }
 
-impl Evaluator for SparseMatrixFeedforwardEvaluator {
-    fn evaluate<T: NetworkIO>(&self, state: T) -> T {
-        let state = NetworkIO::input(state);
-        let mut len = 0;
-        let mut state: CscMatrix<f32> = (&state).into();
-        // performs evaluation by sequentially matrix multiplying and transforming the state with every stage
-        for (stage_matrix, transformations) in self.stages.iter().zip(&self.transformations) {
-            len = transformations.len();
-            state = state * stage_matrix;
-            for (index, activation) in transformations.iter().enumerate() {
-                if let SparseEntryMut::NonZero(value) = state.index_entry_mut(0, index) {
-                    *value = activation(*value);
-                }
+impl<S: Scalar> SparseMatrixFeedforwardEvaluator<S> {
+    /// Estimates the cost of evaluating a single sample, only stored entries are multiplied.
+    ///
+    /// Matrix bytes include the column indices and row offsets of the compressed rows.
+    pub fn cost(&self) -> Cost {
+        Cost {
+            multiply_adds: self.stages.iter().map(|stage| stage.nnz()).sum::<usize>()
+                + Cost::aggregated_inputs(&self.aggregations),
+            stages: self.stages.len(),
+            matrix_bytes: self
+                .stages
+                .iter()
+                .map(|stage| {
+                    stage.nnz() * (core::mem::size_of::<S>() + core::mem::size_of::<usize>())
+                        + (stage.nrows() + 1) * core::mem::size_of::<usize>()
+                })
+                .sum::<usize>()
+                + self.biases.iter().map(|biases| biases.len()).sum::<usize>()
+                    * core::mem::size_of::<S>(),
+        }
+    }
+
+    /// Approximates sigmoid, tanh and gaussian activations by lookup tables like [`crate::matrix::feedforward::evaluator::MatrixFeedforwardEvaluator::with_lookup_tables`].
+    pub fn with_lookup_tables(mut self, resolution: usize, range: S) -> Self {
+        crate::matrix::tabulate(&mut self.transformations, resolution, range);
+        self
+    }
+
+    /// Number of values every sample provides as input.
+    pub fn input_size(&self) -> usize {
+        self.stages.first().map_or(0, |stage| stage.ncols())
+    }
+
+    /// Number of values evaluated for every sample.
+    pub fn output_size(&self) -> usize {
+        self.stages.last().map_or(0, |stage| stage.nrows())
+    }
+
+    pub fn stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Dimensions of every stage as `(rows, columns)`, i.e. the number of values entering and leaving it.
+    pub fn stage_dimensions(&self) -> Vec<(usize, usize)> {
+        self.stages
+            .iter()
+            .map(|stage| (stage.ncols(), stage.nrows()))
+            .collect()
+    }
+
+    /// Draws the stages as terminal friendly ASCII diagram, e.g. to debug without graphviz.
+    ///
+    /// Every stage lists its dimensions and one letter per column as used by the `nodes!` macro,
+    /// `=` marks carries, `?` activations without a letter and non-sum aggregations follow in parentheses, e.g. `t(max)`.
+    pub fn render_ascii(&self) -> String {
+        crate::matrix::render_stages(&self.stage_dimensions(), |index, column| {
+            self.describe(index, column)
+        })
+    }
+
+    fn describe(&self, index: usize, column: usize) -> String {
+        crate::matrix::describe_column(
+            &self.transformations[index][column],
+            self.aggregations[index]
+                .iter()
+                .find(|(aggregated, _, _)| *aggregated == column)
+                .map(|&(_, aggregation, _)| aggregation),
+            self.biases[index][column].is_zero()
+                && self.stages[index].row(column).values() == [S::one()],
+        )
+    }
+
+    /// Computes a single stage, every column is the dot product of a compressed row with the input.
+    fn stage(&self, index: usize, input: &DMatrix<S>) -> DMatrix<S> {
+        let stage = &self.stages[index];
+        let biases = &self.biases[index];
+        let mut state = DMatrix::zeros(input.nrows(), stage.nrows());
+        for (column, weights) in stage.row_iter().enumerate() {
+            for sample in 0..input.nrows() {
+                state[(sample, column)] = weights
+                    .col_indices()
+                    .iter()
+                    .zip(weights.values())
+                    .fold(biases[column], |sum, (&row, &weight)| {
+                        sum + input[(sample, row)] * weight
+                    });
             }
         }
-        NetworkIO::output(DMatrix::from_iterator(
-            1,
-            len,
-            (0..len).map(|index| {
-                if let SparseEntry::NonZero(value) = state.index_entry(0, index) {
-                    *value
-                } else {
-                    0.0
-                }
+        // compute values of nodes that do not sum their inputs, their compressed rows are empty
+        for (column, aggregation, weighted_inputs) in &self.aggregations[index] {
+            for sample in 0..input.nrows() {
+                state[(sample, *column)] = biases[*column]
+                    + aggregation.aggregate(
+                        weighted_inputs
+                            .iter()
+                            .map(|&(row, weight)| input[(sample, row)] * weight),
+                    );
+            }
+        }
+        for (mut column, activation) in state.column_iter_mut().zip(&self.transformations[index]) {
+            for value in column.iter_mut() {
+                *value = activation.apply(*value);
+            }
+        }
+        state
+    }
+
+    /// Computes a single stage for a single sample, `output` receives one value per column.
+    fn stage_into(&self, index: usize, input: &[S], output: &mut [S]) {
+        let biases = &self.biases[index];
+        for ((column, value), weights) in output
+            .iter_mut()
+            .enumerate()
+            .zip(self.stages[index].row_iter())
+        {
+            *value = weights
+                .col_indices()
+                .iter()
+                .zip(weights.values())
+                .fold(biases[column], |sum, (&row, &weight)| {
+                    sum + input[row] * weight
+                });
+        }
+        for (column, aggregation, weighted_inputs) in &self.aggregations[index] {
+            output[*column] = biases[*column]
+                + aggregation.aggregate(
+                    weighted_inputs
+                        .iter()
+                        .map(|&(row, weight)| input[row] * weight),
+                );
+        }
+        for (value, activation) in output.iter_mut().zip(&self.transformations[index]) {
+            *value = activation.apply(*value);
+        }
+    }
+
+    /// Evaluates all stages on the leading values of `scratch.current`, which holds the output at its start afterwards.
+    pub(crate) fn evaluate_scratch(&self, scratch: &mut Scratch<S>) {
+        for (index, stage) in self.stages.iter().enumerate() {
+            self.stage_into(
+                index,
+                &scratch.current[..stage.ncols()],
+                &mut scratch.next[..stage.nrows()],
+            );
+            core::mem::swap(&mut scratch.current, &mut scratch.next);
+        }
+    }
+}
+
+impl<S: Scalar> Evaluator<S> for SparseMatrixFeedforwardEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&self, state: T) -> T {
+        NetworkIO::output(
+            (0..self.stages.len()).fold(NetworkIO::input(state), |state, index| {
+                self.stage(index, &state)
             }),
-        ))
+        )
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
+
+impl<S: Scalar> EvaluateInto<S> for SparseMatrixFeedforwardEvaluator<S> {
+    fn scratch(&self) -> Scratch<S> {
+        Scratch::new(
+            self.stage_dimensions()
+                .into_iter()
+                .map(|(rows, columns)| rows.max(columns))
+                .max()
+                .unwrap_or(0),
+        )
+    }
+
+    fn evaluate_into(&self, input: &[S], output: &mut [S], scratch: &mut Scratch<S>) {
+        scratch.current[..self.input_size()].copy_from_slice(input);
+        self.evaluate_scratch(scratch);
+        output.copy_from_slice(&scratch.current[..self.output_size()]);
+    }
+}
+
+/// Prints one line per stage with its dimensions and the activation of every column, columns only passing on a value are shown as carries.
+impl<S: Scalar> fmt::Display for SparseMatrixFeedforwardEvaluator<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::matrix::fmt_stages(f, &self.stage_dimensions(), |index, column| {
+            self.describe(index, column)
+        })
     }
 }
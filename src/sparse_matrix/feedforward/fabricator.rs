@@ -1,42 +1,51 @@
-use crate::network::{EdgeLike, Fabricator, NetworkLike, NodeLike};
-use nalgebra_sparse::{CooMatrix, CscMatrix};
-use std::collections::HashMap;
+use crate::network::{
+    net::{activations, Edge, Node},
+    prune::active_edges,
+    remap::fabricable,
+    Aggregation, EdgeLike, FabricationError, Fabricator, NetworkLike, NodeId, NodeLike, Scalar,
+};
+use nalgebra::DMatrix;
+use nalgebra_sparse::{CooMatrix, CsrMatrix};
+use std::collections::BTreeMap;
 
 pub struct SparseMatrixFeedforwardFabricator;
 
 impl SparseMatrixFeedforwardFabricator {
-    fn get_sparse(
-        (col_inds, row_inds, data): (Vec<usize>, Vec<usize>, Vec<f32>),
-    ) -> CscMatrix<f32> {
-        let colums = col_inds.iter().max().unwrap() + 1;
-        let rows = row_inds.iter().max().unwrap() + 1;
-
-        CscMatrix::from(
-            &CooMatrix::try_from_triplets(rows, colums, row_inds, col_inds, data).unwrap(),
+    // transposes the stage, so every compressed row holds the incoming weights of one column
+    fn get_sparse<S: Scalar>(
+        (col_inds, row_inds, data): (Vec<usize>, Vec<usize>, Vec<S>),
+        (rows, colums): (usize, usize),
+    ) -> CsrMatrix<S> {
+        CsrMatrix::from(
+            &CooMatrix::try_from_triplets(colums, rows, col_inds, row_inds, data).unwrap(),
         )
     }
 }
 
-impl<N, E> Fabricator<N, E> for SparseMatrixFeedforwardFabricator
+impl<N, E, S, I> Fabricator<N, E, S, I> for SparseMatrixFeedforwardFabricator
 where
-    N: NodeLike,
-    E: EdgeLike,
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
 {
-    type Output = super::evaluator::SparseMatrixFeedforwardEvaluator;
-
-    fn fabricate(net: &impl NetworkLike<N, E>) -> Result<Self::Output, &'static str> {
-        // build dependency graph by collecting incoming edges per node
-        let mut dependency_graph: HashMap<usize, Vec<&E>> = HashMap::new();
-
-        for edge in net.edges() {
-            dependency_graph
-                .entry(edge.end())
-                .and_modify(|dependencies| dependencies.push(edge))
-                .or_insert_with(|| vec![edge]);
+    type Output = super::evaluator::SparseMatrixFeedforwardEvaluator<S>;
+
+    fn fabricate(net: &impl NetworkLike<N, E, S, I>) -> Result<Self::Output, FabricationError> {
+        let net = &fabricable(net);
+        // build dependency graph by collecting incoming edges per node, ordered by id so the same net always fabricates the same stages
+        let mut dependency_graph: BTreeMap<usize, Vec<Edge<S>>> = BTreeMap::new();
+
+        let net_nodes = net.nodes();
+        // look up nodes by id instead of searching them for every computable node
+        let nodes: BTreeMap<usize, &Node<S>> =
+            net_nodes.iter().map(|&node| (node.id(), node)).collect();
+        for edge in active_edges(&net_nodes, net.edges()) {
+            dependency_graph.entry(edge.end()).or_default().push(edge);
         }
 
         if dependency_graph.is_empty() {
-            return Err("no edges present, net invalid");
+            return Err("no edges present, net invalid".into());
         }
 
         // keep track of dependencies present
@@ -45,18 +54,24 @@ where
         // println!("initial dependency_graph {:#?}", dependency_graph);
 
         // contains list of matrices (stages) that form the computable net
-        let mut compute_stages: Vec<(Vec<usize>, Vec<usize>, Vec<f32>)> = Vec::new();
+        let mut compute_stages: Vec<(Vec<usize>, Vec<usize>, Vec<S>)> = Vec::new();
         // contains activation functions corresponding to each stage
-        let mut stage_transformations: Vec<crate::Transformations> = Vec::new();
+        let mut stage_transformations: Vec<crate::Transformations<S>> = Vec::new();
+        // contains dimensions corresponding to each stage, as columns without entries can not be inferred
+        let mut stage_dimensions: Vec<(usize, usize)> = Vec::new();
+        // contains node biases corresponding to each stage
+        let mut stage_biases: Vec<Vec<S>> = Vec::new();
+        // contains non-sum aggregations corresponding to each stage
+        let mut stage_aggregations: Vec<crate::Aggregations<S>> = Vec::new();
         // set available nodes a.k.a net input
-        let mut available_nodes: Vec<usize> = net.inputs().iter().map(|n| n.id()).collect();
+        let mut available_nodes: Vec<usize> = net.inputs_iter().map(|n| n.id()).collect();
         // sort to guarantee each input will be processed by the same node every time
         available_nodes.sort_unstable();
 
         // println!("available_nodes {:?}", available_nodes);
 
         // set wanted nodes a.k.a net output
-        let mut wanted_nodes: Vec<usize> = net.outputs().iter().map(|n| n.id()).collect();
+        let mut wanted_nodes: Vec<usize> = net.outputs_iter().map(|n| n.id()).collect();
         // sort to guarantee each output will appear in the same order every time
         wanted_nodes.sort_unstable();
         let wanted_nodes = wanted_nodes;
@@ -66,7 +81,11 @@ where
         // gather compute stages by finding computable nodes and required carries until all dependencies are resolved
         while !dependency_graph.is_empty() {
             // setup new transformations
-            let mut transformations: crate::Transformations = Vec::new();
+            let mut transformations: crate::Transformations<S> = Vec::new();
+            // setup new biases
+            let mut biases: Vec<S> = Vec::new();
+            // setup new aggregations
+            let mut aggregations: crate::Aggregations<S> = Vec::new();
             // list of nodes becoming available by compute stage
             let mut next_available_nodes: Vec<usize> = Vec::new();
 
@@ -82,7 +101,7 @@ where
                 // marker if all dependencies are available
                 let mut computable = true;
                 // check every dependency
-                for &dependency in dependencies {
+                for dependency in dependencies {
                     let mut found = false;
                     for (row_index, &id) in available_nodes.iter().enumerate() {
                         // index here is row index
@@ -99,17 +118,27 @@ where
                     }
                 }
                 if computable {
-                    stage_column_indices = [stage_column_indices, node_column_indices].concat();
-                    stage_row_indices = [stage_row_indices, node_row_indices].concat();
-                    stage_data = [stage_data, node_data].concat();
+                    let node = nodes[&dependent_node];
+                    let response = node.response();
+                    if node.aggregation() == Aggregation::Sum {
+                        for weight in node_data.iter_mut() {
+                            *weight *= response;
+                        }
+                        stage_column_indices = [stage_column_indices, node_column_indices].concat();
+                        stage_row_indices = [stage_row_indices, node_row_indices].concat();
+                        stage_data = [stage_data, node_data].concat();
+                    } else {
+                        // leave column empty, the value is aggregated from the weighted inputs instead
+                        let mut weighted_inputs: Vec<(usize, S)> =
+                            node_row_indices.into_iter().zip(node_data).collect();
+                        let aggregation =
+                            node.aggregation().respond(&mut weighted_inputs, response);
+                        aggregations.push((column_index, aggregation, weighted_inputs));
+                    }
                     // add activation function to stage transformations
-                    transformations.push(
-                        net.nodes()
-                            .iter()
-                            .find(|&node| node.id() == dependent_node)
-                            .unwrap()
-                            .activation(),
-                    );
+                    transformations.push(activations::Activation::of(node));
+                    // add bias to stage biases
+                    biases.push(node.bias());
                     column_index += 1;
                     // mark node as available in next iteration
                     next_available_nodes.push(dependent_node);
@@ -122,8 +151,10 @@ where
                             carry_row_indices.push(row_index);
                             carry_column_indices.push(column_index);
                             column_index += 1;
-                            carry_data.push(1.0);
-                            transformations.push(|val| val);
+                            carry_data.push(S::one());
+                            transformations
+                                .push(activations::Activation::Function(activations::linear));
+                            biases.push(S::zero());
                             next_available_nodes.push(available_nodes[row_index]);
                         }
                     }
@@ -142,10 +173,13 @@ where
                             stage_column_indices.push(column_index);
                             column_index += 1;
                             stage_row_indices.push(row_index);
-                            stage_data.push(1.0);
+                            stage_data.push(S::one());
 
                             // add identity function for carried vector
-                            transformations.push(|val| val);
+                            transformations
+                                .push(activations::Activation::Function(activations::linear));
+                            // carried values are not biased
+                            biases.push(S::zero());
                             // add node as available
                             next_available_nodes.push(*available_node);
                         }
@@ -160,7 +194,17 @@ where
 
             // if no dependency was removed no progess was made
             if dependency_graph.len() == dependency_count {
-                return Err("can't resolve dependencies, net invalid");
+                // every cycle is left unresolved, so the remaining edges contain all of them
+                let cycles = crate::validation::edge_cycles(
+                    dependency_graph
+                        .values()
+                        .flatten()
+                        .map(|edge| (edge.start(), edge.end())),
+                );
+                if !cycles.is_empty() {
+                    return Err(FabricationError::Cycles(cycles));
+                }
+                return Err("can't resolve dependencies, net invalid".into());
             } else {
                 dependency_count = dependency_graph.len();
             }
@@ -175,6 +219,8 @@ where
                 let mut reordered_stage_column_indices =
                     vec![usize::MAX; stage_column_indices.len()];
                 let mut reordered_transformations = transformations.clone();
+                let mut reordered_biases = biases.clone();
+                let mut reordered_aggregations = Vec::new();
 
                 let mut matched_wanted_count = 0;
 
@@ -191,7 +237,20 @@ where
                             }
 
                             reordered_transformations[new_column_index] =
-                                transformations[old_column_index];
+                                transformations[old_column_index].clone();
+                            reordered_biases[new_column_index] = biases[old_column_index];
+                            if let Some(position) = aggregations
+                                .iter()
+                                .position(|&(column, _, _)| column == old_column_index)
+                            {
+                                let (_, aggregation, weighted_inputs) =
+                                    aggregations.swap_remove(position);
+                                reordered_aggregations.push((
+                                    new_column_index,
+                                    aggregation,
+                                    weighted_inputs,
+                                ));
+                            }
                             matched_wanted_count += 1;
                             break;
                         }
@@ -200,7 +259,7 @@ where
 
                 if matched_wanted_count < wanted_nodes.len() {
                     return Err(
-                        "dependencies resolved but not all outputs computable, net invalid",
+                        "dependencies resolved but not all outputs computable, net invalid".into(),
                     );
                 }
 
@@ -208,11 +267,16 @@ where
 
                 stage_column_indices = reordered_stage_column_indices;
                 transformations = reordered_transformations;
+                biases = reordered_biases;
+                aggregations = reordered_aggregations;
             }
 
-            // add resolved dependencies and transformations to compute stages
+            // add resolved dependencies, transformations and biases to compute stages
             compute_stages.push((stage_column_indices, stage_row_indices, stage_data));
+            stage_dimensions.push((available_nodes.len(), transformations.len()));
             stage_transformations.push(transformations);
+            stage_biases.push(biases);
+            stage_aggregations.push(aggregations);
 
             // set available nodes for next iteration
             available_nodes = next_available_nodes;
@@ -221,9 +285,17 @@ where
         Ok(super::evaluator::SparseMatrixFeedforwardEvaluator {
             stages: compute_stages
                 .into_iter()
-                .map(SparseMatrixFeedforwardFabricator::get_sparse)
+                .zip(stage_dimensions)
+                .map(|(triplets, dimensions)| {
+                    SparseMatrixFeedforwardFabricator::get_sparse(triplets, dimensions)
+                })
                 .collect(),
             transformations: stage_transformations,
+            biases: stage_biases
+                .into_iter()
+                .map(|biases| DMatrix::from_row_slice(1, biases.len(), &biases))
+                .collect(),
+            aggregations: stage_aggregations,
         })
     }
 }
@@ -235,10 +307,35 @@ mod tests {
     use super::SparseMatrixFeedforwardFabricator;
     use crate::{
         edges,
-        network::{net::Net, Evaluator, Fabricator},
+        network::{
+            net::{activations, Edge, Net, Node},
+            Aggregation, EvaluateInto, Evaluator, FabricationError, Fabricator,
+        },
         nodes,
     };
 
+    #[test]
+    fn simple_net_evaluator_cost() {
+        let some_net = Net::new(
+            2,
+            1,
+            nodes!('l', 'l', 's', 'l'),
+            edges!(
+                0--0.5->2,
+                1--0.5->2,
+                2--1.0->3,
+                0--0.25->3
+            ),
+        );
+
+        let cost = SparseMatrixFeedforwardFabricator::fabricate(&some_net)
+            .unwrap()
+            .cost();
+        // only the four edges and the carry of node 0 are stored
+        assert_eq!(cost.stages, 2);
+        assert_eq!(cost.multiply_adds, 5);
+    }
+
     // tests construction and evaluation of simplest network
     #[test]
     fn simple_net_evaluator_0() {
@@ -371,7 +468,10 @@ mod tests {
         let some_net = Net::new(1, 1, nodes!('l', 'l'), Vec::new());
 
         if let Err(message) = SparseMatrixFeedforwardFabricator::fabricate(&some_net) {
-            assert_eq!(message, "no edges present, net invalid");
+            assert_eq!(
+                message,
+                FabricationError::Invalid("no edges present, net invalid")
+            );
         } else {
             unreachable!();
         }
@@ -385,20 +485,47 @@ mod tests {
         if let Err(message) = SparseMatrixFeedforwardFabricator::fabricate(&some_net) {
             assert_eq!(
                 message,
-                "dependencies resolved but not all outputs computable, net invalid"
+                FabricationError::Invalid(
+                    "dependencies resolved but not all outputs computable, net invalid"
+                )
             );
         } else {
             unreachable!();
         }
     }
 
+    // test cyclic net
+    #[test]
+    fn simple_net_evaluator_cycle() {
+        let some_net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            vec![
+                Edge::new(0, 1, 0.5),
+                Edge::new(1, 2, 0.5),
+                Edge::new(2, 1, 0.5),
+                Edge::new(2, 3, 0.5),
+                Edge::new(3, 2, 0.5).with_enabled(false),
+            ],
+        );
+
+        assert_eq!(
+            SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap_err(),
+            FabricationError::Cycles(vec![vec![1, 2]])
+        );
+    }
+
     // test uncomputable output
     #[test]
     fn simple_net_evaluator_8() {
         let some_net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(1--0.5->2));
 
         if let Err(message) = SparseMatrixFeedforwardFabricator::fabricate(&some_net) {
-            assert_eq!(message, "can't resolve dependencies, net invalid");
+            assert_eq!(
+                message,
+                FabricationError::Invalid("can't resolve dependencies, net invalid")
+            );
         } else {
             unreachable!();
         }
@@ -424,4 +551,165 @@ mod tests {
 
         assert_eq!(result, dmatrix![2.5]);
     }
+
+    // test evaluation with f64 precision
+    #[test]
+    fn simple_net_evaluator_f64() {
+        let some_net: Net<f64> = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::linear),
+                Node::new(1, activations::linear),
+                Node::new(2, activations::linear),
+            ],
+            vec![
+                Edge::new(0, 1, 0.1),
+                Edge::new(1, 2, 0.1),
+                Edge::new(0, 2, 0.1),
+            ],
+        );
+
+        let evaluator = SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let result = evaluator.evaluate(dmatrix![3.0]);
+
+        assert_eq!(result, dmatrix![3.0 * 0.1 * 0.1 + 3.0 * 0.1]);
+    }
+
+    // test node bias is added before activation, also for carried nodes
+    #[test]
+    fn simple_net_evaluator_bias() {
+        let some_net = Net::new(
+            1,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR).with_bias(1.0),
+                Node::new(2, activations::RELU).with_bias(-2.0),
+            ],
+            edges!(
+                0--0.5->1,
+                1--0.5->2,
+                0--0.5->2
+            ),
+        );
+
+        let evaluator = SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let result = evaluator.evaluate(dmatrix![5.0]);
+        assert_eq!(result, dmatrix![2.25]);
+
+        let result = evaluator.evaluate(dmatrix![0.0]);
+        assert_eq!(result, dmatrix![0.0]);
+    }
+
+    // test non-sum aggregations, also in the output stage
+    #[test]
+    fn simple_net_evaluator_aggregation() {
+        let some_net = Net::new(
+            2,
+            3,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::LINEAR).with_aggregation(Aggregation::Product),
+                Node::new(3, activations::LINEAR).with_aggregation(Aggregation::Max),
+                Node::new(4, activations::LINEAR)
+                    .with_aggregation(Aggregation::Mean)
+                    .with_bias(1.0),
+                Node::new(5, activations::LINEAR).with_aggregation(Aggregation::Min),
+            ],
+            edges!(
+                0--1.0->2,
+                1--0.5->2,
+                0--1.0->3,
+                2--1.0->3,
+                0--1.0->4,
+                1--1.0->4,
+                0--1.0->5,
+                1--1.0->5
+            ),
+        );
+
+        let evaluator = SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let result = evaluator.evaluate(dmatrix![2.0, 6.0]);
+        assert_eq!(result, dmatrix![6.0, 5.0, 2.0]);
+    }
+
+    // test that every row of the input is evaluated as a separate sample
+    #[test]
+    fn simple_net_evaluator_batch() {
+        let some_net = Net::new(
+            2,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::RELU).with_bias(1.0),
+                Node::new(3, activations::LINEAR),
+                Node::new(4, activations::LINEAR).with_aggregation(Aggregation::Max),
+            ],
+            edges!(
+                0--2.0->2,
+                1--1.0->2,
+                2--0.5->3,
+                0--1.0->4,
+                2--1.0->4
+            ),
+        );
+
+        let evaluator = SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        // the hidden node only has two incoming weights, the second stage has the carry of node 0 besides
+        assert_eq!(
+            evaluator
+                .stages
+                .iter()
+                .map(|stage| stage.nnz())
+                .collect::<Vec<_>>(),
+            vec![3, 1]
+        );
+
+        let result = evaluator.evaluate(dmatrix![1.0, 1.0; -2.0, 1.0; 3.0, 0.0]);
+        assert_eq!(result, dmatrix![2.0, 4.0; 0.0, 0.0; 3.5, 7.0]);
+
+        let mut scratch = evaluator.scratch();
+        let mut output = [0.0; 2];
+        evaluator.evaluate_into(&[3.0, 0.0], &mut output, &mut scratch);
+        assert_eq!(output, [3.5, 7.0]);
+    }
+
+    // test that a fabricated evaluator survives a round trip through serialization
+    #[cfg(feature = "serde")]
+    #[test]
+    fn simple_net_evaluator_serde() {
+        let some_net = Net::new(
+            2,
+            1,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::SIGMOID).with_bias(0.5),
+                Node::new(3, activations::TANH).with_aggregation(Aggregation::Max),
+            ],
+            edges!(
+                0--0.5->2,
+                1--0.5->2,
+                2--1.0->3,
+                0--0.25->3
+            ),
+        );
+
+        let evaluator = SparseMatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+
+        let serialized = serde_json::to_string(&evaluator).unwrap();
+        let deserialized: crate::sparse_matrix::feedforward::evaluator::SparseMatrixFeedforwardEvaluator =
+            serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(
+            deserialized.evaluate(dmatrix![1.0, 2.0]),
+            evaluator.evaluate(dmatrix![1.0, 2.0])
+        );
+    }
 }
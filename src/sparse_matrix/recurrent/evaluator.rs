@@ -1,39 +1,142 @@
+use std::fmt;
+
 use nalgebra::DMatrix;
 
 use crate::{
-    network::{Evaluator, NetworkIO, StatefulEvaluator},
+    matrix::recurrent::evaluator::{with_internal, State},
+    network::{
+        Cost, EvaluateInto, Evaluator, NetworkIO, Scalar, Scratch, StatefulEvaluateInto,
+        StatefulEvaluator,
+    },
     sparse_matrix::feedforward::evaluator::SparseMatrixFeedforwardEvaluator,
 };
 
 #[derive(Debug)]
-pub struct SparseMatrixRecurrentEvaluator {
-    pub internal: DMatrix<f32>,
-    pub evaluator: SparseMatrixFeedforwardEvaluator,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SparseMatrixRecurrentEvaluator<S: Scalar = f32> {
+    pub internal: DMatrix<S>,
+    pub evaluator: SparseMatrixFeedforwardEvaluator<S>,
     pub outputs: usize,
+    /// Id of the node held by every column of [`Self::internal`], delay buffers and cell states of gated nodes have ids of their own.
+    #[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
+    pub memory_ids: Vec<usize>,
 }
 
-impl StatefulEvaluator for SparseMatrixRecurrentEvaluator {
-    fn evaluate<T: NetworkIO>(&mut self, input: T) -> T {
-        let mut input = NetworkIO::input(input);
-        input = DMatrix::from_iterator(
-            1,
-            input.len() + self.internal.len(),
-            input.iter().chain(self.internal.iter()).cloned(),
-        );
+impl<S: Scalar> SparseMatrixRecurrentEvaluator<S> {
+    /// Returns the internal state, i.e. the values of all wrapped outputs and recurrent edge starts of the last evaluation.
+    ///
+    /// With the feature `serde` a clone of it can be stored as a checkpoint and later restored with [`Self::set_state`].
+    pub fn state(&self) -> &DMatrix<S> {
+        &self.internal
+    }
 
-        self.internal = self.evaluator.evaluate(input);
+    /// Replaces the internal state, e.g. to warm-start from a checkpoint taken by [`Self::state`].
+    pub fn set_state(&mut self, state: DMatrix<S>) -> Result<(), &'static str> {
+        if state.shape() != self.internal.shape() {
+            return Err("state shape does not match evaluator");
+        }
+        self.internal = state;
+        Ok(())
+    }
 
-        NetworkIO::output(DMatrix::from_iterator(
-            1,
-            self.outputs,
-            self.internal
-                .slice((0, 0), (1, self.outputs))
-                .iter()
-                .cloned(),
-        ))
+    /// Returns a zeroed [`State`] for [`Self::evaluate_with_state`].
+    pub fn new_state(&self) -> State<S> {
+        State {
+            internal: DMatrix::zeros(1, self.internal.len()),
+        }
+    }
+
+    /// Evaluates with the given [`State`] instead of the internal one, leaving the evaluator untouched.
+    pub fn evaluate_with_state<T: NetworkIO<S>>(&self, state: &mut State<S>, input: T) -> T {
+        state.internal = self
+            .evaluator
+            .evaluate(with_internal(NetworkIO::input(input), &state.internal));
+        NetworkIO::output(state.internal.columns(0, self.outputs).into_owned())
+    }
+
+    /// Estimates the cost of a single evaluation step, i.e. the cost of the wrapped feedforward evaluator.
+    pub fn cost(&self) -> Cost {
+        self.evaluator.cost()
+    }
+
+    /// Number of values every evaluation provides as input, the internal state is fed in by the evaluator itself.
+    pub fn input_size(&self) -> usize {
+        self.evaluator.input_size() - self.internal.len()
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.outputs
+    }
+
+    pub fn stage_count(&self) -> usize {
+        self.evaluator.stage_count()
+    }
+
+    /// Dimensions of every stage of the wrapped feedforward evaluator, including the columns of the internal state.
+    pub fn stage_dimensions(&self) -> Vec<(usize, usize)> {
+        self.evaluator.stage_dimensions()
+    }
+}
+
+impl<S: Scalar> StatefulEvaluator<S> for SparseMatrixRecurrentEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T {
+        self.internal = self
+            .evaluator
+            .evaluate(with_internal(NetworkIO::input(input), &self.internal));
+
+        NetworkIO::output(self.internal.columns(0, self.outputs).into_owned())
     }
 
     fn reset_internal_state(&mut self) {
-        self.internal = DMatrix::from_element(1, self.internal.len(), 0.0);
+        self.internal = DMatrix::from_element(1, self.internal.len(), S::zero());
+    }
+
+    fn reset_state_for(&mut self, node_ids: &[usize]) {
+        for (column, id) in self.memory_ids.iter().enumerate() {
+            if node_ids.contains(id) {
+                self.internal[(0, column)] = S::zero();
+            }
+        }
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
+
+impl<S: Scalar> StatefulEvaluateInto<S> for SparseMatrixRecurrentEvaluator<S> {
+    fn scratch(&self) -> Scratch<S> {
+        self.evaluator.scratch()
+    }
+
+    fn evaluate_into(&mut self, input: &[S], output: &mut [S], scratch: &mut Scratch<S>) {
+        let inputs = self.input_size();
+        scratch.current[..inputs].copy_from_slice(input);
+        scratch.current[inputs..inputs + self.internal.len()]
+            .copy_from_slice(self.internal.as_slice());
+        self.evaluator.evaluate_scratch(scratch);
+        let internal = self.internal.len();
+        self.internal
+            .as_mut_slice()
+            .copy_from_slice(&scratch.current[..internal]);
+        output.copy_from_slice(&self.internal.as_slice()[..self.outputs]);
+    }
+}
+
+/// Prints the size of the internal state followed by the stages of the wrapped feedforward evaluator.
+impl<S: Scalar> fmt::Display for SparseMatrixRecurrentEvaluator<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} inputs, {} outputs, {} state columns",
+            self.input_size(),
+            self.outputs,
+            self.internal.len()
+        )?;
+        write!(f, "{}", self.evaluator)
     }
 }
@@ -2,31 +2,41 @@ use nalgebra::DMatrix;
 
 use crate::{
     network::{
-        net::unroll, EdgeLike, Fabricator, NetworkLike, NodeLike, Recurrent, StatefulFabricator,
+        delayed::expand_delayed,
+        gated::expand_gated,
+        leaky::expand_leaky,
+        net::{memory_ids, unroll},
+        remap::fabricable_recurrent,
+        EdgeLike, FabricationError, Fabricator, NetworkLike, NodeId, NodeLike, Recurrent, Scalar,
+        StatefulFabricator,
     },
     sparse_matrix::feedforward::fabricator::SparseMatrixFeedforwardFabricator,
 };
 
 pub struct SparseMatrixRecurrentFabricator;
 
-impl<N, E> StatefulFabricator<N, E> for SparseMatrixRecurrentFabricator
+impl<N, E, S, I> StatefulFabricator<N, E, S, I> for SparseMatrixRecurrentFabricator
 where
-    N: NodeLike,
-    E: EdgeLike,
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
 {
-    type Output = super::evaluator::SparseMatrixRecurrentEvaluator;
+    type Output = super::evaluator::SparseMatrixRecurrentEvaluator<S>;
 
-    fn fabricate(net: &impl Recurrent<N, E>) -> Result<Self::Output, &'static str> {
-        let unrolled = unroll(net);
+    fn fabricate(net: &impl Recurrent<N, E, S, I>) -> Result<Self::Output, FabricationError> {
+        let net = &fabricable_recurrent(net);
+        let unrolled = unroll(&expand_gated(&expand_delayed(&expand_leaky(net)?)?)?)?;
         let evaluator = SparseMatrixFeedforwardFabricator::fabricate(&unrolled)?;
         let memory = unrolled.outputs().len();
 
         assert!(unrolled.inputs().len() - net.inputs().len() == memory);
 
         Ok(super::evaluator::SparseMatrixRecurrentEvaluator {
-            internal: DMatrix::from_element(1, memory, 0.0),
+            internal: DMatrix::from_element(1, memory, S::zero()),
             evaluator,
             outputs: net.outputs().len(),
+            memory_ids: memory_ids(&unrolled),
         })
     }
 }
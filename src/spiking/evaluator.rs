@@ -0,0 +1,197 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::DMatrix;
+
+use crate::network::{
+    Aggregation, NetworkIO, Scalar, Scratch, StatefulEvaluateInto, StatefulEvaluator,
+};
+
+#[derive(Debug)]
+pub struct SpikingNode<S: Scalar = f32> {
+    pub bias: S,
+    pub response: S,
+    pub aggregation: Aggregation,
+    pub inputs: Vec<(usize, S)>,
+}
+
+#[derive(Debug)]
+pub struct SpikingEvaluator<S: Scalar = f32> {
+    pub input_ids: Vec<usize>,
+    pub output_ids: Vec<usize>,
+    pub nodes: Vec<SpikingNode<S>>,
+    pub potentials: Vec<S>,
+    /// Ids of the nodes as given to the fabricator, default to their indices.
+    pub node_ids: Vec<usize>,
+    /// Potential at which a neuron spikes, defaults to `1.0`.
+    pub threshold: S,
+    /// Factor the potential is multiplied with every step, defaults to `0.9`.
+    pub decay: S,
+    /// Steps a neuron stays silent after spiking, defaults to `1`.
+    pub refractory_period: usize,
+    // spikes of the last step and remaining refractory steps
+    spikes: Vec<S>,
+    refractory: Vec<usize>,
+}
+
+impl<S: Scalar> SpikingEvaluator<S> {
+    /// Starts at rest, `inputs` of every node are pairs of source index and weight.
+    pub fn new(input_ids: Vec<usize>, output_ids: Vec<usize>, nodes: Vec<SpikingNode<S>>) -> Self {
+        Self {
+            input_ids,
+            output_ids,
+            potentials: vec![S::zero(); nodes.len()],
+            spikes: vec![S::zero(); nodes.len()],
+            refractory: vec![0; nodes.len()],
+            node_ids: (0..nodes.len()).collect(),
+            nodes,
+            threshold: S::one(),
+            decay: nalgebra::convert(0.9),
+            refractory_period: 1,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: S) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_decay(mut self, decay: S) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    pub fn with_refractory_period(mut self, refractory_period: usize) -> Self {
+        self.refractory_period = refractory_period;
+        self
+    }
+
+    pub fn with_node_ids(mut self, node_ids: Vec<usize>) -> Self {
+        self.node_ids = node_ids;
+        self
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.input_ids.len()
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.output_ids.len()
+    }
+
+    /// Presents the same input for `steps` steps and returns the spike train, one row per step.
+    pub fn spike_train<T: NetworkIO<S>>(&mut self, input: T, steps: usize) -> DMatrix<S> {
+        let input = NetworkIO::input(input);
+        let mut train = DMatrix::zeros(steps, self.output_ids.len());
+
+        for step in 0..steps {
+            let spikes: DMatrix<S> = self.evaluate(input.clone());
+            train.set_row(step, &spikes.row(0));
+        }
+
+        train
+    }
+
+    /// Advances one time step, `spikes` receives the new spikes of all nodes before they replace the old ones.
+    fn step(&mut self, input: &[S], spikes: &mut [S]) {
+        for (&id, &value) in self.input_ids.iter().zip(input.iter()) {
+            self.spikes[id] = value;
+        }
+
+        // all neurons integrate the spikes of the previous step simultaneously
+        spikes.copy_from_slice(&self.spikes);
+        for (id, node) in self.nodes.iter().enumerate() {
+            if self.input_ids.contains(&id) {
+                continue;
+            }
+            if self.refractory[id] > 0 {
+                self.refractory[id] -= 1;
+                spikes[id] = S::zero();
+                continue;
+            }
+
+            let potential = self.potentials[id] * self.decay
+                + node.bias
+                + node.response
+                    * node.aggregation.aggregate(
+                        node.inputs
+                            .iter()
+                            .map(|&(source, weight)| self.spikes[source] * weight),
+                    );
+
+            if potential >= self.threshold {
+                spikes[id] = S::one();
+                self.potentials[id] = S::zero();
+                self.refractory[id] = self.refractory_period;
+            } else {
+                spikes[id] = S::zero();
+                self.potentials[id] = potential;
+            }
+        }
+        self.spikes.copy_from_slice(spikes);
+    }
+}
+
+impl<S: Scalar> StatefulEvaluator<S> for SpikingEvaluator<S> {
+    fn evaluate<T: NetworkIO<S>>(&mut self, input: T) -> T {
+        let input = NetworkIO::input(input);
+        let mut spikes = vec![S::zero(); self.nodes.len()];
+        self.step(input.as_slice(), &mut spikes);
+
+        NetworkIO::output(DMatrix::from_iterator(
+            1,
+            self.output_ids.len(),
+            self.output_ids.iter().map(|&id| self.spikes[id]),
+        ))
+    }
+
+    fn reset_internal_state(&mut self) {
+        for value in self.potentials.iter_mut().chain(self.spikes.iter_mut()) {
+            *value = S::zero();
+        }
+        for value in self.refractory.iter_mut() {
+            *value = 0;
+        }
+    }
+
+    fn reset_state_for(&mut self, node_ids: &[usize]) {
+        for (index, id) in self.node_ids.iter().enumerate() {
+            if node_ids.contains(id) {
+                self.potentials[index] = S::zero();
+                self.spikes[index] = S::zero();
+                self.refractory[index] = 0;
+            }
+        }
+    }
+
+    fn input_dimension(&self) -> Option<usize> {
+        Some(self.input_size())
+    }
+
+    fn output_dimension(&self) -> Option<usize> {
+        Some(self.output_size())
+    }
+}
+
+impl<S: Scalar> StatefulEvaluateInto<S> for SpikingEvaluator<S> {
+    fn scratch(&self) -> Scratch<S> {
+        Scratch::new(self.nodes.len())
+    }
+
+    fn evaluate_into(&mut self, input: &[S], output: &mut [S], scratch: &mut Scratch<S>) {
+        assert_eq!(
+            input.len(),
+            self.input_size(),
+            "input dimension does not match"
+        );
+        let nodes = self.nodes.len();
+        self.step(input, &mut scratch.next[..nodes]);
+        assert_eq!(
+            output.len(),
+            self.output_size(),
+            "output dimension does not match"
+        );
+        for (value, &id) in output.iter_mut().zip(&self.output_ids) {
+            *value = self.spikes[id];
+        }
+    }
+}
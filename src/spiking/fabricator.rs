@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use crate::network::{
+    prune::active_edges, remap::fabricable_recurrent, EdgeLike, FabricationError, NetworkLike,
+    NodeId, NodeLike, Recurrent, Scalar, StatefulFabricator,
+};
+
+use super::evaluator::{SpikingEvaluator, SpikingNode};
+
+/// Fabricates a [`SpikingEvaluator`], its neuron parameters can be changed with its `with_*` methods.
+#[derive(Debug)]
+pub struct SpikingFabricator;
+
+impl<N, E, S, I> StatefulFabricator<N, E, S, I> for SpikingFabricator
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    type Output = SpikingEvaluator<S>;
+
+    fn fabricate(net: &impl Recurrent<N, E, S, I>) -> Result<Self::Output, FabricationError> {
+        let net = &fabricable_recurrent(net);
+        let mut id_map: HashMap<usize, usize> = HashMap::new();
+        let mut nodes = Vec::new();
+
+        for node in net.nodes_iter() {
+            id_map.insert(node.id(), nodes.len());
+            nodes.push(SpikingNode {
+                bias: node.bias(),
+                response: node.response(),
+                aggregation: node.aggregation(),
+                inputs: Vec::new(),
+            });
+        }
+
+        let net_nodes = net.nodes();
+        for edge in active_edges(&net_nodes, net.edges())
+            .into_iter()
+            .chain(active_edges(&net_nodes, net.recurrent_edges()))
+        {
+            let start = *id_map.get(&edge.start()).ok_or("edge from unknown node")?;
+            let end = *id_map.get(&edge.end()).ok_or("edge to unknown node")?;
+            nodes[end].inputs.push((start, edge.weight()));
+        }
+
+        let input_ids = net.inputs_iter().map(|node| id_map[&node.id()]).collect();
+        let output_ids = net
+            .outputs()
+            .iter()
+            .map(|node| id_map[&node.id()])
+            .collect();
+
+        let node_ids = net.nodes_iter().map(|node| node.id()).collect();
+
+        Ok(SpikingEvaluator::new(input_ids, output_ids, nodes).with_node_ids(node_ids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{dmatrix, DMatrix};
+
+    use super::SpikingFabricator;
+    use crate::{
+        edges,
+        network::{net::Net, StatefulEvaluator, StatefulFabricator},
+        nodes,
+    };
+
+    #[test]
+    fn integrate_and_fire() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--0.4->1));
+
+        let mut evaluator = SpikingFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_decay(1.0)
+            .with_refractory_period(1);
+
+        // potential 0.4, 0.8, 1.2 spikes, refractory, 0.4, 0.8, 1.2 spikes
+        assert_eq!(
+            evaluator.spike_train(dmatrix![1.0], 7),
+            DMatrix::from_column_slice(7, 1, &[0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0])
+        );
+
+        evaluator.reset_internal_state();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![0.0]);
+    }
+
+    #[test]
+    fn leak_prevents_firing() {
+        let some_net = Net::new(1, 1, nodes!('l', 'l'), edges!(0--0.5->1));
+
+        let mut evaluator = SpikingFabricator::fabricate(&some_net)
+            .unwrap()
+            .with_decay(0.4);
+
+        // potential converges to 0.5 / (1 - 0.4) < 1
+        assert_eq!(
+            evaluator.spike_train(dmatrix![1.0], 20),
+            DMatrix::zeros(20, 1)
+        );
+
+        // spikes propagate with one step delay per edge
+        let chain = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->1, 1--1.0->2));
+        let mut evaluator = SpikingFabricator::fabricate(&chain).unwrap();
+        assert_eq!(evaluator.evaluate(dmatrix![1.0]), dmatrix![0.0]);
+        assert_eq!(evaluator.evaluate(dmatrix![0.0]), dmatrix![1.0]);
+    }
+}
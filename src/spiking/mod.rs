@@ -0,0 +1,10 @@
+//! Evaluates [`crate::network::Recurrent`] [`crate::network::NetworkLike`] structures as spiking networks of leaky integrate-and-fire (LIF) neurons.
+//!
+//! Every non-input node keeps a membrane potential which decays every step and accumulates its bias and the weighted spikes of its sources.
+//! Crossing the threshold emits a spike, resets the potential to zero and silences the neuron for the refractory period.
+//! Activations of the nodes are not used, input nodes emit their input values as is, e.g. `1.0` for a spike.
+//! Every call to [`crate::network::StatefulEvaluator::evaluate`] advances one time step and returns the spikes of the outputs.
+
+pub mod evaluator;
+#[cfg(feature = "std")]
+pub mod fabricator;
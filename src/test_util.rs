@@ -0,0 +1,228 @@
+//! Generates random networks, e.g. for benchmarks and property tests.
+
+use alloc::{collections::BTreeSet, vec::Vec};
+
+use crate::{
+    network::net::{activations, Edge, Net, Node},
+    rng::SplitMix,
+};
+
+const ACTIVATIONS: [fn(f32) -> f32; 5] = [
+    activations::LINEAR,
+    activations::SIGMOID,
+    activations::TANH,
+    activations::RELU,
+    activations::GAUSSIAN,
+];
+
+/// Generates a [`Net`] passing [`crate::validate`] from `seed`.
+///
+/// Nodes are numbered inputs first, then hidden, then outputs.
+/// Every pair of nodes in that order is connected with probability `density`, edges only point from lower to higher ids and never start at outputs or end at inputs.
+/// Missing edges are added afterwards, so every input has an outgoing edge, every hidden node an incoming and outgoing one and every output an incoming one.
+/// Every pair of a hidden or output node and a hidden or output node is connected by a recurrent edge with probability `recurrent_density`.
+/// Weights and biases are uniform in `[-1, 1)`, activations are picked from linear, sigmoid, tanh, relu and gaussian.
+///
+/// Panics if there are no inputs or no outputs.
+pub fn random_net(
+    inputs: usize,
+    outputs: usize,
+    hidden: usize,
+    density: f32,
+    recurrent_density: f32,
+    seed: u64,
+) -> Net {
+    assert!(
+        inputs > 0 && outputs > 0,
+        "random nets need at least one input and one output"
+    );
+
+    let mut rng = SplitMix(seed);
+    let count = inputs + hidden + outputs;
+    let is_input = |id: usize| id < inputs;
+    let is_output = |id: usize| id >= inputs + hidden;
+
+    let nodes = (0..count)
+        .map(|id| {
+            if is_input(id) {
+                Node::new(id, activations::LINEAR)
+            } else {
+                Node::new(id, ACTIVATIONS[rng.below(ACTIVATIONS.len())]).with_bias(rng.weight())
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut connections = BTreeSet::new();
+    for start in (0..count).filter(|&id| !is_output(id)) {
+        for end in (start + 1..count).filter(|&id| !is_input(id)) {
+            if rng.unit() < density {
+                connections.insert((start, end));
+            }
+        }
+    }
+
+    // repair nodes left without the edges needed to be computable and contributing
+    for id in 0..count {
+        if !is_output(id) && !connections.iter().any(|&(start, _)| start == id) {
+            let first = inputs.max(id + 1);
+            connections.insert((id, first + rng.below(count - first)));
+        }
+        if !is_input(id) && !connections.iter().any(|&(_, end)| end == id) {
+            let last = id.min(inputs + hidden);
+            connections.insert((rng.below(last), id));
+        }
+    }
+
+    let edges = connections
+        .into_iter()
+        .map(|(start, end)| Edge::new(start, end, rng.weight()))
+        .collect();
+
+    let mut recurrent_edges = Vec::new();
+    for start in inputs..count {
+        for end in inputs..count {
+            if rng.unit() < recurrent_density {
+                recurrent_edges.push(Edge::new(start, end, rng.weight()));
+            }
+        }
+    }
+
+    let mut net = Net::new(inputs, outputs, nodes, edges);
+    net.set_recurrent_edges(recurrent_edges);
+    net
+}
+
+/// Strategy generating [`random_net`]s with up to the given number of nodes per kind, available with the feature `proptest`.
+///
+/// Shrinking reduces the node counts and densities, the seed is shrunk as well and thereby changes the structure.
+#[cfg(feature = "proptest")]
+pub fn arbitrary_net(
+    max_inputs: usize,
+    max_outputs: usize,
+    max_hidden: usize,
+) -> impl proptest::strategy::Strategy<Value = Net> {
+    use proptest::prelude::*;
+
+    (
+        1..=max_inputs.max(1),
+        1..=max_outputs.max(1),
+        0..=max_hidden,
+        0.0..1.0f32,
+        0.0..0.5f32,
+        any::<u64>(),
+    )
+        .prop_map(
+            |(inputs, outputs, hidden, density, recurrent_density, seed)| {
+                random_net(inputs, outputs, hidden, density, recurrent_density, seed)
+            },
+        )
+}
+
+/// Generates nets with up to four inputs and outputs and eight hidden nodes, see [`arbitrary_net`].
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for Net {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::strategy::Strategy;
+
+        arbitrary_net(4, 4, 8).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::DMatrix;
+
+    use super::random_net;
+    use crate::{
+        matrix::{
+            feedforward::fabricator::MatrixFeedforwardFabricator,
+            recurrent::fabricator::MatrixRecurrentFabricator,
+        },
+        network::{
+            net::Net, EdgeLike, Evaluator, Fabricator, NetworkLike, Recurrent, StatefulEvaluator,
+            StatefulFabricator,
+        },
+        validate,
+    };
+
+    #[test]
+    fn random_nets_are_valid() {
+        for seed in 0..50 {
+            let net = random_net(3, 2, (seed % 7) as usize, 0.3, 0.1, seed);
+            assert!(validate(&net).is_valid(), "seed {}", seed);
+
+            let evaluator = MatrixFeedforwardFabricator::fabricate(&net).unwrap();
+            let output = evaluator.evaluate(DMatrix::from_element(1, 3, 0.5));
+            assert!(output.iter().all(|value| value.is_finite()));
+
+            let mut evaluator = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+            let output = evaluator.evaluate(DMatrix::from_element(1, 3, 0.5));
+            assert_eq!(output.ncols(), 2);
+        }
+    }
+
+    #[test]
+    fn random_nets_are_deterministic() {
+        let net = random_net(2, 2, 4, 0.5, 0.2, 7);
+        let other = random_net(2, 2, 4, 0.5, 0.2, 7);
+        let pairs = |net: &Net| {
+            net.edges()
+                .iter()
+                .chain(net.recurrent_edges().iter())
+                .map(|edge| (edge.start(), edge.end(), edge.weight()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(pairs(&net), pairs(&other));
+        assert_ne!(pairs(&net), pairs(&random_net(2, 2, 4, 0.5, 0.2, 8)));
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn fabricators_agree(net in proptest::arbitrary::any::<Net>(), value in -1.0f32..1.0) {
+            use crate::{
+                bytecode::fabricator::BytecodeFabricator,
+                interpreter::fabricator::LoopFabricator,
+                sparse_matrix::{
+                    feedforward::fabricator::SparseMatrixFeedforwardFabricator,
+                    recurrent::fabricator::SparseMatrixRecurrentFabricator,
+                },
+            };
+
+            let input = DMatrix::from_fn(1, net.inputs().len(), |_, column| {
+                value * (column + 1) as f32
+            });
+            let close = |a: &DMatrix<f32>, b: &DMatrix<f32>| {
+                a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-4)
+            };
+
+            let expected = MatrixFeedforwardFabricator::fabricate(&net)
+                .unwrap()
+                .evaluate(input.clone());
+            let sparse = SparseMatrixFeedforwardFabricator::fabricate(&net)
+                .unwrap()
+                .evaluate(input.clone());
+            let interpreted = LoopFabricator::fabricate(&net)
+                .unwrap()
+                .evaluate(input.clone());
+            let compiled = BytecodeFabricator::fabricate(&net)
+                .unwrap()
+                .evaluate(input.clone());
+            proptest::prop_assert!(close(&expected, &sparse));
+            proptest::prop_assert!(close(&expected, &interpreted));
+            proptest::prop_assert!(close(&expected, &compiled));
+
+            let mut dense = MatrixRecurrentFabricator::fabricate(&net).unwrap();
+            let mut sparse = SparseMatrixRecurrentFabricator::fabricate(&net).unwrap();
+            for _ in 0..3 {
+                proptest::prop_assert!(close(
+                    &dense.evaluate(input.clone()),
+                    &sparse.evaluate(input.clone())
+                ));
+            }
+        }
+    }
+}
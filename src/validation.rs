@@ -0,0 +1,432 @@
+//! Checks [`NetworkLike`] structures for defects without fabricating them.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::network::{EdgeLike, NetworkLike, NodeId, NodeLike, Scalar};
+
+/// Lists every defect found by [`validate`], all lists are sorted by node id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport<I: NodeId = usize> {
+    /// Ids used by more than one node.
+    pub duplicate_ids: Vec<I>,
+    /// Edges as `(start, end)` with at least one end not being a node.
+    pub dangling_edges: Vec<(I, I)>,
+    /// Outputs that can not be computed from the inputs.
+    pub unreachable_outputs: Vec<I>,
+    /// Nodes without any edge.
+    pub isolated_nodes: Vec<I>,
+    /// Cycles of edges, each listed as the node ids along the loop starting at its smallest id.
+    pub cycles: Vec<Vec<I>>,
+}
+
+// derived it would require a default id
+impl<I: NodeId> Default for ValidationReport<I> {
+    fn default() -> Self {
+        Self {
+            duplicate_ids: Vec::new(),
+            dangling_edges: Vec::new(),
+            unreachable_outputs: Vec::new(),
+            isolated_nodes: Vec::new(),
+            cycles: Vec::new(),
+        }
+    }
+}
+
+impl<I: NodeId> ValidationReport<I> {
+    /// Returns true if no defects were found.
+    pub fn is_valid(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Checks a [`NetworkLike`] structure for the defects listed in [`ValidationReport`].
+///
+/// Only [`NetworkLike::edges`] are considered, recurrent edges are allowed to form cycles.
+pub fn validate<N, E, S, I>(net: &impl NetworkLike<N, E, S, I>) -> ValidationReport<I>
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    let mut report = ValidationReport::default();
+
+    let mut ids = BTreeSet::new();
+    for node in net.nodes_iter() {
+        if !ids.insert(node.id()) && !report.duplicate_ids.contains(&node.id()) {
+            report.duplicate_ids.push(node.id());
+        }
+    }
+    report.duplicate_ids.sort_unstable();
+
+    let mut successors: BTreeMap<I, Vec<I>> = BTreeMap::new();
+    let mut predecessors: BTreeMap<I, Vec<I>> = BTreeMap::new();
+    for edge in net.edges_iter() {
+        if ids.contains(&edge.start()) && ids.contains(&edge.end()) {
+            successors.entry(edge.start()).or_default().push(edge.end());
+            predecessors
+                .entry(edge.end())
+                .or_default()
+                .push(edge.start());
+        } else {
+            report.dangling_edges.push((edge.start(), edge.end()));
+        }
+    }
+    report.dangling_edges.sort_unstable();
+
+    report.isolated_nodes = ids
+        .iter()
+        .filter(|id| !successors.contains_key(id) && !predecessors.contains_key(id))
+        .copied()
+        .collect();
+
+    // a node is computable once all of its dependencies are
+    let mut computable: BTreeSet<I> = net.inputs_iter().map(|node| node.id()).collect();
+    let mut progress = true;
+    while progress {
+        progress = false;
+        for (&node, dependencies) in predecessors.iter() {
+            if !computable.contains(&node)
+                && dependencies
+                    .iter()
+                    .all(|dependency| computable.contains(dependency))
+            {
+                computable.insert(node);
+                progress = true;
+            }
+        }
+    }
+    report.unreachable_outputs = net
+        .outputs()
+        .iter()
+        .map(|node| node.id())
+        .filter(|id| !computable.contains(id))
+        .collect();
+    report.unreachable_outputs.sort_unstable();
+
+    report.cycles = find_cycles(&successors);
+
+    report
+}
+
+/// Issue that does not stop fabrication but hints at a sloppy genome, see [`fabrication_warnings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FabricationWarning<I: NodeId = usize> {
+    /// Hidden node that can not reach any output by enabled edges, it is evaluated for nothing.
+    UnreachableHidden(I),
+    /// Several edges connect the same nodes, given as `(start, end)`, fabricators sum their weights.
+    DuplicateEdge(I, I),
+    /// Output whose incoming edges are all disabled or of zero weight, so its value does not depend on the inputs.
+    DisabledOutput(I),
+}
+
+// ids are only known to be `Debug`, which prints plain numbers the same as `Display`
+impl<I: NodeId> fmt::Display for FabricationWarning<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnreachableHidden(id) => write!(f, "hidden node {:?} reaches no output", id),
+            Self::DuplicateEdge(start, end) => {
+                write!(f, "several edges from node {:?} to node {:?}", start, end)
+            }
+            Self::DisabledOutput(id) => {
+                write!(f, "output {:?} does not depend on the inputs", id)
+            }
+        }
+    }
+}
+
+/// Lists the [`FabricationWarning`]s of a [`NetworkLike`] structure sorted by kind and node ids, e.g. to log them while evolving.
+///
+/// Only [`NetworkLike::edges`] are considered, like [`validate`] does.
+pub fn fabrication_warnings<N, E, S, I>(
+    net: &impl NetworkLike<N, E, S, I>,
+) -> Vec<FabricationWarning<I>>
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    let mut warnings = Vec::new();
+
+    // walk enabled edges backwards starting at the outputs
+    let mut contributing: BTreeSet<I> = net.outputs_iter().map(|node| node.id()).collect();
+    let mut pending: Vec<I> = contributing.iter().copied().collect();
+    while let Some(id) = pending.pop() {
+        for edge in net.edges_iter().filter(|edge| edge.enabled()) {
+            if edge.end() == id && contributing.insert(edge.start()) {
+                pending.push(edge.start());
+            }
+        }
+    }
+    warnings.extend(
+        net.hidden_iter()
+            .map(|node| node.id())
+            .filter(|id| !contributing.contains(id))
+            .map(FabricationWarning::UnreachableHidden),
+    );
+
+    let mut pairs = BTreeMap::new();
+    for edge in net.edges_iter() {
+        *pairs.entry((edge.start(), edge.end())).or_insert(0) += 1;
+    }
+    warnings.extend(
+        pairs
+            .into_iter()
+            .filter(|&(_, count)| count > 1)
+            .map(|((start, end), _)| FabricationWarning::DuplicateEdge(start, end)),
+    );
+
+    warnings.extend(
+        net.outputs_iter()
+            .map(|node| node.id())
+            .filter(|&id| {
+                !net.edges_iter()
+                    .any(|edge| edge.end() == id && edge.enabled() && !edge.weight().is_zero())
+            })
+            .map(FabricationWarning::DisabledOutput),
+    );
+
+    warnings.sort_unstable();
+    warnings
+}
+
+/// Lists cycles formed by [`NetworkLike::edges`] as node ids along each loop, starting at its smallest id.
+///
+/// Fabricators only report the cycles among the edges they evaluate, see [`crate::network::FabricationError::Cycles`].
+pub fn cycles<N, E, S, I>(net: &impl NetworkLike<N, E, S, I>) -> Vec<Vec<I>>
+where
+    N: NodeLike<S, I>,
+    E: EdgeLike<S, I>,
+    S: Scalar,
+    I: NodeId,
+{
+    edge_cycles(net.edges_iter().map(|edge| (edge.start(), edge.end())))
+}
+
+/// Lists cycles formed by edges given as `(start, end)` like [`cycles`] does.
+pub(crate) fn edge_cycles<I: NodeId>(edges: impl IntoIterator<Item = (I, I)>) -> Vec<Vec<I>> {
+    let mut successors: BTreeMap<I, Vec<I>> = BTreeMap::new();
+    for (start, end) in edges {
+        successors.entry(start).or_default().push(end);
+    }
+    find_cycles(&successors)
+}
+
+/// Finds cycles by depth first search, every back edge yields one cycle.
+fn find_cycles<I: NodeId>(successors: &BTreeMap<I, Vec<I>>) -> Vec<Vec<I>> {
+    // nodes on the current path map to their position in it, finished nodes are never visited again
+    let mut finished = BTreeSet::new();
+    let mut cycles = Vec::new();
+
+    for &root in successors.keys() {
+        if finished.contains(&root) {
+            continue;
+        }
+        let mut path: Vec<I> = vec![root];
+        let mut on_path: BTreeMap<I, usize> = BTreeMap::new();
+        on_path.insert(root, 0);
+        let mut next_child = vec![0_usize];
+
+        while let Some(&node) = path.last() {
+            let children = successors
+                .get(&node)
+                .map_or(&[][..], |children| &children[..]);
+            let child_index = next_child.last_mut().unwrap();
+
+            if let Some(&child) = children.get(*child_index) {
+                *child_index += 1;
+                if let Some(&position) = on_path.get(&child) {
+                    let mut cycle = path[position..].to_vec();
+                    let smallest = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap();
+                    cycle.rotate_left(smallest);
+                    if !cycles.contains(&cycle) {
+                        cycles.push(cycle);
+                    }
+                } else if !finished.contains(&child) {
+                    on_path.insert(child, path.len());
+                    path.push(child);
+                    next_child.push(0);
+                }
+            } else {
+                finished.insert(node);
+                on_path.remove(&node);
+                path.pop();
+                next_child.pop();
+            }
+        }
+    }
+
+    cycles.sort_unstable();
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cycles, fabrication_warnings, validate, FabricationWarning, ValidationReport};
+    use crate::{
+        edges,
+        network::{
+            net::{activations, Edge, Net, Node},
+            EdgeLike, NetworkLike, NodeLike,
+        },
+        nodes,
+    };
+
+    #[test]
+    fn valid_net() {
+        let net = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--1.0->2));
+        assert!(validate(&net).is_valid());
+    }
+
+    #[test]
+    fn report_defects() {
+        let net = Net::new(
+            1,
+            2,
+            vec![
+                Node::new(0, activations::LINEAR),
+                Node::new(1, activations::LINEAR),
+                Node::new(2, activations::LINEAR),
+                Node::new(3, activations::LINEAR),
+                Node::new(3, activations::LINEAR),
+                Node::new(4, activations::LINEAR),
+                Node::new(5, activations::LINEAR),
+            ],
+            edges!(
+                0--1.0->1,
+                1--1.0->2,
+                2--1.0->1,
+                1--1.0->5,
+                0--1.0->9
+            ),
+        );
+
+        assert_eq!(
+            validate(&net),
+            ValidationReport {
+                duplicate_ids: vec![3],
+                dangling_edges: vec![(0, 9)],
+                unreachable_outputs: vec![4, 5],
+                isolated_nodes: vec![3, 4],
+                cycles: vec![vec![1, 2]],
+            }
+        );
+    }
+
+    #[test]
+    fn warn_about_sloppy_genomes() {
+        let net = Net::new(
+            1,
+            2,
+            nodes!('l', 'l', 'l', 'l', 'l'),
+            vec![
+                Edge::new(0, 1, 1.0),
+                Edge::new(0, 2, 1.0),
+                Edge::new(0, 3, 0.5),
+                Edge::new(0, 3, 0.5),
+                Edge::new(1, 4, 1.0).with_enabled(false),
+                Edge::new(0, 4, 0.0),
+            ],
+        );
+
+        let warnings = fabrication_warnings(&net);
+        assert_eq!(
+            warnings,
+            vec![
+                FabricationWarning::UnreachableHidden(1),
+                FabricationWarning::UnreachableHidden(2),
+                FabricationWarning::DuplicateEdge(0, 3),
+                FabricationWarning::DisabledOutput(4),
+            ]
+        );
+        assert_eq!(warnings[0].to_string(), "hidden node 1 reaches no output");
+        assert!(
+            fabrication_warnings(&Net::new(1, 1, nodes!('l', 'l'), edges!(0--1.0->1))).is_empty()
+        );
+    }
+
+    #[test]
+    fn list_cycles() {
+        let net = Net::new(
+            1,
+            1,
+            nodes!('l', 'l', 'l', 'l'),
+            edges!(
+                0--1.0->1,
+                1--1.0->2,
+                2--1.0->1,
+                2--1.0->3,
+                3--1.0->3
+            ),
+        );
+        assert_eq!(cycles(&net), vec![vec![1, 2], vec![3]]);
+    }
+
+    // genome crates often key their genes by innovation pairs rather than plain numbers
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct KeyedNode(&'static str);
+    struct KeyedEdge(&'static str, &'static str);
+    struct KeyedNet(Vec<KeyedNode>, Vec<KeyedEdge>);
+
+    impl NodeLike<f32, &'static str> for KeyedNode {
+        fn id(&self) -> &'static str {
+            self.0
+        }
+        fn activation(&self) -> fn(f32) -> f32 {
+            activations::LINEAR
+        }
+    }
+
+    impl EdgeLike<f32, &'static str> for KeyedEdge {
+        fn start(&self) -> &'static str {
+            self.0
+        }
+        fn end(&self) -> &'static str {
+            self.1
+        }
+        fn weight(&self) -> f32 {
+            1.0
+        }
+    }
+
+    impl NetworkLike<KeyedNode, KeyedEdge, f32, &'static str> for KeyedNet {
+        fn edges(&self) -> Vec<&KeyedEdge> {
+            self.1.iter().collect()
+        }
+        fn inputs(&self) -> Vec<&KeyedNode> {
+            self.0[..1].iter().collect()
+        }
+        fn hidden(&self) -> Vec<&KeyedNode> {
+            self.0[1..self.0.len() - 1].iter().collect()
+        }
+        fn outputs(&self) -> Vec<&KeyedNode> {
+            self.0[self.0.len() - 1..].iter().collect()
+        }
+    }
+
+    #[test]
+    fn validate_keyed_ids() {
+        let net = KeyedNet(
+            vec![KeyedNode("in"), KeyedNode("loop"), KeyedNode("out")],
+            vec![
+                KeyedEdge("in", "loop"),
+                KeyedEdge("loop", "loop"),
+                KeyedEdge("loop", "out"),
+                KeyedEdge("loop", "out"),
+            ],
+        );
+
+        assert_eq!(cycles(&net), vec![vec!["loop"]]);
+        assert_eq!(
+            fabrication_warnings(&net),
+            vec![FabricationWarning::DuplicateEdge("loop", "out")]
+        );
+        assert_eq!(validate(&net).cycles, vec![vec!["loop"]]);
+    }
+}
@@ -0,0 +1,145 @@
+//! Cross-checks evaluators on random inputs, e.g. to localize discrepancies between backends.
+
+use alloc::vec::Vec;
+
+use nalgebra::DMatrix;
+
+use crate::{
+    network::{Evaluator, Scalar},
+    rng::SplitMix,
+};
+
+/// The first sample on which two evaluators disagree, as found by [`equivalent`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discrepancy<S: Scalar = f32> {
+    /// Index of the sample among all evaluated ones.
+    pub sample: usize,
+    /// The input both evaluators were given.
+    pub input: Vec<S>,
+    /// Index of the first output that differs.
+    pub output: usize,
+    /// The value computed by the first evaluator.
+    pub first: S,
+    /// The value computed by the second evaluator.
+    pub second: S,
+}
+
+/// Evaluates `first` and `second` on `samples` inputs uniform in `[-1, 1)` and compares their outputs.
+///
+/// Returns `Ok(None)` if all outputs differ by at most `tolerance`, outputs both being `NaN` are considered equal.
+/// The inputs are drawn from a fixed seed, so every discrepancy can be reproduced by evaluating its input again.
+/// Fails if the evaluators do not report their dimensions or the dimensions differ.
+pub fn equivalent<S: Scalar>(
+    first: &impl Evaluator<S>,
+    second: &impl Evaluator<S>,
+    samples: usize,
+    tolerance: S,
+) -> Result<Option<Discrepancy<S>>, &'static str> {
+    let inputs = match (first.input_dimension(), second.input_dimension()) {
+        (Some(first), Some(second)) if first == second => first,
+        (Some(_), Some(_)) => return Err("input dimensions of the evaluators differ"),
+        _ => return Err("evaluators do not report their input dimension"),
+    };
+    if first.output_dimension() != second.output_dimension() {
+        return Err("output dimensions of the evaluators differ");
+    }
+
+    let mut rng = SplitMix(0);
+    let input = DMatrix::from_fn(samples, inputs, |_, _| {
+        nalgebra::convert::<f64, S>(rng.weight() as f64)
+    });
+    let first_output = first.evaluate(input.clone());
+    let second_output = second.evaluate(input.clone());
+
+    for (sample, (a, b)) in first_output
+        .row_iter()
+        .zip(second_output.row_iter())
+        .enumerate()
+    {
+        let differing = a
+            .iter()
+            .zip(b.iter())
+            .position(|(&a, &b)| !((a - b).abs() <= tolerance || (is_nan(a) && is_nan(b))));
+        if let Some(output) = differing {
+            return Ok(Some(Discrepancy {
+                sample,
+                input: input.row(sample).iter().copied().collect(),
+                output,
+                first: a[output],
+                second: b[output],
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+fn is_nan<S: Scalar>(value: S) -> bool {
+    value.partial_cmp(&value).is_none()
+}
+
+/// Fabricates `net` with the dense and the sparse feedforward backend and checks them with [`equivalent`].
+#[cfg(feature = "std")]
+pub fn dense_sparse_equivalent<N, E, S>(
+    net: &impl crate::network::NetworkLike<N, E, S>,
+    samples: usize,
+    tolerance: S,
+) -> Result<Option<Discrepancy<S>>, &'static str>
+where
+    N: crate::network::NodeLike<S>,
+    E: crate::network::EdgeLike<S>,
+    S: Scalar,
+{
+    use crate::{
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator, network::Fabricator,
+        sparse_matrix::feedforward::fabricator::SparseMatrixFeedforwardFabricator,
+    };
+
+    equivalent(
+        &MatrixFeedforwardFabricator::fabricate(net)?,
+        &SparseMatrixFeedforwardFabricator::fabricate(net)?,
+        samples,
+        tolerance,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dense_sparse_equivalent, equivalent};
+    use crate::{
+        edges,
+        matrix::feedforward::fabricator::MatrixFeedforwardFabricator,
+        network::{net::Net, Fabricator},
+        nodes,
+        test_util::random_net,
+    };
+
+    #[test]
+    fn backends_agree() {
+        for seed in 0..10 {
+            let net = random_net(3, 2, 5, 0.4, 0.0, seed);
+            assert_eq!(dense_sparse_equivalent(&net, 16, 1e-5), Ok(None));
+        }
+    }
+
+    #[test]
+    fn localize_discrepancy() {
+        let some_net = Net::new(1, 1, nodes!('l', 'r', 'l'), edges!(0--1.0->1, 1--1.0->2));
+        let other_net = Net::new(1, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->1, 1--1.0->2));
+        let evaluator = MatrixFeedforwardFabricator::fabricate(&some_net).unwrap();
+        let other = MatrixFeedforwardFabricator::fabricate(&other_net).unwrap();
+
+        assert_eq!(equivalent(&evaluator, &evaluator, 8, 0.0), Ok(None));
+
+        // the nets only differ for negative inputs
+        let discrepancy = equivalent(&evaluator, &other, 8, 0.0).unwrap().unwrap();
+        assert!(discrepancy.input[0] < 0.0);
+        assert_eq!(discrepancy.output, 0);
+        assert_eq!(discrepancy.first, 0.0);
+        assert_eq!(discrepancy.second, discrepancy.input[0]);
+
+        let wider = Net::new(2, 1, nodes!('l', 'l', 'l'), edges!(0--1.0->2, 1--1.0->2));
+        let wider = MatrixFeedforwardFabricator::fabricate(&wider).unwrap();
+        assert!(equivalent(&evaluator, &wider, 8, 0.0).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+//! JavaScript bindings via `wasm-bindgen`, available with the feature `wasm`.
+//!
+//! Build with `wasm-pack build --target web -- --features wasm`, then from JavaScript:
+//!
+//! ```js
+//! // two linear inputs feeding one sigmoid output with bias 0.5
+//! const evaluator = new Evaluator(2, 1, ["linear", "linear", "sigmoid"], [0, 0, 0.5], [0, 2, 0.5, 1, 2, -1]);
+//! const output = evaluator.evaluate(new Float32Array([1, 2]));
+//! ```
+
+use nalgebra::DMatrix;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    matrix::feedforward::{
+        evaluator::MatrixFeedforwardEvaluator, fabricator::MatrixFeedforwardFabricator,
+    },
+    network::{
+        net::{activations, Edge, Net, Node},
+        Evaluator, Fabricator,
+    },
+};
+
+/// A [`MatrixFeedforwardEvaluator`] evaluating `Float32Array`s.
+#[wasm_bindgen(js_name = Evaluator)]
+pub struct WasmEvaluator {
+    evaluator: MatrixFeedforwardEvaluator,
+    inputs: usize,
+}
+
+#[wasm_bindgen(js_class = Evaluator)]
+impl WasmEvaluator {
+    /// Fabricates a network whose node ids are the indices into `activations` and `biases`.
+    ///
+    /// Activations are named like in [`activations`], edges are flattened `start, end, weight` triples.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        inputs: usize,
+        outputs: usize,
+        activations: Vec<String>,
+        biases: &[f32],
+        edges: &[f32],
+    ) -> Result<WasmEvaluator, String> {
+        if activations.len() != biases.len() {
+            return Err("expected one bias per node".to_owned());
+        }
+        if inputs + outputs > activations.len() {
+            return Err("more inputs and outputs than nodes".to_owned());
+        }
+        if !edges.len().is_multiple_of(3) {
+            return Err("edges need to be start, end, weight triples".to_owned());
+        }
+
+        let nodes = activations
+            .iter()
+            .zip(biases)
+            .enumerate()
+            .map(|(id, (activation, &bias))| {
+                activations::from_name(activation)
+                    .map(|activation| Node::new(id, activation).with_bias(bias))
+                    .ok_or_else(|| format!("unknown activation `{}`", activation))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let edges = edges
+            .chunks_exact(3)
+            .map(|edge| Edge::new(edge[0] as usize, edge[1] as usize, edge[2]))
+            .collect();
+
+        let evaluator =
+            MatrixFeedforwardFabricator::fabricate(&Net::new(inputs, outputs, nodes, edges))
+                .map_err(|error| error.to_string())?;
+
+        Ok(Self { evaluator, inputs })
+    }
+
+    /// Evaluates consecutive samples of `inputs` values each and returns their outputs back to back.
+    pub fn evaluate(&self, input: &[f32]) -> Result<Vec<f32>, String> {
+        if !input.len().is_multiple_of(self.inputs) {
+            return Err(format!(
+                "input length needs to be a multiple of {}",
+                self.inputs
+            ));
+        }
+
+        let input = DMatrix::from_row_slice(input.len() / self.inputs, self.inputs, input);
+        let output = self.evaluator.evaluate(input);
+
+        // back to row-major order, one sample after the other
+        Ok(output.transpose().iter().copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WasmEvaluator;
+
+    #[test]
+    fn wasm_evaluator_batch() {
+        let evaluator = WasmEvaluator::new(
+            2,
+            1,
+            vec!["linear".into(), "linear".into(), "relu".into()],
+            &[0.0, 0.0, 0.5],
+            &[0.0, 2.0, 0.5, 1.0, 2.0, 1.0],
+        )
+        .unwrap();
+
+        assert_eq!(
+            evaluator.evaluate(&[1.0, 2.0, -4.0, 0.0]).unwrap(),
+            vec![3.0, 0.0]
+        );
+        assert!(evaluator.evaluate(&[1.0]).is_err());
+    }
+}